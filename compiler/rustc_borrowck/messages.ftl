@@ -62,6 +62,13 @@ borrowck_could_not_normalize =
 borrowck_could_not_prove =
     could not prove `{$predicate}`
 
+borrowck_explain_borrowck_no_match =
+    no function matches `-Z explain-borrowck={$path}`
+    .note = functions are named by their path, like `crate_name::module::function`
+
+borrowck_explain_borrowck_write_failed =
+    failed to write borrowck explanation to `{$path}`: {$error}
+
 borrowck_func_take_self_moved_place =
     `{$func}` takes ownership of the receiver `self`, which moves {$place_name}
 
@@ -0,0 +1,504 @@
+//! `-Z explain-borrowck=<fn path>`: shows what the borrow checker inferred for
+//! a single function, in terms of its source rather than of its MIR.
+//!
+//! For every borrow, this shows the source lines it is live at, and if it has
+//! to outlive a lifetime of the signature, the chain of outlives constraints
+//! requiring that. For every region error, it shows the chain of constraints
+//! from the region that should outlive the other one, and which of those
+//! constraints the diagnostic blames (see
+//! `RegionInferenceContext::best_blame_constraint`).
+//!
+//! Conflicting accesses (e.g. E0499 or E0502) are not region errors, but the
+//! live ranges are usually what explains them.
+//!
+//! The explanation is written as annotated source text, as an HTML page, and
+//! as a graphviz graph of the constraints, next to the other outputs.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::mir::{Body, Location};
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{RegionVid, TyCtxt};
+use rustc_span::Span;
+
+use crate::borrow_set::{BorrowData, BorrowSet};
+use crate::constraints::OutlivesConstraint;
+use crate::dataflow::BorrowIndex;
+use crate::diagnostics::{RegionErrorKind, RegionErrors};
+use crate::nll::ConstraintDescription;
+use crate::region_infer::RegionInferenceContext;
+use crate::session_diagnostics::{ExplainBorrowckNoMatch, ExplainBorrowckWriteFailed};
+
+struct BorrowExplanation<'a, 'tcx> {
+    index: BorrowIndex,
+    data: &'a BorrowData<'tcx>,
+    span: Span,
+    /// The line the borrow is created at, if it is inside the function.
+    created_at: Option<usize>,
+    live_lines: BTreeSet<usize>,
+    /// The constraints requiring the borrow to outlive a lifetime of the
+    /// signature, if any.
+    outlives: Option<(Vec<OutlivesConstraint<'tcx>>, RegionVid)>,
+}
+
+struct ErrorExplanation<'tcx> {
+    description: String,
+    span: Span,
+    path: Vec<OutlivesConstraint<'tcx>>,
+    /// The index of the constraint of `path` the diagnostic points at.
+    blamed: Option<usize>,
+}
+
+struct Explanation<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    regioncx: &'a RegionInferenceContext<'tcx>,
+    def_path: String,
+    body_span: Span,
+    /// The first and last line of the function.
+    lines: (usize, usize),
+    borrows: Vec<BorrowExplanation<'a, 'tcx>>,
+    errors: Vec<ErrorExplanation<'tcx>>,
+}
+
+/// Writes the explanation for `body` if it is the function requested with
+/// `-Z explain-borrowck`.
+pub(crate) fn explain_borrowck<'a, 'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &Body<'tcx>,
+    regioncx: &'a RegionInferenceContext<'tcx>,
+    borrow_set: &'a BorrowSet<'tcx>,
+    nll_errors: &RegionErrors<'tcx>,
+) {
+    let Some(ref requested) = tcx.sess.opts.unstable_opts.explain_borrowck else {
+        return;
+    };
+    let def_id = body.source.def_id();
+    let def_path = with_no_trimmed_paths!(tcx.def_path_str(def_id));
+    if !is_requested(tcx, &def_path, requested) {
+        return;
+    }
+
+    let explanation = Explanation::new(tcx, body, regioncx, borrow_set, nll_errors, def_path);
+
+    let file_name = tcx.def_path(def_id).to_filename_friendly_no_crate();
+    let write = |extension: &str, contents: io::Result<String>| {
+        let path: PathBuf =
+            tcx.output_filenames(()).with_extension(&format!("{file_name}.borrowck.{extension}"));
+        if let Err(err) = contents.and_then(|contents| fs::write(&path, contents)) {
+            tcx.dcx().emit_err(ExplainBorrowckWriteFailed {
+                path: path.display().to_string(),
+                error: err.to_string(),
+            });
+        }
+    };
+    write("txt", Ok(explanation.render_text()));
+    write("html", Ok(explanation.render_html()));
+    write("dot", explanation.render_graphviz());
+}
+
+/// Warns if no function of the crate has the path requested with
+/// `-Z explain-borrowck`, since nothing is explained then.
+pub fn check_explain_borrowck(tcx: TyCtxt<'_>) {
+    let Some(ref requested) = tcx.sess.opts.unstable_opts.explain_borrowck else {
+        return;
+    };
+    let matched = tcx.hir().body_owners().any(|def_id| {
+        is_requested(tcx, &with_no_trimmed_paths!(tcx.def_path_str(def_id)), requested)
+    });
+    if !matched {
+        tcx.dcx().emit_warn(ExplainBorrowckNoMatch { path: requested.clone() });
+    }
+}
+
+/// Whether `def_path` is the requested path, which may start with the crate name.
+fn is_requested(tcx: TyCtxt<'_>, def_path: &str, requested: &str) -> bool {
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let without_crate_name =
+        requested.strip_prefix(crate_name.as_str()).and_then(|path| path.strip_prefix("::"));
+    requested == def_path || without_crate_name == Some(def_path)
+}
+
+impl<'a, 'tcx> Explanation<'a, 'tcx> {
+    fn new(
+        tcx: TyCtxt<'tcx>,
+        body: &Body<'tcx>,
+        regioncx: &'a RegionInferenceContext<'tcx>,
+        borrow_set: &'a BorrowSet<'tcx>,
+        nll_errors: &RegionErrors<'tcx>,
+        def_path: String,
+    ) -> Self {
+        let sm = tcx.sess.source_map();
+        let body_span = body.span;
+        let lines =
+            (sm.lookup_char_pos(body_span.lo()).line, sm.lookup_char_pos(body_span.hi()).line);
+        let line_of = |span: Span| {
+            let span = span.find_ancestor_inside(body_span)?;
+            Some(sm.lookup_char_pos(span.lo()).line)
+        };
+
+        let universal_regions = regioncx.universal_regions();
+        let borrows = borrow_set
+            .iter_enumerated()
+            .map(|(index, data)| {
+                let live_lines = live_locations(body, regioncx, data)
+                    .into_iter()
+                    .filter_map(|location| line_of(body.source_info(location).span))
+                    .collect();
+                let outlives = regioncx.find_constraint_paths_between_regions(data.region, |r| {
+                    universal_regions.is_universal_region(r) && r != universal_regions.fr_fn_body
+                });
+                let span = body.source_info(data.reserve_location).span;
+                BorrowExplanation {
+                    index,
+                    data,
+                    span,
+                    created_at: line_of(span),
+                    live_lines,
+                    outlives,
+                }
+            })
+            .collect();
+
+        let errors = nll_errors
+            .iter()
+            .map(|error| match *error {
+                RegionErrorKind::RegionError { fr_origin, longer_fr, shorter_fr, .. } => {
+                    let target = |r| regioncx.provides_universal_region(r, longer_fr, shorter_fr);
+                    let path = regioncx
+                        .find_constraint_paths_between_regions(longer_fr, &target)
+                        .map(|(path, _)| path)
+                        .unwrap_or_default();
+                    let blamed = if path.is_empty() {
+                        None
+                    } else {
+                        let (blame, _) =
+                            regioncx.best_blame_constraint(longer_fr, fr_origin, &target);
+                        path.iter().rposition(|c| {
+                            c.category == blame.category && c.span == blame.cause.span
+                        })
+                    };
+                    let span = blamed.map_or(body_span, |i| path[i].span);
+                    let description = format!(
+                        "{} must outlive {}",
+                        region_name(regioncx, longer_fr),
+                        region_name(regioncx, shorter_fr)
+                    );
+                    ErrorExplanation { description, span, path, blamed }
+                }
+                RegionErrorKind::TypeTestError { ref type_test } => ErrorExplanation {
+                    description: with_no_trimmed_paths!(format!(
+                        "`{}` must outlive {}",
+                        type_test.generic_kind,
+                        region_name(regioncx, type_test.lower_bound)
+                    )),
+                    span: type_test.span,
+                    path: vec![],
+                    blamed: None,
+                },
+                RegionErrorKind::UnexpectedHiddenRegion { span, hidden_ty, key, member_region } => {
+                    ErrorExplanation {
+                        description: with_no_trimmed_paths!(format!(
+                            "hidden type `{hidden_ty}` of `{}` captures `{member_region:?}`",
+                            tcx.def_path_str(key.def_id)
+                        )),
+                        span,
+                        path: vec![],
+                        blamed: None,
+                    }
+                }
+                RegionErrorKind::BoundUniversalRegionError { longer_fr, placeholder, .. } => {
+                    ErrorExplanation {
+                        description: format!(
+                            "{} must outlive the higher-ranked lifetime {:?}",
+                            region_name(regioncx, longer_fr),
+                            placeholder.bound
+                        ),
+                        span: body_span,
+                        path: vec![],
+                        blamed: None,
+                    }
+                }
+            })
+            .collect();
+
+        Explanation { tcx, regioncx, def_path, body_span, lines, borrows, errors }
+    }
+
+    fn location(&self, span: Span) -> String {
+        self.tcx.sess.source_map().span_to_embeddable_string(span)
+    }
+
+    fn snippet(&self, span: Span) -> String {
+        self.tcx.sess.source_map().span_to_snippet(span).unwrap_or_else(|_| "..".to_string())
+    }
+
+    fn source_line(&self, line: usize) -> String {
+        let sm = self.tcx.sess.source_map();
+        let file = sm.lookup_char_pos(self.body_span.lo()).file;
+        file.get_line(line - 1).map_or_else(String::new, |line| line.into_owned())
+    }
+
+    /// The marker of `borrow` in the gutter of `line`: `*` where the borrow
+    /// is created, `|` where it is live.
+    fn marker(borrow: &BorrowExplanation<'_, '_>, line: usize) -> char {
+        if borrow.created_at == Some(line) {
+            '*'
+        } else if borrow.live_lines.contains(&line) {
+            '|'
+        } else {
+            ' '
+        }
+    }
+
+    fn describe_constraint(&self, constraint: &OutlivesConstraint<'tcx>) -> String {
+        let category = category_name(constraint);
+        format!(
+            "{}: {} due to {category} at {}",
+            region_name(self.regioncx, constraint.sup),
+            region_name(self.regioncx, constraint.sub),
+            self.location(constraint.span)
+        )
+    }
+
+    fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "explanation of `{}` at {}",
+            self.def_path,
+            self.location(self.body_span)
+        );
+
+        let _ = writeln!(out, "\nborrows:");
+        if self.borrows.is_empty() {
+            let _ = writeln!(out, "  (none)");
+        }
+        for borrow in &self.borrows {
+            let _ = writeln!(
+                out,
+                "  {:?}: {} ({}) at {}",
+                borrow.index,
+                self.snippet(borrow.span),
+                borrow.data,
+                self.location(borrow.span)
+            );
+            if let Some((path, target)) = &borrow.outlives {
+                let _ = writeln!(out, "    must outlive {}:", region_name(self.regioncx, *target));
+                for constraint in path {
+                    let _ = writeln!(out, "      {}", self.describe_constraint(constraint));
+                }
+            }
+        }
+
+        if !self.borrows.is_empty() {
+            let _ = writeln!(out, "\nlive ranges (`*`: created, `|`: live):");
+            let (first, last) = self.lines;
+            let line_width = last.to_string().len();
+            let header: String =
+                self.borrows.iter().map(|b| format!("{:^5}", format!("{:?}", b.index))).collect();
+            let _ = writeln!(out, "  {header}");
+            for line in first..=last {
+                let gutter: String =
+                    self.borrows.iter().map(|b| format!("  {}  ", Self::marker(b, line))).collect();
+                let _ =
+                    writeln!(out, "  {gutter} {line:>line_width$} | {}", self.source_line(line));
+            }
+        }
+
+        if !self.errors.is_empty() {
+            let _ = writeln!(out, "\nregion errors (`>`: the constraint the error points at):");
+        }
+        for error in &self.errors {
+            let _ = writeln!(out, "  {} at {}", error.description, self.location(error.span));
+            for (i, constraint) in error.path.iter().enumerate() {
+                let marker = if error.blamed == Some(i) { '>' } else { ' ' };
+                let _ = writeln!(out, "  {marker} {}", self.describe_constraint(constraint));
+            }
+        }
+        out
+    }
+
+    /// Links to the line of `span` in the source listing if it has one.
+    fn html_location(&self, span: Span) -> String {
+        let location = escape_html(&self.location(span));
+        let line = self.tcx.sess.source_map().lookup_char_pos(span.lo()).line;
+        if span.find_ancestor_inside(self.body_span).is_some() {
+            format!("<a href=\"#L{line}\">{location}</a>")
+        } else {
+            location
+        }
+    }
+
+    fn html_constraint(&self, constraint: &OutlivesConstraint<'tcx>, blamed: bool) -> String {
+        let category = category_name(constraint);
+        format!(
+            "<li{}><code>{}: {}</code> due to {} at {}</li>",
+            if blamed { " class=\"blamed\"" } else { "" },
+            escape_html(&region_name(self.regioncx, constraint.sup)),
+            escape_html(&region_name(self.regioncx, constraint.sub)),
+            escape_html(category),
+            self.html_location(constraint.span)
+        )
+    }
+
+    fn render_html(&self) -> String {
+        let mut out = String::new();
+        let def_path = escape_html(&self.def_path);
+        let _ = writeln!(out, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(out, "<title>Borrow checking {def_path}</title>");
+        let _ = writeln!(out, "<style>{HTML_STYLE}</style>\n</head>\n<body>");
+        let _ = writeln!(
+            out,
+            "<h1>Borrow checking <code>{def_path}</code></h1>\n<p>{}</p>",
+            self.html_location(self.body_span)
+        );
+
+        let _ = writeln!(out, "<h2>Live ranges</h2>\n<table class=\"source\">\n<tr><th></th>");
+        for borrow in &self.borrows {
+            let _ = writeln!(out, "<th><a href=\"#{0:?}\">{0:?}</a></th>", borrow.index);
+        }
+        let _ = writeln!(out, "<th></th></tr>");
+        let (first, last) = self.lines;
+        for line in first..=last {
+            let _ = write!(out, "<tr id=\"L{line}\"><td class=\"line\">{line}</td>");
+            for borrow in &self.borrows {
+                let class = match Self::marker(borrow, line) {
+                    '*' => "created",
+                    '|' => "live",
+                    _ => "",
+                };
+                let _ = write!(out, "<td class=\"{class}\"></td>");
+            }
+            let _ =
+                writeln!(out, "<td><pre>{}</pre></td></tr>", escape_html(&self.source_line(line)));
+        }
+        let _ = writeln!(out, "</table>");
+
+        let _ = writeln!(out, "<h2>Borrows</h2>\n<ul>");
+        for borrow in &self.borrows {
+            let _ = writeln!(
+                out,
+                "<li id=\"{:?}\"><code>{:?}</code>: <code>{}</code> (<code>{}</code>) at {}",
+                borrow.index,
+                borrow.index,
+                escape_html(&self.snippet(borrow.span)),
+                escape_html(&borrow.data.to_string()),
+                self.html_location(borrow.span)
+            );
+            if let Some((path, target)) = &borrow.outlives {
+                let _ = writeln!(
+                    out,
+                    "<p>must outlive <code>{}</code>:</p>\n<ol>",
+                    escape_html(&region_name(self.regioncx, *target))
+                );
+                for constraint in path {
+                    let _ = writeln!(out, "{}", self.html_constraint(constraint, false));
+                }
+                let _ = writeln!(out, "</ol>");
+            }
+            let _ = writeln!(out, "</li>");
+        }
+        let _ = writeln!(out, "</ul>");
+
+        if !self.errors.is_empty() {
+            let _ = writeln!(out, "<h2>Region errors</h2>\n<ul>");
+        }
+        for error in &self.errors {
+            let _ = writeln!(
+                out,
+                "<li>{} at {}\n<ol>",
+                escape_html(&error.description),
+                self.html_location(error.span)
+            );
+            for (i, constraint) in error.path.iter().enumerate() {
+                let _ =
+                    writeln!(out, "{}", self.html_constraint(constraint, error.blamed == Some(i)));
+            }
+            let _ = writeln!(out, "</ol>\n</li>");
+        }
+        if !self.errors.is_empty() {
+            let _ = writeln!(out, "</ul>");
+        }
+
+        let _ = writeln!(out, "</body>\n</html>");
+        out
+    }
+
+    fn render_graphviz(&self) -> io::Result<String> {
+        let mut constraints = vec![];
+        let mut blamed = vec![];
+        for borrow in &self.borrows {
+            if let Some((path, _)) = &borrow.outlives {
+                constraints.extend_from_slice(path);
+            }
+        }
+        for error in &self.errors {
+            constraints.extend_from_slice(&error.path);
+            blamed.extend(error.blamed.map(|i| error.path[i]));
+        }
+        let mut out = vec![];
+        self.regioncx.dump_graphviz_constraint_paths(&constraints, &blamed, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+}
+
+const HTML_STYLE: &str = "\
+table.source { border-collapse: collapse; font-family: monospace; }
+table.source td { padding: 0 4px; }
+table.source pre { margin: 0; }
+td.line { color: #888; text-align: right; }
+td.created { background: #c33; }
+td.live { background: #f99; }
+li.blamed { font-weight: bold; }
+";
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn category_name(constraint: &OutlivesConstraint<'_>) -> &'static str {
+    let category = constraint.category.description().trim_end();
+    if category.is_empty() { "constraint" } else { category }
+}
+
+/// The name of `r` in the source if it has one, e.g. `'a`, or `'?N` otherwise.
+fn region_name(regioncx: &RegionInferenceContext<'_>, r: RegionVid) -> String {
+    match regioncx.region_definition(r).external_name.and_then(|r| r.get_name()) {
+        Some(name) => name.to_string(),
+        None => format!("{r:?}"),
+    }
+}
+
+/// The locations reachable from where `borrow` is created without leaving its
+/// region, i.e. where the borrow is live. This is how `Borrows` computes where
+/// borrows go out of scope.
+fn live_locations<'tcx>(
+    body: &Body<'tcx>,
+    regioncx: &RegionInferenceContext<'tcx>,
+    borrow: &BorrowData<'tcx>,
+) -> FxHashSet<Location> {
+    let successors = |location: Location| {
+        let data = &body[location.block];
+        if location.statement_index < data.statements.len() {
+            vec![location.successor_within_block()]
+        } else {
+            data.terminator().successors().map(|block| block.start_location()).collect()
+        }
+    };
+
+    let mut live = FxHashSet::default();
+    live.insert(borrow.reserve_location);
+    let mut stack = successors(borrow.reserve_location);
+    while let Some(location) = stack.pop() {
+        if regioncx.region_contains(borrow.region, location) && live.insert(location) {
+            stack.extend(successors(location));
+        }
+    }
+    live
+}
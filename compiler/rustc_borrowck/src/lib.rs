@@ -64,6 +64,7 @@
 mod dataflow;
 mod def_use;
 mod diagnostics;
+mod explain;
 mod facts;
 mod location;
 mod member_constraints;
@@ -84,6 +85,8 @@
 /// A public API provided for the Rust compiler consumers.
 pub mod consumers;
 
+pub use explain::check_explain_borrowck;
+
 use borrow_set::{BorrowData, BorrowSet};
 use dataflow::{BorrowIndex, BorrowckFlowState as Flows, BorrowckResults, Borrows};
 use nll::PoloniusOutput;
@@ -244,6 +247,10 @@ fn do_mir_borrowck<'tcx>(
         &mut diags,
     );
 
+    // Also write a user-facing explanation of the function, if requested
+    // with `-Z explain-borrowck`.
+    explain::explain_borrowck(tcx, body, &regioncx, &borrow_set, &nll_errors);
+
     // The various `flow_*` structures can be large. We drop `flow_inits` here
     // so it doesn't overlap with the others below. This reduces peak memory
     // usage significantly on some benchmarks.
@@ -57,6 +57,24 @@ pub(crate) fn dump_graphviz_scc_constraints(&self, mut w: &mut dyn Write) -> io:
 
         dot::render(&SccConstraints { regioncx: self, nodes_per_scc }, &mut w)
     }
+
+    /// Write out the given outlives constraints, e.g. the paths explaining
+    /// some region errors, with the `blamed` ones highlighted.
+    pub(crate) fn dump_graphviz_constraint_paths(
+        &self,
+        constraints: &[OutlivesConstraint<'tcx>],
+        blamed: &[OutlivesConstraint<'tcx>],
+        mut w: &mut dyn Write,
+    ) -> io::Result<()> {
+        // Paths to different targets often share a prefix.
+        let mut unique = Vec::with_capacity(constraints.len());
+        for constraint in constraints {
+            if !unique.contains(constraint) {
+                unique.push(*constraint);
+            }
+        }
+        dot::render(&ConstraintPaths { regioncx: self, constraints: unique, blamed }, &mut w)
+    }
 }
 
 struct RawConstraints<'a, 'tcx> {
@@ -169,3 +187,60 @@ fn target(&'this self, edge: &(ConstraintSccIndex, ConstraintSccIndex)) -> Const
         edge.1
     }
 }
+
+struct ConstraintPaths<'a, 'tcx> {
+    regioncx: &'a RegionInferenceContext<'tcx>,
+    constraints: Vec<OutlivesConstraint<'tcx>>,
+    blamed: &'a [OutlivesConstraint<'tcx>],
+}
+
+impl<'a, 'this, 'tcx> dot::Labeller<'this> for ConstraintPaths<'a, 'tcx> {
+    type Node = RegionVid;
+    type Edge = OutlivesConstraint<'tcx>;
+
+    fn graph_id(&'this self) -> dot::Id<'this> {
+        dot::Id::new("ConstraintPaths").unwrap()
+    }
+    fn node_id(&'this self, n: &RegionVid) -> dot::Id<'this> {
+        dot::Id::new(format!("r{}", n.index())).unwrap()
+    }
+    fn node_shape(&'this self, _node: &RegionVid) -> Option<dot::LabelText<'this>> {
+        Some(dot::LabelText::LabelStr(Cow::Borrowed("box")))
+    }
+    fn node_label(&'this self, n: &RegionVid) -> dot::LabelText<'this> {
+        dot::LabelText::LabelStr(render_region_vid(*n, self.regioncx).into())
+    }
+    fn edge_label(&'this self, e: &OutlivesConstraint<'tcx>) -> dot::LabelText<'this> {
+        dot::LabelText::LabelStr(format!("{:?}", e.category).into())
+    }
+    fn edge_style(&'this self, e: &OutlivesConstraint<'tcx>) -> dot::Style {
+        if self.blamed.contains(e) { dot::Style::Bold } else { dot::Style::None }
+    }
+}
+
+impl<'a, 'this, 'tcx> dot::GraphWalk<'this> for ConstraintPaths<'a, 'tcx> {
+    type Node = RegionVid;
+    type Edge = OutlivesConstraint<'tcx>;
+
+    fn nodes(&'this self) -> dot::Nodes<'this, RegionVid> {
+        let mut vids: Vec<RegionVid> =
+            self.constraints.iter().flat_map(|c| [c.sup, c.sub]).collect();
+        vids.sort();
+        vids.dedup();
+        vids.into()
+    }
+    fn edges(&'this self) -> dot::Edges<'this, OutlivesConstraint<'tcx>> {
+        (&self.constraints[..]).into()
+    }
+
+    // Render `a: b` as `a -> b`, indicating the flow
+    // of data during inference.
+
+    fn source(&'this self, edge: &OutlivesConstraint<'tcx>) -> RegionVid {
+        edge.sup
+    }
+
+    fn target(&'this self, edge: &OutlivesConstraint<'tcx>) -> RegionVid {
+        edge.sub
+    }
+}
@@ -479,3 +479,17 @@ pub(crate) struct SimdIntrinsicArgConst {
     pub arg: usize,
     pub intrinsic: String,
 }
+
+#[derive(Diagnostic)]
+#[diag(borrowck_explain_borrowck_no_match)]
+#[note]
+pub(crate) struct ExplainBorrowckNoMatch {
+    pub path: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(borrowck_explain_borrowck_write_failed)]
+pub(crate) struct ExplainBorrowckWriteFailed {
+    pub path: String,
+    pub error: String,
+}
@@ -1256,6 +1256,27 @@ pub fn resolve_path(sess: &Session, path: impl Into<PathBuf>, span: Span) -> PRe
     }
 }
 
+/// Reads the file named by `#[doc(example = "...")]`, once its path was resolved with
+/// [`resolve_path`].
+///
+/// The file is loaded in the source map, so that it is only read once even though the attribute is
+/// looked at when writing the dependencies of the crate, when checking attributes and when building
+/// the documentation. It is also added to the files tracked in the dependencies of the crate.
+pub fn load_doc_example(sess: &Session, file: &Path) -> std::io::Result<Lrc<String>> {
+    let source_map = sess.source_map();
+    let filename = FileName::from(file.to_owned());
+    if let Some(src) = source_map.get_source_file(&filename).and_then(|file| file.src.clone()) {
+        return Ok(src);
+    }
+    let source_file = source_map.load_file(file)?;
+    sess.psess.file_depinfo.borrow_mut().insert(Symbol::intern(&file.to_string_lossy()));
+    // Files imported from the metadata of another crate don't have their source loaded.
+    source_file
+        .src
+        .clone()
+        .ok_or_else(|| std::io::Error::other("the source of the file is not available"))
+}
+
 pub fn parse_macro_name_and_helper_attrs(
     dcx: &rustc_errors::DiagCtxt,
     attr: &Attribute,
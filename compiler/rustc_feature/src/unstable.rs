@@ -448,6 +448,8 @@ pub fn internal(&self, feature: Symbol) -> bool {
     (unstable, doc_cfg, "1.21.0", Some(43781)),
     /// Allows `#[doc(cfg_hide(...))]`.
     (unstable, doc_cfg_hide, "1.57.0", Some(43781)),
+    /// Allows `#[doc(example = "...")]`.
+    (unstable, doc_example, "CURRENT_RUSTC_VERSION", None),
     /// Allows `#[doc(masked)]`.
     (unstable, doc_masked, "1.21.0", Some(44027)),
     /// Allows `dyn* Trait` objects.
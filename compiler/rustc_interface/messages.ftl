@@ -1,3 +1,9 @@
+interface_cant_emit_c_abi =
+    could not emit C ABI description: {$error}
+
+interface_cant_emit_layouts =
+    could not emit layouts: {$error}
+
 interface_cant_emit_mir =
     could not emit MIR: {$error}
 
@@ -63,6 +63,18 @@ pub struct CantEmitMIR {
     pub error: io::Error,
 }
 
+#[derive(Diagnostic)]
+#[diag(interface_cant_emit_c_abi)]
+pub struct CantEmitCAbi {
+    pub error: io::Error,
+}
+
+#[derive(Diagnostic)]
+#[diag(interface_cant_emit_layouts)]
+pub struct CantEmitLayouts {
+    pub error: io::Error,
+}
+
 #[derive(Diagnostic)]
 #[diag(interface_rustc_error_fatal)]
 pub struct RustcErrorFatal {
@@ -421,6 +421,11 @@ fn write_out_deps(tcx: TyCtxt<'_>, outputs: &OutputFilenames, out_filenames: &[P
             .map(|fmap| escape_dep_filename(&fmap.name.prefer_local().to_string()))
             .collect();
 
+        // Files included with `#[doc(example)]` and the records of `-Z shared-generics-cache` are
+        // added to the tracked files below, since they are only loaded now.
+        tcx.ensure().load_doc_examples(());
+        tcx.ensure().shared_generics_cache(());
+
         // Account for explicitly marked-to-track files
         // (e.g. accessed in proc macros).
         let file_depinfo = sess.psess.file_depinfo.borrow();
@@ -739,6 +744,7 @@ fn run_required_analyses(tcx: TyCtxt<'_>) {
             tcx.ensure().check_unsafety(def_id);
             tcx.ensure().mir_borrowck(def_id)
         });
+        rustc_borrowck::check_explain_borrowck(tcx);
     });
     sess.time("MIR_effect_checking", || {
         for def_id in tcx.hir().body_owners() {
@@ -947,6 +953,18 @@ pub fn start_codegen<'tcx>(
         }
     }
 
+    if tcx.sess.opts.output_types.contains_key(&OutputType::Layouts) {
+        if let Err(error) = rustc_passes::layout_dump::emit_layouts(tcx) {
+            tcx.dcx().emit_fatal(errors::CantEmitLayouts { error });
+        }
+    }
+
+    if tcx.sess.opts.output_types.contains_key(&OutputType::CAbi) {
+        if let Err(error) = rustc_passes::c_abi_dump::emit_c_abi(tcx) {
+            tcx.dcx().emit_fatal(errors::CantEmitCAbi { error });
+        }
+    }
+
     codegen
 }
 
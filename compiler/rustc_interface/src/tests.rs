@@ -701,6 +701,7 @@ macro_rules! untracked {
     untracked!(macro_backtrace, true);
     untracked!(meta_stats, true);
     untracked!(mir_include_spans, true);
+    untracked!(mono_item_stats, true);
     untracked!(nll_facts, true);
     untracked!(no_analysis, true);
     untracked!(no_leak_check, true);
@@ -771,6 +772,7 @@ macro_rules! tracked {
     tracked!(dual_proc_macros, true);
     tracked!(dwarf_version, Some(5));
     tracked!(emit_thin_lto, false);
+    tracked!(explain_borrowck, Some(String::from("abc")));
     tracked!(export_executable_symbols, true);
     tracked!(fewer_names, Some(true));
     tracked!(flatten_format_args, false);
@@ -831,6 +833,7 @@ macro_rules! tracked {
     tracked!(sanitizer_recover, SanitizerSet::ADDRESS);
     tracked!(saturating_float_casts, Some(true));
     tracked!(share_generics, Some(true));
+    tracked!(shared_generics_cache, Some(PathBuf::from("abc")));
     tracked!(show_span, Some(String::from("abc")));
     tracked!(simulate_remapped_rust_src_base, Some(PathBuf::from("/rustc/abc")));
     tracked!(split_lto_unit, Some(true));
@@ -1065,6 +1065,12 @@ pub(super) fn crate_hash(tcx: TyCtxt<'_>, _: LocalCrate) -> Svh {
         upstream_crates.hash_stable(&mut hcx, &mut stable_hasher);
         source_file_names.hash_stable(&mut hcx, &mut stable_hasher);
         debugger_visualizers.hash_stable(&mut hcx, &mut stable_hasher);
+        // The instantiations reused from `-Z shared-generics-cache` change the symbols the crate
+        // links against, but come from outside of the crate graph.
+        if tcx.sess.opts.unstable_opts.shared_generics_cache.is_some() {
+            let shared_generics = tcx.shared_generics_cache(()).to_sorted_stable_ord();
+            shared_generics.hash_stable(&mut hcx, &mut stable_hasher);
+        }
         if tcx.sess.opts.incremental.is_some() {
             let definitions = tcx.untracked().definitions.freeze();
             let mut owner_spans: Vec<_> = krate
@@ -1590,6 +1590,24 @@
         desc { "available upstream drop-glue for `{:?}`", args }
     }
 
+    /// Returns the generic instantiations that other crates recorded in the
+    /// `-Z shared-generics-cache` directory. Each entry maps the symbol name an
+    /// instance would have if it were instantiated in the crate defining it
+    /// to the symbol name the recording crate actually defined it under.
+    ///
+    /// The cache lives outside of the crate graph, so it is re-read by every
+    /// session and never considered unchanged. The records read are added to
+    /// the dep-info of the crate.
+    ///
+    /// You likely want to call `rustc_symbol_mangling::shared_generics_cache_symbol()`
+    /// instead of invoking this query directly.
+    query shared_generics_cache(_: ()) -> &'tcx UnordMap<String, String> {
+        arena_cache
+        eval_always
+        no_hash
+        desc { "loading the shared generics cache" }
+    }
+
     /// Returns a list of all `extern` blocks of a crate.
     query foreign_modules(_: CrateNum) -> &'tcx FxIndexMap<DefId, ForeignModule> {
         arena_cache
@@ -1825,6 +1843,15 @@
         eval_always
     }
 
+    /// Loads the files included with `#[doc(example = "...")]` in the local crate, and adds them
+    /// to the files it depends on.
+    ///
+    /// Like `debugger_visualizers`, this reads files from disk which are not tracked anywhere else.
+    query load_doc_examples(_: ()) {
+        desc { "loading the files of `#[doc(example)]`" }
+        eval_always
+    }
+
     query postorder_cnums(_: ()) -> &'tcx [CrateNum] {
         eval_always
         desc { "generating a postorder list of CrateNums" }
@@ -4,6 +4,12 @@ monomorphize_consider_type_length_limit =
 monomorphize_couldnt_dump_mono_stats =
     unexpected error occurred while dumping monomorphization stats: {$error}
 
+monomorphize_couldnt_read_shared_generics_cache =
+    couldn't read the shared generics cache in `{$path}`: {$error}
+
+monomorphize_couldnt_write_shared_generics_cache =
+    couldn't write to the shared generics cache in `{$path}`: {$error}
+
 monomorphize_encountered_error_while_instantiating =
     the above error was encountered while instantiating `{$formatted_item}`
 
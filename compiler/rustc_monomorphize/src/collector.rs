@@ -234,6 +234,7 @@
 use rustc_span::source_map::{dummy_spanned, respan, Spanned};
 use rustc_span::symbol::{sym, Ident};
 use rustc_span::{Span, DUMMY_SP};
+use rustc_symbol_mangling::shared_generics_cache_symbol;
 use rustc_target::abi::Size;
 use std::path::PathBuf;
 use tracing::{debug, instrument, trace};
@@ -1014,6 +1015,11 @@ pub(crate) fn should_codegen_locally<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance
         return false;
     }
 
+    if shared_generics_cache_symbol(tcx, instance.polymorphize(tcx)).is_some() {
+        // Another crate sharing the `-Z shared-generics-cache` directory instantiated it already.
+        return false;
+    }
+
     if let DefKind::Static { .. } = tcx.def_kind(def_id) {
         // We cannot monomorphize statics from upstream crates.
         return false;
@@ -86,6 +86,20 @@ pub struct CouldntDumpMonoStats {
     pub error: String,
 }
 
+#[derive(Diagnostic)]
+#[diag(monomorphize_couldnt_read_shared_generics_cache)]
+pub struct CouldntReadSharedGenericsCache {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(monomorphize_couldnt_write_shared_generics_cache)]
+pub struct CouldntWriteSharedGenericsCache {
+    pub path: String,
+    pub error: String,
+}
+
 #[derive(Diagnostic)]
 #[diag(monomorphize_encountered_error_while_instantiating)]
 pub struct EncounteredErrorWhileInstantiating {
@@ -18,6 +18,7 @@
 mod errors;
 mod partitioning;
 mod polymorphize;
+mod shared_generics_cache;
 mod util;
 
 use collector::should_codegen_locally;
@@ -77,4 +78,5 @@ fn is_llvm_intrinsic(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
 pub fn provide(providers: &mut Providers) {
     partitioning::provide(providers);
     polymorphize::provide(providers);
+    shared_generics_cache::provide(providers);
 }
@@ -121,6 +121,7 @@
 use crate::collector::UsageMap;
 use crate::collector::{self, MonoItemCollectionStrategy};
 use crate::errors::{CouldntDumpMonoStats, SymbolAlreadyDefined, UnknownCguCollectionMode};
+use crate::shared_generics_cache::write_shared_generics_cache;
 
 struct PartitioningCx<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
@@ -1163,6 +1164,14 @@ fn collect_and_partition_mono_items(tcx: TyCtxt<'_>, (): ()) -> (&DefIdSet, &[Co
         }
     }
 
+    if let Some(ref directory) = tcx.sess.opts.unstable_opts.shared_generics_cache {
+        write_shared_generics_cache(tcx, codegen_units, directory);
+    }
+
+    if tcx.sess.opts.unstable_opts.mono_item_stats {
+        print_mono_item_stats(tcx, codegen_units, &usage_map);
+    }
+
     if tcx.sess.opts.unstable_opts.print_mono_items.is_some() {
         let mut item_to_cgus: FxHashMap<_, Vec<_>> = Default::default();
 
@@ -1295,6 +1304,76 @@ struct MonoItem {
     Ok(())
 }
 
+/// Prints, for every generic definition, how many distinct instantiations of it were
+/// collected, the total estimated size of all copies placed into codegen units, and the
+/// crates whose code requested those instantiations. Definitions are sorted by total
+/// estimated size, from heaviest to lightest.
+fn print_mono_item_stats<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    codegen_units: &[CodegenUnit<'tcx>],
+    usage_map: &UsageMap<'tcx>,
+) {
+    #[derive(Default)]
+    struct GenericItemStats<'tcx> {
+        instantiations: FxHashSet<MonoItem<'tcx>>,
+        total_estimate: usize,
+    }
+
+    let mut stats_per_def_id: FxHashMap<DefId, GenericItemStats<'tcx>> = Default::default();
+    for cgu in codegen_units {
+        for (&mono_item, data) in cgu.items() {
+            // Shims are compiler-generated and non-generic definitions are instantiated exactly
+            // once, so neither is interesting here.
+            let MonoItem::Fn(instance) = mono_item else { continue };
+            if !mono_item.is_user_defined()
+                || instance.args.non_erasable_generics(tcx, instance.def_id()).next().is_none()
+            {
+                continue;
+            }
+
+            let stats = stats_per_def_id.entry(instance.def_id()).or_default();
+            stats.instantiations.insert(mono_item);
+            // Inlined items are counted once per codegen unit they are copied into, as each
+            // copy is separately optimized and contributes to the final binary.
+            stats.total_estimate += data.size_estimate;
+        }
+    }
+
+    let mut stats: Vec<_> = stats_per_def_id
+        .into_iter()
+        .map(|(def_id, stats)| {
+            // Roots of the collection have no users; they were requested by the local crate.
+            let mut requesting_crates: Vec<_> = stats
+                .instantiations
+                .iter()
+                .flat_map(|&item| {
+                    let users = usage_map.get_user_items(item);
+                    let krates = users.iter().map(|user| user.krate());
+                    krates.chain(users.is_empty().then_some(LOCAL_CRATE))
+                })
+                .map(|krate| tcx.crate_name(krate))
+                .collect();
+            requesting_crates.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            requesting_crates.dedup();
+
+            let name = with_no_trimmed_paths!(tcx.def_path_str(def_id));
+            (name, stats.instantiations.len(), stats.total_estimate, requesting_crates)
+        })
+        .collect();
+    stats.sort_unstable_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+
+    println!("| Item | Instantiation count | Total Estimated Cost | Requested By |");
+    println!("| --- | ---: | ---: | --- |");
+    for (name, instantiation_count, total_estimate, requesting_crates) in stats {
+        let requesting_crates: Vec<_> =
+            requesting_crates.iter().map(|krate| format!("`{krate}`")).collect();
+        println!(
+            "| `{name}` | {instantiation_count} | {total_estimate} | {} |",
+            requesting_crates.join(", ")
+        );
+    }
+}
+
 pub fn provide(providers: &mut Providers) {
     providers.collect_and_partition_mono_items = collect_and_partition_mono_items;
 
@@ -0,0 +1,216 @@
+//! Workspace-level cache of generic instantiations
+//! ===============================================
+//!
+//! With `-Z share-generics`, a crate links against the instantiations its
+//! upstream crates export instead of instantiating them again. Crates that do
+//! not depend on each other, like siblings in a workspace, still each
+//! instantiate e.g. `Vec<CommonType>` methods on their own, though.
+//!
+//! `-Z shared-generics-cache=<dir>` makes crates record the instantiations of
+//! upstream generic items they define into `<dir>`, one file per crate. Crates
+//! compiled later with the same directory read these records and link against
+//! the recorded symbols instead of instantiating those items again.
+//!
+//! Instances are identified by the symbol name they would have if they were
+//! instantiated in the crate defining them (see
+//! `rustc_symbol_mangling::shared_generics_cache_key`), which is the same for
+//! every crate instantiating them. Each record maps that key to the symbol name
+//! the recording crate actually used.
+//!
+//! Each record starts with a header naming the compiler and the options
+//! affecting symbol names and ABI, and lists for every instantiation the crate
+//! hash of the crate defining the generic item. Records with another header,
+//! and instantiations of items from crates other than the ones the local crate
+//! depends on, are ignored.
+//!
+//! The records a crate reads are listed in its dep-info, so that build systems
+//! rebuild it when one of them changes or disappears, and the instantiations
+//! it reuses are part of its crate hash, so that the crates depending on it
+//! notice when it links against other instantiations.
+//!
+//! Nothing here makes sure the recording crate ends up being linked into the
+//! same artifacts as the crates using its instantiations; the build system
+//! enabling the cache is responsible for that.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::unord::UnordMap;
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::mir::mono::{CodegenUnit, Linkage, MonoItem};
+use rustc_middle::query::Providers;
+use rustc_middle::ty::{InstanceDef, TyCtxt};
+use rustc_span::Symbol;
+use rustc_symbol_mangling::shared_generics_cache_key;
+
+use crate::errors::{CouldntReadSharedGenericsCache, CouldntWriteSharedGenericsCache};
+
+const RECORD_EXTENSION: &str = "instances";
+
+/// Name of the file the given crate records its instantiations into.
+fn record_file_name(tcx: TyCtxt<'_>) -> String {
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let stable_crate_id = tcx.stable_crate_id(LOCAL_CRATE).as_u64();
+    format!("{crate_name}-{stable_crate_id:016x}.{RECORD_EXTENSION}")
+}
+
+/// First line of every record. Records written by another compiler, for another target, or with
+/// other options affecting symbol names or the ABI of the instantiations are ignored.
+fn record_header(tcx: TyCtxt<'_>) -> String {
+    format!(
+        "rustc {} {} {:?} {}",
+        tcx.sess.cfg_version,
+        tcx.sess.opts.target_triple.triple(),
+        tcx.sess.opts.get_symbol_mangling_version(),
+        tcx.sess.panic_strategy().desc(),
+    )
+}
+
+fn shared_generics_cache(tcx: TyCtxt<'_>, (): ()) -> UnordMap<String, String> {
+    let Some(ref directory) = tcx.sess.opts.unstable_opts.shared_generics_cache else {
+        return Default::default();
+    };
+
+    match read_records(tcx, directory) {
+        Ok(cache) => cache,
+        Err(err) => tcx.dcx().emit_fatal(CouldntReadSharedGenericsCache {
+            path: directory.display().to_string(),
+            error: err.to_string(),
+        }),
+    }
+}
+
+fn read_records(tcx: TyCtxt<'_>, directory: &Path) -> io::Result<UnordMap<String, String>> {
+    let own_record = record_file_name(tcx);
+
+    // A missing directory means that no crate recorded anything yet.
+    let mut records: Vec<_> = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(err) => return Err(err),
+    };
+    records.retain(|path| {
+        path.extension().is_some_and(|ext| ext == RECORD_EXTENSION)
+            && path.file_name().is_some_and(|name| name != OsStr::new(&own_record))
+    });
+    // If several crates recorded the same instance, select one deterministically.
+    records.sort();
+
+    // Instantiations of items from another version of a crate than the one the local crate
+    // depends on are stale.
+    let crate_hashes: FxHashMap<String, String> = tcx
+        .crates(())
+        .iter()
+        .map(|&cnum| {
+            let stable_crate_id = tcx.stable_crate_id(cnum).as_u64();
+            (format!("{stable_crate_id:016x}"), tcx.crate_hash(cnum).to_hex())
+        })
+        .collect();
+    let header = record_header(tcx);
+
+    let mut cache = UnordMap::default();
+    for path in records {
+        let contents = fs::read_to_string(&path)?;
+        let mut lines = contents.lines();
+        if lines.next() != Some(header.as_str()) {
+            continue;
+        }
+        tcx.sess.psess.file_depinfo.borrow_mut().insert(Symbol::intern(&path.to_string_lossy()));
+        for line in lines {
+            let fields: Vec<_> = line.split('\t').collect();
+            let &[key, symbol, krate, crate_hash] = &fields[..] else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed record in `{}`: `{line}`", path.display()),
+                ));
+            };
+            if crate_hashes.get(krate).map(String::as_str) != Some(crate_hash) {
+                continue;
+            }
+            cache.entry(key.to_owned()).or_insert_with(|| symbol.to_owned());
+        }
+    }
+
+    Ok(cache)
+}
+
+/// Records the instantiations of upstream generic items defined by the local
+/// crate into the shared generics cache, so that crates compiled later can
+/// link against them.
+pub(crate) fn write_shared_generics_cache<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    codegen_units: &[CodegenUnit<'tcx>],
+    directory: &Path,
+) {
+    if let Err(err) = write_records(tcx, codegen_units, directory) {
+        tcx.dcx().emit_fatal(CouldntWriteSharedGenericsCache {
+            path: directory.display().to_string(),
+            error: err.to_string(),
+        });
+    }
+}
+
+fn write_records<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    codegen_units: &[CodegenUnit<'tcx>],
+    directory: &Path,
+) -> io::Result<()> {
+    // Crates that do not export generics (e.g. executables) define their
+    // instantiations as hidden, so nobody else could link against them.
+    if !tcx.local_crate_exports_generics() {
+        return Ok(());
+    }
+
+    let mut records: Vec<_> = codegen_units
+        .iter()
+        .flat_map(|cgu| cgu.items())
+        .filter_map(|(&mono_item, data)| {
+            let MonoItem::Fn(instance) = mono_item else { return None };
+            let def_id = instance.def_id();
+            let is_generic = instance.args.non_erasable_generics(tcx, def_id).next().is_some();
+            let is_shareable = matches!(instance.def, InstanceDef::Item(_))
+                && !def_id.is_local()
+                && is_generic
+                && data.linkage == Linkage::External;
+            is_shareable.then(|| {
+                let key = shared_generics_cache_key(tcx, instance);
+                let stable_crate_id = tcx.stable_crate_id(def_id.krate).as_u64();
+                (
+                    key,
+                    tcx.symbol_name(instance).name,
+                    format!("{stable_crate_id:016x}"),
+                    tcx.crate_hash(def_id.krate).to_hex(),
+                )
+            })
+        })
+        .collect();
+    records.sort_unstable();
+    records.dedup();
+
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(directory)?;
+
+    // Other crates may be reading the directory concurrently, so only make the
+    // record visible once it is complete.
+    let path = directory.join(record_file_name(tcx));
+    let tmp_path = path.with_extension("tmp");
+    let mut file = io::BufWriter::new(fs::File::create(&tmp_path)?);
+    writeln!(file, "{}", record_header(tcx))?;
+    for (key, symbol, krate, crate_hash) in records {
+        writeln!(file, "{key}\t{symbol}\t{krate}\t{crate_hash}")?;
+    }
+    file.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    fs::rename(&tmp_path, &path)
+}
+
+pub(crate) fn provide(providers: &mut Providers) {
+    providers.shared_generics_cache = shared_generics_cache;
+}
@@ -183,6 +183,9 @@ passes_doc_attr_not_crate_level =
 passes_doc_cfg_hide_takes_list =
     `#[doc(cfg_hide(...))]` takes a list of attributes
 
+passes_doc_example_unreadable =
+    couldn't read example {$file}: {$error}
+
 passes_doc_expect_str =
     doc {$attr_name} attribute expects a string: #[doc({$attr_name} = "a")]
 
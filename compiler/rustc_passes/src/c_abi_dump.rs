@@ -0,0 +1,231 @@
+//! Machine-readable description of the C ABI surface of the local crate,
+//! emitted with `--emit=c-abi`.
+//!
+//! This lists every exported `extern "C"` function, with the way the target's
+//! calling convention passes each argument and the return value, every
+//! exported (e.g. `#[no_mangle]`) static, and the layouts of all concrete ADTs
+//! with a representation meant for FFI. Unlike tools re-parsing the source,
+//! everything here is what the compiler actually computed for the target, so
+//! C headers and other language bindings can be generated from it faithfully.
+
+use std::io;
+
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{LocalDefId, LOCAL_CRATE};
+use rustc_middle::ty::layout::{LayoutCx, LayoutOf};
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{self, Instance, ParamEnv, Ty, TyCtxt};
+use rustc_session::config::OutputType;
+use rustc_target::abi::call::{ArgAbi, PassMode, Reg, RegKind};
+use rustc_target::abi::Size;
+use rustc_target::spec::abi::Abi;
+use serde::Serialize;
+
+use crate::layout_dump::{adt_layout, write_json, AdtLayout};
+
+#[derive(Serialize)]
+struct CrateCAbi {
+    crate_name: String,
+    target: String,
+    functions: Vec<FunctionAbi>,
+    statics: Vec<StaticAbi>,
+    types: Vec<AdtLayout>,
+}
+
+#[derive(Serialize)]
+struct FunctionAbi {
+    path: String,
+    symbol: String,
+    /// `None` if the ABI could not be computed, in which case `error` says why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    abi: Option<FnAbiDesc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FnAbiDesc {
+    /// The target's name for the calling convention, e.g. `C` or `X86_64Win64`.
+    conv: String,
+    c_variadic: bool,
+    can_unwind: bool,
+    ret: ArgAbiDesc,
+    args: Vec<ArgAbiDesc>,
+}
+
+#[derive(Serialize)]
+struct ArgAbiDesc {
+    ty: String,
+    size: u64,
+    align: u64,
+    pass: PassModeDesc,
+}
+
+/// How an argument or return value is passed, see [`PassMode`].
+#[derive(Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum PassModeDesc {
+    /// Not passed at all, because it is a ZST or uninhabited.
+    Ignore,
+    /// Passed as a single scalar or vector value.
+    Direct,
+    /// Passed as two scalar values.
+    Pair,
+    /// Passed in the given registers (or the stack slots they spill to), e.g.
+    /// `["i64", "f64"]`, optionally preceded by a 32-bit padding argument.
+    Cast { pad_i32: bool, registers: Vec<String> },
+    /// Passed behind a pointer, or copied onto the stack if `on_stack`.
+    Indirect { on_stack: bool },
+}
+
+#[derive(Serialize)]
+struct StaticAbi {
+    path: String,
+    symbol: String,
+    ty: String,
+    mutable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    align: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn emit_c_abi(tcx: TyCtxt<'_>) -> io::Result<()> {
+    let mut functions = vec![];
+    let mut statics = vec![];
+    let mut types = vec![];
+    for def_id in tcx.hir_crate_items(()).definitions() {
+        match tcx.def_kind(def_id) {
+            DefKind::Fn | DefKind::AssocFn if is_exported_c_fn(tcx, def_id) => {
+                functions.push(function_abi(tcx, def_id));
+            }
+            DefKind::Static { .. } if is_exported_static(tcx, def_id) => {
+                statics.push(static_abi(tcx, def_id));
+            }
+            DefKind::Struct | DefKind::Enum | DefKind::Union if has_ffi_repr(tcx, def_id) => {
+                types.push(adt_layout(tcx, def_id));
+            }
+            _ => {}
+        }
+    }
+
+    let c_abi = CrateCAbi {
+        crate_name: tcx.crate_name(LOCAL_CRATE).to_string(),
+        target: tcx.sess.opts.target_triple.triple().to_string(),
+        functions,
+        statics,
+        types,
+    };
+    write_json(tcx, OutputType::CAbi, &c_abi)
+}
+
+fn is_exported(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
+    tcx.codegen_fn_attrs(def_id).contains_extern_indicator()
+        || tcx.is_reachable_non_generic(def_id)
+}
+
+fn is_exported_c_fn(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
+    let abi = tcx.fn_sig(def_id).skip_binder().abi();
+    matches!(abi, Abi::C { .. })
+        && !tcx.generics_of(def_id).requires_monomorphization(tcx)
+        && is_exported(tcx, def_id)
+}
+
+fn is_exported_static(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
+    // Statics in `extern` blocks are imported, not exported.
+    !tcx.is_foreign_item(def_id) && is_exported(tcx, def_id)
+}
+
+fn has_ffi_repr(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
+    let repr = tcx.adt_def(def_id).repr();
+    (repr.c() || repr.transparent() || repr.int.is_some())
+        && !tcx.generics_of(def_id).requires_monomorphization(tcx)
+}
+
+fn function_abi(tcx: TyCtxt<'_>, def_id: LocalDefId) -> FunctionAbi {
+    let instance = Instance::mono(tcx, def_id.to_def_id());
+    let fn_abi =
+        tcx.fn_abi_of_instance(ParamEnv::reveal_all().and((instance, ty::List::empty())));
+    let (abi, error) = match fn_abi {
+        Ok(fn_abi) => {
+            let abi = FnAbiDesc {
+                conv: format!("{:?}", fn_abi.conv),
+                c_variadic: fn_abi.c_variadic,
+                can_unwind: fn_abi.can_unwind,
+                ret: arg_abi(&fn_abi.ret),
+                args: fn_abi.args.iter().map(arg_abi).collect(),
+            };
+            (Some(abi), None)
+        }
+        Err(err) => (None, Some(format!("{err:?}"))),
+    };
+
+    FunctionAbi {
+        path: with_no_trimmed_paths!(tcx.def_path_str(def_id)),
+        symbol: tcx.symbol_name(instance).name.to_string(),
+        abi,
+        error,
+    }
+}
+
+fn arg_abi<'tcx>(arg: &ArgAbi<'tcx, Ty<'tcx>>) -> ArgAbiDesc {
+    let pass = match arg.mode {
+        PassMode::Ignore => PassModeDesc::Ignore,
+        PassMode::Direct(_) => PassModeDesc::Direct,
+        PassMode::Pair(..) => PassModeDesc::Pair,
+        PassMode::Cast { pad_i32, ref cast } => {
+            let mut registers: Vec<_> = cast.prefix.iter().flatten().map(reg_name).collect();
+            // The rest is passed as an array of `unit`s, the last of which may be cut short.
+            let unit = cast.rest.unit;
+            if unit.size != Size::ZERO {
+                let total = cast.rest.total.bytes();
+                let unit_size = unit.size.bytes();
+                registers.extend((0..total / unit_size).map(|_| reg_name(&unit)));
+                if total % unit_size != 0 {
+                    let last = Reg { kind: unit.kind, size: Size::from_bytes(total % unit_size) };
+                    registers.push(reg_name(&last));
+                }
+            }
+            PassModeDesc::Cast { pad_i32, registers }
+        }
+        PassMode::Indirect { on_stack, .. } => PassModeDesc::Indirect { on_stack },
+    };
+
+    ArgAbiDesc {
+        ty: with_no_trimmed_paths!(arg.layout.ty.to_string()),
+        size: arg.layout.size.bytes(),
+        align: arg.layout.align.abi.bytes(),
+        pass,
+    }
+}
+
+fn reg_name(reg: &Reg) -> String {
+    let kind = match reg.kind {
+        RegKind::Integer => 'i',
+        RegKind::Float => 'f',
+        RegKind::Vector => 'v',
+    };
+    format!("{kind}{}", reg.size.bits())
+}
+
+fn static_abi(tcx: TyCtxt<'_>, def_id: LocalDefId) -> StaticAbi {
+    let instance = Instance::mono(tcx, def_id.to_def_id());
+    let ty = tcx.type_of(def_id).instantiate_identity();
+    let cx = LayoutCx { tcx, param_env: ParamEnv::reveal_all() };
+    let (size, align, error) = match cx.layout_of(ty) {
+        Ok(layout) => (Some(layout.size.bytes()), Some(layout.align.abi.bytes()), None),
+        Err(err) => (None, None, Some(with_no_trimmed_paths!(err.to_string()))),
+    };
+
+    StaticAbi {
+        path: with_no_trimmed_paths!(tcx.def_path_str(def_id)),
+        symbol: tcx.symbol_name(instance).name.to_string(),
+        ty: with_no_trimmed_paths!(ty.to_string()),
+        mutable: tcx.static_mutability(def_id) == Some(ty::Mutability::Mut),
+        size,
+        align,
+        error,
+    }
+}
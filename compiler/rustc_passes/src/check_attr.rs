@@ -10,6 +10,7 @@
 use rustc_data_structures::fx::FxHashMap;
 use rustc_errors::StashKey;
 use rustc_errors::{Applicability, DiagCtxt, IntoDiagArg, MultiSpan};
+use rustc_expand::base::{load_doc_example, resolve_path};
 use rustc_feature::{AttributeDuplicates, AttributeType, BuiltinAttribute, BUILTIN_ATTRIBUTE_MAP};
 use rustc_hir::def_id::LocalModDefId;
 use rustc_hir::intravisit::{self, Visitor};
@@ -957,6 +958,31 @@ fn check_doc_inline(
         }
     }
 
+    /// Checks that `#[doc(example = "...")]` names a file which can be read, relative to the
+    /// file containing the attribute.
+    fn check_doc_example(&self, attr: &Attribute, meta: &NestedMetaItem) -> bool {
+        let Some(example) = meta.value_str() else {
+            self.doc_attr_str_error(meta, "example");
+            return false;
+        };
+        let file = match resolve_path(&self.tcx.sess, example.as_str(), attr.span) {
+            Ok(file) => file,
+            Err(err) => {
+                err.emit();
+                return false;
+            }
+        };
+        if let Err(error) = load_doc_example(&self.tcx.sess, &file) {
+            self.dcx().emit_err(errors::DocExampleUnreadable {
+                span: meta.span(),
+                file: &file,
+                error,
+            });
+            return false;
+        }
+        true
+    }
+
     fn check_doc_masked(
         &self,
         attr: &Attribute,
@@ -1173,12 +1199,17 @@ fn check_doc_attrs(
                             is_valid = false;
                         }
 
+                        sym::example if !self.check_doc_example(attr, meta) => {
+                            is_valid = false;
+                        }
+
                         // no_default_passes: deprecated
                         // passes: deprecated
                         // plugins: removed, but rustdoc warns about it itself
                         sym::alias
                         | sym::cfg
                         | sym::cfg_hide
+                        | sym::example
                         | sym::hidden
                         | sym::html_favicon_url
                         | sym::html_logo_url
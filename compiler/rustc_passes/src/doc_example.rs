@@ -0,0 +1,48 @@
+//! Loading the files included with the `#[doc(example = "...")]` attribute.
+
+use rustc_ast::Attribute;
+use rustc_expand::base::{load_doc_example, resolve_path};
+use rustc_middle::{query::Providers, ty::TyCtxt};
+use rustc_session::Session;
+use rustc_span::sym;
+
+struct DocExampleLoader<'a> {
+    sess: &'a Session,
+}
+
+impl<'ast> rustc_ast::visit::Visitor<'ast> for DocExampleLoader<'_> {
+    fn visit_attribute(&mut self, attr: &'ast Attribute) {
+        if attr.has_name(sym::doc) {
+            for meta in attr.meta_item_list().unwrap_or_default() {
+                if !meta.has_name(sym::example) {
+                    continue;
+                }
+                let Some(path) = meta.value_str() else { continue };
+                // Invalid paths and unreadable files are reported when checking attributes.
+                match resolve_path(self.sess, path.as_str(), attr.span) {
+                    Ok(file) => {
+                        let _ = load_doc_example(self.sess, &file);
+                    }
+                    Err(err) => err.cancel(),
+                }
+            }
+        }
+        rustc_ast::visit::walk_attribute(self, attr);
+    }
+}
+
+/// Loads the files included with `#[doc(example = "...")]` in the crate, which adds them to its
+/// dependencies. This has to happen before they are written, which is before the attributes are
+/// checked.
+fn load_doc_examples(tcx: TyCtxt<'_>, (): ()) {
+    if !tcx.features().doc_example {
+        return;
+    }
+    let resolver_and_krate = tcx.resolver_for_lowering().borrow();
+    let krate = &*resolver_and_krate.1;
+    rustc_ast::visit::Visitor::visit_crate(&mut DocExampleLoader { sess: tcx.sess }, krate);
+}
+
+pub fn provide(providers: &mut Providers) {
+    providers.load_doc_examples = load_doc_examples;
+}
@@ -232,6 +232,15 @@ pub struct DocKeywordInvalidIdent {
     pub doc_keyword: Symbol,
 }
 
+#[derive(Diagnostic)]
+#[diag(passes_doc_example_unreadable)]
+pub struct DocExampleUnreadable<'a> {
+    #[primary_span]
+    pub span: Span,
+    pub file: &'a Path,
+    pub error: Error,
+}
+
 #[derive(Diagnostic)]
 #[diag(passes_doc_fake_variadic_not_valid)]
 pub struct DocFakeVariadicNotValid {
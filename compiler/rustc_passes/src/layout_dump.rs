@@ -0,0 +1,331 @@
+//! Machine-readable dump of the layouts of all concrete ADTs defined in the
+//! local crate, emitted with `--emit=layouts`.
+//!
+//! Unlike `-Z print-type-sizes`, which only reports the types that happened to
+//! be needed during codegen, in a format meant for humans, this covers every
+//! non-generic struct, enum and union of the crate, and is meant to be checked
+//! by tools, e.g. to verify FFI types or to catch accidental size regressions.
+//!
+//! Integers that may not fit into a JSON number (discriminants and valid
+//! ranges) are written as decimal strings.
+
+use std::fs::File;
+use std::io::{self, Write};
+
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{LocalDefId, LOCAL_CRATE};
+use rustc_middle::ty::layout::{LayoutCx, LayoutOf, TyAndLayout};
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::util::IntTypeExt;
+use rustc_middle::ty::{self, AdtDef, ParamEnv, TyCtxt};
+use rustc_session::config::{OutFileName, OutputType};
+use rustc_target::abi::{Abi, FieldsShape, Niche, Scalar, Size, TagEncoding, Variants};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct CrateLayouts {
+    crate_name: String,
+    target: String,
+    types: Vec<AdtLayout>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct AdtLayout {
+    path: String,
+    kind: &'static str,
+    repr: Vec<String>,
+    /// `None` if the layout could not be computed, in which case `error` says why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    layout: Option<Layout>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Layout {
+    size: u64,
+    align: u64,
+    sized: bool,
+    uninhabited: bool,
+    /// The niche with the most invalid values, which is the one enclosing
+    /// enums use to encode their discriminant. Every niche is listed with the
+    /// fields of the variants.
+    largest_niche: Option<NicheLayout>,
+    /// How the variant of an enum is encoded, `None` for structs, unions and
+    /// enums with at most one inhabited variant.
+    discriminant: Option<DiscriminantLayout>,
+    variants: Vec<VariantLayout>,
+}
+
+/// A scalar whose invalid values can encode the discriminant of an enclosing
+/// enum.
+#[derive(Serialize)]
+struct NicheLayout {
+    offset: u64,
+    size: u64,
+    valid_range_start: String,
+    valid_range_end: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "encoding", rename_all = "snake_case")]
+enum DiscriminantLayout {
+    /// The tag stores the discriminant itself.
+    Direct { tag_offset: u64, tag_size: u64 },
+    /// All variants but `untagged_variant` are encoded by storing
+    /// `(variant index - first niche variant index) + niche_start` into the
+    /// tag, which is a niche of `untagged_variant`.
+    Niche {
+        tag_offset: u64,
+        tag_size: u64,
+        untagged_variant: String,
+        niche_variants: Vec<String>,
+        niche_start: String,
+    },
+}
+
+#[derive(Serialize)]
+struct VariantLayout {
+    name: String,
+    /// Only present for enums.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    discriminant: Option<String>,
+    size: u64,
+    uninhabited: bool,
+    fields: Vec<FieldLayout>,
+}
+
+#[derive(Serialize)]
+struct FieldLayout {
+    name: String,
+    ty: String,
+    offset: u64,
+    size: u64,
+    align: u64,
+    /// The niches of the field, at offsets from the start of the type. For
+    /// arrays, only the niches of the first element are listed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    niches: Vec<NicheLayout>,
+}
+
+pub fn emit_layouts(tcx: TyCtxt<'_>) -> io::Result<()> {
+    let layouts = CrateLayouts {
+        crate_name: tcx.crate_name(LOCAL_CRATE).to_string(),
+        target: tcx.sess.opts.target_triple.triple().to_string(),
+        types: tcx
+            .hir_crate_items(())
+            .definitions()
+            .filter(|&def_id| {
+                matches!(tcx.def_kind(def_id), DefKind::Struct | DefKind::Enum | DefKind::Union)
+                    && !tcx.generics_of(def_id).requires_monomorphization(tcx)
+            })
+            .map(|def_id| adt_layout(tcx, def_id))
+            .collect(),
+    };
+
+    write_json(tcx, OutputType::Layouts, &layouts)
+}
+
+/// Writes `value` as pretty-printed JSON to wherever the given output type
+/// was requested to be emitted.
+pub(crate) fn write_json(
+    tcx: TyCtxt<'_>,
+    output_type: OutputType,
+    value: &impl Serialize,
+) -> io::Result<()> {
+    match tcx.output_filenames(()).path(output_type) {
+        OutFileName::Stdout => {
+            let mut f = io::stdout().lock();
+            serde_json::to_writer_pretty(&mut f, value)?;
+            writeln!(f)?;
+        }
+        OutFileName::Real(path) => {
+            let mut f = io::BufWriter::new(File::create(&path)?);
+            serde_json::to_writer_pretty(&mut f, value)?;
+            writeln!(f)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn adt_layout(tcx: TyCtxt<'_>, def_id: LocalDefId) -> AdtLayout {
+    let adt_def = tcx.adt_def(def_id);
+    let kind = match adt_def.adt_kind() {
+        ty::AdtKind::Struct => "struct",
+        ty::AdtKind::Enum => "enum",
+        ty::AdtKind::Union => "union",
+    };
+
+    let repr = adt_def.repr();
+    let mut repr_attrs = vec![];
+    if repr.c() {
+        repr_attrs.push("C".to_string());
+    }
+    if repr.transparent() {
+        repr_attrs.push("transparent".to_string());
+    }
+    if repr.simd() {
+        repr_attrs.push("simd".to_string());
+    }
+    if let Some(int) = repr.int {
+        repr_attrs.push(int.to_ty(tcx).to_string());
+    }
+    if let Some(pack) = repr.pack {
+        repr_attrs.push(format!("packed({})", pack.bytes()));
+    }
+    if let Some(align) = repr.align {
+        repr_attrs.push(format!("align({})", align.bytes()));
+    }
+
+    let cx = LayoutCx { tcx, param_env: ParamEnv::reveal_all() };
+    let ty = tcx.erase_regions(tcx.type_of(def_id).instantiate_identity());
+    let (layout, error) = match cx.layout_of(ty) {
+        Ok(layout) => (Some(layout_of_adt(&cx, layout, adt_def)), None),
+        Err(err) => (None, Some(with_no_trimmed_paths!(err.to_string()))),
+    };
+
+    AdtLayout {
+        path: with_no_trimmed_paths!(tcx.def_path_str(def_id)),
+        kind,
+        repr: repr_attrs,
+        layout,
+        error,
+    }
+}
+
+fn layout_of_adt<'tcx>(
+    cx: &LayoutCx<'tcx, TyCtxt<'tcx>>,
+    layout: TyAndLayout<'tcx>,
+    adt_def: AdtDef<'tcx>,
+) -> Layout {
+    let tcx = cx.tcx;
+    let variant_name = |index| adt_def.variant(index).name.to_string();
+
+    let largest_niche = layout.largest_niche.map(|niche| niche_layout(cx, niche));
+
+    let discriminant = match layout.variants {
+        Variants::Single { .. } => None,
+        Variants::Multiple { tag, ref tag_encoding, tag_field, .. } => {
+            let tag_offset = layout.fields.offset(tag_field).bytes();
+            let tag_size = tag.size(cx).bytes();
+            Some(match *tag_encoding {
+                TagEncoding::Direct => DiscriminantLayout::Direct { tag_offset, tag_size },
+                TagEncoding::Niche { untagged_variant, ref niche_variants, niche_start } => {
+                    DiscriminantLayout::Niche {
+                        tag_offset,
+                        tag_size,
+                        untagged_variant: variant_name(untagged_variant),
+                        niche_variants: vec![
+                            variant_name(*niche_variants.start()),
+                            variant_name(*niche_variants.end()),
+                        ],
+                        niche_start: niche_start.to_string(),
+                    }
+                }
+            })
+        }
+    };
+
+    let variants = adt_def
+        .variants()
+        .iter_enumerated()
+        .map(|(index, variant_def)| {
+            let variant_layout = layout.for_variant(cx, index);
+            // Variants without fields of their own may share the layout of
+            // the enum's tag, which has no fields to report.
+            let fields = if let FieldsShape::Primitive = variant_layout.fields {
+                vec![]
+            } else {
+                variant_def
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field_def)| {
+                        let field_layout = variant_layout.field(cx, i);
+                        let offset = variant_layout.fields.offset(i);
+                        let mut niches = vec![];
+                        collect_niches(cx, field_layout, offset, &mut niches);
+                        FieldLayout {
+                            name: field_def.name.to_string(),
+                            ty: with_no_trimmed_paths!(field_layout.ty.to_string()),
+                            offset: offset.bytes(),
+                            size: field_layout.size.bytes(),
+                            align: field_layout.align.abi.bytes(),
+                            niches,
+                        }
+                    })
+                    .collect()
+            };
+            VariantLayout {
+                name: variant_def.name.to_string(),
+                discriminant: adt_def
+                    .is_enum()
+                    .then(|| adt_def.discriminant_for_variant(tcx, index).to_string()),
+                size: variant_layout.size.bytes(),
+                uninhabited: variant_layout.abi.is_uninhabited(),
+                fields,
+            }
+        })
+        .collect();
+
+    Layout {
+        size: layout.size.bytes(),
+        align: layout.align.abi.bytes(),
+        sized: layout.is_sized(),
+        uninhabited: layout.abi.is_uninhabited(),
+        largest_niche,
+        discriminant,
+        variants,
+    }
+}
+
+fn niche_layout(cx: &LayoutCx<'_, TyCtxt<'_>>, niche: Niche) -> NicheLayout {
+    let Niche { offset, value, valid_range } = niche;
+    NicheLayout {
+        offset: offset.bytes(),
+        size: value.size(cx).bytes(),
+        valid_range_start: valid_range.start.to_string(),
+        valid_range_end: valid_range.end.to_string(),
+    }
+}
+
+/// Collects the niches of every scalar of `layout`, which is at `offset`.
+fn collect_niches<'tcx>(
+    cx: &LayoutCx<'tcx, TyCtxt<'tcx>>,
+    layout: TyAndLayout<'tcx>,
+    offset: Size,
+    niches: &mut Vec<NicheLayout>,
+) {
+    let mut push = |offset, scalar: Scalar| {
+        if let Some(niche) = Niche::from_scalar(cx, offset, scalar) {
+            niches.push(niche_layout(cx, niche));
+        }
+    };
+    match layout.abi {
+        Abi::Scalar(scalar) => push(offset, scalar),
+        Abi::ScalarPair(a, b) => {
+            push(offset, a);
+            push(offset + a.size(cx).align_to(b.align(cx).abi), b);
+        }
+        Abi::Uninhabited | Abi::Vector { .. } | Abi::Aggregate { .. } => match layout.variants {
+            // The variants overlap, so only the tag is valid whatever the variant is.
+            Variants::Multiple { tag, tag_field, .. } => {
+                push(offset + layout.fields.offset(tag_field), tag);
+            }
+            Variants::Single { .. } => match layout.fields {
+                FieldsShape::Primitive | FieldsShape::Union(_) => {}
+                FieldsShape::Array { count, .. } => {
+                    if count > 0 {
+                        collect_niches(cx, layout.field(cx, 0), offset, niches);
+                    }
+                }
+                FieldsShape::Arbitrary { .. } => {
+                    for i in 0..layout.fields.count() {
+                        let field_offset = offset + layout.fields.offset(i);
+                        collect_niches(cx, layout.field(cx, i), field_offset, niches);
+                    }
+                }
+            },
+        },
+    }
+}
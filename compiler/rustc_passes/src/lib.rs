@@ -15,17 +15,20 @@
 use rustc_middle::query::Providers;
 
 pub mod abi_test;
+pub mod c_abi_dump;
 mod check_attr;
 mod check_const;
 pub mod dead;
 mod debugger_visualizer;
 mod diagnostic_items;
+mod doc_example;
 pub mod entry;
 mod errors;
 #[cfg(debug_assertions)]
 pub mod hir_id_validator;
 pub mod hir_stats;
 mod lang_items;
+pub mod layout_dump;
 pub mod layout_test;
 mod lib_features;
 mod liveness;
@@ -44,6 +47,7 @@ pub fn provide(providers: &mut Providers) {
     dead::provide(providers);
     debugger_visualizer::provide(providers);
     diagnostic_items::provide(providers);
+    doc_example::provide(providers);
     entry::provide(providers);
     lang_items::provide(providers);
     lib_features::provide(providers);
@@ -2,7 +2,9 @@
 use rustc_ast as ast;
 use rustc_ast::util::comments::beautify_doc_string;
 use rustc_data_structures::fx::FxHashMap;
+use rustc_expand::base::{load_doc_example, resolve_path};
 use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
 use rustc_span::def_id::DefId;
 use rustc_span::symbol::{kw, sym, Symbol};
 use rustc_span::{InnerSpan, Span, DUMMY_SP};
@@ -189,9 +191,12 @@ pub fn add_doc_fragment(out: &mut String, frag: &DocFragment) {
     }
 }
 
+/// The files of `#[doc(example = "...")]` attributes are included if `sess` is given, and skipped
+/// otherwise, which is enough when only the links of the documentation are needed.
 pub fn attrs_to_doc_fragments<'a>(
     attrs: impl Iterator<Item = (&'a ast::Attribute, Option<DefId>)>,
     doc_only: bool,
+    sess: Option<&Session>,
 ) -> (Vec<DocFragment>, ast::AttrVec) {
     let mut doc_fragments = Vec::new();
     let mut other_attrs = ast::AttrVec::new();
@@ -205,8 +210,13 @@ pub fn attrs_to_doc_fragments<'a>(
             };
             let fragment = DocFragment { span, doc, kind, item_id, indent: 0 };
             doc_fragments.push(fragment);
-        } else if !doc_only {
-            other_attrs.push(attr.clone());
+        } else {
+            if let Some(sess) = sess {
+                push_example_doc_fragments(sess, attr, item_id, &mut doc_fragments);
+            }
+            if !doc_only {
+                other_attrs.push(attr.clone());
+            }
         }
     }
 
@@ -215,6 +225,55 @@ pub fn attrs_to_doc_fragments<'a>(
     (doc_fragments, other_attrs)
 }
 
+/// Includes the files named by `#[doc(example = "...")]` as Rust code blocks, so that they are
+/// rendered and tested like the rest of the documentation.
+///
+/// Paths are relative to the file containing the attribute, like with `include_str!`. Files
+/// which can't be read are skipped: the error was already reported when checking attributes.
+fn push_example_doc_fragments(
+    sess: &Session,
+    attr: &ast::Attribute,
+    item_id: Option<DefId>,
+    doc_fragments: &mut Vec<DocFragment>,
+) {
+    if !attr.has_name(sym::doc) {
+        return;
+    }
+    for meta in attr.meta_item_list().unwrap_or_default() {
+        if !meta.has_name(sym::example) {
+            continue;
+        }
+        let Some(path) = meta.value_str() else { continue };
+        let file = match resolve_path(sess, path.as_str(), attr.span) {
+            Ok(file) => file,
+            Err(err) => {
+                err.cancel();
+                continue;
+            }
+        };
+        let Ok(code) = load_doc_example(sess, &file) else {
+            debug!("failed to read example {}", file.display());
+            continue;
+        };
+        // The fence must be longer than any run of backticks in the example.
+        let mut longest = 0;
+        let mut run = 0;
+        for c in code.chars() {
+            run = if c == '`' { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        let fence = "`".repeat(longest.max(2) + 1);
+        let code = code.strip_suffix('\n').unwrap_or(code.as_str());
+        doc_fragments.push(DocFragment {
+            span: attr.span,
+            doc: Symbol::intern(&format!("{fence}rust\n{code}\n{fence}")),
+            kind: DocFragmentKind::RawDoc,
+            item_id,
+            indent: 0,
+        });
+    }
+}
+
 fn span_for_value(attr: &ast::Attribute) -> Span {
     if let ast::AttrKind::Normal(normal) = &attr.kind
         && let ast::AttrArgs::Eq(_, ast::AttrArgsEq::Hir(meta)) = &normal.item.args
@@ -408,7 +467,8 @@ pub fn may_be_doc_link(link_type: LinkType) -> bool {
 /// Simplified version of `preprocessed_markdown_links` from rustdoc.
 /// Must return at least the same links as it, but may add some more links on top of that.
 pub(crate) fn attrs_to_preprocessed_links(attrs: &[ast::Attribute]) -> Vec<Box<str>> {
-    let (doc_fragments, _) = attrs_to_doc_fragments(attrs.iter().map(|attr| (attr, None)), true);
+    let (doc_fragments, _) =
+        attrs_to_doc_fragments(attrs.iter().map(|attr| (attr, None)), true, None);
     let doc = prepare_to_doc_link_resolution(&doc_fragments).into_values().next().unwrap();
 
     parse_links(&doc)
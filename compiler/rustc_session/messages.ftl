@@ -102,6 +102,8 @@ session_sanitizer_not_supported = {$us} sanitizer is not supported for this targ
 
 session_sanitizers_not_supported = {$us} sanitizers are not supported for this target
 
+session_shared_generics_cache_requires_share_generics = `-Zshared-generics-cache` requires `-Zshare-generics`
+
 session_skipping_const_checks = skipping const checks
 session_split_debuginfo_unstable_platform = `-Csplit-debuginfo={$debuginfo}` is unstable on this platform
 
@@ -473,6 +473,8 @@ pub enum OutputType {
     Object,
     Exe,
     DepInfo,
+    Layouts,
+    CAbi,
 }
 
 // Safety: Trivial C-Style enums have a stable sort order across compilation sessions.
@@ -491,7 +493,11 @@ fn to_stable_hash_key(&self, _: &HCX) -> Self::KeyType {
 impl OutputType {
     fn is_compatible_with_codegen_units_and_single_output_file(&self) -> bool {
         match *self {
-            OutputType::Exe | OutputType::DepInfo | OutputType::Metadata => true,
+            OutputType::Exe
+            | OutputType::DepInfo
+            | OutputType::Metadata
+            | OutputType::Layouts
+            | OutputType::CAbi => true,
             OutputType::Bitcode
             | OutputType::ThinLinkBitcode
             | OutputType::Assembly
@@ -512,6 +518,8 @@ pub fn shorthand(&self) -> &'static str {
             OutputType::Metadata => "metadata",
             OutputType::Exe => "link",
             OutputType::DepInfo => "dep-info",
+            OutputType::Layouts => "layouts",
+            OutputType::CAbi => "c-abi",
         }
     }
 
@@ -526,13 +534,15 @@ fn from_shorthand(shorthand: &str) -> Option<Self> {
             "metadata" => OutputType::Metadata,
             "link" => OutputType::Exe,
             "dep-info" => OutputType::DepInfo,
+            "layouts" => OutputType::Layouts,
+            "c-abi" => OutputType::CAbi,
             _ => return None,
         })
     }
 
     fn shorthands_display() -> String {
         format!(
-            "`{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`",
+            "`{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`, `{}`",
             OutputType::Bitcode.shorthand(),
             OutputType::ThinLinkBitcode.shorthand(),
             OutputType::Assembly.shorthand(),
@@ -542,6 +552,8 @@ fn shorthands_display() -> String {
             OutputType::Metadata.shorthand(),
             OutputType::Exe.shorthand(),
             OutputType::DepInfo.shorthand(),
+            OutputType::Layouts.shorthand(),
+            OutputType::CAbi.shorthand(),
         )
     }
 
@@ -556,6 +568,8 @@ pub fn extension(&self) -> &'static str {
             OutputType::Metadata => "rmeta",
             OutputType::DepInfo => "d",
             OutputType::Exe => "",
+            OutputType::Layouts => "layouts.json",
+            OutputType::CAbi => "c-abi.json",
         }
     }
 
@@ -564,7 +578,9 @@ pub fn is_text_output(&self) -> bool {
             OutputType::Assembly
             | OutputType::LlvmAssembly
             | OutputType::Mir
-            | OutputType::DepInfo => true,
+            | OutputType::DepInfo
+            | OutputType::Layouts
+            | OutputType::CAbi => true,
             OutputType::Bitcode
             | OutputType::ThinLinkBitcode
             | OutputType::Object
@@ -658,7 +674,10 @@ pub fn should_codegen(&self) -> bool {
             | OutputType::Mir
             | OutputType::Object
             | OutputType::Exe => true,
-            OutputType::Metadata | OutputType::DepInfo => false,
+            OutputType::Metadata
+            | OutputType::DepInfo
+            | OutputType::Layouts
+            | OutputType::CAbi => false,
         })
     }
 
@@ -672,7 +691,9 @@ pub fn should_link(&self) -> bool {
             | OutputType::Mir
             | OutputType::Metadata
             | OutputType::Object
-            | OutputType::DepInfo => false,
+            | OutputType::DepInfo
+            | OutputType::Layouts
+            | OutputType::CAbi => false,
             OutputType::Exe => true,
         })
     }
@@ -1770,10 +1791,14 @@ fn parse_output_types(
                         display = OutputType::shorthands_display(),
                     ))
                 });
-                if output_type == OutputType::ThinLinkBitcode && !unstable_opts.unstable_options {
+                if matches!(
+                    output_type,
+                    OutputType::ThinLinkBitcode | OutputType::Layouts | OutputType::CAbi
+                ) && !unstable_opts.unstable_options
+                {
                     early_dcx.early_fatal(format!(
                         "{} requested but -Zunstable-options not specified",
-                        OutputType::ThinLinkBitcode.shorthand()
+                        output_type.shorthand()
                     ));
                 }
                 output_types.insert(output_type, path);
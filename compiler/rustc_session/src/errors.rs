@@ -149,6 +149,10 @@ pub(crate) struct CannotMixAndMatchSanitizers {
 #[diag(session_sanitizer_kcfi_requires_panic_abort)]
 pub(crate) struct SanitizerKcfiRequiresPanicAbort;
 
+#[derive(Diagnostic)]
+#[diag(session_shared_generics_cache_requires_share_generics)]
+pub(crate) struct SharedGenericsCacheRequiresShareGenerics;
+
 #[derive(Diagnostic)]
 #[diag(session_split_lto_unit_requires_lto)]
 pub(crate) struct SplitLtoUnitRequiresLto;
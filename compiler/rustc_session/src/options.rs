@@ -1668,6 +1668,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         "emit a section containing stack size metadata (default: no)"),
     emit_thin_lto: bool = (true, parse_bool, [TRACKED],
         "emit the bc module with thin LTO info (default: yes)"),
+    explain_borrowck: Option<String> = (None, parse_opt_string, [TRACKED],
+        "write the borrow live ranges and region constraints of the function with the \
+        given path (e.g. `my_crate::foo`) to `<crate>.<function>.borrowck.{txt,html,dot}`"),
     export_executable_symbols: bool = (false, parse_bool, [TRACKED],
         "export symbols from executables, as if they were dynamic libraries"),
     external_clangrt: bool = (false, parse_bool, [UNTRACKED],
@@ -1793,6 +1796,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
     #[rustc_lint_opt_deny_field_access("use `Session::mir_opt_level` instead of this field")]
     mir_opt_level: Option<usize> = (None, parse_opt_number, [TRACKED],
         "MIR optimization level (0-4; default: 1 in non optimized builds and 2 in optimized builds)"),
+    mono_item_stats: bool = (false, parse_bool, [UNTRACKED],
+        "print, for each generic item, the number of instantiations, their total estimated \
+        size and the crates that requested them, sorted by total size (default: no)"),
     move_size_limit: Option<usize> = (None, parse_opt_number, [TRACKED],
         "the size at which the `large_assignments` lint starts to be emitted"),
     mutable_noalias: bool = (true, parse_bool, [TRACKED],
@@ -1935,6 +1941,10 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
                      query-blocked, incr-cache-load, incr-result-hashing, query-keys, function-args, args, llvm, artifact-sizes"),
     share_generics: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "make the current crate share its generic instantiations"),
+    shared_generics_cache: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
+        "directory of a workspace-level cache of generic instantiations: reuse the ones \
+        other crates recorded there instead of instantiating them again, and record this \
+        crate's own (requires `-Z share-generics`)"),
     shell_argfiles: bool = (false, parse_bool, [UNTRACKED],
         "allow argument files to be specified with POSIX \"shell-style\" argument quoting"),
     show_span: Option<String> = (None, parse_opt_string, [TRACKED],
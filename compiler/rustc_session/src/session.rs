@@ -1259,6 +1259,12 @@ fn validate_commandline_args_with_session_available(sess: &Session) {
         sess.dcx().emit_err(errors::SplitLtoUnitRequiresLto);
     }
 
+    // Instantiations from the shared generics cache are linked like those of upstream crates,
+    // which is only possible if this crate also shares its own.
+    if sess.opts.unstable_opts.shared_generics_cache.is_some() && !sess.opts.share_generics() {
+        sess.dcx().emit_err(errors::SharedGenericsCacheRequiresShareGenerics);
+    }
+
     // VFE requires LTO.
     if sess.lto() != config::Lto::Fat {
         if sess.opts.unstable_opts.virtual_function_elimination {
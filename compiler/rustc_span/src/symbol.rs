@@ -717,6 +717,7 @@
         doc_auto_cfg,
         doc_cfg,
         doc_cfg_hide,
+        doc_example,
         doc_keyword,
         doc_masked,
         doc_notable_trait,
@@ -762,6 +763,7 @@
         eq,
         ermsb_target_feature,
         exact_div,
+        example,
         except,
         exchange_malloc,
         exclusive_range_pattern,
@@ -121,6 +121,36 @@ pub fn symbol_name_for_instance_in_crate<'tcx>(
     compute_symbol_name(tcx, instance, || instantiating_crate)
 }
 
+/// Returns the symbol name under which another crate sharing the `-Z shared-generics-cache`
+/// directory has defined the given `instance`, if any. Instances found there do not need to
+/// be instantiated in the local crate and can be linked against instead.
+pub fn shared_generics_cache_symbol<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instance: Instance<'tcx>,
+) -> Option<&'tcx str> {
+    tcx.sess.opts.unstable_opts.shared_generics_cache.as_ref()?;
+
+    // Like for upstream monomorphizations, only instances of generic items defined in other
+    // crates can be provided by another crate, and `compiler_builtins` must not use them.
+    if instance.def_id().is_local()
+        || !matches!(instance.def, ty::InstanceDef::Item(_))
+        || !is_generic(instance, tcx)
+        || tcx.is_compiler_builtins(LOCAL_CRATE)
+    {
+        return None;
+    }
+
+    let key = shared_generics_cache_key(tcx, instance);
+    tcx.shared_generics_cache(()).get(&key).map(|symbol| symbol.as_str())
+}
+
+/// Computes the key identifying `instance` in the `-Z shared-generics-cache` directory. This is
+/// the symbol name the instance would have if it were instantiated in the crate defining it,
+/// which, unlike the name it actually gets, is the same for every crate instantiating it.
+pub fn shared_generics_cache_key<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> String {
+    compute_symbol_name(tcx, instance, || instance.def_id().krate)
+}
+
 pub fn provide(providers: &mut Providers) {
     *providers = Providers { symbol_name: symbol_name_provider, ..*providers };
 }
@@ -129,6 +159,16 @@ pub fn provide(providers: &mut Providers) {
 // instance from the local crate. In particular, it will also look up the
 // correct symbol name of instances from upstream crates.
 fn symbol_name_provider<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> ty::SymbolName<'tcx> {
+    // Instances provided by upstream crates take precedence over those recorded in the
+    // shared generics cache, mirroring the order `should_codegen_locally` checks them in.
+    // `shared_generics_cache_symbol` is checked first, as it returns early unless the cache is
+    // enabled.
+    if let Some(symbol_name) = shared_generics_cache_symbol(tcx, instance) {
+        if instance.upstream_monomorphization(tcx).is_none() {
+            return ty::SymbolName::new(tcx, symbol_name);
+        }
+    }
+
     let symbol_name = compute_symbol_name(tcx, instance, || {
         // This closure determines the instantiating crate for instances that
         // need an instantiating-crate-suffix for their symbol name, in order
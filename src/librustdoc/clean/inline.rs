@@ -418,14 +418,14 @@ pub(crate) fn merge_attrs(
         both.extend_from_slice(old_attrs);
         (
             if let Some(item_id) = item_id {
-                Attributes::from_ast_with_additional(old_attrs, (inner, item_id))
+                Attributes::from_ast_with_additional(old_attrs, (inner, item_id), cx.tcx.sess)
             } else {
-                Attributes::from_ast(&both)
+                Attributes::from_ast(&both, cx.tcx.sess)
             },
             both.cfg(cx.tcx, &cx.cache.hidden_cfg),
         )
     } else {
-        (Attributes::from_ast(&old_attrs), old_attrs.cfg(cx.tcx, &cx.cache.hidden_cfg))
+        (Attributes::from_ast(&old_attrs, cx.tcx.sess), old_attrs.cfg(cx.tcx, &cx.cache.hidden_cfg))
     }
 }
 
@@ -435,7 +435,7 @@ pub(crate) fn from_def_id_and_parts(
             def_id,
             name,
             kind,
-            Box::new(Attributes::from_ast(ast_attrs)),
+            Box::new(Attributes::from_ast(ast_attrs, cx.tcx.sess)),
             ast_attrs.cfg(cx.tcx, &cx.cache.hidden_cfg),
         )
     }
@@ -1165,25 +1165,28 @@ pub(crate) fn is_doc_hidden(&self) -> bool {
         self.has_doc_flag(sym::hidden)
     }
 
-    pub(crate) fn from_ast(attrs: &[ast::Attribute]) -> Attributes {
-        Attributes::from_ast_iter(attrs.iter().map(|attr| (attr, None)), false)
+    pub(crate) fn from_ast(attrs: &[ast::Attribute], sess: &Session) -> Attributes {
+        Attributes::from_ast_iter(attrs.iter().map(|attr| (attr, None)), false, sess)
     }
 
     pub(crate) fn from_ast_with_additional(
         attrs: &[ast::Attribute],
         (additional_attrs, def_id): (&[ast::Attribute], DefId),
+        sess: &Session,
     ) -> Attributes {
         // Additional documentation should be shown before the original documentation.
         let attrs1 = additional_attrs.iter().map(|attr| (attr, Some(def_id)));
         let attrs2 = attrs.iter().map(|attr| (attr, None));
-        Attributes::from_ast_iter(attrs1.chain(attrs2), false)
+        Attributes::from_ast_iter(attrs1.chain(attrs2), false, sess)
     }
 
+    /// `sess` is used to load the files included with `#[doc(example = "...")]`.
     pub(crate) fn from_ast_iter<'a>(
         attrs: impl Iterator<Item = (&'a ast::Attribute, Option<DefId>)>,
         doc_only: bool,
+        sess: &Session,
     ) -> Attributes {
-        let (doc_strings, other_attrs) = attrs_to_doc_fragments(attrs, doc_only);
+        let (doc_strings, other_attrs) = attrs_to_doc_fragments(attrs, doc_only, Some(sess));
         Attributes { doc_strings, other_attrs }
     }
 
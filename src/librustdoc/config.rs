@@ -38,11 +38,19 @@ pub(crate) enum OutputFormat {
     Json,
     #[default]
     Html,
+    /// Compares the crate's JSON representation with the one given to `--baseline`.
+    ApiDiff,
+    /// One Markdown file per module and item.
+    Markdown,
+    /// The pages of `Markdown`, as man pages.
+    Man,
 }
 
 impl OutputFormat {
+    /// Whether the crate is rendered to rustdoc's JSON format. This includes the API diff, which
+    /// needs to see the same items as a JSON baseline would contain.
     pub(crate) fn is_json(&self) -> bool {
-        matches!(self, OutputFormat::Json)
+        matches!(self, OutputFormat::Json | OutputFormat::ApiDiff)
     }
 }
 
@@ -53,6 +61,9 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "json" => Ok(OutputFormat::Json),
             "html" => Ok(OutputFormat::Html),
+            "api-diff" => Ok(OutputFormat::ApiDiff),
+            "markdown" => Ok(OutputFormat::Markdown),
+            "man" => Ok(OutputFormat::Man),
             _ => Err(format!("unknown output format `{value}`")),
         }
     }
@@ -118,6 +129,11 @@ pub(crate) struct Options {
     /// Optional path to persist the doctest executables to, defaults to a
     /// temporary directory if not set.
     pub(crate) persist_doctests: Option<PathBuf>,
+    /// Optional path to a directory remembering which doctests passed, so that they can be
+    /// skipped as long as neither their code nor the way they are compiled changes.
+    pub(crate) doctest_cache_dir: Option<PathBuf>,
+    /// Whether to compile the doctests which are not marked `standalone` into a single binary.
+    pub(crate) merge_doctests: bool,
     /// Runtool to run doctests with
     pub(crate) runtool: Option<String>,
     /// Arguments to pass to the runtool
@@ -160,6 +176,10 @@ pub(crate) struct Options {
     /// the compiler will scrape examples and not generate documentation.
     pub(crate) scrape_examples_options: Option<ScrapeExamplesOptions>,
 
+    /// The documentation directories to merge into the output directory with `--merge-docs`. If
+    /// this isn't empty, rustdoc merges them instead of documenting a crate.
+    pub(crate) merge_docs: Vec<PathBuf>,
+
     /// Note: this field is duplicated in `RenderOptions` because it's useful
     /// to have it in both places.
     pub(crate) unstable_features: rustc_feature::UnstableFeatures,
@@ -203,6 +223,8 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("test_args", &self.test_args)
             .field("test_run_directory", &self.test_run_directory)
             .field("persist_doctests", &self.persist_doctests)
+            .field("doctest_cache_dir", &self.doctest_cache_dir)
+            .field("merge_doctests", &self.merge_doctests)
             .field("show_coverage", &self.show_coverage)
             .field("crate_version", &self.crate_version)
             .field("runtool", &self.runtool)
@@ -213,6 +235,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("test_builder_wrappers", &self.test_builder_wrappers)
             .field("nocapture", &self.nocapture)
             .field("scrape_examples_options", &self.scrape_examples_options)
+            .field("merge_docs", &self.merge_docs)
             .field("unstable_features", &self.unstable_features)
             .finish()
     }
@@ -284,10 +307,23 @@ pub(crate) struct RenderOptions {
     pub(crate) generate_link_to_definition: bool,
     /// Set of function-call locations to include as examples
     pub(crate) call_locations: AllCallLocations,
+    /// If `true`, examples whose source or called function changed since they were scraped
+    /// are marked as stale.
+    pub(crate) check_scraped_examples: bool,
     /// If `true`, Context::init will not emit shared files.
     pub(crate) no_emit_shared: bool,
     /// If `true`, HTML source code pages won't be generated.
     pub(crate) html_no_source: bool,
+    /// The JSON output of a previous version of the crate to compare against when using
+    /// `--output-format api-diff`.
+    pub(crate) api_diff_baseline: Option<PathBuf>,
+    /// If `true`, the text of doc comments is indexed for the search, next to the search index.
+    pub(crate) full_text_search: bool,
+    /// If `true`, `--show-coverage` also lists the items missing documentation or examples,
+    /// and the totals of each module.
+    pub(crate) coverage_detail: bool,
+    /// The minimum percentage of documented items under which `--show-coverage` fails.
+    pub(crate) coverage_threshold: Option<f64>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -465,9 +501,29 @@ fn println_condition(condition: Condition) {
             return None;
         }
 
+        let out_dir = matches.opt_str("out-dir").map(|s| PathBuf::from(&s));
+        let output = matches.opt_str("output").map(|s| PathBuf::from(&s));
+        let output = match (out_dir, output) {
+            (Some(_), Some(_)) => {
+                dcx.fatal("cannot use both 'out-dir' and 'output' at once");
+            }
+            (Some(out_dir), None) => out_dir,
+            (None, Some(output)) => output,
+            (None, None) => PathBuf::from("doc"),
+        };
+
+        let merge_docs: Vec<PathBuf> = if matches.opt_present("merge-docs") {
+            if matches.free.is_empty() {
+                dcx.fatal("--merge-docs requires the documentation directories to merge");
+            }
+            matches.free.iter().map(PathBuf::from).collect()
+        } else {
+            Vec::new()
+        };
+
         let (lint_opts, describe_lints, lint_cap) = get_cmd_lint_options(early_dcx, matches);
 
-        let input = if describe_lints {
+        let input = if describe_lints || !merge_docs.is_empty() {
             "" // dummy, this won't be used
         } else {
             match matches.free.as_slice() {
@@ -543,16 +599,6 @@ fn println_condition(condition: Condition) {
 
         let test_builder_wrappers =
             matches.opt_strs("test-builder-wrapper").iter().map(PathBuf::from).collect();
-        let out_dir = matches.opt_str("out-dir").map(|s| PathBuf::from(&s));
-        let output = matches.opt_str("output").map(|s| PathBuf::from(&s));
-        let output = match (out_dir, output) {
-            (Some(_), Some(_)) => {
-                dcx.fatal("cannot use both 'out-dir' and 'output' at once");
-            }
-            (Some(out_dir), None) => out_dir,
-            (None, Some(output)) => output,
-            (None, None) => PathBuf::from("doc"),
-        };
 
         let cfgs = matches.opt_strs("cfg");
         let check_cfgs = matches.opt_strs("check-cfg");
@@ -677,11 +723,26 @@ fn println_condition(condition: Condition) {
         let output_format = match matches.opt_str("output-format") {
             Some(s) => match OutputFormat::try_from(s.as_str()) {
                 Ok(out_fmt) => {
-                    if !out_fmt.is_json() && show_coverage {
+                    if out_fmt == OutputFormat::Html && show_coverage {
                         dcx.fatal(
                             "html output format isn't supported for the --show-coverage option",
                         );
                     }
+                    if out_fmt == OutputFormat::ApiDiff && show_coverage {
+                        dcx.fatal(
+                            "api-diff output format isn't supported for the --show-coverage option",
+                        );
+                    }
+                    if out_fmt == OutputFormat::Markdown && show_coverage {
+                        dcx.fatal(
+                            "markdown output format isn't supported for the --show-coverage option",
+                        );
+                    }
+                    if out_fmt == OutputFormat::Man && show_coverage {
+                        dcx.fatal(
+                            "man output format isn't supported for the --show-coverage option",
+                        );
+                    }
                     out_fmt
                 }
                 Err(e) => dcx.fatal(e),
@@ -706,6 +767,8 @@ fn println_condition(condition: Condition) {
         let static_root_path = matches.opt_str("static-root-path");
         let test_run_directory = matches.opt_str("test-run-directory").map(PathBuf::from);
         let persist_doctests = matches.opt_str("persist-doctests").map(PathBuf::from);
+        let doctest_cache_dir = matches.opt_str("doctest-cache-dir").map(PathBuf::from);
+        let merge_doctests = matches.opt_present("merge-doctests");
         let test_builder = matches.opt_str("test-builder").map(PathBuf::from);
         let codegen_options_strs = matches.opt_strs("C");
         let unstable_opts_strs = matches.opt_strs("Z");
@@ -724,6 +787,40 @@ fn println_condition(condition: Condition) {
         let extern_html_root_takes_precedence =
             matches.opt_present("extern-html-root-takes-precedence");
         let html_no_source = matches.opt_present("html-no-source");
+        let api_diff_baseline = matches.opt_str("baseline").map(PathBuf::from);
+        let full_text_search = matches.opt_present("full-text-search");
+        match (output_format, &api_diff_baseline) {
+            (OutputFormat::ApiDiff, None) => {
+                dcx.fatal("the api-diff output format requires a `--baseline` JSON file");
+            }
+            (
+                OutputFormat::Json
+                | OutputFormat::Html
+                | OutputFormat::Markdown
+                | OutputFormat::Man,
+                Some(_),
+            ) => {
+                dcx.fatal("--baseline option can only be used with the api-diff output format");
+            }
+            _ => {}
+        }
+
+        let coverage_detail = matches.opt_present("coverage-detail");
+        let coverage_threshold = match matches.opt_str("coverage-threshold") {
+            Some(threshold) => match threshold.trim_end_matches('%').parse::<f64>() {
+                Ok(percentage) if (0.0..=100.0).contains(&percentage) => Some(percentage),
+                _ => dcx.fatal(format!(
+                    "--coverage-threshold must be a percentage between 0 and 100, found `{threshold}`"
+                )),
+            },
+            None => None,
+        };
+        if (coverage_detail || coverage_threshold.is_some()) && !show_coverage {
+            dcx.fatal(
+                "--coverage-detail and --coverage-threshold options can only be used with \
+                 --show-coverage",
+            );
+        }
 
         if generate_link_to_definition && (show_coverage || output_format != OutputFormat::Html) {
             dcx.fatal(
@@ -733,6 +830,13 @@ fn println_condition(condition: Condition) {
 
         let scrape_examples_options = ScrapeExamplesOptions::new(matches, &dcx);
         let with_examples = matches.opt_strs("with-examples");
+        let check_scraped_examples = matches.opt_present("check-scraped-examples");
+        if check_scraped_examples && with_examples.is_empty() && scrape_examples_options.is_none() {
+            dcx.fatal(
+                "--check-scraped-examples must be used with --with-examples or \
+                 --scrape-examples-output-path",
+            );
+        }
         let call_locations = crate::scrape_examples::load_call_locations(with_examples, &dcx);
 
         let unstable_features =
@@ -765,6 +869,8 @@ fn println_condition(condition: Condition) {
             crate_version,
             test_run_directory,
             persist_doctests,
+            doctest_cache_dir,
+            merge_doctests,
             runtool,
             runtool_args,
             enable_per_target_ignores,
@@ -777,6 +883,7 @@ fn println_condition(condition: Condition) {
             output_format,
             json_unused_externs,
             scrape_examples_options,
+            merge_docs,
             unstable_features,
             expanded_args: args,
         };
@@ -806,8 +913,13 @@ fn println_condition(condition: Condition) {
             emit,
             generate_link_to_definition,
             call_locations,
+            check_scraped_examples,
             no_emit_shared: false,
             html_no_source,
+            api_diff_baseline,
+            full_text_search,
+            coverage_detail,
+            coverage_threshold,
         };
         Some((options, render_options))
     }
@@ -346,6 +346,7 @@ pub(crate) fn run_global_ctxt(
         render_options,
         show_coverage,
     };
+    ctxt.cache.full_text_search = ctxt.render_options.full_text_search;
 
     for cnum in tcx.crates_including_speculative(()) {
         crate::visit_lib::lib_embargo_visit_item(&mut ctxt, cnum.as_def_id());
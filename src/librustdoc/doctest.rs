@@ -1,3 +1,6 @@
+mod cache;
+mod merged;
+
 use rustc_ast as ast;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::sync::Lrc;
@@ -38,6 +41,9 @@
 use crate::html::markdown::{self, ErrorCodes, Ignore, LangString};
 use crate::lint::init_lints;
 
+use self::cache::DoctestCache;
+use self::merged::MergedDoctests;
+
 /// Options that apply to all doctests in a crate or Markdown file (for `rustdoc foo.md`).
 #[derive(Clone, Default)]
 pub(crate) struct GlobalTestOptions {
@@ -370,11 +376,61 @@ fn wrapped_rustc_command(rustc_wrappers: &[PathBuf], rustc_binary: &Path) -> Com
     command
 }
 
+/// Creates the command compiling a doctest, with the flags shared by all doctests.
+fn rustc_command(
+    rustdoc_options: &IndividualTestOptions,
+    edition: Edition,
+    output_file: &Path,
+    supports_color: bool,
+) -> Command {
+    let rustc_binary = rustdoc_options
+        .test_builder
+        .as_deref()
+        .unwrap_or_else(|| rustc_interface::util::rustc_path().expect("found rustc"));
+    let mut compiler = wrapped_rustc_command(&rustdoc_options.test_builder_wrappers, rustc_binary);
+
+    compiler.arg(&format!("@{}", rustdoc_options.arg_file.display()));
+
+    if let Some(sysroot) = &rustdoc_options.maybe_sysroot {
+        compiler.arg(format!("--sysroot={}", sysroot.display()));
+    }
+
+    compiler.arg("--edition").arg(&edition.to_string());
+    compiler.arg("-o").arg(output_file);
+    compiler.arg("--target").arg(match &rustdoc_options.target {
+        TargetTriple::TargetTriple(s) => s.clone(),
+        TargetTriple::TargetJson { path_for_rustdoc, .. } => {
+            path_for_rustdoc.to_str().expect("target path must be valid unicode").to_string()
+        }
+    });
+    if let ErrorOutputType::HumanReadable(kind) = rustdoc_options.error_format {
+        let (short, color_config) = kind.unzip();
+
+        if short {
+            compiler.arg("--error-format").arg("short");
+        }
+
+        match color_config {
+            ColorConfig::Never => {
+                compiler.arg("--color").arg("never");
+            }
+            ColorConfig::Always => {
+                compiler.arg("--color").arg("always");
+            }
+            ColorConfig::Auto => {
+                compiler.arg("--color").arg(if supports_color { "always" } else { "never" });
+            }
+        }
+    }
+
+    compiler
+}
+
 fn run_test(
     test: &str,
     crate_name: &str,
     line: usize,
-    rustdoc_options: IndividualTestOptions,
+    rustdoc_options: &IndividualTestOptions,
     mut lang_string: LangString,
     no_run: bool,
     opts: &GlobalTestOptions,
@@ -395,22 +451,9 @@ fn run_test(
     let rust_out = add_exe_suffix("rust_out".to_owned(), &rustdoc_options.target);
     let output_file = rustdoc_options.outdir.path().join(rust_out);
 
-    let rustc_binary = rustdoc_options
-        .test_builder
-        .as_deref()
-        .unwrap_or_else(|| rustc_interface::util::rustc_path().expect("found rustc"));
-    let mut compiler = wrapped_rustc_command(&rustdoc_options.test_builder_wrappers, rustc_binary);
-
-    compiler.arg(&format!("@{}", rustdoc_options.arg_file.display()));
-
-    if let Some(sysroot) = &rustdoc_options.maybe_sysroot {
-        compiler.arg(format!("--sysroot={}", sysroot.display()));
-    }
-
-    compiler.arg("--edition").arg(&edition.to_string());
+    let mut compiler = rustc_command(rustdoc_options, edition, &output_file, supports_color);
     compiler.env("UNSTABLE_RUSTDOC_TEST_PATH", path);
     compiler.env("UNSTABLE_RUSTDOC_TEST_LINE", format!("{}", line as isize - line_offset as isize));
-    compiler.arg("-o").arg(&output_file);
     if lang_string.test_harness {
         compiler.arg("--test");
     }
@@ -424,31 +467,6 @@ fn run_test(
     if no_run && !lang_string.compile_fail && rustdoc_options.should_persist_doctests {
         compiler.arg("--emit=metadata");
     }
-    compiler.arg("--target").arg(match rustdoc_options.target {
-        TargetTriple::TargetTriple(s) => s,
-        TargetTriple::TargetJson { path_for_rustdoc, .. } => {
-            path_for_rustdoc.to_str().expect("target path must be valid unicode").to_string()
-        }
-    });
-    if let ErrorOutputType::HumanReadable(kind) = rustdoc_options.error_format {
-        let (short, color_config) = kind.unzip();
-
-        if short {
-            compiler.arg("--error-format").arg("short");
-        }
-
-        match color_config {
-            ColorConfig::Never => {
-                compiler.arg("--color").arg("never");
-            }
-            ColorConfig::Always => {
-                compiler.arg("--color").arg("always");
-            }
-            ColorConfig::Auto => {
-                compiler.arg("--color").arg(if supports_color { "always" } else { "never" });
-            }
-        }
-    }
 
     compiler.arg("-");
     compiler.stdin(Stdio::piped());
@@ -516,21 +534,32 @@ fn drop(&mut self) {
         return Ok(());
     }
 
+    run_test_binary(rustdoc_options, output_file, &lang_string, |_| {})
+}
+
+/// Runs a compiled doctest, letting `configure` add environment variables to the command.
+fn run_test_binary(
+    rustdoc_options: &IndividualTestOptions,
+    output_file: PathBuf,
+    lang_string: &LangString,
+    configure: impl FnOnce(&mut Command),
+) -> Result<(), TestFailure> {
     // Run the code!
     let mut cmd;
 
     let output_file = make_maybe_absolute_path(output_file);
-    if let Some(tool) = rustdoc_options.runtool {
+    if let Some(tool) = &rustdoc_options.runtool {
         let tool = make_maybe_absolute_path(tool.into());
         cmd = Command::new(tool);
-        cmd.args(rustdoc_options.runtool_args);
+        cmd.args(&rustdoc_options.runtool_args);
         cmd.arg(output_file);
     } else {
         cmd = Command::new(output_file);
     }
-    if let Some(run_directory) = rustdoc_options.test_run_directory {
+    if let Some(run_directory) = &rustdoc_options.test_run_directory {
         cmd.current_dir(run_directory);
     }
+    configure(&mut cmd);
 
     let result = if rustdoc_options.nocapture {
         cmd.status().map(|status| process::Output {
@@ -569,6 +598,26 @@ fn make_maybe_absolute_path(path: PathBuf) -> PathBuf {
     }
 }
 
+/// Returns the crate attributes inserted at the top of every doctest, one per line.
+fn crate_attrs_prelude(opts: &GlobalTestOptions) -> String {
+    let mut prelude = String::new();
+
+    if opts.attrs.is_empty() {
+        // If there aren't any attributes supplied by #![doc(test(attr(...)))], then allow some
+        // lints that are commonly triggered in doctests. The crate-level test attributes are
+        // commonly used to make tests fail in case they trigger warnings, so having this there in
+        // that case may cause some tests to pass when they shouldn't have.
+        prelude.push_str("#![allow(unused)]\n");
+    }
+
+    // Next, any attributes that came from the crate root via #![doc(test(attr(...)))].
+    for attr in &opts.attrs {
+        prelude.push_str(&format!("#![{attr}]\n"));
+    }
+
+    prelude
+}
+
 /// Transforms a test into code that can be compiled into a Rust binary, and returns the number of
 /// lines before the test code begins as well as if the output stream supports colors or not.
 pub(crate) fn make_test(
@@ -581,25 +630,10 @@ pub(crate) fn make_test(
 ) -> (String, usize, bool) {
     let (crate_attrs, everything_else, crates) = partition_source(s, edition);
     let everything_else = everything_else.trim();
-    let mut line_offset = 0;
-    let mut prog = String::new();
+    let mut prog = crate_attrs_prelude(opts);
+    let mut line_offset = prog.lines().count();
     let mut supports_color = false;
 
-    if opts.attrs.is_empty() {
-        // If there aren't any attributes supplied by #![doc(test(attr(...)))], then allow some
-        // lints that are commonly triggered in doctests. The crate-level test attributes are
-        // commonly used to make tests fail in case they trigger warnings, so having this there in
-        // that case may cause some tests to pass when they shouldn't have.
-        prog.push_str("#![allow(unused)]\n");
-        line_offset += 1;
-    }
-
-    // Next, any attributes that came from the crate root via #![doc(test(attr(...)))].
-    for attr in &opts.attrs {
-        prog.push_str(&format!("#![{attr}]\n"));
-        line_offset += 1;
-    }
-
     // Now push any outer attributes from the example, assuming they
     // are intended to be crate attributes.
     prog.push_str(&crate_attrs);
@@ -1029,6 +1063,8 @@ pub(crate) struct Collector {
     unused_extern_reports: Arc<Mutex<Vec<UnusedExterns>>>,
     compiling_test_count: AtomicUsize,
     arg_file: PathBuf,
+    cache: Option<Arc<DoctestCache>>,
+    merged: Option<Arc<MergedDoctests>>,
 }
 
 impl Collector {
@@ -1042,6 +1078,34 @@ pub(crate) fn new(
         enable_per_target_ignores: bool,
         arg_file: PathBuf,
     ) -> Collector {
+        // Skipped and merged doctests don't report their unused externs.
+        let json_unused_externs = rustdoc_options.json_unused_externs.is_enabled();
+        let cache = match rustdoc_options.doctest_cache_dir {
+            Some(ref dir) if !json_unused_externs => {
+                match DoctestCache::new(dir, &rustdoc_options, &crate_name, &opts, &arg_file) {
+                    Ok(cache) => Some(Arc::new(cache)),
+                    Err(err) => {
+                        eprintln!("Couldn't use the doctest cache directory: {err}");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let merged = (rustdoc_options.merge_doctests && !json_unused_externs).then(|| {
+            let rustdoc_test_options = IndividualTestOptions::new(
+                &rustdoc_options,
+                &arg_file,
+                "merged_doctests".to_owned(),
+            );
+            Arc::new(MergedDoctests::new(
+                crate_name.clone(),
+                opts.clone(),
+                rustdoc_options.edition,
+                rustdoc_test_options,
+            ))
+        });
+
         Collector {
             tests: Vec::new(),
             names: Vec::new(),
@@ -1057,6 +1121,8 @@ pub(crate) fn new(
             unused_extern_reports: Default::default(),
             compiling_test_count: AtomicUsize::new(0),
             arg_file,
+            cache,
+            merged,
         }
     }
 
@@ -1139,15 +1205,30 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
         let rustdoc_test_options =
             IndividualTestOptions::new(&self.rustdoc_options, &self.arg_file, test_id);
 
+        let ignore = match config.ignore {
+            Ignore::All => true,
+            Ignore::None => false,
+            Ignore::Some(ref ignores) => ignores.iter().any(|s| target_str.contains(s)),
+        };
+        let cache = self.cache.clone().map(|cache| {
+            let key = cache.key(&test, &config, no_run, edition);
+            (cache, key)
+        });
+        let cached = cache.as_ref().is_some_and(|(cache, key)| cache.contains(key));
+        let merged = self
+            .merged
+            .clone()
+            .filter(|merged| !ignore && !cached && merged.accepts(&config, edition))
+            .map(|merged| {
+                let index = merged.add(test.clone());
+                (merged, index)
+            });
+
         debug!("creating test {name}: {test}");
         self.tests.push(test::TestDescAndFn {
             desc: test::TestDesc {
                 name: test::DynTestName(name),
-                ignore: match config.ignore {
-                    Ignore::All => true,
-                    Ignore::None => false,
-                    Ignore::Some(ref ignores) => ignores.iter().any(|s| target_str.contains(s)),
-                },
+                ignore,
                 ignore_message: None,
                 source_file: "",
                 start_line: 0,
@@ -1164,18 +1245,33 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
                 let report_unused_externs = |uext| {
                     unused_externs.lock().unwrap().push(uext);
                 };
-                let res = run_test(
-                    &test,
-                    &crate_name,
-                    line,
-                    rustdoc_test_options,
-                    config,
-                    no_run,
-                    &opts,
-                    edition,
-                    path,
-                    report_unused_externs,
-                );
+                let merged_res = merged
+                    .as_ref()
+                    .and_then(|(merged, index)| merged.run(*index, &config, no_run));
+                let res = if cached {
+                    Ok(())
+                } else if let Some(res) = merged_res {
+                    res
+                } else {
+                    run_test(
+                        &test,
+                        &crate_name,
+                        line,
+                        &rustdoc_test_options,
+                        config,
+                        no_run,
+                        &opts,
+                        edition,
+                        path,
+                        report_unused_externs,
+                    )
+                };
+                if res.is_ok()
+                    && !cached
+                    && let Some((cache, key)) = &cache
+                {
+                    cache.insert(key);
+                }
 
                 if let Err(err) = res {
                     match err {
@@ -1324,7 +1420,7 @@ fn visit_testable<F: FnOnce(&mut Self)>(
 
         // The collapse-docs pass won't combine sugared/raw doc attributes, or included files with
         // anything else, this will combine them for us.
-        let attrs = Attributes::from_ast(ast_attrs);
+        let attrs = Attributes::from_ast(ast_attrs, self.tcx.sess);
         if let Some(doc) = attrs.opt_doc_value() {
             // Use the outermost invocation, so that doctest names come from where the docs were written.
             let span = ast_attrs
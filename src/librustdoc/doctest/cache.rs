@@ -0,0 +1,103 @@
+//! Remembering which doctests passed, enabled with `--doctest-cache-dir`.
+//!
+//! Every doctest gets a key hashing its code, its code block attributes and everything that
+//! changes the way it is compiled and run: the rustdoc version, the compiler flags, the
+//! `#![doc(test(...))]` attributes of the crate, and the contents of the crates passed with
+//! `--extern`, which is how a change to the documented crate is noticed. Their modification time
+//! isn't enough: build systems and `touch` can change it without changing the crate, and a crate
+//! rebuilt within the granularity of the file system keeps it.
+//! When a doctest passes, an empty file named after its key is created in the cache directory,
+//! and the doctest is skipped as long as that file exists.
+//!
+//! Failures are never cached, so a failing doctest always runs and reports its output.
+
+#[cfg(test)]
+mod tests;
+
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustc_data_structures::stable_hasher::{Hash128, StableHasher};
+use rustc_span::edition::Edition;
+
+use super::GlobalTestOptions;
+use crate::config::Options as RustdocOptions;
+use crate::html::markdown::LangString;
+
+pub(crate) struct DoctestCache {
+    dir: PathBuf,
+    /// The hash of everything that affects all the doctests of the crate.
+    crate_hash: Hash128,
+}
+
+impl DoctestCache {
+    pub(crate) fn new(
+        dir: &Path,
+        options: &RustdocOptions,
+        crate_name: &str,
+        opts: &GlobalTestOptions,
+        arg_file: &Path,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut hasher = StableHasher::new();
+        rustc_interface::util::rustc_version_str().hash(&mut hasher);
+        crate_name.hash(&mut hasher);
+        opts.no_crate_inject.hash(&mut hasher);
+        opts.attrs.hash(&mut hasher);
+        // The arguments file holds the cfgs, the library paths, the externs and the
+        // codegen and unstable options.
+        fs::read(arg_file)?.hash(&mut hasher);
+        for extern_str in &options.extern_strs {
+            if let Some((_, path)) = extern_str.split_once('=')
+                && let Ok(contents) = fs::read(path)
+            {
+                contents.hash(&mut hasher);
+            }
+        }
+        options.maybe_sysroot.hash(&mut hasher);
+        options.target.to_string().hash(&mut hasher);
+        options.test_builder.hash(&mut hasher);
+        options.test_builder_wrappers.hash(&mut hasher);
+        options.runtool.hash(&mut hasher);
+        options.runtool_args.hash(&mut hasher);
+        options.test_run_directory.hash(&mut hasher);
+
+        Ok(DoctestCache { dir: dir.to_owned(), crate_hash: hasher.finish() })
+    }
+
+    /// Returns the key of a doctest, given its code as written in the documentation.
+    pub(crate) fn key(
+        &self,
+        test: &str,
+        lang_string: &LangString,
+        no_run: bool,
+        edition: Edition,
+    ) -> String {
+        let mut hasher = StableHasher::new();
+        self.crate_hash.as_u128().hash(&mut hasher);
+        test.hash(&mut hasher);
+        lang_string.should_panic.hash(&mut hasher);
+        lang_string.test_harness.hash(&mut hasher);
+        lang_string.compile_fail.hash(&mut hasher);
+        lang_string.error_codes.hash(&mut hasher);
+        no_run.hash(&mut hasher);
+        edition.to_string().hash(&mut hasher);
+        format!("{:x}", hasher.finish::<Hash128>())
+    }
+
+    /// Whether the doctest with this key passed before.
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.dir.join(key).exists()
+    }
+
+    /// Remembers that the doctest with this key passed.
+    pub(crate) fn insert(&self, key: &str) {
+        // The cache is only an optimization, failing to write to it is not an error.
+        if let Err(err) = File::create(self.dir.join(key)) {
+            debug!("failed to cache doctest result: {err}");
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::hash::Hash;
+use std::path::PathBuf;
+
+use rustc_data_structures::stable_hasher::StableHasher;
+use rustc_span::edition::Edition;
+
+use super::DoctestCache;
+use crate::html::markdown::LangString;
+
+fn cache(crate_hash: u64) -> DoctestCache {
+    let mut hasher = StableHasher::new();
+    crate_hash.hash(&mut hasher);
+    DoctestCache { dir: PathBuf::new(), crate_hash: hasher.finish() }
+}
+
+const TEST: &str = "assert_eq!(1 + 1, 2);";
+
+#[test]
+fn key_is_stable() {
+    let key = cache(0).key(TEST, &LangString::default(), false, Edition::Edition2021);
+    assert_eq!(key, cache(0).key(TEST, &LangString::default(), false, Edition::Edition2021));
+    assert!(key.chars().all(|c| c.is_ascii_hexdigit()), "{key}");
+}
+
+#[test]
+fn key_changes() {
+    let default = LangString::default();
+    let should_panic = LangString { should_panic: true, ..LangString::default() };
+    let test_harness = LangString { test_harness: true, ..LangString::default() };
+    let compile_fail = LangString { compile_fail: true, ..LangString::default() };
+    let error_codes = LangString { error_codes: vec!["E0308".to_owned()], ..LangString::default() };
+    let mut keys = vec![
+        cache(0).key(TEST, &default, false, Edition::Edition2021),
+        cache(0).key("assert_eq!(1 + 1, 3);", &default, false, Edition::Edition2021),
+        cache(0).key(TEST, &should_panic, false, Edition::Edition2021),
+        cache(0).key(TEST, &test_harness, false, Edition::Edition2021),
+        cache(0).key(TEST, &compile_fail, false, Edition::Edition2021),
+        cache(0).key(TEST, &error_codes, false, Edition::Edition2021),
+        cache(0).key(TEST, &default, true, Edition::Edition2021),
+        cache(0).key(TEST, &default, false, Edition::Edition2018),
+        cache(1).key(TEST, &default, false, Edition::Edition2021),
+    ];
+    let len = keys.len();
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys.len(), len, "{keys:?}");
+}
+
+#[test]
+fn key_ignores_rendering_attributes() {
+    let lang_string = LangString {
+        original: "rust,custom".to_owned(),
+        added_classes: vec!["custom".to_owned()],
+        ..LangString::default()
+    };
+    assert_eq!(
+        cache(0).key(TEST, &lang_string, false, Edition::Edition2021),
+        cache(0).key(TEST, &LangString::default(), false, Edition::Edition2021),
+    );
+}
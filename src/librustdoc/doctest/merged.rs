@@ -0,0 +1,216 @@
+//! Compiling doctests into a single binary, enabled with `--merge-doctests`.
+//!
+//! Most of the time spent running the doctests of a crate goes into compiling and linking one
+//! binary per doctest. Instead, each doctest can be wrapped in a module of a single crate, whose
+//! `main` function runs the doctest named by an environment variable. That crate is compiled
+//! when the first doctest runs, and every doctest still runs in its own process, so its result
+//! is reported on its own.
+//!
+//! Doctests which wouldn't compile once moved into a module (crate attributes, exported
+//! symbols and macros, paths relative to the crate root) and doctests marked `standalone`
+//! are compiled on their own. So are the doctests of a merged crate which fails to compile,
+//! and the merged doctests which fail, so that failures are reported exactly as they would be
+//! without merging.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use rustc_span::edition::Edition;
+
+use super::{
+    add_exe_suffix, crate_attrs_prelude, make_test, partition_source, run_test_binary,
+    rustc_command, GlobalTestOptions, IndividualTestOptions, TestFailure,
+};
+use crate::html::markdown::LangString;
+
+/// The environment variable naming the doctest run by the merged binary.
+const DOCTEST_ENV_VAR: &str = "__RUSTDOC_MERGED_DOCTEST";
+
+pub(crate) struct MergedDoctests {
+    crate_name: String,
+    opts: GlobalTestOptions,
+    edition: Edition,
+    rustdoc_options: IndividualTestOptions,
+    /// The code of the doctests, as written in the documentation.
+    tests: Mutex<Vec<String>>,
+    /// The merged binary and which doctests it contains, or `None` if it failed to compile.
+    compiled: OnceLock<Option<(PathBuf, Vec<bool>)>>,
+}
+
+impl MergedDoctests {
+    pub(crate) fn new(
+        crate_name: String,
+        opts: GlobalTestOptions,
+        edition: Edition,
+        rustdoc_options: IndividualTestOptions,
+    ) -> Self {
+        MergedDoctests {
+            crate_name,
+            opts,
+            edition,
+            rustdoc_options,
+            tests: Mutex::new(Vec::new()),
+            compiled: OnceLock::new(),
+        }
+    }
+
+    /// Whether a doctest with these attributes may be merged with the others.
+    pub(crate) fn accepts(&self, lang_string: &LangString, edition: Edition) -> bool {
+        !lang_string.standalone
+            && !lang_string.compile_fail
+            && !lang_string.test_harness
+            // Paths are resolved differently inside of modules in the 2015 edition.
+            && edition == self.edition
+            && edition >= Edition::Edition2018
+    }
+
+    /// Adds a doctest to the merged binary, returning its index.
+    pub(crate) fn add(&self, test: String) -> usize {
+        let mut tests = self.tests.lock().unwrap();
+        tests.push(test);
+        tests.len() - 1
+    }
+
+    /// Runs a doctest from the merged binary, compiling it first if needed.
+    ///
+    /// Returns `None` if the doctest must be compiled and run on its own instead.
+    pub(crate) fn run(
+        &self,
+        index: usize,
+        lang_string: &LangString,
+        no_run: bool,
+    ) -> Option<Result<(), TestFailure>> {
+        let (binary, merged) = self.compiled.get_or_init(|| self.compile()).as_ref()?;
+        if !merged[index] {
+            return None;
+        }
+        if no_run {
+            return Some(Ok(()));
+        }
+        match run_test_binary(&self.rustdoc_options, binary.clone(), lang_string, |cmd| {
+            cmd.env(DOCTEST_ENV_VAR, index.to_string());
+        }) {
+            // Running the doctest again on its own gives the line numbers of the documentation
+            // in the output, but with `--nocapture` the output was already printed.
+            Err(_) if !self.rustdoc_options.nocapture => None,
+            res => Some(res),
+        }
+    }
+
+    fn compile(&self) -> Option<(PathBuf, Vec<bool>)> {
+        let tests = self.tests.lock().unwrap();
+        let (source, merged) = merged_source(&self.crate_name, &self.opts, self.edition, &tests);
+        if !merged.contains(&true) {
+            return None;
+        }
+
+        let outdir = self.rustdoc_options.outdir.path();
+        let source_file = outdir.join("merged_doctests.rs");
+        if let Err(err) = fs::write(&source_file, source) {
+            debug!("failed to write merged doctests: {err}");
+            return None;
+        }
+        let rust_out = add_exe_suffix("rust_out".to_owned(), &self.rustdoc_options.target);
+        let output_file = outdir.join(rust_out);
+
+        let mut compiler = rustc_command(&self.rustdoc_options, self.edition, &output_file, false);
+        compiler.arg(&source_file);
+
+        debug!("compiler invocation for merged doctests: {compiler:?}");
+
+        match compiler.output() {
+            Ok(output) if output.status.success() => Some((output_file, merged)),
+            Ok(output) => {
+                debug!(
+                    "merged doctests failed to compile:\n{}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                None
+            }
+            Err(err) => {
+                debug!("failed to run the compiler on merged doctests: {err}");
+                None
+            }
+        }
+    }
+}
+
+/// Whether a doctest still compiles once moved into a module.
+fn can_merge(test: &str, edition: Edition) -> bool {
+    let (crate_attrs, _, _) = partition_source(test, edition);
+    if crate_attrs.contains("#![") {
+        return false;
+    }
+    // `#[macro_use] extern crate` only works at the crate root, and exported symbols or macros
+    // would clash with the ones of other doctests.
+    let attrs = [
+        "macro_use",
+        "macro_export",
+        "no_mangle",
+        "export_name",
+        "global_allocator",
+        "panic_handler",
+    ];
+    if attrs.iter().any(|attr| test.contains(attr)) {
+        return false;
+    }
+    // `crate::` and `$crate::` would refer to the merged crate.
+    test.match_indices("crate::")
+        .all(|(i, _)| test[..i].ends_with(|c: char| c == '_' || c.is_alphanumeric()))
+}
+
+/// Returns the source of the merged binary, and which of the doctests it contains.
+pub(crate) fn merged_source(
+    crate_name: &str,
+    opts: &GlobalTestOptions,
+    edition: Edition,
+    tests: &[String],
+) -> (String, Vec<bool>) {
+    let prelude = crate_attrs_prelude(opts);
+    let mut source = prelude.clone();
+    let mut arms = String::new();
+    let mut merged = Vec::with_capacity(tests.len());
+
+    for (index, test) in tests.iter().enumerate() {
+        let code = if can_merge(test, edition) {
+            let (code, _, _) = make_test(test, Some(crate_name), false, opts, edition, None);
+            // The crate attributes are only written once, at the top of the merged crate.
+            code.strip_prefix(&prelude).map(str::to_owned)
+        } else {
+            None
+        };
+        let Some(code) = code else {
+            merged.push(false);
+            continue;
+        };
+        merged.push(true);
+
+        write!(
+            source,
+            "mod __doctest_{index} {{\n{code}\n\
+             pub(crate) fn __main() -> impl std::process::Termination {{ main() }}\n}}\n",
+        )
+        .unwrap();
+        writeln!(
+            arms,
+            "        \"{index}\" => std::process::Termination::report(__doctest_{index}::__main()),",
+        )
+        .unwrap();
+    }
+
+    write!(
+        source,
+        "fn main() -> std::process::ExitCode {{\n    \
+             match std::env::var(\"{DOCTEST_ENV_VAR}\").as_deref() {{\n\
+             {arms}        _ => panic!(\"unknown doctest\"),\n    \
+             }}\n\
+         }}\n",
+    )
+    .unwrap();
+
+    debug!("merged doctests:\n{source}");
+
+    (source, merged)
+}
@@ -1,5 +1,6 @@
+use super::merged::merged_source;
 use super::{make_test, GlobalTestOptions};
-use rustc_span::edition::DEFAULT_EDITION;
+use rustc_span::edition::{Edition, DEFAULT_EDITION};
 
 #[test]
 fn make_test_basic() {
@@ -344,3 +345,60 @@ fn main() {
     let (output, len, _) = make_test(input, None, false, &opts, DEFAULT_EDITION, None);
     assert_eq!((output, len), (expected, 1));
 }
+
+#[test]
+fn merged_source_wraps_tests_in_modules() {
+    // each test gets its own module, and `main` runs the test named by an environment variable
+    let opts = GlobalTestOptions::default();
+    let tests = vec![
+        "assert_eq!(2+2, 4);".to_string(),
+        "#![feature(sick_rad)]
+assert_eq!(2+2, 4);"
+            .to_string(),
+        "fn main() -> Result<(), ()> {
+    Ok(())
+}"
+        .to_string(),
+    ];
+    let expected = "#![allow(unused)]
+mod __doctest_0 {
+fn main() {
+assert_eq!(2+2, 4);
+}
+pub(crate) fn __main() -> impl std::process::Termination { main() }
+}
+mod __doctest_2 {
+fn main() -> Result<(), ()> {
+    Ok(())
+}
+pub(crate) fn __main() -> impl std::process::Termination { main() }
+}
+fn main() -> std::process::ExitCode {
+    match std::env::var(\"__RUSTDOC_MERGED_DOCTEST\").as_deref() {
+        \"0\" => std::process::Termination::report(__doctest_0::__main()),
+        \"2\" => std::process::Termination::report(__doctest_2::__main()),
+        _ => panic!(\"unknown doctest\"),
+    }
+}
+";
+    let (output, merged) = merged_source("asdf", &opts, Edition::Edition2021, &tests);
+    assert_eq!((output.as_str(), merged), (expected, vec![true, false, true]));
+}
+
+#[test]
+fn merged_source_skips_unmergeable_tests() {
+    // tests relying on being at the crate root are compiled on their own
+    let opts = GlobalTestOptions::default();
+    let tests = vec![
+        "#[macro_export]
+macro_rules! m { () => {} }"
+            .to_string(),
+        "fn f() {}
+use crate::f;"
+            .to_string(),
+        "macro_rules! m { () => { $crate::f() } }".to_string(),
+        "asdf::f();".to_string(),
+    ];
+    let (_, merged) = merged_source("asdf", &opts, Edition::Edition2021, &tests);
+    assert_eq!(merged, [false, false, false, true]);
+}
@@ -12,6 +12,7 @@
 use crate::formats::Impl;
 use crate::html::format::join_with_double_colon;
 use crate::html::markdown::short_markdown_summary;
+use crate::html::render::search_index::full_text::doc_terms;
 use crate::html::render::search_index::get_function_type_for_search;
 use crate::html::render::IndexItem;
 use crate::visit_lib::RustdocEffectiveVisibilities;
@@ -89,6 +90,8 @@ pub(crate) struct Cache {
     /// Whether to document hidden items.
     /// This is stored in `Cache` so it doesn't need to be passed through all rustdoc functions.
     pub(crate) document_hidden: bool,
+    /// Whether to index the text of doc comments for the search (`--full-text-search`).
+    pub(crate) full_text_search: bool,
 
     /// Crates marked with [`#[doc(masked)]`][doc_masked].
     ///
@@ -384,6 +387,11 @@ fn is_from_private_dep(tcx: TyCtxt<'_>, cache: &Cache, def_id: DefId) -> bool {
                             ),
                             aliases: item.attrs.get_doc_aliases(),
                             deprecation: item.deprecation(self.tcx),
+                            doc_terms: if self.cache.full_text_search {
+                                doc_terms(&item.doc_value())
+                            } else {
+                                Vec::new()
+                            },
                         });
                     }
                 }
@@ -182,6 +182,21 @@ fn map_line(s: &str) -> Line<'_> {
     }
 }
 
+/// Returns the code of a code block as it is shown in the documentation: the lines hidden with `#`
+/// are removed from Rust code blocks, and other code blocks are kept as they are.
+pub(crate) fn visible_code(kind: &CodeBlockKind<'_>, code: &str) -> String {
+    let rust = match kind {
+        CodeBlockKind::Fenced(lang) => {
+            LangString::parse_without_check(lang, ErrorCodes::No, false, false).rust
+        }
+        CodeBlockKind::Indented => true,
+    };
+    if !rust {
+        return code.to_owned();
+    }
+    code.lines().filter_map(|l| map_line(l).for_html()).intersperse("\n".into()).collect()
+}
+
 /// Convert chars from a title for an id.
 ///
 /// "Hello, world!" -> "hello-world"
@@ -869,6 +884,7 @@ pub(crate) struct LangString {
     pub(crate) rust: bool,
     pub(crate) test_harness: bool,
     pub(crate) compile_fail: bool,
+    pub(crate) standalone: bool,
     pub(crate) error_codes: Vec<String>,
     pub(crate) edition: Option<Edition>,
     pub(crate) added_classes: Vec<String>,
@@ -1203,6 +1219,7 @@ fn default() -> Self {
             rust: true,
             test_harness: false,
             compile_fail: false,
+            standalone: false,
             error_codes: Vec::new(),
             edition: None,
             added_classes: Vec::new(),
@@ -1284,6 +1301,10 @@ fn parse(
                         seen_rust_tags = !seen_other_tags || seen_rust_tags;
                         data.no_run = true;
                     }
+                    LangStringToken::LangToken("standalone") => {
+                        data.standalone = true;
+                        seen_rust_tags = !seen_other_tags || seen_rust_tags;
+                    }
                     LangStringToken::LangToken(x) if x.starts_with("edition") => {
                         data.edition = x[7..].parse::<Edition>().ok();
                     }
@@ -133,6 +133,8 @@ pub(crate) struct SharedContext<'tcx> {
     pub(crate) cache: Cache,
 
     pub(crate) call_locations: AllCallLocations,
+    /// Whether to mark the stale scraped examples, see `--check-scraped-examples`.
+    pub(crate) check_scraped_examples: bool,
 }
 
 impl SharedContext<'_> {
@@ -467,6 +469,7 @@ fn init(
             show_type_layout,
             generate_link_to_definition,
             call_locations,
+            check_scraped_examples,
             no_emit_shared,
             html_no_source,
             ..
@@ -554,6 +557,7 @@ fn init(
             span_correspondence_map: matches,
             cache,
             call_locations,
+            check_scraped_examples,
         };
 
         let dst = output;
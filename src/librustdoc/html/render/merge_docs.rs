@@ -0,0 +1,179 @@
+//! Merging documentation built by separate rustdoc invocations, enabled with `--merge-docs`.
+//!
+//! Crates documented into the same `--out-dir` share a search index, a list of crates and the
+//! lists of implementors of their traits, which `write_shared` updates as each crate is
+//! documented. When crates are documented into separate directories, for instance by separate
+//! CI jobs, `--merge-docs` copies these directories into one and merges their shared files, as
+//! if the crates had been documented together. When a crate is documented in more than one of
+//! the directories, the last one wins. The output directory can be one of the directories to
+//! merge, in which case it wins over the directories before it, but not over the ones after it.
+//!
+//! The directories must have been generated by the same version of rustdoc, with the same
+//! `--resource-suffix`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::write_shared::{
+    collect, collect_json, crate_list_html, crates_js, implementors_js, search_index_js,
+    src_files_js, type_impls_js,
+};
+use crate::docfs::PathError;
+use crate::error::Error;
+use crate::html::static_files::suffix_path;
+use crate::try_err;
+
+#[cfg(test)]
+mod tests;
+
+/// The entries of each crate in a shared file.
+type CrateEntries = BTreeMap<String, String>;
+
+#[derive(Default)]
+struct MergedDocs {
+    search_index: CrateEntries,
+    src_files: CrateEntries,
+    /// The files of the `trait.impl` and `type.impl` directories, by path.
+    impls: BTreeMap<PathBuf, CrateEntries>,
+    /// The "Index of crates" page, if one of the directories has it.
+    index_page: Option<String>,
+}
+
+pub(crate) fn merge_docs(
+    inputs: &[PathBuf],
+    output: &Path,
+    resource_suffix: &str,
+) -> Result<(), Error> {
+    let search_index_js_name = suffix_path("search-index.js", resource_suffix);
+    let src_files_js_name = suffix_path("src-files.js", resource_suffix);
+    let crates_js_name = suffix_path("crates.js", resource_suffix);
+
+    let mut merged = MergedDocs::default();
+    try_err!(fs::create_dir_all(output), output);
+    let output_canonical = try_err!(output.canonicalize(), output);
+
+    // The files are listed before anything is copied to the output directory, which may be one of
+    // the directories to merge.
+    let mut listed = Vec::with_capacity(inputs.len());
+    let mut output_position = None;
+    for (i, input) in inputs.iter().enumerate() {
+        if !input.is_dir() {
+            return Err(Error::new("not a documentation directory", input));
+        }
+        if try_err!(input.canonicalize(), input) == output_canonical {
+            output_position = Some(i);
+        }
+        listed.push(try_err!(files(input), input));
+    }
+    // The files the output directory had may only be replaced by the directories after it.
+    let output_files: BTreeSet<&Path> = match output_position {
+        Some(position) => listed[position].iter().map(|(_, relative)| relative.as_path()).collect(),
+        None => BTreeSet::new(),
+    };
+
+    for (i, files) in listed.iter().enumerate() {
+        for (path, relative) in files {
+            let (path, relative) = (path.as_path(), relative.as_path());
+            let copy = match output_position {
+                Some(position) if i == position => false,
+                Some(position) if i < position => !output_files.contains(relative),
+                _ => true,
+            };
+            let entries = if relative == search_index_js_name {
+                Some((&mut merged.search_index, try_err!(collect_json(path, None), path)))
+            } else if relative == src_files_js_name {
+                Some((&mut merged.src_files, try_err!(collect_json(path, None), path)))
+            } else if relative.starts_with("trait.impl") || relative.starts_with("type.impl") {
+                let entries = merged.impls.entry(relative.to_owned()).or_default();
+                Some((entries, try_err!(collect(path, None), path)))
+            } else {
+                None
+            };
+
+            if let Some((entries, (data, krates))) = entries {
+                entries.extend(krates.into_iter().zip(data));
+            } else if relative == Path::new("index.html") {
+                merged.index_page = Some(try_err!(fs::read_to_string(path), path));
+            } else if relative == crates_js_name || relative == Path::new(".lock") {
+                // The list of crates is generated from the search index.
+            } else if copy {
+                let dst = output.join(relative);
+                if let Some(parent) = dst.parent() {
+                    try_err!(fs::create_dir_all(parent), parent);
+                }
+                try_err!(fs::copy(path, &dst), &dst);
+            }
+        }
+    }
+
+    let write = |relative: &Path, content: String| -> Result<(), Error> {
+        let dst = output.join(relative);
+        if let Some(parent) = dst.parent() {
+            try_err!(fs::create_dir_all(parent), parent);
+        }
+        try_err!(fs::write(&dst, content), &dst);
+        Ok(())
+    };
+
+    // The entries are sorted like `write_shared` does, so that merging gives the same files as
+    // documenting all the crates into the same directory.
+    let sorted = |entries: CrateEntries| {
+        let mut entries: Vec<String> = entries.into_values().collect();
+        entries.sort();
+        entries
+    };
+
+    let krates: Vec<String> = merged.search_index.keys().cloned().collect();
+    write(&search_index_js_name, search_index_js(&sorted(merged.search_index)))?;
+    write(&crates_js_name, crates_js(&krates))?;
+    if !merged.src_files.is_empty() {
+        write(&src_files_js_name, src_files_js(&sorted(merged.src_files)))?;
+    }
+    for (relative, entries) in merged.impls {
+        let content = if relative.starts_with("trait.impl") {
+            implementors_js(&sorted(entries))
+        } else {
+            type_impls_js(&sorted(entries))
+        };
+        write(&relative, content)?;
+    }
+    if let Some(index_page) = merged.index_page {
+        write(Path::new("index.html"), merge_crate_list(&index_page, &krates))?;
+    }
+
+    Ok(())
+}
+
+/// Returns all the files of a directory and its subdirectories, with their path relative to it.
+fn files(dir: &Path) -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(current) = dirs.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                let relative = path.strip_prefix(dir).unwrap().to_owned();
+                files.push((path, relative));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Replaces the list of crates of a generated "Index of crates" page. Pages given with
+/// `--index-page` are left as they are.
+fn merge_crate_list(index_page: &str, krates: &[String]) -> String {
+    const START: &str = "<h1>List of all crates</h1>";
+    if let Some(start) = index_page.find(START)
+        && let Some(len) = index_page[start..].find("</ul>")
+    {
+        let end = start + len + "</ul>".len();
+        format!("{}{}{}", &index_page[..start], crate_list_html(krates), &index_page[end..])
+    } else {
+        index_page.to_owned()
+    }
+}
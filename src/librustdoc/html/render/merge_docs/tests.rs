@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::Path;
+
+use super::{merge_crate_list, merge_docs};
+use crate::html::render::write_shared::{
+    crate_list_html, crates_js, implementors_js, search_index_js,
+};
+
+fn write(dir: &Path, relative: &str, content: &str) {
+    let path = dir.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn read(dir: &Path, relative: &str) -> String {
+    fs::read_to_string(dir.join(relative)).unwrap()
+}
+
+/// Writes the shared files of a documentation directory with a single crate.
+fn document(dir: &Path, krate: &str, index: &str) {
+    write(dir, "search-index.js", &search_index_js(&[format!(r#"["{krate}",{index}]"#)]));
+    write(dir, "crates.js", &crates_js(&[krate.to_owned()]));
+    write(
+        dir,
+        "trait.impl/core/clone/trait.Clone.js",
+        &implementors_js(&[format!(r#""{krate}":[["impl Clone for {krate}::S"]]"#)]),
+    );
+    write(dir, &format!("{krate}/struct.S.html"), krate);
+}
+
+#[test]
+fn test_merge_docs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (a, b, out) = (tmp.path().join("a"), tmp.path().join("b"), tmp.path().join("out"));
+    document(&a, "foo", "{}");
+    document(&b, "bar", "{}");
+
+    merge_docs(&[a, b], &out, "").unwrap();
+
+    assert_eq!(
+        read(&out, "search-index.js"),
+        search_index_js(&[r#"["bar",{}]"#.to_owned(), r#"["foo",{}]"#.to_owned()]),
+    );
+    assert_eq!(read(&out, "crates.js"), crates_js(&["bar".to_owned(), "foo".to_owned()]));
+    assert_eq!(
+        read(&out, "trait.impl/core/clone/trait.Clone.js"),
+        implementors_js(&[
+            r#""bar":[["impl Clone for bar::S"]]"#.to_owned(),
+            r#""foo":[["impl Clone for foo::S"]]"#.to_owned(),
+        ]),
+    );
+    assert_eq!(read(&out, "foo/struct.S.html"), "foo");
+    assert_eq!(read(&out, "bar/struct.S.html"), "bar");
+}
+
+#[test]
+fn test_merge_docs_into_input() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+    document(&a, "foo", r#"{"old":1}"#);
+    document(&b, "foo", r#"{"new":1}"#);
+    write(&b, "foo/struct.S.html", "new");
+
+    merge_docs(&[a.clone(), b], &a, "").unwrap();
+
+    // The last directory wins.
+    assert_eq!(read(&a, "search-index.js"), search_index_js(&[r#"["foo",{"new":1}]"#.to_owned()]));
+    assert_eq!(read(&a, "crates.js"), crates_js(&["foo".to_owned()]));
+    assert_eq!(read(&a, "foo/struct.S.html"), "new");
+}
+
+#[test]
+fn test_merge_docs_into_last_input() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+    document(&a, "foo", r#"{"old":1}"#);
+    write(&a, "foo/struct.S.html", "old");
+    write(&a, "foo/struct.T.html", "old");
+    document(&b, "foo", r#"{"new":1}"#);
+    write(&b, "foo/struct.S.html", "new");
+
+    merge_docs(&[a, b.clone()], &b, "").unwrap();
+
+    // The output directory wins over the directories before it.
+    assert_eq!(read(&b, "search-index.js"), search_index_js(&[r#"["foo",{"new":1}]"#.to_owned()]));
+    assert_eq!(read(&b, "foo/struct.S.html"), "new");
+    assert_eq!(read(&b, "foo/struct.T.html"), "old");
+}
+
+#[test]
+fn test_merge_crate_list() {
+    let krates = ["bar".to_owned(), "foo".to_owned()];
+    let page = format!("<main>{}</main><ul>footer</ul>", crate_list_html(&["foo".to_owned()]));
+    assert_eq!(
+        merge_crate_list(&page, &krates),
+        format!("<main>{}</main><ul>footer</ul>", crate_list_html(&krates)),
+    );
+
+    // Pages given with `--index-page` are not changed.
+    let page = "<h1>My workspace</h1><ul><li>foo</li></ul>";
+    assert_eq!(merge_crate_list(page, &krates), page);
+}
@@ -29,6 +29,7 @@
 mod tests;
 
 mod context;
+mod merge_docs;
 mod print_item;
 mod sidebar;
 mod span_map;
@@ -36,6 +37,7 @@
 mod write_shared;
 
 pub(crate) use self::context::*;
+pub(crate) use self::merge_docs::merge_docs;
 pub(crate) use self::span_map::{collect_spans_and_sources, LinkFromSrc};
 
 use std::collections::VecDeque;
@@ -79,7 +81,7 @@
 };
 use crate::html::sources;
 use crate::html::static_files::SCRAPE_EXAMPLES_HELP_MD;
-use crate::scrape_examples::{CallData, CallLocation};
+use crate::scrape_examples::{fn_sig_hash, source_hash, CallData, CallLocation};
 use crate::try_none;
 use crate::DOC_RUST_LANG_ORG_CHANNEL;
 
@@ -123,6 +125,8 @@ pub(crate) struct IndexItem {
     pub(crate) search_type: Option<IndexItemFunctionType>,
     pub(crate) aliases: Box<[Symbol]>,
     pub(crate) deprecation: Option<Deprecation>,
+    /// The terms of the doc comment for the full-text index, empty unless it is enabled.
+    pub(crate) doc_terms: Vec<(String, u32)>,
 }
 
 /// A type used for the search index.
@@ -2172,7 +2176,7 @@ pub(crate) enum ItemSection {
 }
 
 impl ItemSection {
-    const ALL: &'static [Self] = {
+    pub(crate) const ALL: &'static [Self] = {
         use ItemSection::*;
         // NOTE: The order here affects the order in the UI.
         // Keep this synchronized with addSidebarItems in main.js
@@ -2235,7 +2239,7 @@ fn id(self) -> &'static str {
         }
     }
 
-    fn name(self) -> &'static str {
+    pub(crate) fn name(self) -> &'static str {
         match self {
             Self::Reexports => "Re-exports",
             Self::Modules => "Modules",
@@ -2266,7 +2270,7 @@ fn name(self) -> &'static str {
     }
 }
 
-fn item_ty_to_section(ty: ItemType) -> ItemSection {
+pub(crate) fn item_ty_to_section(ty: ItemType) -> ItemSection {
     match ty {
         ItemType::ExternCrate | ItemType::Import => ItemSection::Reexports,
         ItemType::Module => ItemSection::Modules,
@@ -2392,17 +2396,49 @@ fn render_call_locations<W: fmt::Write>(mut w: W, cx: &mut Context<'_>, item: &c
         (url, title)
     };
 
+    // With `--check-scraped-examples`, the examples are compared with the current signature of
+    // the function and the current source of the examples. Examples scraped without the flag
+    // don't have the hashes to compare with, and are shown as usual.
+    let current_fn_sig_hash = cx.shared.check_scraped_examples.then(|| fn_sig_hash(tcx, def_id));
+
     // Generate the HTML for a single example, being the title and code block
     let write_example = |mut w: &mut W, (path, call_data): (&PathBuf, &CallData)| -> bool {
+        let span = item.span(tcx).map_or(DUMMY_SP, |span| span.inner());
         let contents = match fs::read_to_string(&path) {
             Ok(contents) => contents,
             Err(err) => {
-                let span = item.span(tcx).map_or(DUMMY_SP, |span| span.inner());
                 tcx.dcx().span_err(span, format!("failed to read file {}: {err}", path.display()));
                 return false;
             }
         };
 
+        let mut stale = false;
+        if let Some(current_fn_sig_hash) = current_fn_sig_hash
+            && let (Some(scraped_source_hash), Some(scraped_fn_sig_hash)) =
+                (call_data.source_hash, call_data.fn_sig_hash)
+        {
+            // The locations of the calls can't be trusted in a file which changed, so its
+            // examples are left out rather than marked.
+            if source_hash(&contents) != scraped_source_hash {
+                tcx.dcx().span_warn(
+                    span,
+                    format!("{} changed since its examples were scraped", path.display()),
+                );
+                return false;
+            }
+            if current_fn_sig_hash != scraped_fn_sig_hash {
+                tcx.dcx().span_warn(
+                    span,
+                    format!(
+                        "the signature of this function changed since the examples of {} \
+                         were scraped",
+                        path.display()
+                    ),
+                );
+                stale = true;
+            }
+        }
+
         // To reduce file sizes, we only want to embed the source code needed to understand the example, not
         // the entire file. So we find the smallest byte range that covers all items enclosing examples.
         assert!(!call_data.locations.is_empty());
@@ -2439,14 +2475,36 @@ fn render_call_locations<W: fmt::Write>(mut w: W, cx: &mut Context<'_>, item: &c
         let needs_expansion = line_max - line_min > NUM_VISIBLE_LINES;
         let locations_encoded = serde_json::to_string(&line_ranges).unwrap();
 
+        // Name the test or example target the call site was compiled in.
+        let target = if cx.shared.check_scraped_examples {
+            let kind = if call_data.is_test {
+                "test"
+            } else if call_data.is_bin {
+                "example"
+            } else {
+                "crate"
+            };
+            format!(" in {kind} <code>{}</code>", Escape(&call_data.target))
+        } else {
+            String::new()
+        };
+        let stale_badge = if stale {
+            " <span class=\"stale-example\" \
+               title=\"The signature of this function changed since this example was scraped\">\
+               stale</span>"
+        } else {
+            ""
+        };
+
         write!(
             &mut w,
-            "<div class=\"scraped-example {expanded_cls}\" data-locs=\"{locations}\">\
+            "<div class=\"scraped-example {expanded_cls}{stale_cls}\" data-locs=\"{locations}\">\
                 <div class=\"scraped-example-title\">\
-                   {name} (<a href=\"{url}\">{title}</a>)\
+                   {name} (<a href=\"{url}\">{title}</a>){target}{stale_badge}\
                 </div>\
                 <div class=\"code-wrapper\">",
             expanded_cls = if needs_expansion { "" } else { "expanded" },
+            stale_cls = if stale { " stale" } else { "" },
             name = call_data.display_name,
             url = init_url,
             title = init_title,
@@ -1,4 +1,5 @@
 pub(crate) mod encode;
+pub(crate) mod full_text;
 
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, VecDeque};
@@ -20,6 +21,7 @@
 use crate::html::render::{self, IndexItem, IndexItemFunctionType, RenderType, RenderTypeId};
 
 use encode::{bitmap_to_string, write_vlqhex_to_string};
+use full_text::{build_full_text_index, doc_terms};
 
 /// The serialized search description sharded version
 ///
@@ -49,6 +51,8 @@
 pub(crate) struct SerializedSearchIndex {
     pub(crate) index: String,
     pub(crate) desc: Vec<(usize, String)>,
+    /// The JSON-encoded full-text index, if `--full-text-search` was passed.
+    pub(crate) full_text: Option<String>,
 }
 
 const DESC_INDEX_SHARD_LEN: usize = 128 * 1024;
@@ -92,6 +96,11 @@ pub(crate) fn build_index<'tcx>(
                 ),
                 aliases: item.attrs.get_doc_aliases(),
                 deprecation: item.deprecation(tcx),
+                doc_terms: if cache.full_text_search {
+                    doc_terms(&item.doc_value())
+                } else {
+                    Vec::new()
+                },
             });
         }
     }
@@ -442,6 +451,8 @@ struct CrateData<'a> {
         desc_index: String,
         // A list of items with no description. This is eventually turned into a bitmap.
         empty_desc: Vec<u32>,
+        // Whether a full-text index was emitted for this crate.
+        has_full_text: bool,
     }
 
     struct Paths {
@@ -622,8 +633,8 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             }
 
             let has_aliases = !self.aliases.is_empty();
-            let mut crate_data =
-                serializer.serialize_struct("CrateData", if has_aliases { 9 } else { 8 })?;
+            let len = (if has_aliases { 9 } else { 8 }) + usize::from(self.has_full_text);
+            let mut crate_data = serializer.serialize_struct("CrateData", len)?;
             crate_data.serialize_field("t", &types)?;
             crate_data.serialize_field("n", &names)?;
             crate_data.serialize_field("q", &full_paths)?;
@@ -638,6 +649,9 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             if has_aliases {
                 crate_data.serialize_field("a", &self.aliases)?;
             }
+            if self.has_full_text {
+                crate_data.serialize_field("x", &1)?;
+            }
             crate_data.end()
         }
     }
@@ -681,6 +695,14 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         desc.iter().map(|(len, _)| *len).sum::<usize>() + empty_desc.len()
     );
 
+    let full_text = cache.full_text_search.then(|| {
+        let crate_terms = doc_terms(&krate.module.doc_value());
+        build_full_text_index(
+            std::iter::once(&crate_terms[..])
+                .chain(crate_items.iter().map(|item| &item.doc_terms[..])),
+        )
+    });
+
     // The index, which is actually used to search, is JSON
     // It uses `JSON.parse(..)` to actually load, since JSON
     // parses faster than the full JavaScript syntax.
@@ -694,6 +716,7 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             associated_item_disambiguators: &associated_item_disambiguators,
             desc_index,
             empty_desc,
+            has_full_text: full_text.is_some(),
         })
         .expect("failed serde conversion")
         // All these `replace` calls are because we have to go through JS string for JSON content.
@@ -702,7 +725,7 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         // We need to escape double quotes for the JSON.
         .replace("\\\"", "\\\\\"")
     );
-    SerializedSearchIndex { index, desc }
+    SerializedSearchIndex { index, desc, full_text }
 }
 
 pub(crate) fn get_function_type_for_search<'tcx>(
@@ -0,0 +1,183 @@
+//! The full-text search index, enabled with `--full-text-search`.
+//!
+//! Doc comments are reduced to plain text, split into lowercase words, stripped of
+//! stop words and stemmed. Each crate then gets an inverted index from terms to the
+//! items whose documentation contains them, with a BM25 score precomputed for every
+//! (term, item) pair, so that `search.js` only has to add up the scores of the query terms.
+//!
+//! The tokenizer and the stemmer are duplicated in `search.js` (see `fullTextTerms`), and
+//! both sides must be kept in sync, otherwise queries won't match the indexed terms.
+
+use pulldown_cmark::{Event, Parser, Tag};
+use rustc_data_structures::fx::FxHashMap;
+use serde::Serialize;
+
+use crate::html::markdown::summary_opts;
+
+#[cfg(test)]
+mod tests;
+
+/// Words too common to be worth indexing.
+const STOP_WORDS: &[&str] = &[
+    "a", "also", "an", "and", "are", "as", "at", "be", "by", "can", "for", "from", "if", "in",
+    "into", "is", "it", "its", "no", "not", "of", "on", "or", "so", "than", "that", "the", "then",
+    "there", "these", "this", "to", "was", "we", "when", "which", "will", "with", "you", "your",
+];
+
+/// BM25 term frequency saturation.
+const K1: f64 = 1.2;
+/// BM25 document length normalization.
+const B: f64 = 0.75;
+
+/// Returns the stemmed terms of a doc comment, with the number of times they appear, sorted
+/// by term. Code blocks and raw HTML are not indexed, inline code is.
+pub(crate) fn doc_terms(doc: &str) -> Vec<(String, u32)> {
+    if doc.is_empty() {
+        return Vec::new();
+    }
+
+    let mut counts = FxHashMap::<String, u32>::default();
+    let mut in_code_block = false;
+    for event in Parser::new_ext(doc, summary_opts()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Text(text) | Event::Code(text) if !in_code_block => {
+                for term in terms(&text) {
+                    *counts.entry(term).or_insert(0) += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut terms: Vec<_> = counts.into_iter().collect();
+    terms.sort_unstable();
+    terms
+}
+
+/// Words are made of ASCII letters and digits, and of any other character outside of the
+/// Latin-1, general and CJK punctuation blocks. This is simpler to match in `search.js` than
+/// `char::is_alphanumeric`.
+fn is_word_char(c: char) -> bool {
+    match c {
+        '\u{80}'..='\u{bf}' | '\u{2000}'..='\u{206f}' | '\u{3000}'..='\u{303f}' => false,
+        _ => c.is_ascii_alphanumeric() || !c.is_ascii(),
+    }
+}
+
+/// Splits some plain text into the stemmed terms that are looked up in the index.
+pub(crate) fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !is_word_char(c))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stem(&word))
+        .filter(|term| term.chars().count() >= 2)
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// A deliberately small subset of the Porter stemmer: plurals, `-ed`/`-ing`, and final `y`
+/// and `e`. It only needs to map the usual inflections of a word to the same term.
+pub(crate) fn stem(word: &str) -> String {
+    // Only plain lowercase ASCII words are stemmed, other words are indexed as they are.
+    if word.len() < 4 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_owned();
+    }
+    let mut w = word.to_owned();
+
+    // Plurals.
+    if w.ends_with("sses") || w.ends_with("ies") {
+        w.truncate(w.len() - 2);
+    } else if w.ends_with('s') && !["ss", "us", "is"].iter().any(|s| w.ends_with(s)) {
+        w.pop();
+    }
+
+    // Past tenses and gerunds, as long as some vowel is left in the stem.
+    for suffix in ["ing", "ed"] {
+        if let Some(rest) = w.strip_suffix(suffix)
+            && rest.len() >= 3
+            && rest.chars().any(is_vowel)
+        {
+            w.truncate(rest.len());
+            let mut chars = w.chars().rev();
+            if let (Some(last), Some(prev)) = (chars.next(), chars.next())
+                && last == prev
+                && !is_vowel(last)
+                && !matches!(last, 'l' | 's' | 'z')
+            {
+                w.pop();
+            }
+            break;
+        }
+    }
+
+    // `retry`, `retries` and `retried` all become `retri`.
+    if w.len() > 2 && w.ends_with('y') && !w[..w.len() - 1].ends_with(is_vowel) {
+        w.pop();
+        w.push('i');
+    }
+
+    // `cache`, `caches` and `caching` all become `cach`.
+    if w.len() > 4 && w.ends_with('e') {
+        w.pop();
+    }
+
+    w
+}
+
+/// The serialized full-text index of a crate.
+#[derive(Serialize)]
+struct FullTextIndex<'a> {
+    /// The indexed terms, sorted.
+    t: Vec<&'a str>,
+    /// For each term, a flat list of `item, score` pairs, where `item` is the position of the
+    /// item in the search index (`0` being the crate itself), and `score` is its BM25 score
+    /// for the term, times 100.
+    p: Vec<Vec<u32>>,
+}
+
+/// Builds the full-text index of a crate from the terms of its items, given in the order
+/// of the search index, the crate itself first.
+pub(crate) fn build_full_text_index<'a>(
+    items: impl Iterator<Item = &'a [(String, u32)]>,
+) -> String {
+    let items: Vec<_> = items.collect();
+    let lengths: Vec<u32> =
+        items.iter().map(|terms| terms.iter().map(|&(_, count)| count).sum()).collect();
+    let documented = lengths.iter().filter(|&&len| len != 0).count().max(1);
+    let average_length = f64::from(lengths.iter().sum::<u32>()).max(1.) / documented as f64;
+
+    let mut postings: FxHashMap<&str, Vec<(u32, u32)>> = FxHashMap::default();
+    for (index, terms) in items.iter().enumerate() {
+        for (term, count) in terms.iter() {
+            postings.entry(term).or_default().push((index as u32, *count));
+        }
+    }
+
+    let mut terms: Vec<&str> = postings.keys().copied().collect();
+    terms.sort_unstable();
+    let p = terms
+        .iter()
+        .map(|term| {
+            let items = &postings[term];
+            let df = items.len() as f64;
+            let idf = (1. + (documented as f64 - df + 0.5) / (df + 0.5)).ln();
+            items
+                .iter()
+                .flat_map(|&(index, count)| {
+                    let tf = f64::from(count);
+                    let length = f64::from(lengths[index as usize]);
+                    let norm = K1 * (1. - B + B * length / average_length);
+                    let score = idf * tf * (K1 + 1.) / (tf + norm);
+                    [index, ((score * 100.).round() as u32).max(1)]
+                })
+                .collect()
+        })
+        .collect();
+
+    serde_json::to_string(&FullTextIndex { t: terms, p }).expect("failed serde conversion")
+}
@@ -0,0 +1,74 @@
+use super::{build_full_text_index, doc_terms, stem, terms};
+
+#[test]
+fn test_stem() {
+    for (word, expected) in [
+        ("retry", "retri"),
+        ("retries", "retri"),
+        ("retried", "retri"),
+        ("retrying", "retri"),
+        ("cache", "cach"),
+        ("caches", "cach"),
+        ("caching", "cach"),
+        ("setting", "set"),
+        ("classes", "class"),
+        ("status", "status"),
+        ("passed", "pass"),
+        ("key", "key"),
+        ("need", "need"),
+        ("größe", "größe"),
+    ] {
+        assert_eq!(stem(word), expected, "stem({word:?})");
+    }
+}
+
+#[test]
+fn test_terms() {
+    let terms: Vec<_> = terms("Sets the retry_policy of a Client, in seconds.").collect();
+    assert_eq!(terms, ["set", "retri", "polici", "client", "second"]);
+}
+
+#[test]
+fn test_doc_terms() {
+    let doc = "Retries the request.\n\
+               \n\
+               The `RetryPolicy` decides when to retry.\n\
+               \n\
+               ```\n\
+               let request = Request::new();\n\
+               ```";
+    assert_eq!(
+        doc_terms(doc),
+        [
+            ("decid".to_owned(), 1),
+            ("request".to_owned(), 1),
+            ("retri".to_owned(), 2),
+            ("retrypolici".to_owned(), 1),
+        ]
+    );
+    assert!(doc_terms("").is_empty());
+}
+
+#[test]
+fn test_build_full_text_index() {
+    let krate = doc_terms("A client.");
+    let retry = doc_terms("Retries the request with the retry policy.");
+    let send = doc_terms("Sends the request.");
+    let index = build_full_text_index([&krate[..], &[][..], &retry[..], &send[..]].into_iter());
+    let index: serde_json::Value = serde_json::from_str(&index).unwrap();
+
+    assert_eq!(index["t"], serde_json::json!(["client", "polici", "request", "retri", "send"]));
+    let postings = index["p"].as_array().unwrap();
+    // Items are numbered in the order they were given, the crate being `0`.
+    let items = |postings: &serde_json::Value| -> Vec<u64> {
+        postings.as_array().unwrap().chunks(2).map(|pair| pair[0].as_u64().unwrap()).collect()
+    };
+    assert_eq!(items(&postings[0]), [0]);
+    assert_eq!(items(&postings[2]), [2, 3]);
+    assert_eq!(items(&postings[3]), [2]);
+    // A term that appears in fewer items scores higher.
+    let score = |term: usize, pair: usize| postings[term][pair * 2 + 1].as_u64().unwrap();
+    assert!(score(3, 0) > score(2, 0));
+    // A shorter doc comment scores higher for the same term.
+    assert!(score(2, 1) > score(2, 0));
+}
@@ -104,97 +104,6 @@ pub(super) fn write_shared(
         })?;
     }
 
-    /// Read a file and return all lines that match the `"{crate}":{data},` format,
-    /// and return a tuple `(Vec<DataString>, Vec<CrateNameString>)`.
-    ///
-    /// This forms the payload of files that look like this:
-    ///
-    /// ```javascript
-    /// var data = {
-    /// "{crate1}":{data},
-    /// "{crate2}":{data}
-    /// };
-    /// use_data(data);
-    /// ```
-    ///
-    /// The file needs to be formatted so that *only crate data lines start with `"`*.
-    fn collect(path: &Path, krate: &str) -> io::Result<(Vec<String>, Vec<String>)> {
-        let mut ret = Vec::new();
-        let mut krates = Vec::new();
-
-        if path.exists() {
-            let prefix = format!("\"{krate}\"");
-            for line in BufReader::new(File::open(path)?).lines() {
-                let line = line?;
-                if !line.starts_with('"') {
-                    continue;
-                }
-                if line.starts_with(&prefix) {
-                    continue;
-                }
-                if line.ends_with(',') {
-                    ret.push(line[..line.len() - 1].to_string());
-                } else {
-                    // No comma (it's the case for the last added crate line)
-                    ret.push(line.to_string());
-                }
-                krates.push(
-                    line.split('"')
-                        .find(|s| !s.is_empty())
-                        .map(|s| s.to_owned())
-                        .unwrap_or_else(String::new),
-                );
-            }
-        }
-        Ok((ret, krates))
-    }
-
-    /// Read a file and return all lines that match the <code>"{crate}":{data},\ </code> format,
-    /// and return a tuple `(Vec<DataString>, Vec<CrateNameString>)`.
-    ///
-    /// This forms the payload of files that look like this:
-    ///
-    /// ```javascript
-    /// var data = JSON.parse('{\
-    /// "{crate1}":{data},\
-    /// "{crate2}":{data}\
-    /// }');
-    /// use_data(data);
-    /// ```
-    ///
-    /// The file needs to be formatted so that *only crate data lines start with `"`*.
-    fn collect_json(path: &Path, krate: &str) -> io::Result<(Vec<String>, Vec<String>)> {
-        let mut ret = Vec::new();
-        let mut krates = Vec::new();
-
-        if path.exists() {
-            let prefix = format!("[\"{krate}\"");
-            for line in BufReader::new(File::open(path)?).lines() {
-                let line = line?;
-                if !line.starts_with("[\"") {
-                    continue;
-                }
-                if line.starts_with(&prefix) {
-                    continue;
-                }
-                if line.ends_with("],\\") {
-                    ret.push(line[..line.len() - 2].to_string());
-                } else {
-                    // Ends with "\\" (it's the case for the last added crate line)
-                    ret.push(line[..line.len() - 1].to_string());
-                }
-                krates.push(
-                    line[1..] // We skip the `[` parent at the beginning of the line.
-                        .split('"')
-                        .find(|s| !s.is_empty())
-                        .map(|s| s.to_owned())
-                        .unwrap_or_else(String::new),
-                );
-            }
-        }
-        Ok((ret, krates))
-    }
-
     use std::ffi::OsString;
 
     #[derive(Debug, Default)]
@@ -285,7 +194,7 @@ fn add_path(self: &Rc<Self>, path: &Path) {
         let dst = cx.dst.join(&format!("src-files{}.js", cx.shared.resource_suffix));
         let make_sources = || {
             let (mut all_sources, _krates) =
-                try_err!(collect_json(&dst, krate.name(cx.tcx()).as_str()), &dst);
+                try_err!(collect_json(&dst, Some(krate.name(cx.tcx()).as_str())), &dst);
             all_sources.push(format!(
                 r#"["{}",{}]"#,
                 &krate.name(cx.tcx()),
@@ -298,13 +207,7 @@ fn add_path(self: &Rc<Self>, path: &Path) {
                     .replace("\\\"", "\\\\\"")
             ));
             all_sources.sort();
-            // This needs to be `var`, not `const`.
-            // This variable needs declared in the current global scope so that if
-            // src-script.js loads first, it can pick it up.
-            let mut v = String::from("var srcIndex = new Map(JSON.parse('[\\\n");
-            v.push_str(&all_sources.join(",\\\n"));
-            v.push_str("\\\n]'));\ncreateSrcSidebar();\n");
-            Ok(v.into_bytes())
+            Ok(src_files_js(&all_sources).into_bytes())
         };
         write_invocation_specific("src-files.js", &make_sources)?;
     }
@@ -312,7 +215,7 @@ fn add_path(self: &Rc<Self>, path: &Path) {
     // Update the search index and crate list.
     let dst = cx.dst.join(&format!("search-index{}.js", cx.shared.resource_suffix));
     let (mut all_indexes, mut krates) =
-        try_err!(collect_json(&dst, krate.name(cx.tcx()).as_str()), &dst);
+        try_err!(collect_json(&dst, Some(krate.name(cx.tcx()).as_str())), &dst);
     all_indexes.push(search_index.index);
     krates.push(krate.name(cx.tcx()).to_string());
     krates.sort();
@@ -321,19 +224,7 @@ fn add_path(self: &Rc<Self>, path: &Path) {
     // with rustdoc running in parallel.
     all_indexes.sort();
     write_invocation_specific("search-index.js", &|| {
-        // This needs to be `var`, not `const`.
-        // This variable needs declared in the current global scope so that if
-        // search.js loads first, it can pick it up.
-        let mut v = String::from("var searchIndex = new Map(JSON.parse('[\\\n");
-        v.push_str(&all_indexes.join(",\\\n"));
-        v.push_str(
-            r#"\
-]'));
-if (typeof exports !== 'undefined') exports.searchIndex = searchIndex;
-else if (window.initSearch) window.initSearch(searchIndex);
-"#,
-        );
-        Ok(v.into_bytes())
+        Ok(search_index_js(&all_indexes).into_bytes())
     })?;
 
     let search_desc_dir = cx.dst.join(format!("search.desc/{krate}", krate = krate.name(cx.tcx())));
@@ -362,10 +253,33 @@ fn add_path(self: &Rc<Self>, path: &Path) {
         );
     }
 
-    write_invocation_specific("crates.js", &|| {
-        let krates = krates.iter().map(|k| format!("\"{k}\"")).join(",");
-        Ok(format!("window.ALL_CRATES = [{krates}];").into_bytes())
-    })?;
+    let search_full_text_dir =
+        cx.dst.join(format!("search.fulltext/{krate}", krate = krate.name(cx.tcx())));
+    if Path::new(&search_full_text_dir).exists() {
+        try_err!(std::fs::remove_dir_all(&search_full_text_dir), &search_full_text_dir);
+    }
+    if let Some(data) = search_index.full_text {
+        try_err!(std::fs::create_dir_all(&search_full_text_dir), &search_full_text_dir);
+        let output_filename = static_files::suffix_path(
+            &format!("{kratename}-fulltext-.js"),
+            &cx.shared.resource_suffix,
+        );
+        let path = search_full_text_dir.join(output_filename);
+        try_err!(
+            std::fs::write(
+                &path,
+                &format!(
+                    r##"searchState.loadedFullText({kratename}, {data})"##,
+                    kratename = serde_json::to_string(&kratename).unwrap(),
+                    data = serde_json::to_string(&data).unwrap(),
+                )
+                .into_bytes()
+            ),
+            &path
+        );
+    }
+
+    write_invocation_specific("crates.js", &|| Ok(crates_js(&krates).into_bytes()))?;
 
     if options.enable_index_page {
         if let Some(index_page) = options.index_page.clone() {
@@ -388,15 +302,7 @@ fn add_path(self: &Rc<Self>, path: &Path) {
                 rust_logo: true,
             };
 
-            let content = format!(
-                "<h1>List of all crates</h1><ul class=\"all-items\">{}</ul>",
-                krates.iter().format_with("", |k, f| {
-                    f(&format_args!(
-                        "<li><a href=\"{trailing_slash}index.html\">{k}</a></li>",
-                        trailing_slash = ensure_trailing_slash(k),
-                    ))
-                })
-            );
+            let content = crate_list_html(&krates);
             let v = layout::render(&shared.layout, &page, "", content, &shared.style_files);
             shared.fs.write(dst, v)?;
         }
@@ -643,24 +549,14 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             aliased_type.target_fqp[aliased_type.target_fqp.len() - 1]
         ));
 
-        let (mut all_impls, _) = try_err!(collect(&mydst, krate.name(cx.tcx()).as_str()), &mydst);
+        let (mut all_impls, _) =
+            try_err!(collect(&mydst, Some(krate.name(cx.tcx()).as_str())), &mydst);
         all_impls.push(impls);
         // Sort the implementors by crate so the file will be generated
         // identically even with rustdoc running in parallel.
         all_impls.sort();
 
-        let mut v = String::from("(function() {var type_impls = {\n");
-        v.push_str(&all_impls.join(",\n"));
-        v.push_str("\n};");
-        v.push_str(
-            "if (window.register_type_impls) {\
-                 window.register_type_impls(type_impls);\
-             } else {\
-                 window.pending_type_impls = type_impls;\
-             }",
-        );
-        v.push_str("})()");
-        cx.shared.fs.write(mydst, v)?;
+        cx.shared.fs.write(mydst, type_impls_js(&all_impls))?;
     }
 
     // Update the list of all implementors for traits
@@ -754,24 +650,188 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         mydst.push(&format!("{remote_item_type}.{}.js", remote_path[remote_path.len() - 1]));
 
         let (mut all_implementors, _) =
-            try_err!(collect(&mydst, krate.name(cx.tcx()).as_str()), &mydst);
+            try_err!(collect(&mydst, Some(krate.name(cx.tcx()).as_str())), &mydst);
         all_implementors.push(implementors);
         // Sort the implementors by crate so the file will be generated
         // identically even with rustdoc running in parallel.
         all_implementors.sort();
 
-        let mut v = String::from("(function() {var implementors = {\n");
-        v.push_str(&all_implementors.join(",\n"));
-        v.push_str("\n};");
-        v.push_str(
-            "if (window.register_implementors) {\
-                 window.register_implementors(implementors);\
-             } else {\
-                 window.pending_implementors = implementors;\
-             }",
-        );
-        v.push_str("})()");
-        cx.shared.fs.write(mydst, v)?;
+        cx.shared.fs.write(mydst, implementors_js(&all_implementors))?;
     }
     Ok(())
 }
+
+/// Read a file and return all lines that match the `"{crate}":{data},` format,
+/// except for the lines of `krate`, and return a tuple
+/// `(Vec<DataString>, Vec<CrateNameString>)`.
+///
+/// This forms the payload of files that look like this:
+///
+/// ```javascript
+/// var data = {
+/// "{crate1}":{data},
+/// "{crate2}":{data}
+/// };
+/// use_data(data);
+/// ```
+///
+/// The file needs to be formatted so that *only crate data lines start with `"`*.
+pub(super) fn collect(path: &Path, krate: Option<&str>) -> io::Result<(Vec<String>, Vec<String>)> {
+    let mut ret = Vec::new();
+    let mut krates = Vec::new();
+
+    if path.exists() {
+        let prefix = krate.map(|krate| format!("\"{krate}\""));
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.starts_with('"') {
+                continue;
+            }
+            if prefix.as_ref().is_some_and(|prefix| line.starts_with(prefix)) {
+                continue;
+            }
+            if line.ends_with(',') {
+                ret.push(line[..line.len() - 1].to_string());
+            } else {
+                // No comma (it's the case for the last added crate line)
+                ret.push(line.to_string());
+            }
+            krates.push(
+                line.split('"')
+                    .find(|s| !s.is_empty())
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(String::new),
+            );
+        }
+    }
+    Ok((ret, krates))
+}
+
+/// Read a file and return all lines that match the <code>"{crate}":{data},\ </code> format,
+/// except for the lines of `krate`, and return a tuple
+/// `(Vec<DataString>, Vec<CrateNameString>)`.
+///
+/// This forms the payload of files that look like this:
+///
+/// ```javascript
+/// var data = JSON.parse('{\
+/// "{crate1}":{data},\
+/// "{crate2}":{data}\
+/// }');
+/// use_data(data);
+/// ```
+///
+/// The file needs to be formatted so that *only crate data lines start with `"`*.
+pub(super) fn collect_json(
+    path: &Path,
+    krate: Option<&str>,
+) -> io::Result<(Vec<String>, Vec<String>)> {
+    let mut ret = Vec::new();
+    let mut krates = Vec::new();
+
+    if path.exists() {
+        let prefix = krate.map(|krate| format!("[\"{krate}\""));
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.starts_with("[\"") {
+                continue;
+            }
+            if prefix.as_ref().is_some_and(|prefix| line.starts_with(prefix)) {
+                continue;
+            }
+            if line.ends_with("],\\") {
+                ret.push(line[..line.len() - 2].to_string());
+            } else {
+                // Ends with "\\" (it's the case for the last added crate line)
+                ret.push(line[..line.len() - 1].to_string());
+            }
+            krates.push(
+                line[1..] // We skip the `[` parent at the beginning of the line.
+                    .split('"')
+                    .find(|s| !s.is_empty())
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(String::new),
+            );
+        }
+    }
+    Ok((ret, krates))
+}
+
+/// Returns the content of `src-files.js`, given the source files of each crate.
+pub(super) fn src_files_js(all_sources: &[String]) -> String {
+    // This needs to be `var`, not `const`.
+    // This variable needs declared in the current global scope so that if
+    // src-script.js loads first, it can pick it up.
+    let mut v = String::from("var srcIndex = new Map(JSON.parse('[\\\n");
+    v.push_str(&all_sources.join(",\\\n"));
+    v.push_str("\\\n]'));\ncreateSrcSidebar();\n");
+    v
+}
+
+/// Returns the content of `search-index.js`, given the search index of each crate.
+pub(super) fn search_index_js(all_indexes: &[String]) -> String {
+    // This needs to be `var`, not `const`.
+    // This variable needs declared in the current global scope so that if
+    // search.js loads first, it can pick it up.
+    let mut v = String::from("var searchIndex = new Map(JSON.parse('[\\\n");
+    v.push_str(&all_indexes.join(",\\\n"));
+    v.push_str(
+        r#"\
+]'));
+if (typeof exports !== 'undefined') exports.searchIndex = searchIndex;
+else if (window.initSearch) window.initSearch(searchIndex);
+"#,
+    );
+    v
+}
+
+/// Returns the content of `crates.js`, the list of crates shown in the sidebar.
+pub(super) fn crates_js(krates: &[String]) -> String {
+    let krates = krates.iter().map(|k| format!("\"{k}\"")).join(",");
+    format!("window.ALL_CRATES = [{krates}];")
+}
+
+/// Returns the content of the "Index of crates" page.
+pub(super) fn crate_list_html(krates: &[String]) -> String {
+    format!(
+        "<h1>List of all crates</h1><ul class=\"all-items\">{}</ul>",
+        krates.iter().format_with("", |k, f| {
+            f(&format_args!(
+                "<li><a href=\"{trailing_slash}index.html\">{k}</a></li>",
+                trailing_slash = ensure_trailing_slash(k),
+            ))
+        })
+    )
+}
+
+/// Returns the content of a `type.impl` file, given the impls of each crate.
+pub(super) fn type_impls_js(all_impls: &[String]) -> String {
+    let mut v = String::from("(function() {var type_impls = {\n");
+    v.push_str(&all_impls.join(",\n"));
+    v.push_str("\n};");
+    v.push_str(
+        "if (window.register_type_impls) {\
+             window.register_type_impls(type_impls);\
+         } else {\
+             window.pending_type_impls = type_impls;\
+         }",
+    );
+    v.push_str("})()");
+    v
+}
+
+/// Returns the content of a `trait.impl` file, given the implementors of each crate.
+pub(super) fn implementors_js(all_implementors: &[String]) -> String {
+    let mut v = String::from("(function() {var implementors = {\n");
+    v.push_str(&all_implementors.join(",\n"));
+    v.push_str("\n};");
+    v.push_str(
+        "if (window.register_implementors) {\
+             window.register_implementors(implementors);\
+         } else {\
+             window.pending_implementors = implementors;\
+         }",
+    );
+    v.push_str("})()");
+    v
+}
@@ -353,6 +353,31 @@ function preLoadCss(cssUrl) {
         loadedDescShard: function(crate, shard, data) {
             this.descShards.get(crate)[shard].resolve(data.split("\n"));
         },
+        fullTextIndexes: new Map(),
+        loadFullText: async function(fullTextIndex) {
+            if (fullTextIndex.promise === null) {
+                fullTextIndex.promise = new Promise((resolve, reject) => {
+                    // Like `descShard.resolve`, this is called in `loadedFullText`
+                    // by the search.fulltext script.
+                    fullTextIndex.resolve = resolve;
+                    const crate = fullTextIndex.crate;
+                    const url = resourcePath(
+                        `search.fulltext/${crate}/${crate}-fulltext-`,
+                        ".js",
+                    );
+                    loadScript(url, reject);
+                });
+            }
+            return fullTextIndex.promise;
+        },
+        loadedFullText: function(crate, data) {
+            const {t, p} = JSON.parse(data);
+            const postings = new Map();
+            for (const [i, term] of t.entries()) {
+                postings.set(term, p[i]);
+            }
+            this.fullTextIndexes.get(crate).resolve(postings);
+        },
     };
 
     const toggleAllDocsId = "toggle-all-docs";
@@ -236,6 +236,60 @@ function editDistance(a, b, limit) {
     return editDistanceState.calculate(a, b, limit);
 }
 
+// Words that are not indexed by the full-text search.
+//
+// This list, `fullTextStem` and `fullTextTerms` must be kept in sync with
+// librustdoc/html/render/search_index/full_text.rs.
+const FULL_TEXT_STOP_WORDS = new Set([
+    "a", "also", "an", "and", "are", "as", "at", "be", "by", "can", "for", "from", "if", "in",
+    "into", "is", "it", "its", "no", "not", "of", "on", "or", "so", "than", "that", "the", "then",
+    "there", "these", "this", "to", "was", "we", "when", "which", "will", "with", "you", "your",
+]);
+
+function fullTextStem(word) {
+    if (word.length < 4 || !/^[a-z]+$/.test(word)) {
+        return word;
+    }
+    let w = word;
+    if (w.endsWith("sses") || w.endsWith("ies")) {
+        w = w.slice(0, -2);
+    } else if (w.endsWith("s") && !["ss", "us", "is"].some(suffix => w.endsWith(suffix))) {
+        w = w.slice(0, -1);
+    }
+    for (const suffix of ["ing", "ed"]) {
+        const rest = w.slice(0, -suffix.length);
+        if (w.endsWith(suffix) && rest.length >= 3 && /[aeiou]/.test(rest)) {
+            w = rest;
+            const last = w[w.length - 1];
+            if (last === w[w.length - 2] && !"aeioulsz".includes(last)) {
+                w = w.slice(0, -1);
+            }
+            break;
+        }
+    }
+    if (w.length > 2 && w.endsWith("y") && !"aeiou".includes(w[w.length - 2])) {
+        w = w.slice(0, -1) + "i";
+    }
+    if (w.length > 4 && w.endsWith("e")) {
+        w = w.slice(0, -1);
+    }
+    return w;
+}
+
+/**
+ * Splits a query into the terms of the full-text index.
+ *
+ * @param {string} text
+ * @returns {Array<string>}
+ */
+function fullTextTerms(text) {
+    return text.split(/[^0-9A-Za-z\u00c0-\u1fff\u2070-\u2fff\u3040-\uffff]+/)
+        .map(word => word.toLowerCase())
+        .filter(word => word !== "" && !FULL_TEXT_STOP_WORDS.has(word))
+        .map(fullTextStem)
+        .filter(term => [...term].length >= 2);
+}
+
 function initSearch(rawSearchIndex) {
     const MAX_RESULTS = 200;
     const NO_TYPE_FILTER = -1;
@@ -1313,19 +1367,80 @@ function initSearch(rawSearchIndex) {
      * @param {Array<Result>} results_in_args
      * @param {Array<Result>} results_returned
      * @param {Array<Result>} results_others
+     * @param {Array<Result>|null} results_docs - `null` if no crate has a full-text index
      * @param {ParsedQuery} parsedQuery
      *
      * @return {ResultsTable}
      */
-    function createQueryResults(results_in_args, results_returned, results_others, parsedQuery) {
+    function createQueryResults(
+        results_in_args,
+        results_returned,
+        results_others,
+        results_docs,
+        parsedQuery,
+    ) {
         return {
             "in_args": results_in_args,
             "returned": results_returned,
             "others": results_others,
+            "docs": results_docs,
             "query": parsedQuery,
         };
     }
 
+    /**
+     * Looks up the words of the query in the full-text indexes of the crates which have one.
+     *
+     * Items are ranked by the number of query terms found in their documentation, then by
+     * the sum of their precomputed scores for those terms.
+     *
+     * @param {string} query
+     * @param {string|null} filterCrates
+     *
+     * @return {Promise<Array<{id: integer, dist: integer}>>}
+     */
+    async function execFullTextQuery(query, filterCrates) {
+        const terms = [...new Set(fullTextTerms(query))];
+        if (terms.length === 0) {
+            return [];
+        }
+        const fullTextIndexes = [...searchState.fullTextIndexes.values()]
+            .filter(fullTextIndex => filterCrates === null || fullTextIndex.crate === filterCrates);
+        const allPostings = await Promise.all(
+            fullTextIndexes.map(fullTextIndex => searchState.loadFullText(fullTextIndex)),
+        );
+        const matches = new Map();
+        for (const [i, postings] of allPostings.entries()) {
+            const offset = fullTextIndexes[i].offset;
+            for (const term of terms) {
+                const list = postings.get(term);
+                if (list === undefined) {
+                    continue;
+                }
+                // `list` alternates item indexes, relative to the crate, and scores.
+                for (let j = 0; j < list.length; j += 2) {
+                    const id = offset + list[j];
+                    const match = matches.get(id);
+                    if (match === undefined) {
+                        matches.set(id, {id, dist: 0, terms: 1, score: list[j + 1]});
+                    } else {
+                        match.terms += 1;
+                        match.score += list[j + 1];
+                    }
+                }
+            }
+        }
+        return [...matches.values()].sort((a, b) => {
+            if (a.terms !== b.terms) {
+                return b.terms - a.terms;
+            }
+            if (a.score !== b.score) {
+                return b.score - a.score;
+            }
+            return a.id - b.id;
+        });
+    }
+
     /**
      * Executes the parsed query and builds a {ResultsTable}.
      *
@@ -2533,13 +2648,21 @@ function initSearch(rawSearchIndex) {
             sortResults(results_returned, true, currentCrate),
             sortResults(results_others, false, currentCrate),
         ]);
+        const docs = searchState.fullTextIndexes.size === 0 ?
+            null :
+            transformResults(await execFullTextQuery(parsedQuery.original, filterCrates));
         const ret = createQueryResults(
             sorted_in_args,
             sorted_returned,
             sorted_others,
+            docs,
             parsedQuery);
         handleAliases(ret, parsedQuery.original.replace(/"/g, ""), filterCrates, currentCrate);
-        await Promise.all([ret.others, ret.returned, ret.in_args].map(async list => {
+        const lists = [ret.others, ret.returned, ret.in_args];
+        if (ret.docs !== null) {
+            lists.push(ret.docs);
+        }
+        await Promise.all(lists.map(async list => {
             const descs = await Promise.all(list.map(result => {
                 return searchIndexEmptyDesc.get(result.crate).contains(result.bitIndex) ?
                     "" :
@@ -2557,7 +2680,8 @@ function initSearch(rawSearchIndex) {
     }
 
     function nextTab(direction) {
-        const next = (searchState.currentTab + direction + 3) % searchState.focusedByTab.length;
+        const nbTabs = searchState.focusedByTab.length;
+        const next = (searchState.currentTab + direction + nbTabs) % nbTabs;
         searchState.focusedByTab[searchState.currentTab] = document.activeElement;
         printTab(next);
         focusSearchResult();
@@ -2767,25 +2891,30 @@ ${item.displayPath}<span class="${type}">${name}</span>\
 
         currentResults = results.query.userQuery;
 
-        const [ret_others, ret_in_args, ret_returned] = await Promise.all([
+        const [ret_others, ret_in_args, ret_returned, ret_docs] = await Promise.all([
             addTab(results.others, results.query, true),
             addTab(results.in_args, results.query, false),
             addTab(results.returned, results.query, false),
+            results.docs === null ? null : addTab(results.docs, results.query, false),
         ]);
 
         // Navigate to the relevant tab if the current tab is empty, like in case users search
         // for "-> String". If they had selected another tab previously, they have to click on
         // it again.
         let currentTab = searchState.currentTab;
+        const nbDocs = ret_docs === null ? 0 : ret_docs[1];
         if ((currentTab === 0 && ret_others[1] === 0) ||
                 (currentTab === 1 && ret_in_args[1] === 0) ||
-                (currentTab === 2 && ret_returned[1] === 0)) {
+                (currentTab === 2 && ret_returned[1] === 0) ||
+                (currentTab === 3 && nbDocs === 0)) {
             if (ret_others[1] !== 0) {
                 currentTab = 0;
             } else if (ret_in_args[1] !== 0) {
                 currentTab = 1;
             } else if (ret_returned[1] !== 0) {
                 currentTab = 2;
+            } else if (nbDocs !== 0) {
+                currentTab = 3;
             }
         }
 
@@ -2820,6 +2949,7 @@ ${item.displayPath}<span class="${type}">${name}</span>\
                 makeTabHeader(0, "In Names", ret_others[1]) +
                 makeTabHeader(1, "In Parameters", ret_in_args[1]) +
                 makeTabHeader(2, "In Return Types", ret_returned[1]) +
+                (ret_docs === null ? "" : makeTabHeader(3, "In Docs", ret_docs[1])) +
                 "</div>";
         } else {
             const signatureTabTitle =
@@ -2854,6 +2984,9 @@ ${item.displayPath}<span class="${type}">${name}</span>\
         resultsElem.appendChild(ret_others[0]);
         resultsElem.appendChild(ret_in_args[0]);
         resultsElem.appendChild(ret_returned[0]);
+        if (ret_docs !== null) {
+            resultsElem.appendChild(ret_docs[0]);
+        }
 
         search.innerHTML = output;
         const crateSearch = document.getElementById("crate-search");
@@ -3514,6 +3647,15 @@ ${item.displayPath}<span class="${type}">${name}</span>\
             id += 1;
             searchIndex.push(crateRow);
             currentIndex += 1;
+            if (crateCorpus.x) {
+                // The full-text index of the crate is only loaded when searching.
+                searchState.fullTextIndexes.set(crate, {
+                    crate,
+                    offset: crateRow.id,
+                    promise: null,
+                    resolve: null,
+                });
+            }
             if (!searchIndexEmptyDesc.get(crate).contains(0)) {
                 descIndex += 1;
             }
@@ -0,0 +1,838 @@
+//! Comparison of a crate's public API with a previous version of it.
+//!
+//! With `--output-format api-diff --baseline old.json`, the crate is converted to rustdoc's JSON
+//! format as usual, but instead of being written out, it is compared with the JSON output of a
+//! previous version of the crate. Every change to the public API is reported and classified by
+//! the semver bump it requires.
+//!
+//! Item ids are not stable between two compilations of a crate, so items are matched by the
+//! public path they can be reached at, and their signatures are compared by rendering them to
+//! strings.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use rustc_data_structures::fx::FxHashSet;
+use serde::Serialize;
+
+use rustdoc_json_types as types;
+
+use crate::docfs::PathError;
+use crate::error::Error;
+use crate::try_err;
+
+#[cfg(test)]
+mod tests;
+
+/// The part of the version number that has to be bumped for a change, following the
+/// [semver compatibility rules](https://doc.rust-lang.org/cargo/reference/semver.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SemverImpact {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for SemverImpact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SemverImpact::Patch => "patch",
+            SemverImpact::Minor => "minor",
+            SemverImpact::Major => "major",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+    Deprecated,
+    Undeprecated,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+            ChangeKind::Deprecated => "deprecated",
+            ChangeKind::Undeprecated => "undeprecated",
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ApiChange {
+    /// The public path of the changed item. Impls are named after the type they are for.
+    pub(crate) path: String,
+    /// What the item is, e.g. `function`, `field` or `auto trait impl`.
+    pub(crate) item_kind: &'static str,
+    pub(crate) change: ChangeKind,
+    pub(crate) impact: SemverImpact,
+    /// The signature of the item in the baseline, if it exists there.
+    pub(crate) old: Option<String>,
+    /// The signature of the item in the current crate, if it exists there.
+    pub(crate) new: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ApiDiff {
+    /// The largest impact of all changes, `None` if the public API did not change.
+    pub(crate) required_bump: Option<SemverImpact>,
+    /// All changes, the ones with the largest impact first.
+    pub(crate) changes: Vec<ApiChange>,
+}
+
+/// Loads the baseline, compares it with `krate` and writes the report to `out_path`. A summary
+/// of the report is printed to stdout.
+pub(crate) fn render_api_diff(
+    baseline: &Path,
+    krate: &types::Crate,
+    out_path: &Path,
+) -> Result<(), Error> {
+    let file = try_err!(File::open(baseline), baseline);
+    let old: types::Crate = try_err!(serde_json::from_reader(BufReader::new(file)), baseline);
+    if old.format_version != types::FORMAT_VERSION {
+        return Err(Error::new(
+            format!(
+                "the baseline uses version {} of the JSON format, but this rustdoc uses version {}",
+                old.format_version,
+                types::FORMAT_VERSION,
+            ),
+            baseline,
+        ));
+    }
+
+    let diff = diff(&old, krate);
+
+    let mut file = BufWriter::new(try_err!(File::create(out_path), out_path));
+    try_err!(serde_json::ser::to_writer(&mut file, &diff), out_path);
+    try_err!(file.flush(), out_path);
+
+    print!("{diff}");
+    Ok(())
+}
+
+impl fmt::Display for ApiDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(
+                f,
+                "{:<5} {} {} `{}`",
+                change.impact, change.change, change.item_kind, change.path
+            )?;
+            if change.change == ChangeKind::Changed
+                && let (Some(old), Some(new)) = (&change.old, &change.new)
+            {
+                writeln!(f, "        - {old}")?;
+                writeln!(f, "        + {new}")?;
+            }
+        }
+        match self.required_bump {
+            Some(bump) => writeln!(f, "required version bump: {bump}"),
+            None => writeln!(f, "the public API did not change"),
+        }
+    }
+}
+
+pub(crate) fn diff(old: &types::Crate, new: &types::Crate) -> ApiDiff {
+    let old = collect(old);
+    let new = collect(new);
+
+    let mut changes = vec![];
+    let mut push = |path: &str,
+                    entry: &ApiEntry,
+                    change,
+                    impact,
+                    old: Option<&ApiEntry>,
+                    new: Option<&ApiEntry>| {
+        changes.push(ApiChange {
+            path: path.to_owned(),
+            item_kind: entry.kind,
+            change,
+            impact,
+            old: old.map(|e| e.signature.clone()),
+            new: new.map(|e| e.signature.clone()),
+        })
+    };
+    for (path, old_entry) in &old {
+        let Some(new_entry) = new.get(path) else {
+            push(path, old_entry, ChangeKind::Removed, SemverImpact::Major, Some(old_entry), None);
+            continue;
+        };
+        if let Some(impact) = change_impact(old_entry, new_entry) {
+            push(path, new_entry, ChangeKind::Changed, impact, Some(old_entry), Some(new_entry));
+        } else if old_entry.deprecated != new_entry.deprecated {
+            let change = if new_entry.deprecated {
+                ChangeKind::Deprecated
+            } else {
+                ChangeKind::Undeprecated
+            };
+            push(path, new_entry, change, SemverImpact::Patch, Some(old_entry), Some(new_entry));
+        }
+    }
+    for (path, new_entry) in &new {
+        if !old.contains_key(path) {
+            push(path, new_entry, ChangeKind::Added, new_entry.addition, None, Some(new_entry));
+        }
+    }
+
+    changes.sort_by(|a, b| b.impact.cmp(&a.impact).then_with(|| a.path.cmp(&b.path)));
+    let required_bump = changes.iter().map(|change| change.impact).max();
+    ApiDiff { required_bump, changes }
+}
+
+/// Returns the impact of going from `old` to `new`, or `None` if the signature did not change.
+fn change_impact(old: &ApiEntry, new: &ApiEntry) -> Option<SemverImpact> {
+    if old.kind == new.kind && old.signature == new.signature {
+        return None;
+    }
+    let impact = match new.kind {
+        // Losing an auto trait impl breaks users, gaining one does not.
+        "auto trait impl" if old.kind == new.kind && old.negative && !new.negative => {
+            SemverImpact::Minor
+        }
+        // Making a function `const` only allows more uses of it.
+        "function" | "method" if old.signature == new.signature.replacen("const ", "", 1) => {
+            SemverImpact::Minor
+        }
+        _ => SemverImpact::Major,
+    };
+    Some(impact)
+}
+
+/// A part of the public API that can be compared across versions.
+#[derive(Debug)]
+struct ApiEntry {
+    kind: &'static str,
+    signature: String,
+    deprecated: bool,
+    /// For impls, whether this is a negative impl (`impl !Send for T`).
+    negative: bool,
+    /// The impact of adding this entry to a crate that did not have it.
+    addition: SemverImpact,
+}
+
+/// Collects all parts of the public API of `krate`, keyed by their public path.
+fn collect(krate: &types::Crate) -> BTreeMap<String, ApiEntry> {
+    let mut collector =
+        Collector { krate, entries: BTreeMap::new(), visited_modules: FxHashSet::default() };
+    if let Some(root) = krate.index.get(&krate.root) {
+        let name = root.name.clone().unwrap_or_default();
+        collector.item(root, name);
+    }
+    collector.entries
+}
+
+struct Collector<'a> {
+    krate: &'a types::Crate,
+    entries: BTreeMap<String, ApiEntry>,
+    /// Glob re-exports can make modules reachable from themselves.
+    visited_modules: FxHashSet<&'a types::Id>,
+}
+
+impl<'a> Collector<'a> {
+    fn get(&self, id: &types::Id) -> Option<&'a types::Item> {
+        self.krate.index.get(id)
+    }
+
+    fn add(&mut self, path: String, item: &types::Item, kind: &'static str, signature: String) {
+        self.add_entry(path, item, kind, signature, SemverImpact::Minor);
+    }
+
+    fn add_entry(
+        &mut self,
+        path: String,
+        item: &types::Item,
+        kind: &'static str,
+        signature: String,
+        addition: SemverImpact,
+    ) {
+        let deprecated = item.deprecation.is_some();
+        self.entries
+            .insert(path, ApiEntry { kind, signature, deprecated, negative: false, addition });
+    }
+
+    fn module_items(&mut self, module: &'a types::Module, path: &str) {
+        for id in &module.items {
+            let Some(item) = self.get(id) else { continue };
+            match &item.inner {
+                types::ItemEnum::Import(import) => self.import(item, import, path),
+                _ => {
+                    let Some(name) = &item.name else { continue };
+                    self.item(item, format!("{path}::{name}"));
+                }
+            }
+        }
+    }
+
+    fn import(&mut self, item: &'a types::Item, import: &'a types::Import, path: &str) {
+        let target = import.id.as_ref().and_then(|id| self.get(id));
+        match target {
+            Some(target) if import.glob => {
+                if let types::ItemEnum::Module(module) = &target.inner
+                    && self.visited_modules.insert(&target.id)
+                {
+                    self.module_items(module, path);
+                }
+            }
+            Some(target) => self.item(target, format!("{path}::{}", import.name)),
+            // Re-exports of items from other crates can only be compared by their source.
+            None => {
+                let signature = if import.glob {
+                    format!("pub use {}::*", import.source)
+                } else {
+                    format!("pub use {} as {}", import.source, import.name)
+                };
+                let path = if import.glob {
+                    format!("{path}::{}::*", import.source)
+                } else {
+                    format!("{path}::{}", import.name)
+                };
+                self.add(path, item, "re-export", signature);
+            }
+        }
+    }
+
+    fn item(&mut self, item: &'a types::Item, path: String) {
+        let name = item.name.as_deref().unwrap_or_default();
+        match &item.inner {
+            types::ItemEnum::Module(module) => {
+                self.add(path.clone(), item, "module", format!("mod {name}"));
+                if self.visited_modules.insert(&item.id) {
+                    self.module_items(module, &path);
+                }
+            }
+            types::ItemEnum::ExternCrate { name, rename } => {
+                let signature = match rename {
+                    Some(rename) => format!("extern crate {name} as {rename}"),
+                    None => format!("extern crate {name}"),
+                };
+                self.add(path, item, "extern crate", signature);
+            }
+            types::ItemEnum::Struct(struct_) => {
+                let (body, fields, stripped) = match &struct_.kind {
+                    types::StructKind::Unit => (";", vec![], false),
+                    types::StructKind::Tuple(fields) => {
+                        let stripped = fields.iter().any(Option::is_none);
+                        ("(..);", fields.iter().flatten().collect(), stripped)
+                    }
+                    types::StructKind::Plain { fields, fields_stripped } => {
+                        (" { .. }", fields.iter().collect(), *fields_stripped)
+                    }
+                };
+                let signature = format!(
+                    "{}struct {name}{}{}{body}{}",
+                    non_exhaustive(item),
+                    generics(&struct_.generics),
+                    where_clause(&struct_.generics),
+                    if stripped { " /* private fields */" } else { "" },
+                );
+                self.add(path.clone(), item, "struct", signature);
+                self.fields(&fields, &path, stripped || is_non_exhaustive(item));
+                self.impls(&struct_.impls, &path);
+            }
+            types::ItemEnum::Union(union_) => {
+                let signature = format!(
+                    "union {name}{}{} {{ .. }}{}",
+                    generics(&union_.generics),
+                    where_clause(&union_.generics),
+                    if union_.fields_stripped { " /* private fields */" } else { "" },
+                );
+                self.add(path.clone(), item, "union", signature);
+                let fields = union_.fields.iter().collect::<Vec<_>>();
+                self.fields(&fields, &path, union_.fields_stripped);
+                self.impls(&union_.impls, &path);
+            }
+            types::ItemEnum::Enum(enum_) => {
+                let signature = format!(
+                    "{}enum {name}{}{}",
+                    non_exhaustive(item),
+                    generics(&enum_.generics),
+                    where_clause(&enum_.generics),
+                );
+                self.add(path.clone(), item, "enum", signature);
+                // Matches on the enum need to handle new variants, unless it is non-exhaustive.
+                let addition = if is_non_exhaustive(item) {
+                    SemverImpact::Minor
+                } else {
+                    SemverImpact::Major
+                };
+                for id in &enum_.variants {
+                    let Some(variant) = self.get(id) else { continue };
+                    self.variant(variant, format!("{path}::{}", variant_name(variant)), addition);
+                }
+                self.impls(&enum_.impls, &path);
+            }
+            types::ItemEnum::Function(function) => {
+                self.add(path, item, "function", fn_signature(name, function));
+            }
+            types::ItemEnum::Trait(trait_) => {
+                let supertraits = if trait_.bounds.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", bounds(&trait_.bounds))
+                };
+                let signature = format!(
+                    "{}{}trait {name}{}{supertraits}{}",
+                    if trait_.is_unsafe { "unsafe " } else { "" },
+                    if trait_.is_auto { "auto " } else { "" },
+                    generics(&trait_.generics),
+                    where_clause(&trait_.generics),
+                );
+                self.add(path.clone(), item, "trait", signature);
+                for id in &trait_.items {
+                    let Some(assoc) = self.get(id) else { continue };
+                    // Implementors have to provide new items that have no default.
+                    let addition = match &assoc.inner {
+                        types::ItemEnum::Function(f) if !f.has_body => SemverImpact::Major,
+                        types::ItemEnum::AssocConst { default: None, .. }
+                        | types::ItemEnum::AssocType { default: None, .. } => SemverImpact::Major,
+                        _ => SemverImpact::Minor,
+                    };
+                    self.assoc_item(assoc, &path, "trait", addition);
+                }
+            }
+            types::ItemEnum::TraitAlias(alias) => {
+                let signature = format!(
+                    "trait {name}{} = {}{}",
+                    generics(&alias.generics),
+                    bounds(&alias.params),
+                    where_clause(&alias.generics),
+                );
+                self.add(path, item, "trait alias", signature);
+            }
+            types::ItemEnum::TypeAlias(alias) => {
+                let signature = format!(
+                    "type {name}{}{} = {}",
+                    generics(&alias.generics),
+                    where_clause(&alias.generics),
+                    ty(&alias.type_),
+                );
+                self.add(path, item, "type alias", signature);
+            }
+            types::ItemEnum::Constant(constant) => {
+                self.add(path, item, "constant", format!("const {name}: {}", ty(&constant.type_)));
+            }
+            types::ItemEnum::Static(static_) => {
+                let signature = format!(
+                    "static {}{name}: {}",
+                    if static_.mutable { "mut " } else { "" },
+                    ty(&static_.type_),
+                );
+                self.add(path, item, "static", signature);
+            }
+            types::ItemEnum::ForeignType => {
+                self.add(path, item, "foreign type", format!("type {name}"));
+            }
+            types::ItemEnum::Macro(_) => {
+                self.add(path, item, "macro", format!("macro_rules! {name}"));
+            }
+            types::ItemEnum::ProcMacro(proc_macro) => {
+                let signature = match proc_macro.kind {
+                    types::MacroKind::Bang => format!("{name}!"),
+                    types::MacroKind::Attr => format!("#[{name}]"),
+                    types::MacroKind::Derive if proc_macro.helpers.is_empty() => {
+                        format!("#[derive({name})]")
+                    }
+                    types::MacroKind::Derive => {
+                        format!("#[derive({name})], helpers: {}", proc_macro.helpers.join(", "))
+                    }
+                };
+                self.add(path, item, "proc macro", signature);
+            }
+            types::ItemEnum::Primitive(primitive) => {
+                self.impls(&primitive.impls, &path);
+            }
+            // Only reachable through their parents, which handle them.
+            types::ItemEnum::Import(_)
+            | types::ItemEnum::StructField(_)
+            | types::ItemEnum::Variant(_)
+            | types::ItemEnum::Impl(_)
+            | types::ItemEnum::OpaqueTy(_)
+            | types::ItemEnum::AssocConst { .. }
+            | types::ItemEnum::AssocType { .. } => {}
+        }
+    }
+
+    /// Adds the public fields of a struct, union or variant. `extensible` tells whether fields
+    /// can be added without breaking code constructing or destructuring the parent.
+    fn fields(&mut self, fields: &[&types::Id], path: &str, extensible: bool) {
+        let addition = if extensible { SemverImpact::Minor } else { SemverImpact::Major };
+        for id in fields {
+            let Some(field) = self.get(id) else { continue };
+            let (Some(name), types::ItemEnum::StructField(type_)) = (&field.name, &field.inner)
+            else {
+                continue;
+            };
+            let signature = format!("{name}: {}", ty(type_));
+            self.add_entry(format!("{path}::{name}"), field, "field", signature, addition);
+        }
+    }
+
+    fn variant(&mut self, variant: &'a types::Item, path: String, addition: SemverImpact) {
+        let types::ItemEnum::Variant(inner) = &variant.inner else { return };
+        let name = variant_name(variant);
+        let (body, fields, stripped) = match &inner.kind {
+            types::VariantKind::Plain => ("", vec![], false),
+            types::VariantKind::Tuple(fields) => {
+                let stripped = fields.iter().any(Option::is_none);
+                ("(..)", fields.iter().flatten().collect(), stripped)
+            }
+            types::VariantKind::Struct { fields, fields_stripped } => {
+                (" { .. }", fields.iter().collect(), *fields_stripped)
+            }
+        };
+        let discriminant = match &inner.discriminant {
+            Some(discriminant) => format!(" = {}", discriminant.value),
+            None => String::new(),
+        };
+        let signature = format!(
+            "{}{name}{body}{discriminant}{}",
+            non_exhaustive(variant),
+            if stripped { " /* private fields */" } else { "" },
+        );
+        self.add_entry(path.clone(), variant, "variant", signature, addition);
+        self.fields(&fields, &path, stripped || is_non_exhaustive(variant));
+    }
+
+    fn assoc_item(
+        &mut self,
+        item: &types::Item,
+        parent: &str,
+        parent_kind: &str,
+        addition: SemverImpact,
+    ) {
+        let Some(name) = item.name.as_deref() else { return };
+        let path = format!("{parent}::{name}");
+        match &item.inner {
+            types::ItemEnum::Function(function) => {
+                let kind = if parent_kind == "trait" { "trait method" } else { "method" };
+                self.add_entry(path, item, kind, fn_signature(name, function), addition);
+            }
+            types::ItemEnum::AssocConst { type_, default } => {
+                let mut signature = format!("const {name}: {}", ty(type_));
+                if parent_kind == "trait" && default.is_some() {
+                    signature.push_str(" = ..");
+                }
+                self.add_entry(path, item, "associated constant", signature, addition);
+            }
+            types::ItemEnum::AssocType { generics: g, bounds: b, default } => {
+                let mut signature = format!("type {name}{}", generics(g));
+                if !b.is_empty() {
+                    signature.push_str(&format!(": {}", bounds(b)));
+                }
+                signature.push_str(&where_clause(g));
+                if let Some(default) = default {
+                    signature.push_str(&format!(" = {}", ty(default)));
+                }
+                self.add_entry(path, item, "associated type", signature, addition);
+            }
+            _ => {}
+        }
+    }
+
+    fn impls(&mut self, impls: &[types::Id], path: &str) {
+        for id in impls {
+            let Some(item) = self.get(id) else { continue };
+            let types::ItemEnum::Impl(impl_) = &item.inner else { continue };
+            // Blanket impls come from other crates and only depend on their bounds.
+            if impl_.blanket_impl.is_some() {
+                continue;
+            }
+            let Some(trait_) = &impl_.trait_ else {
+                for id in &impl_.items {
+                    let Some(assoc) = self.get(id) else { continue };
+                    self.assoc_item(assoc, path, "impl", SemverImpact::Minor);
+                }
+                continue;
+            };
+            let signature = format!(
+                "{}impl{} {}{} for {}{}",
+                if impl_.is_unsafe { "unsafe " } else { "" },
+                generics(&impl_.generics),
+                if impl_.negative { "!" } else { "" },
+                path(trait_),
+                ty(&impl_.for_),
+                where_clause(&impl_.generics),
+            );
+            let kind = if impl_.synthetic { "auto trait impl" } else { "trait impl" };
+            let key = format!("{path}::<impl {}{}>", self.trait_path(trait_), path_args(trait_));
+            let deprecated = item.deprecation.is_some();
+            self.entries.insert(
+                key,
+                ApiEntry {
+                    kind,
+                    signature,
+                    deprecated,
+                    negative: impl_.negative,
+                    addition: SemverImpact::Minor,
+                },
+            );
+        }
+    }
+
+    /// The full path of the trait of an impl, so that impls of different traits with the same
+    /// name are told apart.
+    fn trait_path(&self, trait_: &types::Path) -> String {
+        match self.krate.paths.get(&trait_.id) {
+            Some(summary) => summary.path.join("::"),
+            None => trait_.name.clone(),
+        }
+    }
+}
+
+fn variant_name(variant: &types::Item) -> &str {
+    variant.name.as_deref().unwrap_or_default()
+}
+
+fn is_non_exhaustive(item: &types::Item) -> bool {
+    item.attrs.iter().any(|attr| attr == "#[non_exhaustive]")
+}
+
+fn non_exhaustive(item: &types::Item) -> &'static str {
+    if is_non_exhaustive(item) { "#[non_exhaustive] " } else { "" }
+}
+
+pub(crate) fn fn_signature(name: &str, function: &types::Function) -> String {
+    format!(
+        "{}fn {name}{}{}{}",
+        header(&function.header),
+        generics(&function.generics),
+        decl(&function.decl),
+        where_clause(&function.generics),
+    )
+}
+
+fn header(header: &types::Header) -> String {
+    let mut s = String::new();
+    if header.const_ {
+        s.push_str("const ");
+    }
+    if header.async_ {
+        s.push_str("async ");
+    }
+    if header.unsafe_ {
+        s.push_str("unsafe ");
+    }
+    let abi = match &header.abi {
+        types::Abi::Rust => None,
+        types::Abi::C { unwind } => Some(("C", *unwind)),
+        types::Abi::Cdecl { unwind } => Some(("cdecl", *unwind)),
+        types::Abi::Stdcall { unwind } => Some(("stdcall", *unwind)),
+        types::Abi::Fastcall { unwind } => Some(("fastcall", *unwind)),
+        types::Abi::Aapcs { unwind } => Some(("aapcs", *unwind)),
+        types::Abi::Win64 { unwind } => Some(("win64", *unwind)),
+        types::Abi::SysV64 { unwind } => Some(("sysv64", *unwind)),
+        types::Abi::System { unwind } => Some(("system", *unwind)),
+        types::Abi::Other(abi) => Some((abi.as_str(), false)),
+    };
+    if let Some((abi, unwind)) = abi {
+        s.push_str(&format!("extern \"{abi}{}\" ", if unwind { "-unwind" } else { "" }));
+    }
+    s
+}
+
+fn decl(decl: &types::FnDecl) -> String {
+    let mut inputs = decl
+        .inputs
+        .iter()
+        .map(|(name, type_)| format!("{name}: {}", ty(type_)))
+        .collect::<Vec<_>>();
+    if decl.c_variadic {
+        inputs.push("...".to_owned());
+    }
+    match &decl.output {
+        Some(output) => format!("({}) -> {}", inputs.join(", "), ty(output)),
+        None => format!("({})", inputs.join(", ")),
+    }
+}
+
+pub(crate) fn generics(generics: &types::Generics) -> String {
+    let params = generics
+        .params
+        .iter()
+        .filter(|param| {
+            !matches!(param.kind, types::GenericParamDefKind::Type { synthetic: true, .. })
+        })
+        .map(generic_param)
+        .collect::<Vec<_>>();
+    if params.is_empty() { String::new() } else { format!("<{}>", params.join(", ")) }
+}
+
+fn generic_param(param: &types::GenericParamDef) -> String {
+    let name = &param.name;
+    match &param.kind {
+        types::GenericParamDefKind::Lifetime { outlives } if outlives.is_empty() => name.clone(),
+        types::GenericParamDefKind::Lifetime { outlives } => {
+            format!("{name}: {}", outlives.join(" + "))
+        }
+        types::GenericParamDefKind::Type { bounds: b, default, .. } => {
+            let mut s = name.clone();
+            if !b.is_empty() {
+                s.push_str(&format!(": {}", bounds(b)));
+            }
+            if let Some(default) = default {
+                s.push_str(&format!(" = {}", ty(default)));
+            }
+            s
+        }
+        types::GenericParamDefKind::Const { type_, default } => {
+            let mut s = format!("const {name}: {}", ty(type_));
+            if let Some(default) = default {
+                s.push_str(&format!(" = {default}"));
+            }
+            s
+        }
+    }
+}
+
+fn hrtb(params: &[types::GenericParamDef]) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("for<{}> ", params.iter().map(generic_param).collect::<Vec<_>>().join(", "))
+    }
+}
+
+pub(crate) fn where_clause(generics: &types::Generics) -> String {
+    if generics.where_predicates.is_empty() {
+        return String::new();
+    }
+    let predicates = generics
+        .where_predicates
+        .iter()
+        .map(|predicate| match predicate {
+            types::WherePredicate::BoundPredicate { type_, bounds: b, generic_params } => {
+                format!("{}{}: {}", hrtb(generic_params), ty(type_), bounds(b))
+            }
+            types::WherePredicate::RegionPredicate { lifetime, bounds: b } => {
+                format!("{lifetime}: {}", bounds(b))
+            }
+            types::WherePredicate::EqPredicate { lhs, rhs } => {
+                format!("{} == {}", ty(lhs), term(rhs))
+            }
+        })
+        .collect::<Vec<_>>();
+    format!(" where {}", predicates.join(", "))
+}
+
+pub(crate) fn bounds(bounds: &[types::GenericBound]) -> String {
+    bounds
+        .iter()
+        .map(|bound| match bound {
+            types::GenericBound::TraitBound { trait_, generic_params, modifier } => {
+                let modifier = match modifier {
+                    types::TraitBoundModifier::None => "",
+                    types::TraitBoundModifier::Maybe => "?",
+                    types::TraitBoundModifier::MaybeConst => "~const ",
+                };
+                format!("{}{modifier}{}", hrtb(generic_params), path(trait_))
+            }
+            types::GenericBound::Outlives(lifetime) => lifetime.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn term(term: &types::Term) -> String {
+    match term {
+        types::Term::Type(type_) => ty(type_),
+        types::Term::Constant(constant) => constant.expr.clone(),
+    }
+}
+
+pub(crate) fn path(path: &types::Path) -> String {
+    format!("{}{}", path.name, path_args(path))
+}
+
+fn path_args(path: &types::Path) -> String {
+    path.args.as_deref().map_or(String::new(), generic_args)
+}
+
+fn generic_args(args: &types::GenericArgs) -> String {
+    match args {
+        types::GenericArgs::AngleBracketed { args, bindings } => {
+            let args = args
+                .iter()
+                .map(|arg| match arg {
+                    types::GenericArg::Lifetime(lifetime) => lifetime.clone(),
+                    types::GenericArg::Type(type_) => ty(type_),
+                    types::GenericArg::Const(constant) => constant.expr.clone(),
+                    types::GenericArg::Infer => "_".to_owned(),
+                })
+                .chain(bindings.iter().map(|binding| {
+                    let name = format!("{}{}", binding.name, generic_args(&binding.args));
+                    match &binding.binding {
+                        types::TypeBindingKind::Equality(t) => format!("{name} = {}", term(t)),
+                        types::TypeBindingKind::Constraint(b) => format!("{name}: {}", bounds(b)),
+                    }
+                }))
+                .collect::<Vec<_>>();
+            if args.is_empty() { String::new() } else { format!("<{}>", args.join(", ")) }
+        }
+        types::GenericArgs::Parenthesized { inputs, output } => {
+            let inputs = inputs.iter().map(ty).collect::<Vec<_>>().join(", ");
+            match output {
+                Some(output) => format!("({inputs}) -> {}", ty(output)),
+                None => format!("({inputs})"),
+            }
+        }
+    }
+}
+
+pub(crate) fn ty(type_: &types::Type) -> String {
+    match type_ {
+        types::Type::ResolvedPath(p) => path(p),
+        types::Type::DynTrait(dyn_trait) => {
+            let mut traits = dyn_trait
+                .traits
+                .iter()
+                .map(|poly| format!("{}{}", hrtb(&poly.generic_params), path(&poly.trait_)))
+                .collect::<Vec<_>>();
+            traits.extend(dyn_trait.lifetime.clone());
+            format!("dyn {}", traits.join(" + "))
+        }
+        types::Type::Generic(name) | types::Type::Primitive(name) => name.clone(),
+        types::Type::FunctionPointer(fn_ptr) => {
+            format!(
+                "{}{}fn{}",
+                hrtb(&fn_ptr.generic_params),
+                header(&fn_ptr.header),
+                decl(&fn_ptr.decl)
+            )
+        }
+        types::Type::Tuple(elems) if elems.len() == 1 => format!("({},)", ty(&elems[0])),
+        types::Type::Tuple(elems) => {
+            format!("({})", elems.iter().map(ty).collect::<Vec<_>>().join(", "))
+        }
+        types::Type::Slice(type_) => format!("[{}]", ty(type_)),
+        types::Type::Array { type_, len } => format!("[{}; {len}]", ty(type_)),
+        types::Type::Pat { type_, __pat_unstable_do_not_use: pat } => {
+            format!("{} is {pat}", ty(type_))
+        }
+        types::Type::ImplTrait(b) => format!("impl {}", bounds(b)),
+        types::Type::Infer => "_".to_owned(),
+        types::Type::RawPointer { mutable, type_ } => {
+            format!("*{} {}", if *mutable { "mut" } else { "const" }, ty(type_))
+        }
+        types::Type::BorrowedRef { lifetime, mutable, type_ } => {
+            let lifetime = lifetime.as_ref().map_or(String::new(), |lt| format!("{lt} "));
+            format!("&{lifetime}{}{}", if *mutable { "mut " } else { "" }, ty(type_))
+        }
+        types::Type::QualifiedPath { name, args, self_type, trait_ } => match trait_ {
+            Some(trait_) => {
+                format!("<{} as {}>::{name}{}", ty(self_type), path(trait_), generic_args(args))
+            }
+            None => format!("{}::{name}{}", ty(self_type), generic_args(args)),
+        },
+    }
+}
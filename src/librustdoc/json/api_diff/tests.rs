@@ -0,0 +1,239 @@
+use rustdoc_json_types as types;
+
+use super::{diff, ChangeKind, SemverImpact};
+
+fn id(id: &str) -> types::Id {
+    types::Id(id.to_owned())
+}
+
+fn item(item_id: &str, name: Option<&str>, inner: types::ItemEnum) -> types::Item {
+    types::Item {
+        id: id(item_id),
+        crate_id: 0,
+        name: name.map(str::to_owned),
+        span: None,
+        visibility: types::Visibility::Public,
+        docs: None,
+        links: Default::default(),
+        attrs: vec![],
+        deprecation: None,
+        inner,
+    }
+}
+
+/// Builds a crate named `krate` whose root module contains `top_level`. `items` contains all items
+/// of the crate except for the root module.
+fn krate(top_level: &[&str], items: Vec<types::Item>) -> types::Crate {
+    let root = item(
+        "0:0",
+        Some("krate"),
+        types::ItemEnum::Module(types::Module {
+            is_crate: true,
+            items: top_level.iter().map(|i| id(i)).collect(),
+            is_stripped: false,
+        }),
+    );
+    types::Crate {
+        root: root.id.clone(),
+        crate_version: None,
+        includes_private: false,
+        index: items.into_iter().chain([root]).map(|i| (i.id.clone(), i)).collect(),
+        paths: Default::default(),
+        external_crates: Default::default(),
+        format_version: types::FORMAT_VERSION,
+    }
+}
+
+fn no_generics() -> types::Generics {
+    types::Generics { params: vec![], where_predicates: vec![] }
+}
+
+fn primitive(name: &str) -> types::Type {
+    types::Type::Primitive(name.to_owned())
+}
+
+fn function(is_const: bool, inputs: &[(&str, types::Type)]) -> types::ItemEnum {
+    types::ItemEnum::Function(types::Function {
+        decl: types::FnDecl {
+            inputs: inputs.iter().map(|(name, ty)| (name.to_string(), ty.clone())).collect(),
+            output: None,
+            c_variadic: false,
+        },
+        generics: no_generics(),
+        header: types::Header {
+            const_: is_const,
+            unsafe_: false,
+            async_: false,
+            abi: types::Abi::Rust,
+        },
+        has_body: true,
+    })
+}
+
+fn enum_(variants: &[&str]) -> types::ItemEnum {
+    types::ItemEnum::Enum(types::Enum {
+        generics: no_generics(),
+        variants_stripped: false,
+        variants: variants.iter().map(|v| id(v)).collect(),
+        impls: vec![],
+    })
+}
+
+fn variant() -> types::ItemEnum {
+    types::ItemEnum::Variant(types::Variant { kind: types::VariantKind::Plain, discriminant: None })
+}
+
+fn struct_with_auto_impl(negative: bool) -> Vec<types::Item> {
+    let path = |name: &str, path_id: &str| types::Path {
+        name: name.to_owned(),
+        id: id(path_id),
+        args: None,
+    };
+    vec![
+        item(
+            "0:1",
+            Some("S"),
+            types::ItemEnum::Struct(types::Struct {
+                kind: types::StructKind::Unit,
+                generics: no_generics(),
+                impls: vec![id("a:2")],
+            }),
+        ),
+        item(
+            "a:2",
+            None,
+            types::ItemEnum::Impl(types::Impl {
+                is_unsafe: false,
+                generics: no_generics(),
+                provided_trait_methods: vec![],
+                trait_: Some(path("Send", "2:3")),
+                for_: types::Type::ResolvedPath(path("S", "0:1")),
+                items: vec![],
+                negative,
+                synthetic: true,
+                blanket_impl: None,
+            }),
+        ),
+    ]
+}
+
+#[test]
+fn unchanged_api() {
+    let old = krate(&["0:1"], vec![item("0:1", Some("f"), function(false, &[]))]);
+    let diff = diff(&old, &old);
+    assert_eq!(diff.required_bump, None);
+    assert!(diff.changes.is_empty());
+}
+
+#[test]
+fn items_are_matched_by_path() {
+    let old = krate(&["0:1"], vec![item("0:1", Some("f"), function(false, &[]))]);
+    let new = krate(&["0:7"], vec![item("0:7", Some("f"), function(false, &[]))]);
+    assert!(diff(&old, &new).changes.is_empty());
+}
+
+#[test]
+fn removed_and_added_items() {
+    let old = krate(
+        &["0:1", "0:2"],
+        vec![
+            item("0:1", Some("f"), function(false, &[])),
+            item("0:2", Some("g"), function(false, &[])),
+        ],
+    );
+    let new = krate(
+        &["0:2", "0:3"],
+        vec![
+            item("0:2", Some("g"), function(false, &[])),
+            item("0:3", Some("h"), function(false, &[])),
+        ],
+    );
+    let diff = diff(&old, &new);
+    assert_eq!(diff.required_bump, Some(SemverImpact::Major));
+    let changes = diff
+        .changes
+        .iter()
+        .map(|c| (c.path.as_str(), c.change, c.impact))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            ("krate::f", ChangeKind::Removed, SemverImpact::Major),
+            ("krate::h", ChangeKind::Added, SemverImpact::Minor),
+        ]
+    );
+}
+
+#[test]
+fn changed_signature() {
+    let old = krate(
+        &["0:1"],
+        vec![item("0:1", Some("f"), function(false, &[("x", primitive("u32"))]))],
+    );
+    let new = krate(
+        &["0:1"],
+        vec![item("0:1", Some("f"), function(false, &[("x", primitive("u64"))]))],
+    );
+    let diff = diff(&old, &new);
+    let [change] = &diff.changes[..] else { panic!("expected one change: {:?}", diff.changes) };
+    assert_eq!((change.change, change.impact), (ChangeKind::Changed, SemverImpact::Major));
+    assert_eq!(change.old.as_deref(), Some("fn f(x: u32)"));
+    assert_eq!(change.new.as_deref(), Some("fn f(x: u64)"));
+}
+
+#[test]
+fn const_fn_is_minor() {
+    let old = krate(&["0:1"], vec![item("0:1", Some("f"), function(false, &[]))]);
+    let new = krate(&["0:1"], vec![item("0:1", Some("f"), function(true, &[]))]);
+    assert_eq!(diff(&old, &new).required_bump, Some(SemverImpact::Minor));
+    assert_eq!(diff(&new, &old).required_bump, Some(SemverImpact::Major));
+}
+
+#[test]
+fn new_variants() {
+    let old = krate(
+        &["0:1"],
+        vec![item("0:1", Some("E"), enum_(&["0:2"])), item("0:2", Some("A"), variant())],
+    );
+    let new = krate(
+        &["0:1"],
+        vec![
+            item("0:1", Some("E"), enum_(&["0:2", "0:3"])),
+            item("0:2", Some("A"), variant()),
+            item("0:3", Some("B"), variant()),
+        ],
+    );
+    assert_eq!(diff(&old, &new).required_bump, Some(SemverImpact::Major));
+
+    let non_exhaustive = |mut krate: types::Crate| {
+        krate.index.get_mut(&id("0:1")).unwrap().attrs.push("#[non_exhaustive]".to_owned());
+        krate
+    };
+    let diff = diff(&non_exhaustive(old), &non_exhaustive(new));
+    assert_eq!(diff.required_bump, Some(SemverImpact::Minor));
+    assert_eq!(diff.changes[0].path, "krate::E::B");
+}
+
+#[test]
+fn auto_trait_impls() {
+    let send = krate(&["0:1"], struct_with_auto_impl(false));
+    let not_send = krate(&["0:1"], struct_with_auto_impl(true));
+
+    let lost = diff(&send, &not_send);
+    assert_eq!(lost.required_bump, Some(SemverImpact::Major));
+    assert_eq!(lost.changes[0].item_kind, "auto trait impl");
+    assert_eq!(lost.changes[0].new.as_deref(), Some("impl !Send for S"));
+
+    assert_eq!(diff(&not_send, &send).required_bump, Some(SemverImpact::Minor));
+}
+
+#[test]
+fn deprecation_is_patch() {
+    let old = krate(&["0:1"], vec![item("0:1", Some("f"), function(false, &[]))]);
+    let mut new = old.clone();
+    new.index.get_mut(&id("0:1")).unwrap().deprecation =
+        Some(types::Deprecation { since: Some("1.1.0".to_owned()), note: None });
+    let diff = diff(&old, &new);
+    assert_eq!(diff.required_bump, Some(SemverImpact::Patch));
+    assert_eq!(diff.changes[0].change, ChangeKind::Deprecated);
+}
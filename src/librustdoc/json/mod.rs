@@ -4,7 +4,8 @@
 //! output. See [the RFC](https://github.com/rust-lang/rfcs/pull/2963) and the [`types`] module
 //! docs for usage and details.
 
-mod conversions;
+pub(crate) mod api_diff;
+pub(crate) mod conversions;
 mod import_finder;
 
 use std::cell::RefCell;
@@ -41,6 +42,8 @@ pub(crate) struct JsonRenderer<'tcx> {
     out_path: PathBuf,
     cache: Rc<Cache>,
     imported_items: DefIdSet,
+    /// If set, the crate is compared with this JSON file instead of being written out.
+    api_diff_baseline: Option<PathBuf>,
 }
 
 impl<'tcx> JsonRenderer<'tcx> {
@@ -124,6 +127,7 @@ fn init(
                 out_path: options.output,
                 cache: Rc::new(cache),
                 imported_items,
+                api_diff_baseline: options.api_diff_baseline,
             },
             krate,
         ))
@@ -275,8 +279,16 @@ fn after_krate(&mut self) -> Result<(), Error> {
         let out_dir = self.out_path.clone();
         try_err!(create_dir_all(&out_dir), out_dir);
 
+        let crate_name = output.index.get(&output.root).unwrap().name.clone().unwrap();
+        if let Some(baseline) = &self.api_diff_baseline {
+            let out_path = out_dir.join(format!("{crate_name}-api-diff.json"));
+            return self.tcx.sess.time("rustdoc_api_diff", || {
+                api_diff::render_api_diff(baseline, &output, &out_path)
+            });
+        }
+
         let mut p = out_dir;
-        p.push(output.index.get(&output.root).unwrap().name.clone().unwrap());
+        p.push(crate_name);
         p.set_extension("json");
         let mut file = BufWriter::new(try_err!(File::create(&p), p));
         self.tcx
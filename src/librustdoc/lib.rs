@@ -117,6 +117,7 @@ macro_rules! map {
 mod json;
 pub(crate) mod lint;
 mod markdown;
+mod md;
 mod passes;
 mod scrape_examples;
 mod theme;
@@ -596,6 +597,14 @@ fn opts() -> Vec<RustcOptGroup> {
                 "path to function call information (for displaying examples in the documentation)",
             )
         }),
+        unstable("check-scraped-examples", |o| {
+            o.optflag(
+                "",
+                "check-scraped-examples",
+                "record, or check, whether the source or called function of examples changed since \
+                 they were scraped",
+            )
+        }),
         // deprecated / removed options
         unstable("disable-minification", |o| o.optflagmulti("", "disable-minification", "removed")),
         stable("plugin-path", |o| {
@@ -645,6 +654,51 @@ fn opts() -> Vec<RustcOptGroup> {
         unstable("html-no-source", |o| {
             o.optflag("", "html-no-source", "Disable HTML source code pages generation")
         }),
+        unstable("baseline", |o| {
+            o.optopt(
+                "",
+                "baseline",
+                "JSON output of a previous version of the crate to compare against with \
+                 `--output-format api-diff`",
+                "PATH",
+            )
+        }),
+        unstable("full-text-search", |o| {
+            o.optflag("", "full-text-search", "Index the text of doc comments for the search")
+        }),
+        unstable("doctest-cache-dir", |o| {
+            o.optopt(
+                "",
+                "doctest-cache-dir",
+                "Directory to cache the results of passing doctests",
+                "PATH",
+            )
+        }),
+        unstable("merge-doctests", |o| {
+            o.optflag("", "merge-doctests", "Compile the doctests of a crate into one binary")
+        }),
+        unstable("coverage-detail", |o| {
+            o.optflag(
+                "",
+                "coverage-detail",
+                "List the items missing documentation or examples with --show-coverage",
+            )
+        }),
+        unstable("coverage-threshold", |o| {
+            o.optopt(
+                "",
+                "coverage-threshold",
+                "Fail --show-coverage if less than this percentage of items are documented",
+                "PERCENTAGE",
+            )
+        }),
+        unstable("merge-docs", |o| {
+            o.optflag(
+                "",
+                "merge-docs",
+                "Merge the given documentation directories into the output directory",
+            )
+        }),
     ]
 }
 
@@ -728,6 +782,16 @@ fn main_args(
     let diag =
         core::new_dcx(options.error_format, None, options.diagnostic_width, &options.unstable_opts);
 
+    if !options.merge_docs.is_empty() {
+        let res = html::render::merge_docs(
+            &options.merge_docs,
+            &render_options.output,
+            &render_options.resource_suffix,
+        )
+        .map_err(|err| format!("couldn't merge documentation: {err}"));
+        return wrap_return(&diag, res);
+    }
+
     match (options.should_test, options.markdown_input()) {
         (true, Some(_)) => return wrap_return(&diag, markdown::test(options)),
         (true, None) => return doctest::run(&diag, options),
@@ -816,8 +880,16 @@ fn main_args(
                     config::OutputFormat::Html => sess.time("render_html", || {
                         run_renderer::<html::render::Context<'_>>(krate, render_opts, cache, tcx)
                     }),
-                    config::OutputFormat::Json => sess.time("render_json", || {
-                        run_renderer::<json::JsonRenderer<'_>>(krate, render_opts, cache, tcx)
+                    config::OutputFormat::Json | config::OutputFormat::ApiDiff => {
+                        sess.time("render_json", || {
+                            run_renderer::<json::JsonRenderer<'_>>(krate, render_opts, cache, tcx)
+                        })
+                    }
+                    config::OutputFormat::Markdown => sess.time("render_markdown", || {
+                        run_renderer::<md::MarkdownRenderer<'_>>(krate, render_opts, cache, tcx)
+                    }),
+                    config::OutputFormat::Man => sess.time("render_man", || {
+                        run_renderer::<md::ManRenderer<'_>>(krate, render_opts, cache, tcx)
                     }),
                 }
             })
@@ -0,0 +1,861 @@
+//! Rustdoc's Markdown backend
+//!
+//! This module renders a crate as a tree of Markdown files instead of HTML pages. The layout
+//! mirrors the HTML output: every module gets a directory with an `index.md` listing its items,
+//! and every other item gets a `{type}.{name}.md` page next to it. Signatures are printed from the
+//! JSON representation of the items, and intra-doc links are rewritten to relative paths between
+//! the generated files.
+//!
+//! The same pages can also be written as man pages, with `--output-format man`. They are converted
+//! from Markdown to roff when they are written, and all go in a flat `man3` directory where links
+//! between them are man page references.
+
+#[cfg(test)]
+mod tests;
+
+use std::fmt::Write as _;
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use pulldown_cmark::{
+    BrokenLink, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Parser, Tag,
+};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_resolve::rustdoc::main_body_opts;
+use rustc_span::symbol::Symbol;
+
+use rustdoc_json_types as types;
+
+use crate::clean::types::ExternalLocation;
+use crate::clean::{self, ItemLink, RenderedLink};
+use crate::config::RenderOptions;
+use crate::docfs::PathError;
+use crate::error::Error;
+use crate::formats::cache::Cache;
+use crate::formats::item_type::ItemType;
+use crate::formats::{FormatRenderer, Impl};
+use crate::html::format::href_relative_parts;
+use crate::html::markdown::{plain_text_summary, visible_code};
+use crate::html::render::{item_ty_to_section, ItemSection};
+use crate::json::api_diff::{bounds, fn_signature, generics, path, ty, where_clause};
+use crate::json::conversions::{from_clean_item, IntoWithTcx};
+use crate::passes::collect_intra_doc_links::UrlFragment;
+use crate::try_err;
+
+/// The format the pages are written in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PageFormat {
+    Markdown,
+    Man,
+}
+
+#[derive(Clone)]
+pub(crate) struct MarkdownRenderer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    format: PageFormat,
+    /// Path of the module being rendered, starting with the crate name.
+    current: Vec<Symbol>,
+    /// The directory the pages of the current module are written to. Man pages are all written
+    /// to the `man3` directory below the output directory instead.
+    dst: PathBuf,
+    cache: Rc<Cache>,
+}
+
+impl<'tcx> MarkdownRenderer<'tcx> {
+    fn new(options: RenderOptions, cache: Cache, tcx: TyCtxt<'tcx>, format: PageFormat) -> Self {
+        MarkdownRenderer {
+            tcx,
+            format,
+            current: Vec::new(),
+            dst: options.output,
+            cache: Rc::new(cache),
+        }
+    }
+
+    /// Returns the link to the page of `item` in `module`, or of `module` itself if `item` is
+    /// `None`, from a page of the current module.
+    fn local_href(&self, module: &[Symbol], item: Option<(ItemType, Symbol)>) -> String {
+        if self.format == PageFormat::Man {
+            return format!("{}(3)", man_page(module, item));
+        }
+        let mut href = String::new();
+        for part in href_relative_parts(module, &self.current) {
+            let _ = write!(href, "{part}/");
+        }
+        match item {
+            Some((item_type, name)) => {
+                let _ = write!(href, "{item_type}.{name}.md");
+            }
+            None => href.push_str("index.md"),
+        }
+        href
+    }
+
+    /// Returns the path of the page documenting `did`, relative to the current module. Items of
+    /// crates documented elsewhere are linked to their HTML documentation instead.
+    fn href(&self, did: DefId, fragment: &Option<UrlFragment>) -> Option<String> {
+        let cache = &self.cache;
+        let (fqp, item_type) = cache.paths.get(&did).or_else(|| cache.external_paths.get(&did))?;
+        let (module, file_name) = match item_type {
+            ItemType::Module => (&fqp[..], None),
+            _ => (&fqp[..fqp.len() - 1], fqp.last()),
+        };
+        let location = if did.is_local() {
+            &ExternalLocation::Local
+        } else {
+            cache.extern_locations.get(&did.krate)?
+        };
+        let mut href = match location {
+            // A man page can't be linked to a section of another one, so links to members point
+            // to the page of their parent.
+            ExternalLocation::Local if self.format == PageFormat::Man => {
+                return Some(self.local_href(module, file_name.map(|name| (*item_type, *name))));
+            }
+            ExternalLocation::Local => {
+                self.local_href(module, file_name.map(|name| (*item_type, *name)))
+            }
+            ExternalLocation::Remote(url) => {
+                let mut href = url.clone();
+                if !href.ends_with('/') {
+                    href.push('/');
+                }
+                for part in module {
+                    let _ = write!(href, "{part}/");
+                }
+                match file_name {
+                    Some(name) => {
+                        let _ = write!(href, "{item_type}.{name}.html");
+                    }
+                    None => href.push_str("index.html"),
+                }
+                href
+            }
+            ExternalLocation::Unknown => return None,
+        };
+        if let Some(fragment) = fragment {
+            fragment.render(&mut href, self.tcx);
+        }
+        Some(href)
+    }
+
+    /// Returns the intra-doc links of `item` that point to a page we can link to.
+    fn links(&self, item: &clean::Item) -> Vec<RenderedLink> {
+        let Some(links) = self.cache.intra_doc_links.get(&item.item_id) else {
+            return vec![];
+        };
+        links
+            .iter()
+            .filter_map(|ItemLink { link, link_text, page_id, fragment }| {
+                Some(RenderedLink {
+                    original_text: link.clone(),
+                    new_text: link_text.clone(),
+                    href: self.href(*page_id, fragment)?,
+                    tooltip: String::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// Writes the documentation of `item`, with its headings nested below a heading of level
+    /// `level`.
+    fn docs(&self, item: &clean::Item, level: usize, out: &mut String) {
+        if let Some(doc) = item.opt_doc_value() {
+            out.push_str(rewrite_docs(&doc, &self.links(item), level).trim_end());
+            out.push_str("\n\n");
+        }
+    }
+
+    /// Writes an item that is documented on the page of its parent, such as a field, a variant
+    /// or an associated item. The anchor matches the fragment of intra-doc links to it.
+    fn member(&self, item: &clean::Item, level: usize, out: &mut String) {
+        let (Some(name), Some(signature)) = (item.name, signature(item, self.tcx)) else {
+            return;
+        };
+        let _ = write!(
+            out,
+            "<a id=\"{}.{name}\"></a>\n\n{} `{signature}`\n\n",
+            item.type_(),
+            "#".repeat(level),
+        );
+        self.docs(item, level, out);
+    }
+
+    fn module_page(&self, item: &clean::Item, module: &clean::Module) -> String {
+        let mut out = format!(
+            "# {} {}\n\n",
+            if item.is_crate() { "Crate" } else { "Module" },
+            join_path(&self.current),
+        );
+        self.docs(item, 1, &mut out);
+
+        let mut items = module
+            .items
+            .iter()
+            .filter(|item| !item.is_stripped() && (item.name.is_some() || item.is_import()))
+            .map(|item| (item_ty_to_section(item.type_()), item))
+            .collect::<Vec<_>>();
+        items.sort_by_cached_key(|(_, item)| item.name.map(|name| name.to_string()));
+        for &section in ItemSection::ALL {
+            let mut items = items.iter().filter(|(s, _)| *s == section).peekable();
+            if items.peek().is_none() {
+                continue;
+            }
+            let _ = write!(out, "## {}\n\n", section.name());
+            for (_, item) in items {
+                if section == ItemSection::Reexports {
+                    if let Some(signature) = signature(item, self.tcx) {
+                        let _ = writeln!(out, "- `{signature}`");
+                    }
+                    continue;
+                }
+                let name = item.name.unwrap();
+                let href = match item.type_() {
+                    ItemType::Module => {
+                        let mut module = self.current.clone();
+                        module.push(name);
+                        self.local_href(&module, None)
+                    }
+                    item_type => self.local_href(&self.current, Some((item_type, name))),
+                };
+                let _ = write!(out, "- [{name}]({href})");
+                let summary = plain_text_summary(&item.doc_value(), &item.link_names(&self.cache));
+                if !summary.is_empty() {
+                    let _ = write!(out, ": {summary}");
+                }
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn item_page(&self, item: &clean::Item) -> String {
+        let mut out = format!(
+            "# {} {}::{}\n\n",
+            title(item.type_()),
+            join_path(&self.current),
+            item.name.unwrap(),
+        );
+        if let Some(signature) = signature(item, self.tcx) {
+            let _ = write!(out, "```rust\n{signature}\n```\n\n");
+        }
+        self.docs(item, 1, &mut out);
+
+        match *item.kind {
+            clean::StructItem(clean::Struct { ref fields, .. })
+            | clean::UnionItem(clean::Union { ref fields, .. }) => {
+                self.members("Fields", fields.iter(), &mut out);
+            }
+            clean::EnumItem(ref enum_) => {
+                self.members("Variants", enum_.variants.iter(), &mut out);
+            }
+            clean::TraitItem(ref trait_) => {
+                let (required, provided): (Vec<_>, Vec<_>) = trait_.items.iter().partition(|item| {
+                    matches!(
+                        *item.kind,
+                        clean::TyMethodItem(_)
+                            | clean::TyAssocConstItem(..)
+                            | clean::TyAssocTypeItem(..)
+                    )
+                });
+                self.members("Required Items", required.into_iter(), &mut out);
+                self.members("Provided Items", provided.into_iter(), &mut out);
+                if let Some(implementors) = self.cache.implementors.get(&trait_.def_id) {
+                    self.impl_list("Implementors", implementors.iter(), &mut out);
+                }
+            }
+            _ => {}
+        }
+        if let Some(impls) = item.def_id().and_then(|did| self.cache.impls.get(&did)) {
+            let (inherent, trait_impls): (Vec<_>, Vec<_>) =
+                impls.iter().partition(|i| i.inner_impl().trait_.is_none());
+            if !inherent.is_empty() {
+                out.push_str("## Implementations\n\n");
+                for i in inherent {
+                    let _ = write!(out, "### `{}`\n\n", impl_signature(i.inner_impl(), self.tcx));
+                    self.docs(&i.impl_item, 3, &mut out);
+                    for item in i.inner_impl().items.iter().filter(|item| !item.is_stripped()) {
+                        self.member(item, 4, &mut out);
+                    }
+                }
+            }
+            self.impl_list("Trait Implementations", trait_impls.into_iter(), &mut out);
+        }
+        out
+    }
+
+    fn members<'a>(
+        &self,
+        heading: &str,
+        members: impl Iterator<Item = &'a clean::Item>,
+        out: &mut String,
+    ) {
+        let mut members = members.filter(|item| !item.is_stripped()).peekable();
+        if members.peek().is_some() {
+            let _ = write!(out, "## {heading}\n\n");
+            for item in members {
+                self.member(item, 3, out);
+            }
+        }
+    }
+
+    fn impl_list<'a>(
+        &self,
+        heading: &str,
+        impls: impl Iterator<Item = &'a Impl>,
+        out: &mut String,
+    ) {
+        let mut impls =
+            impls.map(|i| impl_signature(i.inner_impl(), self.tcx)).collect::<Vec<_>>();
+        if impls.is_empty() {
+            return;
+        }
+        impls.sort();
+        impls.dedup();
+        let _ = write!(out, "## {heading}\n\n");
+        for signature in impls {
+            let _ = writeln!(out, "- `{signature}`");
+        }
+        out.push('\n');
+    }
+
+    /// Writes the page of `item` in the current module, or of the current module itself if `item`
+    /// is `None`.
+    fn write(&self, item: Option<(ItemType, Symbol)>, contents: String) -> Result<(), Error> {
+        let (dir, file_name, contents) = match self.format {
+            PageFormat::Markdown => {
+                let file_name = match item {
+                    Some((item_type, name)) => format!("{item_type}.{name}.md"),
+                    None => "index.md".to_owned(),
+                };
+                (self.dst.clone(), file_name, contents)
+            }
+            PageFormat::Man => {
+                let page = man_page(&self.current, item);
+                let contents = markdown_to_roff(&page, &contents);
+                (self.dst.join("man3"), format!("{page}.3"), contents)
+            }
+        };
+        try_err!(fs::create_dir_all(&dir), &dir);
+        let path = dir.join(file_name);
+        try_err!(fs::write(&path, contents.trim_end().to_owned() + "\n"), &path);
+        Ok(())
+    }
+}
+
+impl<'tcx> FormatRenderer<'tcx> for MarkdownRenderer<'tcx> {
+    fn descr() -> &'static str {
+        "markdown"
+    }
+
+    const RUN_ON_MODULE: bool = true;
+
+    fn init(
+        krate: clean::Crate,
+        options: RenderOptions,
+        cache: Cache,
+        tcx: TyCtxt<'tcx>,
+    ) -> Result<(Self, clean::Crate), Error> {
+        debug!("Initializing markdown renderer");
+
+        Ok((MarkdownRenderer::new(options, cache, tcx, PageFormat::Markdown), krate))
+    }
+
+    fn make_child_renderer(&self) -> Self {
+        self.clone()
+    }
+
+    fn item(&mut self, item: clean::Item) -> Result<(), Error> {
+        // Re-exports are listed in their module, and impls on the page of their type.
+        if item.is_stripped()
+            || matches!(item.type_(), ItemType::Import | ItemType::ExternCrate | ItemType::Impl)
+        {
+            return Ok(());
+        }
+        let page = self.item_page(&item);
+        self.write(Some((item.type_(), item.name.unwrap())), page)
+    }
+
+    fn mod_item_in(&mut self, item: &clean::Item) -> Result<(), Error> {
+        let item_name = item.name.unwrap();
+        if self.format == PageFormat::Markdown {
+            self.dst.push(item_name.as_str());
+        }
+        self.current.push(item_name);
+
+        // Stripped modules are still recursed into, as they can contain re-exported items.
+        if let clean::ModuleItem(ref module) = *item.kind {
+            let page = self.module_page(item, module);
+            self.write(None, page)?;
+        }
+        Ok(())
+    }
+
+    fn mod_item_out(&mut self) -> Result<(), Error> {
+        if self.format == PageFormat::Markdown {
+            self.dst.pop();
+        }
+        self.current.pop();
+        Ok(())
+    }
+
+    fn after_krate(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn cache(&self) -> &Cache {
+        &self.cache
+    }
+}
+
+/// Renders the same pages as [`MarkdownRenderer`], as man pages in section 3.
+#[derive(Clone)]
+pub(crate) struct ManRenderer<'tcx>(MarkdownRenderer<'tcx>);
+
+impl<'tcx> FormatRenderer<'tcx> for ManRenderer<'tcx> {
+    fn descr() -> &'static str {
+        "man"
+    }
+
+    const RUN_ON_MODULE: bool = true;
+
+    fn init(
+        krate: clean::Crate,
+        options: RenderOptions,
+        cache: Cache,
+        tcx: TyCtxt<'tcx>,
+    ) -> Result<(Self, clean::Crate), Error> {
+        debug!("Initializing man renderer");
+
+        Ok((ManRenderer(MarkdownRenderer::new(options, cache, tcx, PageFormat::Man)), krate))
+    }
+
+    fn make_child_renderer(&self) -> Self {
+        self.clone()
+    }
+
+    fn item(&mut self, item: clean::Item) -> Result<(), Error> {
+        self.0.item(item)
+    }
+
+    fn mod_item_in(&mut self, item: &clean::Item) -> Result<(), Error> {
+        self.0.mod_item_in(item)
+    }
+
+    fn mod_item_out(&mut self) -> Result<(), Error> {
+        self.0.mod_item_out()
+    }
+
+    fn after_krate(&mut self) -> Result<(), Error> {
+        self.0.after_krate()
+    }
+
+    fn cache(&self) -> &Cache {
+        self.0.cache()
+    }
+}
+
+fn join_path(path: &[Symbol]) -> String {
+    path.iter().map(|s| s.as_str()).collect::<Vec<_>>().join("::")
+}
+
+/// Returns the name of the man page documenting `item` in `module`, or `module` itself if `item` is
+/// `None`. All the pages are in the same directory, so the name contains the path of the item, and
+/// its type to tell apart items of different namespaces.
+fn man_page(module: &[Symbol], item: Option<(ItemType, Symbol)>) -> String {
+    let mut page = module.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(".");
+    if let Some((item_type, name)) = item {
+        let _ = write!(page, ".{item_type}.{name}");
+    }
+    page
+}
+
+fn title(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::Module => "Module",
+        ItemType::ExternCrate => "Extern Crate",
+        ItemType::Import => "Re-export",
+        ItemType::Struct => "Struct",
+        ItemType::Enum => "Enum",
+        ItemType::Function => "Function",
+        ItemType::TypeAlias => "Type Alias",
+        ItemType::Static => "Static",
+        ItemType::Trait => "Trait",
+        ItemType::Impl => "Impl",
+        ItemType::TyMethod | ItemType::Method => "Method",
+        ItemType::StructField => "Field",
+        ItemType::Variant => "Variant",
+        ItemType::Macro => "Macro",
+        ItemType::AssocType => "Associated Type",
+        ItemType::Constant => "Constant",
+        ItemType::AssocConst => "Associated Constant",
+        ItemType::Union => "Union",
+        ItemType::ForeignType => "Foreign Type",
+        ItemType::OpaqueTy => "Opaque Type",
+        ItemType::Keyword => "Keyword",
+        ItemType::Primitive => "Primitive Type",
+        ItemType::ProcAttribute => "Attribute Macro",
+        ItemType::ProcDerive => "Derive Macro",
+        ItemType::TraitAlias => "Trait Alias",
+    }
+}
+
+/// Returns the declaration of `item` without its body, or `None` for items that don't have one.
+fn signature(item: &clean::Item, tcx: TyCtxt<'_>) -> Option<String> {
+    let name = item.name.map(|name| name.to_string()).unwrap_or_default();
+    let fields = |fields: &[clean::Item]| {
+        fields
+            .iter()
+            .map(|field| match *field.kind {
+                clean::StructFieldItem(ref type_) => {
+                    let type_ = ty(&type_.clone().into_tcx(tcx));
+                    match field.name {
+                        Some(name) if !name.as_str().starts_with(char::is_numeric) => {
+                            format!("{name}: {type_}")
+                        }
+                        _ => type_,
+                    }
+                }
+                _ => "_".to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let signature = match *item.kind {
+        clean::ModuleItem(_)
+        | clean::PrimitiveItem(_)
+        | clean::KeywordItem
+        | clean::StrippedItem(_)
+        | clean::ImplItem(_) => return None,
+        clean::VariantItem(clean::Variant { ref kind, .. }) => match kind {
+            clean::VariantKind::CLike => name,
+            clean::VariantKind::Tuple(tuple) => format!("{name}({})", fields(tuple)),
+            clean::VariantKind::Struct(s) => format!("{name} {{ {} }}", fields(&s.fields)),
+        },
+        _ => match from_clean_item(item.clone(), tcx) {
+            types::ItemEnum::ExternCrate { name, rename: Some(rename) } => {
+                format!("extern crate {name} as {rename}")
+            }
+            types::ItemEnum::ExternCrate { name, rename: None } => format!("extern crate {name}"),
+            types::ItemEnum::Import(import) if import.glob => {
+                format!("pub use {}::*", import.source)
+            }
+            types::ItemEnum::Import(import) if import.source.ends_with(&*import.name) => {
+                format!("pub use {}", import.source)
+            }
+            types::ItemEnum::Import(import) => {
+                format!("pub use {} as {}", import.source, import.name)
+            }
+            types::ItemEnum::Struct(types::Struct { generics: g, .. })
+            | types::ItemEnum::Union(types::Union { generics: g, .. })
+            | types::ItemEnum::Enum(types::Enum { generics: g, .. }) => format!(
+                "{} {name}{}{}",
+                item.type_(),
+                generics(&g),
+                where_clause(&g),
+            ),
+            types::ItemEnum::StructField(type_) => format!("{name}: {}", ty(&type_)),
+            types::ItemEnum::Function(function) => fn_signature(&name, &function),
+            types::ItemEnum::Trait(trait_) => format!(
+                "{}{}trait {name}{}{}{}",
+                if trait_.is_unsafe { "unsafe " } else { "" },
+                if trait_.is_auto { "auto " } else { "" },
+                generics(&trait_.generics),
+                if trait_.bounds.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", bounds(&trait_.bounds))
+                },
+                where_clause(&trait_.generics),
+            ),
+            types::ItemEnum::TraitAlias(alias) => format!(
+                "trait {name}{} = {}{}",
+                generics(&alias.generics),
+                bounds(&alias.params),
+                where_clause(&alias.generics),
+            ),
+            types::ItemEnum::TypeAlias(alias) => format!(
+                "type {name}{}{} = {}",
+                generics(&alias.generics),
+                where_clause(&alias.generics),
+                ty(&alias.type_),
+            ),
+            types::ItemEnum::Constant(constant) => format!("const {name}: {}", ty(&constant.type_)),
+            types::ItemEnum::Static(static_) => format!(
+                "static {}{name}: {}",
+                if static_.mutable { "mut " } else { "" },
+                ty(&static_.type_),
+            ),
+            types::ItemEnum::ForeignType => format!("type {name}"),
+            types::ItemEnum::Macro(source) => source,
+            types::ItemEnum::ProcMacro(proc_macro) => match proc_macro.kind {
+                types::MacroKind::Bang => format!("{name}!"),
+                types::MacroKind::Attr => format!("#[{name}]"),
+                types::MacroKind::Derive => format!("#[derive({name})]"),
+            },
+            types::ItemEnum::AssocConst { type_, .. } => format!("const {name}: {}", ty(&type_)),
+            types::ItemEnum::AssocType { generics: g, bounds: b, default } => format!(
+                "type {name}{}{}{}{}",
+                generics(&g),
+                if b.is_empty() { String::new() } else { format!(": {}", bounds(&b)) },
+                where_clause(&g),
+                default.map(|default| format!(" = {}", ty(&default))).unwrap_or_default(),
+            ),
+            types::ItemEnum::Module(_)
+            | types::ItemEnum::Variant(_)
+            | types::ItemEnum::Impl(_)
+            | types::ItemEnum::OpaqueTy(_)
+            | types::ItemEnum::Primitive(_) => return None,
+        },
+    };
+    Some(signature)
+}
+
+fn impl_signature(impl_: &clean::Impl, tcx: TyCtxt<'_>) -> String {
+    let impl_: types::Impl = impl_.clone().into_tcx(tcx);
+    let trait_ = match &impl_.trait_ {
+        Some(trait_) => format!("{}{} for ", if impl_.negative { "!" } else { "" }, path(trait_)),
+        None => String::new(),
+    };
+    format!(
+        "{}impl{} {trait_}{}{}",
+        if impl_.is_unsafe { "unsafe " } else { "" },
+        generics(&impl_.generics),
+        ty(&impl_.for_),
+        where_clause(&impl_.generics),
+    )
+}
+
+/// Rewrites the intra-doc links of a doc comment to the destinations given in `links`, and moves
+/// its headings `heading_offset` levels down so they nest below the heading of the item.
+///
+/// Links are rewritten in the source rather than by re-rendering the parsed Markdown, so the rest
+/// of the documentation is kept exactly as it was written.
+fn rewrite_docs(doc: &str, links: &[RenderedLink], heading_offset: usize) -> String {
+    let find_link = |dest: &str| links.iter().find(|link| *link.original_text == *dest);
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut definitions = String::new();
+
+    // `[text][label]` with `[label]: path` written out: rewrite the definition.
+    let parser = Parser::new_ext(doc, main_body_opts());
+    for (_, definition) in parser.reference_definitions().iter() {
+        if let Some(link) = find_link(&definition.dest)
+            && let Some(start) = doc[definition.span.clone()].find(&*definition.dest)
+        {
+            let start = definition.span.start + start;
+            edits.push((start..start + definition.dest.len(), link.href.clone()));
+        }
+    }
+
+    let mut callback = |broken_link: BrokenLink<'_>| {
+        let link = find_link(&broken_link.reference)?;
+        Some((CowStr::from(link.href.clone()), CowStr::Borrowed("")))
+    };
+    let parser = Parser::new_with_broken_link_callback(doc, main_body_opts(), Some(&mut callback));
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(level, ..)) if heading_offset > 0 => {
+                // Setext headings (underlined with `=` or `-`) only have two levels, so they are
+                // kept as they are.
+                let source = &doc[range.clone()];
+                if source.trim_start_matches(' ').starts_with('#') {
+                    let start = range.start + source.find('#').unwrap();
+                    let offset = heading_offset.min(6 - level as usize);
+                    edits.push((start..start, "#".repeat(offset)));
+                }
+            }
+            // `[fn@f]` or `[fn@f][]`, resolved by the callback: spell out the destination and
+            // drop the disambiguator from the text.
+            Event::Start(Tag::Link(
+                LinkType::ShortcutUnknown | LinkType::CollapsedUnknown,
+                dest,
+                _,
+            )) => {
+                if let Some(link) = links.iter().find(|link| *link.href == *dest) {
+                    let text = if doc[range.clone()].starts_with("[`") {
+                        format!("`{}`", link.new_text)
+                    } else {
+                        link.new_text.to_string()
+                    };
+                    edits.push((range, format!("[{text}]({})", link.href)));
+                }
+            }
+            // `[text][fn@f]`, resolved by the callback: define the reference.
+            Event::Start(Tag::Link(LinkType::ReferenceUnknown, dest, _)) => {
+                if let Some(link) = links.iter().find(|link| *link.href == *dest) {
+                    let _ = write!(definitions, "\n[{}]: {}", link.original_text, link.href);
+                }
+            }
+            Event::Start(Tag::Link(LinkType::Inline, dest, _)) => {
+                if let Some(link) = find_link(&dest)
+                    && let Some(start) = doc[range.clone()].rfind(&*dest)
+                {
+                    let start = range.start + start;
+                    edits.push((start..start + dest.len(), link.href.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut out = String::with_capacity(doc.len());
+    let mut last = 0;
+    for (range, replacement) in edits {
+        out.push_str(&doc[last..range.start]);
+        out.push_str(&replacement);
+        last = range.end;
+    }
+    out.push_str(&doc[last..]);
+    if !definitions.is_empty() {
+        out.push('\n');
+        out.push_str(&definitions);
+    }
+    out
+}
+
+/// Converts a page written by [`MarkdownRenderer`] to the roff format of man pages.
+///
+/// Only the Markdown the pages are made of is handled: the title of the page becomes its `NAME`
+/// section, the other headings become sections and subsections, and code blocks are shown as they
+/// are in the HTML output. Raw HTML, such as the anchors of members, is dropped.
+fn markdown_to_roff(page: &str, markdown: &str) -> String {
+    let mut out = format!(".TH \"{page}\" 3\n");
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut item_start = false;
+    let mut links = Vec::new();
+    let mut code_block: Option<(CodeBlockKind<'_>, String)> = None;
+
+    // Starts a new paragraph, keeping the indentation of the list item it is in.
+    let paragraph = |out: &mut String, lists: &[Option<u64>], item_start: &mut bool| {
+        if lists.is_empty() {
+            control(out, ".PP");
+        } else if !std::mem::take(item_start) {
+            control(out, ".sp");
+        }
+    };
+
+    for event in Parser::new_ext(markdown, main_body_opts()) {
+        if let Some((_, code)) = &mut code_block {
+            match event {
+                Event::Text(text) => {
+                    code.push_str(&text);
+                    continue;
+                }
+                Event::End(Tag::CodeBlock(_)) => {}
+                _ => continue,
+            }
+        }
+        match event {
+            Event::Start(Tag::Heading(HeadingLevel::H1, ..)) => {
+                control(&mut out, ".SH NAME");
+                push_roff(&mut out, page);
+                out.push_str(" \\- ");
+            }
+            Event::Start(Tag::Heading(HeadingLevel::H2, ..)) => {
+                end_line(&mut out);
+                out.push_str(".SH ");
+            }
+            Event::Start(Tag::Heading(..)) => {
+                end_line(&mut out);
+                out.push_str(".SS ");
+            }
+            Event::Start(Tag::Paragraph) => paragraph(&mut out, &lists, &mut item_start),
+            Event::End(Tag::Heading(..) | Tag::Paragraph | Tag::Item) => end_line(&mut out),
+            Event::Start(Tag::BlockQuote) => control(&mut out, ".RS 4"),
+            Event::End(Tag::BlockQuote) => control(&mut out, ".RE"),
+            Event::Start(Tag::CodeBlock(kind)) => {
+                paragraph(&mut out, &lists, &mut item_start);
+                code_block = Some((kind, String::new()));
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                let (kind, code) = code_block.take().unwrap();
+                control(&mut out, ".RS 4\n.nf");
+                push_roff(&mut out, &visible_code(&kind, &code));
+                control(&mut out, ".fi\n.RE");
+            }
+            Event::Start(Tag::List(start)) => {
+                if !lists.is_empty() {
+                    control(&mut out, ".RS 2");
+                }
+                lists.push(start);
+            }
+            Event::End(Tag::List(_)) => {
+                lists.pop();
+                if !lists.is_empty() {
+                    control(&mut out, ".RE");
+                }
+            }
+            Event::Start(Tag::Item) => {
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        control(&mut out, &format!(".IP {number}. 4"));
+                        *number += 1;
+                    }
+                    _ => control(&mut out, ".IP \\(bu 2"),
+                }
+                item_start = true;
+            }
+            Event::Start(Tag::Emphasis) => out.push_str("\\fI"),
+            Event::Start(Tag::Strong) => out.push_str("\\fB"),
+            Event::End(Tag::Emphasis | Tag::Strong) => out.push_str("\\fR"),
+            Event::Start(Tag::Link(_, dest, _)) => links.push(dest),
+            Event::End(Tag::Link(..)) => {
+                let dest = links.pop().unwrap();
+                if let Some(page) = dest.strip_suffix("(3)") {
+                    out.push_str(" (\\fB");
+                    push_roff(&mut out, page);
+                    out.push_str("\\fR(3))");
+                } else if !dest.starts_with('#') {
+                    push_roff(&mut out, &format!(" <{dest}>"));
+                }
+            }
+            Event::End(Tag::TableCell) => out.push('\t'),
+            Event::End(Tag::TableHead | Tag::TableRow) => control(&mut out, ".br"),
+            Event::Text(text) => push_roff(&mut out, &text),
+            Event::Code(code) => {
+                out.push_str("\\fB");
+                push_roff(&mut out, &code);
+                out.push_str("\\fR");
+            }
+            Event::SoftBreak => out.push('\n'),
+            Event::HardBreak => control(&mut out, ".br"),
+            Event::Rule => control(&mut out, ".sp"),
+            Event::FootnoteReference(name) => push_roff(&mut out, &format!("[{name}]")),
+            Event::TaskListMarker(checked) => out.push_str(if checked { "[x] " } else { "[ ] " }),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Ends the current line of a roff document, if it isn't empty.
+fn end_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Writes a roff request on a line of its own.
+fn control(out: &mut String, request: &str) {
+    end_line(out);
+    out.push_str(request);
+    out.push('\n');
+}
+
+/// Appends text to a roff document, escaping the characters roff would interpret.
+fn push_roff(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if matches!(c, '.' | '\'') && (out.is_empty() || out.ends_with('\n')) {
+            out.push_str("\\&");
+        }
+        match c {
+            '\\' => out.push_str("\\e"),
+            '-' => out.push_str("\\-"),
+            _ => out.push(c),
+        }
+    }
+}
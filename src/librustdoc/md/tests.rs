@@ -0,0 +1,95 @@
+use super::{markdown_to_roff, rewrite_docs};
+use crate::clean::RenderedLink;
+
+fn link(original_text: &str, new_text: &str, href: &str) -> RenderedLink {
+    RenderedLink {
+        original_text: original_text.into(),
+        new_text: new_text.into(),
+        href: href.to_owned(),
+        tooltip: String::new(),
+    }
+}
+
+#[test]
+fn inline_links() {
+    let links = [link("crate::Parser", "crate::Parser", "struct.Parser.md")];
+    assert_eq!(
+        rewrite_docs("See [the parser](crate::Parser) for details.", &links, 0),
+        "See [the parser](struct.Parser.md) for details.",
+    );
+}
+
+#[test]
+fn shortcut_links() {
+    let links = [
+        link("`fn@parse`", "parse", "fn.parse.md"),
+        link("Parser", "Parser", "../struct.Parser.md#method.new"),
+    ];
+    assert_eq!(
+        rewrite_docs("Calls [`fn@parse`] and [Parser][].", &links, 0),
+        "Calls [`parse`](fn.parse.md) and [Parser](../struct.Parser.md#method.new).",
+    );
+}
+
+#[test]
+fn reference_links() {
+    let links = [link("crate::Parser", "crate::Parser", "struct.Parser.md")];
+    assert_eq!(
+        rewrite_docs("Uses [a parser][p].\n\n[p]: crate::Parser\n", &links, 0),
+        "Uses [a parser][p].\n\n[p]: struct.Parser.md\n",
+    );
+    assert_eq!(
+        rewrite_docs("Uses [a parser][crate::Parser].", &links, 0),
+        "Uses [a parser][crate::Parser].\n\n[crate::Parser]: struct.Parser.md",
+    );
+}
+
+#[test]
+fn unresolved_links() {
+    let doc = "[Missing], [`std::vec`] and [a link](https://example.com).";
+    assert_eq!(rewrite_docs(doc, &[link("Other", "Other", "struct.Other.md")], 0), doc);
+}
+
+#[test]
+fn heading_offset() {
+    let doc = "# Examples\n\nText\n\n ##### Deep\n\nTitle\n=====\n\n```\n# hidden\n```\n";
+    assert_eq!(
+        rewrite_docs(doc, &[], 2),
+        "### Examples\n\nText\n\n ###### Deep\n\nTitle\n=====\n\n```\n# hidden\n```\n",
+    );
+    assert_eq!(rewrite_docs(doc, &[], 0), doc);
+}
+
+#[test]
+fn roff() {
+    let markdown = "# Struct foo::Parser\n\n```rust\npub struct Parser\n```\n\n\
+        See [`tokenize`](foo.fn.tokenize(3)) and *more*.\n\n## Examples\n\n\
+        ```\n# use foo::Parser;\nlet p = Parser::new(\"-\");\n```\n\n\
+        - [new](foo.fn.new(3)): Creates a parser.\n- .hidden\n";
+    assert_eq!(
+        markdown_to_roff("foo.struct.Parser", markdown),
+        r#".TH "foo.struct.Parser" 3
+.SH NAME
+foo.struct.Parser \- Struct foo::Parser
+.PP
+.RS 4
+.nf
+pub struct Parser
+.fi
+.RE
+.PP
+See \fBtokenize\fR (\fBfoo.fn.tokenize\fR(3)) and \fImore\fR.
+.SH Examples
+.PP
+.RS 4
+.nf
+let p = Parser::new("\-");
+.fi
+.RE
+.IP \(bu 2
+new (\fBfoo.fn.new\fR(3)): Creates a parser.
+.IP \(bu 2
+\&.hidden
+"#,
+    );
+}
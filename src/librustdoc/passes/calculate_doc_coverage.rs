@@ -6,8 +6,10 @@
 use crate::passes::Pass;
 use crate::visit::DocVisitor;
 use rustc_hir as hir;
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
 use rustc_lint::builtin::MISSING_DOCS;
 use rustc_middle::lint::LintLevelSource;
+use rustc_middle::ty::print::with_no_trimmed_paths;
 use rustc_session::lint;
 use rustc_span::FileName;
 use serde::Serialize;
@@ -22,10 +24,18 @@
 };
 
 fn calculate_doc_coverage(krate: clean::Crate, ctx: &mut DocContext<'_>) -> clean::Crate {
-    let mut calc = CoverageCalculator { items: Default::default(), ctx };
+    let mut calc = CoverageCalculator {
+        items: Default::default(),
+        modules: Default::default(),
+        module_stack: Vec::new(),
+        missing_docs: Vec::new(),
+        missing_examples: Vec::new(),
+        ctx,
+    };
     calc.visit_crate(&krate);
 
     calc.print_results();
+    calc.check_threshold();
 
     krate
 }
@@ -100,8 +110,32 @@ fn add_assign(&mut self, rhs: Self) {
     }
 }
 
+/// An item missing documentation or examples, listed with `--coverage-detail`.
+#[derive(Serialize)]
+struct MissingItem {
+    path: String,
+    file: String,
+    line: usize,
+}
+
+/// The JSON output of `--coverage-detail`.
+#[derive(Serialize)]
+struct DetailedReport<'a> {
+    files: BTreeMap<String, &'a ItemCount>,
+    modules: &'a BTreeMap<String, ItemCount>,
+    total: ItemCount,
+    missing_docs: &'a [MissingItem],
+    missing_examples: &'a [MissingItem],
+}
+
 struct CoverageCalculator<'a, 'b> {
     items: BTreeMap<FileName, ItemCount>,
+    /// The counts of each module, only computed with `--coverage-detail`.
+    modules: BTreeMap<String, ItemCount>,
+    /// The paths of the modules being visited.
+    module_stack: Vec<String>,
+    missing_docs: Vec<MissingItem>,
+    missing_examples: Vec<MissingItem>,
     ctx: &'a mut DocContext<'b>,
 }
 
@@ -115,15 +149,89 @@ fn limit_filename_len(filename: String) -> String {
     }
 }
 
+/// Prints the counts of each file or module, followed by their total.
+fn print_table<'c>(header: &str, rows: impl Iterator<Item = (String, &'c ItemCount)>) {
+    let mut total = ItemCount::default();
+
+    fn print_table_line() {
+        println!("+-{0:->35}-+-{0:->10}-+-{0:->10}-+-{0:->10}-+-{0:->10}-+", "");
+    }
+
+    fn print_table_record(name: &str, count: ItemCount, percentage: f64, examples_percentage: f64) {
+        println!(
+            "| {name:<35} | {with_docs:>10} | {percentage:>9.1}% | {with_examples:>10} | \
+            {examples_percentage:>9.1}% |",
+            with_docs = count.with_docs,
+            with_examples = count.with_examples,
+        );
+    }
+
+    print_table_line();
+    println!(
+        "| {:<35} | {:>10} | {:>10} | {:>10} | {:>10} |",
+        header, "Documented", "Percentage", "Examples", "Percentage",
+    );
+    print_table_line();
+
+    for (name, &count) in rows {
+        if let Some(percentage) = count.percentage() {
+            print_table_record(
+                &limit_filename_len(name),
+                count,
+                percentage,
+                count.examples_percentage().unwrap_or(0.),
+            );
+
+            total += count;
+        }
+    }
+
+    print_table_line();
+    print_table_record(
+        "Total",
+        total,
+        total.percentage().unwrap_or(0.0),
+        total.examples_percentage().unwrap_or(0.0),
+    );
+    print_table_line();
+}
+
+fn print_missing_items(title: &str, items: &[MissingItem]) {
+    if items.is_empty() {
+        return;
+    }
+    println!("\n{title}:");
+    for item in items {
+        println!("    {}:{}: {}", item.file, item.line, item.path);
+    }
+}
+
 impl<'a, 'b> CoverageCalculator<'a, 'b> {
+    fn total(&self) -> ItemCount {
+        let mut total = ItemCount::default();
+        for &count in self.items.values() {
+            total += count;
+        }
+        total
+    }
+
     fn to_json(&self) -> String {
-        serde_json::to_string(
-            &self
-                .items
-                .iter()
-                .map(|(k, v)| (k.prefer_local().to_string(), v))
-                .collect::<BTreeMap<String, &ItemCount>>(),
-        )
+        let files = self
+            .items
+            .iter()
+            .map(|(k, v)| (k.prefer_local().to_string(), v))
+            .collect::<BTreeMap<String, &ItemCount>>();
+        if self.ctx.render_options.coverage_detail {
+            serde_json::to_string(&DetailedReport {
+                files,
+                modules: &self.modules,
+                total: self.total(),
+                missing_docs: &self.missing_docs,
+                missing_examples: &self.missing_examples,
+            })
+        } else {
+            serde_json::to_string(&files)
+        }
         .expect("failed to convert JSON data to string")
     }
 
@@ -133,54 +241,43 @@ fn print_results(&self) {
             println!("{}", self.to_json());
             return;
         }
-        let mut total = ItemCount::default();
 
-        fn print_table_line() {
-            println!("+-{0:->35}-+-{0:->10}-+-{0:->10}-+-{0:->10}-+-{0:->10}-+", "");
-        }
+        print_table(
+            "File",
+            self.items
+                .iter()
+                .map(|(file, count)| (file.prefer_local().to_string_lossy().into_owned(), count)),
+        );
 
-        fn print_table_record(
-            name: &str,
-            count: ItemCount,
-            percentage: f64,
-            examples_percentage: f64,
-        ) {
-            println!(
-                "| {name:<35} | {with_docs:>10} | {percentage:>9.1}% | {with_examples:>10} | \
-                {examples_percentage:>9.1}% |",
-                with_docs = count.with_docs,
-                with_examples = count.with_examples,
-            );
+        if self.ctx.render_options.coverage_detail {
+            println!();
+            print_table("Module", self.modules.iter().map(|(path, count)| (path.clone(), count)));
+            print_missing_items("Items missing documentation", &self.missing_docs);
+            print_missing_items("Items missing examples", &self.missing_examples);
         }
+    }
 
-        print_table_line();
-        println!(
-            "| {:<35} | {:>10} | {:>10} | {:>10} | {:>10} |",
-            "File", "Documented", "Percentage", "Examples", "Percentage",
-        );
-        print_table_line();
-
-        for (file, &count) in &self.items {
-            if let Some(percentage) = count.percentage() {
-                print_table_record(
-                    &limit_filename_len(file.prefer_local().to_string_lossy().into()),
-                    count,
-                    percentage,
-                    count.examples_percentage().unwrap_or(0.),
-                );
-
-                total += count;
-            }
+    /// Emits an error if the percentage of documented items is below `--coverage-threshold`.
+    fn check_threshold(&self) {
+        let Some(threshold) = self.ctx.render_options.coverage_threshold else { return };
+        // A crate without any item to document is fully documented.
+        let percentage = self.total().percentage().unwrap_or(100.0);
+        if percentage < threshold {
+            self.ctx.tcx.dcx().err(format!(
+                "documentation coverage is {percentage:.1}%, below the threshold of {threshold}%"
+            ));
         }
+    }
 
-        print_table_line();
-        print_table_record(
-            "Total",
-            total,
-            total.percentage().unwrap_or(0.0),
-            total.examples_percentage().unwrap_or(0.0),
-        );
-        print_table_line();
+    /// Returns the path of an item, starting with the name of the crate.
+    fn item_path(&self, def_id: DefId) -> String {
+        let tcx = self.ctx.tcx;
+        let path = with_no_trimmed_paths!(tcx.def_path_str(def_id));
+        if path.is_empty() {
+            tcx.crate_name(LOCAL_CRATE).to_string()
+        } else {
+            format!("{}::{path}", tcx.crate_name(LOCAL_CRATE))
+        }
     }
 }
 
@@ -205,6 +302,13 @@ fn visit_item(&mut self, i: &clean::Item) {
             // doesn't make sense, as all methods on a type are in one single impl block
             clean::ImplItem(_) => {}
             _ => {
+                let detail = self.ctx.render_options.coverage_detail;
+                // Modules are counted in their own totals, next to the items they contain.
+                let is_module = detail && i.is_mod();
+                if is_module {
+                    self.module_stack.push(self.item_path(i.item_id.expect_def_id()));
+                }
+
                 let has_docs = !i.attrs.doc_strings.is_empty();
                 let mut tests = Tests { found_tests: 0 };
 
@@ -259,12 +363,42 @@ fn visit_item(&mut self, i: &clean::Item) {
                 if let Some(span) = i.span(self.ctx.tcx) {
                     let filename = span.filename(self.ctx.sess());
                     debug!("counting {:?} {:?} in {filename:?}", i.type_(), i.name);
-                    self.items.entry(filename).or_default().count_item(
+                    let should_have_doc_examples = should_have_doc_example(self.ctx, i);
+                    let mut count = ItemCount::default();
+                    count.count_item(
                         has_docs,
                         has_doc_example,
-                        should_have_doc_example(self.ctx, i),
+                        should_have_doc_examples,
                         should_have_docs,
                     );
+
+                    if detail {
+                        if let Some(module) = self.module_stack.last() {
+                            *self.modules.entry(module.clone()).or_default() += count;
+                        }
+                        let missing_docs = should_have_docs && !has_docs;
+                        let missing_example = should_have_doc_examples && !has_doc_example;
+                        if missing_docs || missing_example {
+                            let item = MissingItem {
+                                path: self.item_path(i.item_id.expect_def_id()),
+                                file: filename.prefer_local().to_string(),
+                                line: span.lo(self.ctx.sess()).line,
+                            };
+                            if missing_docs {
+                                self.missing_docs.push(item);
+                            } else {
+                                self.missing_examples.push(item);
+                            }
+                        }
+                    }
+
+                    *self.items.entry(filename).or_default() += count;
+                }
+
+                if is_module {
+                    self.visit_item_recur(i);
+                    self.module_stack.pop();
+                    return;
                 }
             }
         }
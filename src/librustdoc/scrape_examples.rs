@@ -7,6 +7,7 @@
 use crate::html::render::Context;
 
 use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::{Hash64, HashStable, StableHasher};
 use rustc_hir::{
     self as hir,
     intravisit::{self, Visitor},
@@ -22,19 +23,22 @@
 };
 use rustc_session::getopts;
 use rustc_span::{
-    def_id::{CrateNum, DefPathHash, LOCAL_CRATE},
+    def_id::{CrateNum, DefId, DefPathHash, LOCAL_CRATE},
     edition::Edition,
     BytePos, FileName, SourceFile,
 };
 
 use std::fs;
-use std::path::PathBuf;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub(crate) struct ScrapeExamplesOptions {
     output_path: PathBuf,
     target_crates: Vec<String>,
     pub(crate) scrape_tests: bool,
+    /// Whether to record what `--check-scraped-examples` needs to check the examples later.
+    check: bool,
 }
 
 impl ScrapeExamplesOptions {
@@ -42,11 +46,13 @@ pub(crate) fn new(matches: &getopts::Matches, dcx: &rustc_errors::DiagCtxt) -> O
         let output_path = matches.opt_str("scrape-examples-output-path");
         let target_crates = matches.opt_strs("scrape-examples-target-crate");
         let scrape_tests = matches.opt_present("scrape-tests");
+        let check = matches.opt_present("check-scraped-examples");
         match (output_path, !target_crates.is_empty(), scrape_tests) {
             (Some(output_path), true, _) => Some(ScrapeExamplesOptions {
                 output_path: PathBuf::from(output_path),
                 target_crates,
                 scrape_tests,
+                check,
             }),
             (Some(_), false, _) | (None, true, _) => {
                 dcx.fatal("must use --scrape-examples-output-path and --scrape-examples-target-crate together");
@@ -106,6 +112,38 @@ pub(crate) struct CallData {
     pub(crate) display_name: String,
     pub(crate) edition: Edition,
     pub(crate) is_bin: bool,
+    /// The name of the crate the example was scraped from.
+    pub(crate) target: String,
+    /// Whether the example was scraped from a test crate.
+    pub(crate) is_test: bool,
+    /// The hash of the file when it was scraped, see [`source_hash`]. Only recorded with
+    /// `--check-scraped-examples`.
+    pub(crate) source_hash: Option<Hash64>,
+    /// The hash of the signature of the called function when it was scraped, see [`fn_sig_hash`].
+    /// Only recorded with `--check-scraped-examples`.
+    pub(crate) fn_sig_hash: Option<Hash64>,
+}
+
+/// Hashes the source of an example file, to notice when it changed after being scraped, and
+/// the locations of its calls can't be trusted anymore.
+pub(crate) fn source_hash(contents: &str) -> Hash64 {
+    let mut hasher = StableHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes the signature of a function, to notice when it changed after its calls were scraped,
+/// and the examples may not compile anymore.
+///
+/// The hash only depends on the `DefPathHash`es of the items in the signature, so it is the same
+/// in the crate defining the function and in the crates using it.
+pub(crate) fn fn_sig_hash(tcx: TyCtxt<'_>, def_id: DefId) -> Hash64 {
+    let sig = tcx.fn_sig(def_id).instantiate_identity();
+    tcx.with_stable_hashing_context(|mut hcx| {
+        let mut hasher = StableHasher::new();
+        sig.hash_stable(&mut hcx, &mut hasher);
+        hasher.finish()
+    })
 }
 
 pub(crate) type FnCallLocations = FxHashMap<PathBuf, CallData>;
@@ -119,6 +157,19 @@ struct FindCalls<'a, 'tcx> {
     target_crates: Vec<CrateNum>,
     calls: &'a mut AllCallLocations,
     bin_crate: bool,
+    /// Whether to record the hashes checked by `--check-scraped-examples`.
+    check: bool,
+    /// The hashes of the example files, computed once per file.
+    source_hashes: FxHashMap<PathBuf, Option<Hash64>>,
+}
+
+impl FindCalls<'_, '_> {
+    fn source_hash(&mut self, path: &Path) -> Option<Hash64> {
+        *self.source_hashes.entry(path.to_owned()).or_insert_with(|| {
+            // The locations of the calls are offsets in the file as it is on disk.
+            fs::read_to_string(path).ok().map(|contents| source_hash(&contents))
+        })
+    }
 }
 
 impl<'a, 'tcx> Visitor<'tcx> for FindCalls<'a, 'tcx>
@@ -239,12 +290,35 @@ fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
                     }
                 };
 
+                let source_hash = if self.check {
+                    let Some(source_hash) = self.source_hash(&abs_path) else {
+                        trace!("Could not read file: {}", abs_path.display());
+                        return;
+                    };
+                    Some(source_hash)
+                } else {
+                    None
+                };
+
                 let mk_call_data = || {
                     let display_name = file_path.display().to_string();
                     let edition = call_span.edition();
                     let is_bin = self.bin_crate;
-
-                    CallData { locations: Vec::new(), url, display_name, edition, is_bin }
+                    let target = tcx.crate_name(LOCAL_CRATE).to_string();
+                    let is_test = tcx.sess.is_test_crate();
+                    let fn_sig_hash = self.check.then(|| fn_sig_hash(tcx, *def_id));
+
+                    CallData {
+                        locations: Vec::new(),
+                        url,
+                        display_name,
+                        edition,
+                        is_bin,
+                        target,
+                        is_test,
+                        source_hash,
+                        fn_sig_hash,
+                    }
                 };
 
                 let fn_key = tcx.def_path_hash(*def_id);
@@ -300,8 +374,16 @@ pub(crate) fn run(
 
         // Run call-finder on all items
         let mut calls = FxHashMap::default();
-        let mut finder =
-            FindCalls { calls: &mut calls, tcx, map: tcx.hir(), cx, target_crates, bin_crate };
+        let mut finder = FindCalls {
+            calls: &mut calls,
+            tcx,
+            map: tcx.hir(),
+            cx,
+            target_crates,
+            bin_crate,
+            check: options.check,
+            source_hashes: FxHashMap::default(),
+        };
         tcx.hir().visit_all_item_likes_in_crate(&mut finder);
 
         // The visitor might have found a type error, which we need to
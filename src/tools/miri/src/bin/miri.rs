@@ -43,7 +43,10 @@
 use rustc_session::search_paths::PathKind;
 use rustc_session::{CtfeBacktrace, EarlyDiagCtxt};
 
-use miri::{BacktraceStyle, BorrowTrackerMethod, ProvenanceMode, RetagFields};
+use miri::{
+    BacktraceStyle, BorrowTrackerMethod, ErrorFormat, ExploreStrategy, ProvenanceMode,
+    RecordReplay, RetagFields, Schedule,
+};
 
 struct MiriCompilerCalls {
     miri_config: miri::MiriConfig,
@@ -81,11 +84,6 @@ fn after_analysis<'tcx>(
                 tcx.dcx().fatal("miri only makes sense on bin crates");
             }
 
-            let (entry_def_id, entry_type) = if let Some(entry_def) = tcx.entry_fn(()) {
-                entry_def
-            } else {
-                tcx.dcx().fatal("miri can only run programs that have a main function");
-            };
             let mut config = self.miri_config.clone();
 
             // Add filename to `miri` arguments.
@@ -111,7 +109,18 @@ fn after_analysis<'tcx>(
                     optimizations is usually marginal at best.");
             }
 
-            if let Some(return_code) = miri::eval_entry(tcx, entry_def_id, entry_type, config) {
+            let return_code = if let Some(target) = &config.fuzz {
+                // Fuzz targets usually use `#![no_main]`, so do not require a main function.
+                miri::fuzz_entry(tcx, &config, target)
+            } else {
+                let (entry_def_id, entry_type) = if let Some(entry_def) = tcx.entry_fn(()) {
+                    entry_def
+                } else {
+                    tcx.dcx().fatal("miri can only run programs that have a main function");
+                };
+                miri::eval_entry(tcx, entry_def_id, entry_type, config)
+            };
+            if let Some(return_code) = return_code {
                 std::process::exit(
                     i32::try_from(return_code).expect("Return value was too large!"),
                 );
@@ -556,6 +565,51 @@ fn main() {
         } else if let Some(param) = arg.strip_prefix("-Zmiri-preemption-rate=") {
             miri_config.preemption_rate =
                 parse_rate(param).unwrap_or_else(|err| show_error!("-Zmiri-preemption-rate {err}"));
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-explore=") {
+            let strategy = param
+                .parse::<ExploreStrategy>()
+                .unwrap_or_else(|err| show_error!("-Zmiri-explore: {err}"));
+            miri_config.explore = Some(strategy);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-explore-limit=") {
+            let limit = param.parse::<u32>().unwrap_or_else(|err| {
+                show_error!("-Zmiri-explore-limit requires a `u32`: {}", err)
+            });
+            miri_config.explore_limit = limit;
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-explore-replay=") {
+            let schedule = param
+                .parse::<Schedule>()
+                .unwrap_or_else(|err| show_error!("-Zmiri-explore-replay: {err}"));
+            miri_config.explore_replay = Some(schedule);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-record=") {
+            if miri_config.record_replay.is_some() {
+                show_error!("-Zmiri-record and -Zmiri-replay can only be specified once");
+            }
+            miri_config.record_replay = Some(RecordReplay::Record(param.into()));
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-replay=") {
+            if miri_config.record_replay.is_some() {
+                show_error!("-Zmiri-record and -Zmiri-replay can only be specified once");
+            }
+            miri_config.record_replay = Some(RecordReplay::Replay(param.into()));
+        } else if arg == "-Zmiri-fuzz" {
+            miri_config.fuzz = Some(miri::DEFAULT_FUZZ_TARGET.to_owned());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-fuzz=") {
+            miri_config.fuzz = Some(param.to_owned());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-fuzz-runs=") {
+            let runs = param
+                .parse::<u32>()
+                .unwrap_or_else(|err| show_error!("-Zmiri-fuzz-runs requires a `u32`: {}", err));
+            miri_config.fuzz_runs = runs;
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-fuzz-corpus=") {
+            miri_config.fuzz_corpus = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-fuzz-max-len=") {
+            let max_len = param.parse::<usize>().unwrap_or_else(|err| {
+                show_error!("-Zmiri-fuzz-max-len requires a `usize`: {}", err)
+            });
+            miri_config.fuzz_max_len = max_len;
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-heap-profile=") {
+            miri_config.heap_profile = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-process-stubs=") {
+            miri_config.process_stubs = Some(param.into());
         } else if arg == "-Zmiri-report-progress" {
             // This makes it take a few seconds between progress reports on my laptop.
             miri_config.report_progress = Some(1_000_000);
@@ -578,6 +632,12 @@ fn main() {
                 "full" => BacktraceStyle::Full,
                 _ => show_error!("-Zmiri-backtrace may only be 0, 1, or full"),
             };
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-error-format=") {
+            miri_config.error_format = match param {
+                "human" => ErrorFormat::Human,
+                "json" => ErrorFormat::Json,
+                _ => show_error!("-Zmiri-error-format may only be human or json"),
+            };
         } else if let Some(param) = arg.strip_prefix("-Zmiri-native-lib=") {
             let filename = param.to_string();
             if std::path::Path::new(&filename).exists() {
@@ -618,6 +678,27 @@ fn main() {
         );
     }
 
+    // Miri's reports are regular rustc diagnostics, so rustc's JSON emitter prints them, along with
+    // everything else rustc and Miri print.
+    if miri_config.error_format == ErrorFormat::Json
+        && !rustc_args.iter().any(|arg| arg.starts_with("--error-format"))
+    {
+        rustc_args.push("--error-format=json".to_owned());
+    }
+
+    if miri_config.record_replay.is_some() && miri_config.explore.is_some() {
+        show_error!("-Zmiri-explore cannot be combined with -Zmiri-record or -Zmiri-replay");
+    }
+    if miri_config.fuzz.is_some()
+        && (miri_config.explore.is_some()
+            || miri_config.explore_replay.is_some()
+            || miri_config.record_replay.is_some())
+    {
+        show_error!(
+            "-Zmiri-fuzz cannot be combined with -Zmiri-explore, -Zmiri-explore-replay, -Zmiri-record or -Zmiri-replay"
+        );
+    }
+
     debug!("rustc arguments: {:?}", rustc_args);
     debug!("crate arguments: {:?}", miri_config.args);
     run_compiler(
@@ -13,8 +13,10 @@ fn err_sb_ub<'tcx>(
     msg: String,
     help: Vec<String>,
     history: Option<TagHistory>,
+    alloc_id: AllocId,
+    tags: Vec<BorTag>,
 ) -> InterpError<'tcx> {
-    err_machine_stop!(TerminationInfo::StackedBorrowsUb { msg, help, history })
+    err_machine_stop!(TerminationInfo::StackedBorrowsUb { msg, help, history, alloc_id, tags })
 }
 
 #[derive(Clone, Debug)]
@@ -401,6 +403,8 @@ pub(super) fn grant_error(&self, stack: &Stack) -> InterpError<'tcx> {
             format!("{action}{}", error_cause(stack, op.orig_tag)),
             helps,
             op.orig_tag.and_then(|orig_tag| self.get_logs_relevant_to(orig_tag, None)),
+            self.history.id,
+            op.orig_tag.and_then(Some).into_iter().chain([op.new_tag]).collect(),
         )
     }
 
@@ -424,6 +428,8 @@ pub(super) fn access_error(&self, stack: &Stack) -> InterpError<'tcx> {
             format!("{action}{}", error_cause(stack, op.tag)),
             vec![operation_summary("an access", self.history.id, op.range)],
             op.tag.and_then(|tag| self.get_logs_relevant_to(tag, None)),
+            self.history.id,
+            op.tag.and_then(Some).into_iter().collect(),
         )
     }
 
@@ -451,6 +457,8 @@ pub(super) fn protector_error(&self, item: &Item, kind: ProtectorKind) -> Interp
                     format!("deallocating while item {item:?} is {protected} by call {call_id:?}",),
                     vec![],
                     None,
+                    self.history.id,
+                    vec![item.tag()],
                 ),
             Operation::Retag(RetagOp { orig_tag: tag, .. })
             | Operation::Access(AccessOp { tag, .. }) =>
@@ -460,6 +468,8 @@ pub(super) fn protector_error(&self, item: &Item, kind: ProtectorKind) -> Interp
                     ),
                     vec![],
                     tag.and_then(|tag| self.get_logs_relevant_to(tag, Some(item.tag()))),
+                    self.history.id,
+                    tag.and_then(Some).into_iter().chain([item.tag()]).collect(),
                 ),
         }
     }
@@ -478,6 +488,8 @@ pub fn dealloc_error(&self, stack: &Stack) -> InterpError<'tcx> {
             ),
             vec![],
             op.tag.and_then(|tag| self.get_logs_relevant_to(tag, None)),
+            self.history.id,
+            op.tag.and_then(Some).into_iter().collect(),
         )
     }
 
@@ -357,7 +357,15 @@ pub fn build<'tcx>(self) -> InterpError<'tcx> {
             conflicting_tag_name,
             true,
         );
-        err_machine_stop!(TerminationInfo::TreeBorrowsUb { title, details, history })
+        let mut tags = vec![self.accessed_info.tag, self.conflicting_info.tag];
+        tags.dedup();
+        err_machine_stop!(TerminationInfo::TreeBorrowsUb {
+            title,
+            details,
+            history,
+            alloc_id: self.alloc_id,
+            tags,
+        })
     }
 }
 
@@ -1,5 +1,8 @@
 use std::cell::Cell;
-use std::time::{Duration, Instant as StdInstant};
+use std::rc::Rc;
+use std::time::{Duration, Instant as StdInstant, SystemTime};
+
+use crate::Recorder;
 
 /// When using a virtual clock, this defines how many nanoseconds we pretend are passing for each
 /// basic block.
@@ -8,12 +11,12 @@
 /// (See `tests/pass/shims/time-with-isolation*.rs`.)
 const NANOSECONDS_PER_BASIC_BLOCK: u64 = 5000;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Instant {
     kind: InstantKind,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum InstantKind {
     Host(StdInstant),
     Virtual { nanoseconds: u64 },
@@ -49,6 +52,8 @@ pub fn duration_since(&self, earlier: Instant) -> Duration {
 #[derive(Debug)]
 pub struct Clock {
     kind: ClockKind,
+    /// Records the time on the host, or replays it.
+    recorder: Option<Rc<Recorder>>,
 }
 
 #[derive(Debug)]
@@ -65,14 +70,14 @@ enum ClockKind {
 
 impl Clock {
     /// Create a new clock based on the availability of communication with the host.
-    pub fn new(communicate: bool) -> Self {
+    pub fn new(communicate: bool, recorder: Option<Rc<Recorder>>) -> Self {
         let kind = if communicate {
             ClockKind::Host { time_anchor: StdInstant::now() }
         } else {
             ClockKind::Virtual { nanoseconds: 0.into() }
         };
 
-        Self { kind }
+        Self { kind, recorder }
     }
 
     /// Let the time pass for a small interval.
@@ -90,7 +95,12 @@ pub fn tick(&self) {
     /// Sleep for the desired duration.
     pub fn sleep(&self, duration: Duration) {
         match &self.kind {
-            ClockKind::Host { .. } => std::thread::sleep(duration),
+            ClockKind::Host { .. } => {
+                // When replaying, the recording already says when the sleep ended.
+                if !self.recorder.as_ref().is_some_and(|recorder| recorder.is_replaying()) {
+                    std::thread::sleep(duration)
+                }
+            }
             ClockKind::Virtual { nanoseconds } => {
                 // Just pretend that we have slept for some time.
                 let nanos: u64 = duration.as_nanos().try_into().unwrap();
@@ -109,9 +119,23 @@ pub fn anchor(&self) -> Instant {
 
     pub fn now(&self) -> Instant {
         match &self.kind {
-            ClockKind::Host { .. } => Instant { kind: InstantKind::Host(StdInstant::now()) },
+            ClockKind::Host { time_anchor } => {
+                let elapsed = match &self.recorder {
+                    Some(recorder) => recorder.decide("monotonic", || time_anchor.elapsed()),
+                    None => time_anchor.elapsed(),
+                };
+                Instant { kind: InstantKind::Host(*time_anchor + elapsed) }
+            }
             ClockKind::Virtual { nanoseconds } =>
                 Instant { kind: InstantKind::Virtual { nanoseconds: nanoseconds.get() } },
         }
     }
+
+    /// Return the current system time of the host.
+    pub fn system_time(&self) -> SystemTime {
+        match &self.recorder {
+            Some(recorder) => recorder.decide("system-time", SystemTime::now),
+            None => SystemTime::now(),
+        }
+    }
 }
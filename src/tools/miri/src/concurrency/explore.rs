@@ -0,0 +1,552 @@
+//! Systematic exploration of thread schedules, enabled with `-Zmiri-explore`.
+//!
+//! Without it, the scheduler only preempts threads at random, so finding a
+//! concurrency bug means running Miri with many different seeds. With it, the
+//! program is executed repeatedly within one Miri invocation, each time with a
+//! different schedule, until an execution fails or the schedules are exhausted.
+//!
+//! Threads may only be preempted right after a *visible operation*: a call to
+//! an atomic intrinsic or to a foreign function (which is how locks, thread
+//! creation and joining etc. are implemented). Preempting in between those
+//! cannot change the outcome of the program, except for data races, which the
+//! data race detector reports no matter how the racing accesses are ordered.
+//!
+//! Two strategies are supported:
+//! - `preemption-bound=<N>` enumerates all schedules with at most `N`
+//!   preemptions, in depth-first order (iterative context bounding, as in
+//!   CHESS). Most concurrency bugs need very few preemptions to show up.
+//! - `pct=<D>` runs "probabilistic concurrency testing": threads get random
+//!   priorities, the highest-priority enabled thread always runs, and the
+//!   priority of the running thread is lowered at `D - 1` random points. Each
+//!   execution uses a different (but fixed) seed, so the results are
+//!   reproducible, and bugs of depth `D` are found with a known probability.
+//!
+//! Different schedules often produce equivalent executions, differing only in
+//! the order of independent operations (e.g. two loads, or accesses to
+//! different locations). They are detected by comparing the normal forms of
+//! their traces, and the number of distinct executions is reported. They are
+//! still all explored: the schedules extending two equivalent executions are
+//! not the same, so skipping one of them would skip schedules with at most
+//! `N` preemptions.
+//!
+//! The locations an operation accesses are the pointers passed to it. To not
+//! read memory, which would affect the borrow tracker and the data race
+//! detector, only the arguments held in locals as immediates are looked at;
+//! if any other argument may be a pointer, the operation is considered to
+//! access every location.
+//!
+//! A schedule is written as the list of scheduling decisions that differ from
+//! what the default scheduler would have done, e.g. `3:1,17:0` means "at the
+//! 3rd scheduling decision run thread 1, and at the 17th run thread 0". The
+//! schedule of a failing execution is printed so that it can be replayed
+//! deterministically with `-Zmiri-explore-replay`.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use either::Either;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxHasher};
+use rustc_hir::def_id::DefId;
+use rustc_middle::{mir, ty};
+use rustc_session::config::EntryFnType;
+
+use crate::eval::eval_entry_with_explorer;
+use crate::heap_profile::{write_heap_profile, HeapProfile};
+use crate::*;
+
+/// The exploration strategy selected with `-Zmiri-explore`.
+#[derive(Clone, Copy, Debug)]
+pub enum ExploreStrategy {
+    /// Enumerate all schedules with at most this many preemptions.
+    PreemptionBound(u32),
+    /// Probabilistic concurrency testing with the given bug depth.
+    Pct { depth: u32 },
+}
+
+impl FromStr for ExploreStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, param) = s.split_once('=').unwrap_or((s, ""));
+        let parse = |default: u32| {
+            if param.is_empty() {
+                Ok(default)
+            } else {
+                param.parse::<u32>().map_err(|_| format!("`{param}` is not a valid number"))
+            }
+        };
+        match name {
+            "preemption-bound" => Ok(ExploreStrategy::PreemptionBound(parse(2)?)),
+            "pct" =>
+                match parse(3)? {
+                    0 => Err("the depth of `pct` must be at least 1".to_owned()),
+                    depth => Ok(ExploreStrategy::Pct { depth }),
+                },
+            _ => Err(format!("unknown strategy `{name}`, expected `preemption-bound` or `pct`")),
+        }
+    }
+}
+
+/// Scheduling decisions deviating from the default scheduler, each given as
+/// the index of the decision and the thread that was picked.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schedule(Vec<(u64, ThreadId)>);
+
+impl Schedule {
+    fn get(&self, decision: u64) -> Option<ThreadId> {
+        // Decisions are sorted by index.
+        self.0.binary_search_by_key(&decision, |&(index, _)| index).ok().map(|i| self.0[i].1)
+    }
+
+    fn last_decision(&self) -> Option<u64> {
+        self.0.last().map(|&(index, _)| index)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (decision, thread)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{decision}:{}", thread.to_u32())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut decisions = vec![];
+        for entry in s.split(',').filter(|entry| !entry.is_empty()) {
+            let parsed = entry.split_once(':').and_then(|(decision, thread)| {
+                Some((decision.parse::<u64>().ok()?, ThreadId::from(thread.parse::<u32>().ok()?)))
+            });
+            let Some((decision, thread)) = parsed else {
+                return Err(format!("`{entry}` is not of the form `<decision>:<thread>`"));
+            };
+            if decisions.last().is_some_and(|&(last, _)| last >= decision) {
+                return Err("the decisions must be sorted by index".to_owned());
+            }
+            decisions.push((decision, thread));
+        }
+        Ok(Schedule(decisions))
+    }
+}
+
+/// A visible operation executed by a thread, see the module docs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Event {
+    thread: ThreadId,
+    /// The address the operation accesses, if it is known. Operations on
+    /// unknown addresses are considered to depend on every other operation.
+    address: Option<u64>,
+    write: bool,
+}
+
+/// A point at which the active thread could have been preempted.
+#[derive(Debug)]
+struct PreemptionPoint {
+    decision: u64,
+    /// The enabled threads other than the active one.
+    alternatives: Vec<ThreadId>,
+}
+
+/// How the explorer picks threads.
+#[derive(Debug)]
+enum Mode {
+    /// Follow the given schedule.
+    Replay(Schedule),
+    Pct {
+        rng: StdRng,
+        depth: u32,
+        priorities: FxHashMap<ThreadId, u64>,
+        /// The decisions at which the priority of the active thread is lowered.
+        change_points: FxHashSet<u64>,
+    },
+}
+
+/// Makes the scheduling decisions of a single execution, and records what
+/// happened in it.
+#[derive(Debug)]
+pub struct Explorer {
+    mode: Mode,
+    next_decision: u64,
+    /// The decisions made, relative to the default scheduler.
+    schedule: Schedule,
+    preemption_points: Vec<PreemptionPoint>,
+    events: Vec<Event>,
+}
+
+impl Explorer {
+    pub fn replay(schedule: Schedule) -> Self {
+        Self::new(Mode::Replay(schedule))
+    }
+
+    /// PCT with the given depth, for a program making about `decisions`
+    /// scheduling decisions.
+    fn pct(depth: u32, seed: u64, decisions: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let change_points = (1..depth).map(|_| rng.gen_range(0..decisions.max(1))).collect();
+        Self::new(Mode::Pct { rng, depth, priorities: Default::default(), change_points })
+    }
+
+    fn new(mode: Mode) -> Self {
+        Explorer {
+            mode,
+            next_decision: 0,
+            schedule: Schedule::default(),
+            preemption_points: vec![],
+            events: vec![],
+        }
+    }
+
+    /// Picks the thread to run next among `enabled`, where the default
+    /// scheduler would pick `default`. `preemption` says whether `default` is
+    /// the active thread, which could keep running.
+    pub fn choose(
+        &mut self,
+        default: ThreadId,
+        enabled: &[ThreadId],
+        preemption: bool,
+    ) -> ThreadId {
+        let decision = self.next_decision;
+        self.next_decision += 1;
+        if preemption {
+            let alternatives = enabled.iter().copied().filter(|&id| id != default).collect();
+            self.preemption_points.push(PreemptionPoint { decision, alternatives });
+        }
+
+        let choice = match &mut self.mode {
+            Mode::Replay(schedule) =>
+                schedule.get(decision).filter(|id| enabled.contains(id)).unwrap_or(default),
+            Mode::Pct { rng, depth, priorities, change_points } => {
+                // Threads get a random priority above all the ones change points assign.
+                for &id in enabled {
+                    priorities.entry(id).or_insert_with(|| {
+                        u64::from(*depth) + rng.gen_range(0..u64::from(u32::MAX))
+                    });
+                }
+                if preemption && change_points.contains(&decision) {
+                    let lowered = change_points.iter().filter(|&&point| point < decision).count();
+                    priorities.insert(default, u64::from(*depth) - 1 - lowered as u64);
+                }
+                *enabled.iter().max_by_key(|id| priorities[*id]).unwrap()
+            }
+        };
+        if choice != default {
+            self.schedule.0.push((decision, choice));
+        }
+        choice
+    }
+
+    /// Records that the active thread executes a visible operation accessing
+    /// the given addresses, or any address if they are unknown.
+    pub fn record_operation(&mut self, thread: ThreadId, addresses: Option<Vec<u64>>, write: bool) {
+        match addresses {
+            None => self.record_event(thread, None, write),
+            Some(addresses) =>
+                for address in addresses {
+                    self.record_event(thread, Some(address), write);
+                },
+        }
+    }
+
+    fn record_event(&mut self, thread: ThreadId, address: Option<u64>, write: bool) {
+        self.events.push(Event { thread, address, write });
+    }
+
+    /// A hash of the execution that is the same for all executions which only
+    /// differ in the order of independent operations.
+    ///
+    /// This computes the Foata normal form of the trace: every event gets the
+    /// length of the longest chain of events it depends on, and the events
+    /// are then sorted by that.
+    fn trace_hash(&self) -> u64 {
+        #[derive(Default)]
+        struct Levels {
+            last_write: usize,
+            max_read: usize,
+        }
+
+        let mut thread_levels = FxHashMap::<ThreadId, usize>::default();
+        let mut address_levels = FxHashMap::<u64, Levels>::default();
+        // The level of the last operation on an unknown address, and of any operation.
+        let mut unknown_level = 0;
+        let mut max_level = 0;
+        let mut normal_form = Vec::with_capacity(self.events.len());
+        for event in &self.events {
+            let thread_level = thread_levels.entry(event.thread).or_default();
+            let level = 1 + match event.address {
+                None => max_level,
+                Some(address) => {
+                    let levels = address_levels.entry(address).or_default();
+                    let conflicting = if event.write {
+                        levels.last_write.max(levels.max_read)
+                    } else {
+                        levels.last_write
+                    };
+                    conflicting.max(unknown_level)
+                }
+            }
+            .max(*thread_level);
+
+            *thread_level = level;
+            match event.address {
+                None => unknown_level = level,
+                Some(address) => {
+                    let levels = address_levels.get_mut(&address).unwrap();
+                    if event.write {
+                        levels.last_write = level;
+                    } else {
+                        levels.max_read = levels.max_read.max(level);
+                    }
+                }
+            }
+            max_level = max_level.max(level);
+            normal_form.push((level, *event));
+        }
+        normal_form.sort_unstable();
+
+        let mut hasher = FxHasher::default();
+        normal_form.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// If the terminator about to be executed is a visible operation, returns the
+/// addresses it accesses (`None` if they are unknown) and whether it may write
+/// to them.
+pub(crate) fn visible_operation<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+) -> Option<(Option<Vec<u64>>, bool)> {
+    let frame = ecx.frame();
+    let location = frame.current_loc().left()?;
+    let terminator = frame.body.basic_blocks[location.block].terminator();
+    let mir::TerminatorKind::Call { func, args, .. } = &terminator.kind else {
+        return None;
+    };
+    let ty::FnDef(def_id, _) = *func.ty(frame.body, *ecx.tcx).kind() else {
+        return None;
+    };
+    let write = match ecx.tcx.intrinsic(def_id) {
+        Some(intrinsic) if intrinsic.name.as_str().starts_with("atomic_") =>
+            !intrinsic.name.as_str().starts_with("atomic_load"),
+        Some(_) => return None,
+        None if ecx.tcx.is_foreign_item(def_id) => true,
+        None => return None,
+    };
+
+    // Only look at the arguments that do not require reading memory, see the module docs.
+    let mut addresses = vec![];
+    for arg in args.iter() {
+        let scalars = match &arg.node {
+            mir::Operand::Constant(constant) if constant.ty().is_primitive() => continue,
+            mir::Operand::Constant(_) => return Some((None, write)),
+            mir::Operand::Copy(place) | mir::Operand::Move(place) => {
+                let Some(local) = place.as_local() else { return Some((None, write)) };
+                match frame.locals[local].as_mplace_or_imm() {
+                    Some(Either::Right(Immediate::Scalar(a))) => [Some(a), None],
+                    Some(Either::Right(Immediate::ScalarPair(a, b))) => [Some(a), Some(b)],
+                    Some(Either::Right(Immediate::Uninit)) => [None, None],
+                    _ => return Some((None, write)),
+                }
+            }
+        };
+        for scalar in scalars.into_iter().flatten() {
+            if let Scalar::Ptr(ptr, _) = scalar {
+                addresses.push(Pointer::<Option<_>>::from(ptr).addr().bytes());
+            }
+        }
+    }
+    // Operations without pointer arguments, e.g. joining a thread, are not known to be
+    // independent of anything either.
+    Some(((!addresses.is_empty()).then_some(addresses), write))
+}
+
+/// The state of an exploration across executions.
+struct Exploration<'a, 'tcx> {
+    tcx: ty::TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: EntryFnType,
+    config: &'a MiriConfig,
+    executions: u32,
+    distinct: FxHashSet<u64>,
+    /// The heap profile of the last execution, if requested.
+    heap_profile: Option<HeapProfile>,
+}
+
+impl Exploration<'_, '_> {
+    fn limit_reached(&self) -> bool {
+        self.executions >= self.config.explore_limit
+    }
+
+    /// Runs the program with the given explorer, and returns it. If the
+    /// execution fails, its schedule is reported and the result to exit with
+    /// is returned as error.
+    fn run(&mut self, explorer: Explorer) -> Result<Explorer, Option<i64>> {
+        self.executions += 1;
+        let (result, explorer, heap_profile) = eval_entry_with_explorer(
+            self.tcx,
+            self.entry_id,
+            self.entry_type,
+            self.config,
+            explorer,
+        );
+        self.heap_profile = heap_profile;
+        self.distinct.insert(explorer.trace_hash());
+        // A panicking test results in a non-zero exit code rather than an error.
+        if result.is_some_and(|code| code == 0) {
+            return Ok(explorer);
+        }
+        self.tcx.dcx().note(format!(
+            "this execution was found after exploring {} schedules, \
+            replay it with `-Zmiri-explore-replay={}`",
+            self.executions, explorer.schedule
+        ));
+        Err(result)
+    }
+}
+
+/// Runs the program once per schedule chosen by `strategy`, until an execution
+/// fails or `-Zmiri-explore-limit` executions were run.
+pub fn explore_entry<'tcx>(
+    tcx: ty::TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: EntryFnType,
+    config: &MiriConfig,
+    strategy: ExploreStrategy,
+) -> Option<i64> {
+    let mut exploration = Exploration {
+        tcx,
+        entry_id,
+        entry_type,
+        config,
+        executions: 0,
+        distinct: Default::default(),
+        heap_profile: None,
+    };
+    let result = explore(&mut exploration, strategy);
+    if let (Some(path), Some(heap_profile)) = (&config.heap_profile, &exploration.heap_profile) {
+        write_heap_profile(tcx, path, heap_profile);
+    }
+    if let Err(result) = result {
+        return result;
+    }
+
+    let stopped = if exploration.limit_reached() {
+        " before reaching the limit set by `-Zmiri-explore-limit`"
+    } else {
+        ""
+    };
+    tcx.dcx().note(format!(
+        "explored {} schedules ({} distinct executions){stopped} without finding an error",
+        exploration.executions,
+        exploration.distinct.len()
+    ));
+    Some(0)
+}
+
+fn explore(
+    exploration: &mut Exploration<'_, '_>,
+    strategy: ExploreStrategy,
+) -> Result<(), Option<i64>> {
+    match strategy {
+        ExploreStrategy::PreemptionBound(bound) => {
+            let mut stack = vec![Schedule::default()];
+            while let Some(schedule) = stack.pop() {
+                if exploration.limit_reached() {
+                    break;
+                }
+                let explorer = exploration.run(Explorer::replay(schedule.clone()))?;
+                if schedule.0.len() >= usize::try_from(bound).unwrap() {
+                    continue;
+                }
+                // Extend the schedule with one more preemption after the ones it already has.
+                // Push the latest ones first, so that the earliest ones are explored first.
+                let last_decision = schedule.last_decision();
+                for point in explorer.preemption_points.iter().rev() {
+                    if last_decision.is_some_and(|last| point.decision <= last) {
+                        continue;
+                    }
+                    for &thread in point.alternatives.iter().rev() {
+                        let mut child = schedule.clone();
+                        child.0.push((point.decision, thread));
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        ExploreStrategy::Pct { depth } => {
+            // Use an execution of the default schedule to estimate the number of decisions.
+            let explorer = exploration.run(Explorer::replay(Schedule::default()))?;
+            let decisions = explorer.next_decision;
+            let mut seed = 0;
+            while !exploration.limit_reached() {
+                exploration.run(Explorer::pct(depth, seed, decisions))?;
+                seed += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_strategy() {
+        assert!(matches!("pct".parse(), Ok(ExploreStrategy::Pct { depth: 3 })));
+        assert!(matches!("pct=5".parse(), Ok(ExploreStrategy::Pct { depth: 5 })));
+        assert!(matches!("preemption-bound=0".parse(), Ok(ExploreStrategy::PreemptionBound(0))));
+        assert!("pct=0".parse::<ExploreStrategy>().is_err());
+        assert!("random".parse::<ExploreStrategy>().is_err());
+    }
+
+    #[test]
+    fn schedule_roundtrip() {
+        let schedule: Schedule = "3:1,17:0".parse().unwrap();
+        assert_eq!(schedule.get(17), Some(ThreadId::from(0)));
+        assert_eq!(schedule.get(4), None);
+        assert_eq!(schedule.to_string(), "3:1,17:0");
+        assert_eq!("".parse::<Schedule>(), Ok(Schedule::default()));
+        assert!("17:0,3:1".parse::<Schedule>().is_err());
+    }
+
+    fn trace(events: &[(u32, Option<u64>, bool)]) -> u64 {
+        let mut explorer = Explorer::replay(Schedule::default());
+        for &(thread, address, write) in events {
+            explorer.record_event(ThreadId::from(thread), address, write);
+        }
+        explorer.trace_hash()
+    }
+
+    #[test]
+    fn independent_operations_commute() {
+        // Loads, and accesses to different addresses, can be reordered.
+        assert_eq!(
+            trace(&[(0, Some(8), false), (1, Some(8), false)]),
+            trace(&[(1, Some(8), false), (0, Some(8), false)]),
+        );
+        assert_eq!(
+            trace(&[(0, Some(8), true), (1, Some(16), true), (0, Some(8), false)]),
+            trace(&[(1, Some(16), true), (0, Some(8), true), (0, Some(8), false)]),
+        );
+        // A load and a store to the same address cannot.
+        assert_ne!(
+            trace(&[(0, Some(8), false), (1, Some(8), true)]),
+            trace(&[(1, Some(8), true), (0, Some(8), false)]),
+        );
+        // Neither can operations on unknown addresses.
+        assert_ne!(
+            trace(&[(0, None, true), (1, Some(16), false)]),
+            trace(&[(1, Some(16), false), (0, None, true)]),
+        );
+    }
+}
@@ -18,6 +18,7 @@
 use rustc_target::spec::abi::Abi;
 
 use crate::concurrency::data_race;
+use crate::concurrency::explore::{visible_operation, Explorer};
 use crate::shims::tls;
 use crate::*;
 
@@ -172,6 +173,8 @@ pub enum BlockReason {
     Futex { addr: u64 },
     /// Blocked on an InitOnce.
     InitOnce(InitOnceId),
+    /// Blocked until a file descriptor becomes ready.
+    Fd,
 }
 
 /// The state of a thread.
@@ -406,7 +409,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
 }
 
 /// The moment in time when a blocked thread should be woken up.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Timeout {
     Monotonic(Instant),
     RealTime(SystemTime),
@@ -418,7 +421,7 @@ fn get_wait_time(&self, clock: &Clock) -> Duration {
         match self {
             Timeout::Monotonic(instant) => instant.duration_since(clock.now()),
             Timeout::RealTime(time) =>
-                time.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO),
+                time.duration_since(clock.system_time()).unwrap_or(Duration::ZERO),
         }
     }
 }
@@ -437,6 +440,11 @@ pub struct ThreadManager<'tcx> {
     thread_local_alloc_ids: FxHashMap<(DefId, ThreadId), Pointer<Provenance>>,
     /// A flag that indicates that we should change the active thread.
     yield_active_thread: bool,
+    /// Makes the scheduling decisions when exploring schedules (`-Zmiri-explore`).
+    explorer: Option<Explorer>,
+    /// A flag that indicates that the active thread just executed a visible
+    /// operation, so the explorer may preempt it.
+    at_preemption_point: bool,
 }
 
 impl VisitProvenance for ThreadManager<'_> {
@@ -446,6 +454,8 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             thread_local_alloc_ids,
             active_thread: _,
             yield_active_thread: _,
+            explorer: _,
+            at_preemption_point: _,
         } = self;
 
         for thread in threads {
@@ -467,6 +477,8 @@ fn default() -> Self {
             threads,
             thread_local_alloc_ids: Default::default(),
             yield_active_thread: false,
+            explorer: None,
+            at_preemption_point: false,
         }
     }
 }
@@ -705,6 +717,35 @@ fn yield_active_thread(&mut self) {
         self.yield_active_thread = true;
     }
 
+    pub(crate) fn set_explorer(&mut self, explorer: Option<Explorer>) {
+        self.explorer = explorer;
+    }
+
+    pub(crate) fn take_explorer(&mut self) -> Option<Explorer> {
+        self.explorer.take()
+    }
+
+    /// Lets the explorer pick the thread to run next, where the default policy picked the active
+    /// thread. `preemption` says whether the active thread could keep running.
+    fn explore_active_thread(&mut self, preemption: bool) {
+        let Some(explorer) = &mut self.explorer else { return };
+        let enabled: Vec<ThreadId> = self
+            .threads
+            .iter_enumerated()
+            .filter(|(_, thread)| thread.state.is_enabled())
+            .map(|(id, _)| id)
+            .collect();
+        let next = explorer.choose(self.active_thread, &enabled, preemption);
+        if next != self.active_thread {
+            info!(
+                "---------- Exploring thread `{}` (default: `{}`) ----------------------------------------",
+                self.get_thread_display_name(next),
+                self.get_thread_display_name(self.active_thread)
+            );
+            self.active_thread = next;
+        }
+    }
+
     /// Get the wait time for the next timeout, or `None` if no timeout is pending.
     fn next_callback_wait_time(&self, clock: &Clock) -> Option<Duration> {
         self.threads
@@ -726,9 +767,14 @@ fn next_callback_wait_time(&self, clock: &Clock) -> Option<Duration> {
     /// long as we can and switch only when we have to (the active thread was
     /// blocked, terminated, or has explicitly asked to be preempted).
     fn schedule(&mut self, clock: &Clock) -> InterpResult<'tcx, SchedulingAction> {
+        let at_preemption_point = mem::take(&mut self.at_preemption_point);
         // This thread and the program can keep going.
         if self.threads[self.active_thread].state.is_enabled() && !self.yield_active_thread {
-            // The currently active thread is still enabled, just continue with it.
+            // The currently active thread is still enabled, just continue with it -- unless we are
+            // exploring schedules and this is a point at which to try preempting it.
+            if at_preemption_point {
+                self.explore_active_thread(/* preemption */ true);
+            }
             return Ok(SchedulingAction::ExecuteStep);
         }
         // The active thread yielded or got terminated. Let's see if there are any timeouts to take
@@ -768,6 +814,7 @@ fn schedule(&mut self, clock: &Clock) -> InterpResult<'tcx, SchedulingAction> {
         }
         self.yield_active_thread = false;
         if self.threads[self.active_thread].state.is_enabled() {
+            self.explore_active_thread(/* preemption */ false);
             return Ok(SchedulingAction::ExecuteStep);
         }
         // We have not found a thread to execute.
@@ -834,9 +881,10 @@ fn run_on_stack_empty(&mut self) -> InterpResult<'tcx, Poll<()>> {
             .on_stack_empty
             .take()
             .expect("`on_stack_empty` not set up, or already running");
-        let res = callback(this)?;
+        let res = callback(this);
+        // Put the callback back even on errors, so that the thread can be reused by `-Zmiri-fuzz`.
         this.active_thread_mut().on_stack_empty = Some(callback);
-        Ok(res)
+        res
     }
 }
 
@@ -1131,6 +1179,17 @@ fn maybe_preempt_active_thread(&mut self) {
         use rand::Rng as _;
 
         let this = self.eval_context_mut();
+        if this.machine.threads.explorer.is_some() {
+            // When exploring schedules, only visible operations are preemption points, and the
+            // explorer rather than the RNG decides whether to preempt.
+            if let Some((addresses, write)) = visible_operation(this) {
+                let threads = &mut this.machine.threads;
+                let thread = threads.active_thread;
+                threads.explorer.as_mut().unwrap().record_operation(thread, addresses, write);
+                threads.at_preemption_point = true;
+            }
+            return;
+        }
         if this.machine.rng.get_mut().gen_bool(this.machine.preemption_rate) {
             this.yield_active_thread();
         }
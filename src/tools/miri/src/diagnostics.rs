@@ -1,4 +1,5 @@
 use std::fmt::{self, Write};
+use std::mem;
 use std::num::NonZero;
 
 use rustc_errors::{Diag, DiagMessage, Level};
@@ -21,11 +22,15 @@ pub enum TerminationInfo {
         msg: String,
         help: Vec<String>,
         history: Option<TagHistory>,
+        alloc_id: AllocId,
+        tags: Vec<BorTag>,
     },
     TreeBorrowsUb {
         title: String,
         details: Vec<String>,
         history: tree_diagnostics::HistoryData,
+        alloc_id: AllocId,
+        tags: Vec<BorTag>,
     },
     Int2PtrWithStrictProvenance,
     Deadlock,
@@ -49,6 +54,9 @@ pub enum TerminationInfo {
         retag_explain: bool,
     },
     UnsupportedForeignItem(String),
+    RecordReplay(String),
+    /// The fuzz target returned, so `-Zmiri-fuzz` can continue with the next input.
+    FuzzInputDone,
 }
 
 pub struct RacingOp {
@@ -87,6 +95,34 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                     op2.thread_info
                 ),
             UnsupportedForeignItem(msg) => write!(f, "{msg}"),
+            RecordReplay(msg) => write!(f, "{msg}"),
+            FuzzInputDone => write!(f, "the fuzz target returned"),
+        }
+    }
+}
+
+impl TerminationInfo {
+    fn report_details(&self) -> ReportDetails {
+        use TerminationInfo::*;
+        let kind = match self {
+            Exit { .. } | FuzzInputDone => "exit",
+            Abort(_) => "abort",
+            UnsupportedInIsolation(_) | Int2PtrWithStrictProvenance | UnsupportedForeignItem(_) =>
+                "unsupported",
+            StackedBorrowsUb { .. } => "stacked_borrows",
+            TreeBorrowsUb { .. } => "tree_borrows",
+            DataRace { .. } => "data_race",
+            Deadlock => "deadlock",
+            RecordReplay(_) => "record_replay",
+            MultipleSymbolDefinitions { .. } | SymbolShimClashing { .. } => "link_error",
+        };
+        match self {
+            // The history of the tags is part of the help messages.
+            StackedBorrowsUb { alloc_id, tags, .. } | TreeBorrowsUb { alloc_id, tags, .. } =>
+                ReportDetails { kind, alloc_ids: vec![*alloc_id], tags: tags.clone() },
+            DataRace { ptr, .. } =>
+                ReportDetails { kind, alloc_ids: vec![ptr.provenance], ..Default::default() },
+            _ => ReportDetails::new(kind),
         }
     }
 }
@@ -139,6 +175,24 @@ pub enum DiagLevel {
     Note,
 }
 
+/// Details of a report that are only added as notes with `-Zmiri-error-format=json`, where
+/// tools rather than humans read the report.
+#[derive(Default)]
+pub struct ReportDetails {
+    /// What is being reported, e.g. `undefined_behavior` or `memory_leak`.
+    pub kind: &'static str,
+    /// The allocations involved in the report.
+    pub alloc_ids: Vec<AllocId>,
+    /// The borrow tracker tags involved in the report.
+    pub tags: Vec<BorTag>,
+}
+
+impl ReportDetails {
+    pub fn new(kind: &'static str) -> Self {
+        ReportDetails { kind, ..Default::default() }
+    }
+}
+
 /// Attempts to prune a stacktrace to omit the Rust runtime, and returns a bool indicating if any
 /// frames were pruned. If the stacktrace does not have any local frames, we conclude that it must
 /// be pointing to a problem in the Rust runtime itself, and do not prune it at all.
@@ -215,12 +269,14 @@ pub fn report_error<'tcx>(
         use TerminationInfo::*;
         let title = match info {
             Exit { code, leak_check } => return Some((*code, *leak_check)),
+            FuzzInputDone => return Some((0, false)),
             Abort(_) => Some("abnormal termination"),
             UnsupportedInIsolation(_) | Int2PtrWithStrictProvenance | UnsupportedForeignItem(_) =>
                 Some("unsupported operation"),
             StackedBorrowsUb { .. } | TreeBorrowsUb { .. } | DataRace { .. } =>
                 Some("Undefined Behavior"),
             Deadlock => Some("deadlock"),
+            RecordReplay(_) => Some("record/replay failure"),
             MultipleSymbolDefinitions { .. } | SymbolShimClashing { .. } => None,
         };
         #[rustfmt::skip]
@@ -253,7 +309,7 @@ pub fn report_error<'tcx>(
                 }
                 helps
             },
-            TreeBorrowsUb { title: _, details, history } => {
+            TreeBorrowsUb { details, history, .. } => {
                 let mut helps = vec![
                     (None, format!("this indicates a potential bug in the program: it performed an invalid operation, but the Tree Borrows rules it violated are still experimental"))
                 ];
@@ -270,6 +326,10 @@ pub fn report_error<'tcx>(
                     (Some(*first), format!("it's first defined here, in crate `{first_crate}`")),
                     (Some(*second), format!("then it's defined here again, in crate `{second_crate}`")),
                 ],
+            RecordReplay(_) =>
+                vec![
+                    (None, format!("a replayed execution must use the same program and the same flags (other than `-Zmiri-replay`) as the recorded one")),
+                ],
             SymbolShimClashing { link_name, span } =>
                 vec![(Some(*span), format!("the `{link_name}` symbol is defined here"))],
             Int2PtrWithStrictProvenance =>
@@ -372,6 +432,25 @@ pub fn report_error<'tcx>(
         (Some(title), helps)
     };
 
+    let details = match e.kind() {
+        MachineStop(info) => {
+            let info = info.downcast_ref::<TerminationInfo>().expect("invalid MachineStop payload");
+            info.report_details()
+        }
+        UndefinedBehavior(info) => {
+            let alloc_ids = match info {
+                PointerUseAfterFree(alloc_id, _)
+                | PointerOutOfBounds { alloc_id, .. }
+                | InvalidUninitBytes(Some((alloc_id, _))) => vec![*alloc_id],
+                _ => vec![],
+            };
+            ReportDetails { kind: "undefined_behavior", alloc_ids, ..Default::default() }
+        }
+        ResourceExhaustion(_) => ReportDetails::new("resource_exhaustion"),
+        Unsupported(_) => ReportDetails::new("unsupported"),
+        _ => ReportDetails::new("post_monomorphization"),
+    };
+
     let stacktrace = ecx.generate_stacktrace();
     let (stacktrace, mut any_pruned) = prune_stacktrace(stacktrace, &ecx.machine);
 
@@ -404,14 +483,21 @@ pub fn report_error<'tcx>(
 
     msg.insert(0, format_interp_error(ecx.tcx.dcx(), e));
 
+    let mut notes = vec![];
+    if ecx.machine.error_format == ErrorFormat::Json && !extra.is_empty() {
+        // Nothing may be printed between JSON reports, so the dump becomes part of the report.
+        notes.push((None, mem::take(&mut extra)));
+    }
+
     report_msg(
         DiagLevel::Error,
         if let Some(title) = title { format!("{title}: {}", msg[0]) } else { msg[0].clone() },
         msg,
-        vec![],
+        notes,
         helps,
         &stacktrace,
         Some(ecx.active_thread()),
+        details,
         &ecx.machine,
     );
 
@@ -431,6 +517,7 @@ pub fn report_error<'tcx>(
                     vec![],
                     &stacktrace,
                     Some(thread),
+                    ReportDetails::new("deadlock"),
                     &ecx.machine,
                 )
             }
@@ -481,6 +568,7 @@ pub fn report_leaks<'tcx>(
             vec![],
             &backtrace,
             None, // we don't know the thread this is from
+            ReportDetails { kind: "memory_leak", alloc_ids: vec![id], ..Default::default() },
             &ecx.machine,
         );
     }
@@ -504,6 +592,7 @@ pub fn report_msg<'tcx>(
     helps: Vec<(Option<SpanData>, String)>,
     stacktrace: &[FrameInfo<'tcx>],
     thread: Option<ThreadId>,
+    details: ReportDetails,
     machine: &MiriMachine<'tcx>,
 ) {
     let span = stacktrace.first().map_or(DUMMY_SP, |fi| fi.span);
@@ -562,10 +651,14 @@ pub fn report_msg<'tcx>(
     }
     write!(backtrace_title, ":").unwrap();
     err.note(backtrace_title);
+    let json = machine.error_format == ErrorFormat::Json;
     for (idx, frame_info) in stacktrace.iter().enumerate() {
         let is_local = machine.is_local(frame_info);
-        // No span for non-local frames and the first frame (which is the error site).
-        if is_local && idx > 0 {
+        if json {
+            // Tools reading the report want the span of every frame.
+            err.span_note(frame_info.span, frame_info.to_string());
+        } else if is_local && idx > 0 {
+            // No span for non-local frames and the first frame (which is the error site).
             err.subdiagnostic(err.dcx, frame_info.as_note(machine.tcx));
         } else {
             let sm = sess.source_map();
@@ -574,6 +667,19 @@ pub fn report_msg<'tcx>(
         }
     }
 
+    if json {
+        err.note(format!("kind: {}", details.kind));
+        if !details.alloc_ids.is_empty() {
+            let alloc_ids =
+                details.alloc_ids.iter().map(|id| format!("{id:?}")).collect::<Vec<_>>();
+            err.note(format!("allocations: {}", alloc_ids.join(", ")));
+        }
+        if !details.tags.is_empty() {
+            let tags = details.tags.iter().map(|tag| format!("{tag:?}")).collect::<Vec<_>>();
+            err.note(format!("borrow tracker tags: {}", tags.join(", ")));
+        }
+    }
+
     err.emit();
 }
 
@@ -677,6 +783,26 @@ pub fn emit_diagnostic(&self, e: NonHaltingDiagnostic) {
             _ => vec![],
         };
 
+        let kind = match &e {
+            RejectedIsolatedOp(_) => "rejected_isolated_op",
+            Int2Ptr { .. } => "int2ptr_cast",
+            ProgressReport { .. } => "progress_report",
+            _ => "tracking",
+        };
+        let details = match &e {
+            CreatedPointerTag(tag, _, alloc) =>
+                ReportDetails {
+                    kind,
+                    alloc_ids: alloc.iter().map(|(alloc_id, ..)| *alloc_id).collect(),
+                    tags: BorTag::new(tag.get()).into_iter().collect(),
+                },
+            PoppedPointerTag(item, _) =>
+                ReportDetails { kind, tags: vec![item.tag()], ..Default::default() },
+            CreatedAlloc(alloc_id, ..) | FreedAlloc(alloc_id) | AccessedAlloc(alloc_id, _) =>
+                ReportDetails { kind, alloc_ids: vec![*alloc_id], ..Default::default() },
+            _ => ReportDetails::new(kind),
+        };
+
         report_msg(
             diag_level,
             title,
@@ -685,6 +811,7 @@ pub fn emit_diagnostic(&self, e: NonHaltingDiagnostic) {
             helps,
             &stacktrace,
             Some(self.threads.active_thread()),
+            details,
             self,
         );
     }
@@ -713,6 +840,7 @@ fn handle_ice(&self) {
             vec![],
             &stacktrace,
             Some(this.active_thread()),
+            ReportDetails::new("ice"),
             &this.machine,
         );
     }
@@ -7,8 +7,10 @@
 use std::task::Poll;
 use std::thread;
 
+use crate::concurrency::explore::{explore_entry, Explorer};
 use crate::concurrency::thread::TlsAllocAction;
 use crate::diagnostics::report_leaks;
+use crate::heap_profile::{finish_heap_profile, write_heap_profile, HeapProfile};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_hir::def::Namespace;
 use rustc_hir::def_id::DefId;
@@ -78,6 +80,14 @@ pub enum BacktraceStyle {
     Off,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Renders reports as regular rustc diagnostics.
+    Human,
+    /// Prints each report as a single line of JSON, following rustc's JSON diagnostic schema.
+    Json,
+}
+
 /// Configuration needed to spawn a Miri instance.
 #[derive(Clone)]
 pub struct MiriConfig {
@@ -130,6 +140,8 @@ pub struct MiriConfig {
     pub panic_on_unsupported: bool,
     /// Which style to use for printing backtraces.
     pub backtrace_style: BacktraceStyle,
+    /// Which format to use for reporting errors and other diagnostics.
+    pub error_format: ErrorFormat,
     /// Which provenance to use for int2ptr casts
     pub provenance_mode: ProvenanceMode,
     /// Whether to ignore any output by the program. This is helpful when debugging miri
@@ -156,6 +168,26 @@ pub struct MiriConfig {
     pub address_reuse_rate: f64,
     /// Probability for address reuse across threads.
     pub address_reuse_cross_thread_rate: f64,
+    /// If `Some`, run the program repeatedly, exploring its thread schedules with this strategy.
+    pub explore: Option<ExploreStrategy>,
+    /// The maximal number of executions when exploring schedules.
+    pub explore_limit: u32,
+    /// If `Some`, run the program with this schedule (as printed when exploring schedules).
+    pub explore_replay: Option<Schedule>,
+    /// If `Some`, record all non-deterministic decisions to a file, or replay them from a file.
+    pub record_replay: Option<RecordReplay>,
+    /// If `Some`, fuzz the exported function with this symbol instead of running `main`.
+    pub fuzz: Option<String>,
+    /// The number of mutated inputs to try when fuzzing.
+    pub fuzz_runs: u32,
+    /// The directory the fuzzing corpus is read from and written to.
+    pub fuzz_corpus: Option<PathBuf>,
+    /// The maximal length of the inputs generated when fuzzing.
+    pub fuzz_max_len: usize,
+    /// If `Some`, write a heap profile to this file.
+    pub heap_profile: Option<PathBuf>,
+    /// If `Some`, child processes whose command line is listed in this file are stubbed.
+    pub process_stubs: Option<PathBuf>,
 }
 
 impl Default for MiriConfig {
@@ -183,6 +215,7 @@ fn default() -> MiriConfig {
             measureme_out: None,
             panic_on_unsupported: false,
             backtrace_style: BacktraceStyle::Short,
+            error_format: ErrorFormat::Human,
             provenance_mode: ProvenanceMode::Default,
             mute_stdout_stderr: false,
             preemption_rate: 0.01, // 1%
@@ -195,6 +228,16 @@ fn default() -> MiriConfig {
             collect_leak_backtraces: true,
             address_reuse_rate: 0.5,
             address_reuse_cross_thread_rate: 0.1,
+            explore: None,
+            explore_limit: 1000,
+            explore_replay: None,
+            record_replay: None,
+            fuzz: None,
+            fuzz_runs: 1000,
+            fuzz_corpus: None,
+            fuzz_max_len: 4096,
+            heap_profile: None,
+            process_stubs: None,
         }
     }
 }
@@ -261,25 +304,20 @@ fn on_main_stack_empty(
     }
 }
 
-/// Returns a freshly created `InterpCx`.
-/// Public because this is also used by `priroda`.
-pub fn create_ecx<'tcx>(
+/// Creates the interpreter and the machine, without pushing any stack frame. Returns the
+/// interpreter together with `argc` and `argv` for the start function.
+pub fn init_ecx<'tcx>(
     tcx: TyCtxt<'tcx>,
-    entry_id: DefId,
-    entry_type: EntryFnType,
     config: &MiriConfig,
-) -> InterpResult<'tcx, InterpCx<'tcx, MiriMachine<'tcx>>> {
+    on_main_stack_empty: StackEmptyCallback<'tcx>,
+) -> InterpResult<'tcx, (MiriInterpCx<'tcx>, Scalar<Provenance>, Immediate<Provenance>)> {
     let param_env = ty::ParamEnv::reveal_all();
     let layout_cx = LayoutCx { tcx, param_env };
     let mut ecx =
         InterpCx::new(tcx, rustc_span::DUMMY_SP, param_env, MiriMachine::new(config, layout_cx));
 
     // Some parts of initialization require a full `InterpCx`.
-    MiriMachine::late_init(&mut ecx, config, {
-        let mut state = MainThreadState::default();
-        // Cannot capture anything GC-relevant here.
-        Box::new(move |m| state.on_main_stack_empty(m))
-    })?;
+    MiriMachine::late_init(&mut ecx, config, on_main_stack_empty)?;
 
     // Make sure we have MIR. We check MIR for some stable monomorphic function in libcore.
     let sentinel = ecx.try_resolve_path(&["core", "ascii", "escape_default"], Namespace::ValueNS);
@@ -290,10 +328,8 @@ pub fn create_ecx<'tcx>(
         );
     }
 
-    // Setup first stack frame.
-    let entry_instance = ty::Instance::mono(tcx, entry_id);
-
-    // First argument is constructed later, because it's skipped if the entry function uses #[start].
+    // The first argument of the start function is constructed later, because it's skipped if the
+    // entry function uses #[start].
 
     // Second argument (argc): length of `config.args`.
     let argc = Scalar::from_target_usize(u64::try_from(config.args.len()).unwrap(), &ecx);
@@ -361,6 +397,26 @@ pub fn create_ecx<'tcx>(
         argv
     };
 
+    Ok((ecx, argc, argv))
+}
+
+/// Returns a freshly created `InterpCx`.
+/// Public because this is also used by `priroda`.
+pub fn create_ecx<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: EntryFnType,
+    config: &MiriConfig,
+) -> InterpResult<'tcx, InterpCx<'tcx, MiriMachine<'tcx>>> {
+    let (mut ecx, argc, argv) = init_ecx(tcx, config, {
+        let mut state = MainThreadState::default();
+        // Cannot capture anything GC-relevant here.
+        Box::new(move |m| state.on_main_stack_empty(m))
+    })?;
+
+    // Setup first stack frame.
+    let entry_instance = ty::Instance::mono(tcx, entry_id);
+
     // Return place (in static memory so that it does not count as leak).
     let ret_place = ecx.allocate(ecx.machine.layouts.isize, MiriMemoryKind::Machine.into())?;
     ecx.machine.main_fn_ret_place = Some(ret_place.clone());
@@ -427,10 +483,42 @@ pub fn eval_entry<'tcx>(
     entry_type: EntryFnType,
     config: MiriConfig,
 ) -> Option<i64> {
-    // Copy setting before we move `config`.
+    if let Some(strategy) = config.explore {
+        return explore_entry(tcx, entry_id, entry_type, &config, strategy);
+    }
+    let explorer = config.explore_replay.clone().map(Explorer::replay);
+    let (result, _explorer, heap_profile) =
+        eval_entry_inner(tcx, entry_id, entry_type, &config, explorer);
+    if let (Some(path), Some(heap_profile)) = (&config.heap_profile, heap_profile) {
+        write_heap_profile(tcx, path, &heap_profile);
+    }
+    result
+}
+
+/// Evaluates the entry function once, with the scheduling decisions made by `explorer`.
+/// Returns the explorer, which recorded the execution, and the heap profile if requested.
+pub(crate) fn eval_entry_with_explorer<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: EntryFnType,
+    config: &MiriConfig,
+    explorer: Explorer,
+) -> (Option<i64>, Explorer, Option<HeapProfile>) {
+    let (result, explorer, heap_profile) =
+        eval_entry_inner(tcx, entry_id, entry_type, config, Some(explorer));
+    (result, explorer.unwrap(), heap_profile)
+}
+
+fn eval_entry_inner<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: EntryFnType,
+    config: &MiriConfig,
+    explorer: Option<Explorer>,
+) -> (Option<i64>, Option<Explorer>, Option<HeapProfile>) {
     let ignore_leaks = config.ignore_leaks;
 
-    let mut ecx = match create_ecx(tcx, entry_id, entry_type, &config) {
+    let mut ecx = match create_ecx(tcx, entry_id, entry_type, config) {
         Ok(v) => v,
         Err(err) => {
             let (kind, backtrace) = err.into_parts();
@@ -438,6 +526,7 @@ pub fn eval_entry<'tcx>(
             panic!("Miri initialization error: {kind:?}")
         }
     };
+    ecx.machine.threads.set_explorer(explorer);
 
     // Perform the main execution.
     let res: thread::Result<InterpResult<'_, !>> =
@@ -462,8 +551,26 @@ pub fn eval_entry<'tcx>(
         EnvVars::cleanup(&mut ecx).expect("error during env var cleanup");
     }
 
+    if let Some(recorder) = &ecx.machine.recorder {
+        if let Err(err) = recorder.flush() {
+            tcx.dcx().err(format!("failed to write the recording: {err}"));
+        }
+    }
+
+    let heap_profile = finish_heap_profile(&ecx, &config.args);
+    let explorer = ecx.machine.threads.take_explorer();
+    (check_result(&ecx, res, ignore_leaks), explorer, heap_profile)
+}
+
+/// Reports the error the execution ended with, if any, and checks for leaks.
+fn check_result<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    res: InterpErrorInfo<'tcx>,
+    ignore_leaks: bool,
+) -> Option<i64> {
+    let tcx = *ecx.tcx;
     // Process the result.
-    let (return_code, leak_check) = report_error(&ecx, res)?;
+    let (return_code, leak_check) = report_error(ecx, res)?;
     if leak_check && !ignore_leaks {
         // Check for thread leaks.
         if !ecx.have_all_terminated() {
@@ -475,7 +582,7 @@ pub fn eval_entry<'tcx>(
         info!("Additional static roots: {:?}", ecx.machine.static_roots);
         let leaks = ecx.find_leaked_allocations(&ecx.machine.static_roots);
         if !leaks.is_empty() {
-            report_leaks(&ecx, leaks);
+            report_leaks(ecx, leaks);
             let leak_message = "the evaluated program leaked memory, pass `-Zmiri-ignore-leaks` to disable this check";
             if ecx.machine.collect_leak_backtraces {
                 // If we are collecting leak backtraces, each leak is a distinct error diagnostic.
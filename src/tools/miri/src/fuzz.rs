@@ -0,0 +1,325 @@
+//! Coverage-guided fuzzing, enabled with `-Zmiri-fuzz`.
+//!
+//! Instead of running `main`, Miri repeatedly calls a fuzz target: an exported function with the
+//! signature of `LLVMFuzzerTestOneInput`, i.e. `fn(*const u8, usize) -> i32`, as defined by
+//! `libfuzzer-sys` and `cargo fuzz`. All calls happen in the same interpreter, so whatever the
+//! target initializes (lazily initialized statics, thread-locals of the main thread, ...) is only
+//! initialized once.
+//!
+//! While the target runs, the machine records which basic blocks of the local crates were
+//! executed. Inputs that reach new basic blocks are added to the corpus, and new inputs are
+//! generated by mutating inputs of the corpus. Fuzzing stops at the first error (Undefined
+//! Behavior, a panic that aborts, ...), and Miri prints the input that caused it.
+
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::task::Poll;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustc_data_structures::fx::FxHasher;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_span::Symbol;
+use rustc_target::abi::{Align, Size};
+
+use crate::*;
+
+/// The symbol of the fuzz target if `-Zmiri-fuzz` does not name one.
+pub const DEFAULT_FUZZ_TARGET: &str = "LLVMFuzzerTestOneInput";
+
+/// Values that often trigger edge cases, used when overwriting bytes.
+const INTERESTING_VALUES: &[u64] = &[
+    0,
+    1,
+    0x7f,
+    0x80,
+    0xff,
+    0x7fff,
+    0x8000,
+    0xffff,
+    0x7fff_ffff,
+    0x8000_0000,
+    0xffff_ffff,
+    u64::MAX,
+];
+
+/// Generates new inputs from the inputs of the corpus.
+pub(crate) struct Mutator {
+    rng: StdRng,
+    max_len: usize,
+}
+
+impl Mutator {
+    pub(crate) fn new(seed: u64, max_len: usize) -> Self {
+        Mutator { rng: StdRng::seed_from_u64(seed), max_len }
+    }
+
+    /// Returns a copy of a random input of `corpus` with a few random mutations applied.
+    pub(crate) fn mutate(&mut self, corpus: &[Vec<u8>]) -> Vec<u8> {
+        let mut input = corpus[self.rng.gen_range(0..corpus.len())].clone();
+        for _ in 0..self.rng.gen_range(1..=4) {
+            self.mutate_once(&mut input, corpus);
+        }
+        input.truncate(self.max_len);
+        input
+    }
+
+    fn mutate_once(&mut self, input: &mut Vec<u8>, corpus: &[Vec<u8>]) {
+        let rng = &mut self.rng;
+        if input.is_empty() {
+            // Growing is the only mutation that makes sense.
+            let len = rng.gen_range(1..=8);
+            input.extend((0..len).map(|_| rng.gen::<u8>()));
+            return;
+        }
+        let pos = rng.gen_range(0..input.len());
+        match rng.gen_range(0..7) {
+            // Flip a bit.
+            0 => input[pos] ^= 1 << rng.gen_range(0..8),
+            // Replace a byte.
+            1 => input[pos] = rng.gen(),
+            // Insert random bytes.
+            2 => {
+                let len = rng.gen_range(1..=8);
+                let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+                input.splice(pos..pos, bytes);
+            }
+            // Remove bytes.
+            3 => {
+                let end = rng.gen_range(pos..=input.len().min(pos + 8));
+                input.drain(pos..end);
+            }
+            // Duplicate a range of bytes.
+            4 => {
+                let end = rng.gen_range(pos..=input.len());
+                let bytes = input[pos..end].to_vec();
+                input.splice(pos..pos, bytes);
+            }
+            // Overwrite with an interesting integer, in either byte order.
+            5 => {
+                let value = INTERESTING_VALUES[rng.gen_range(0..INTERESTING_VALUES.len())];
+                let width = [1, 2, 4, 8][rng.gen_range(0..4)];
+                let le_bytes = value.to_le_bytes();
+                let mut bytes = le_bytes[..width].to_vec();
+                if rng.gen() {
+                    bytes.reverse();
+                }
+                let end = input.len().min(pos + width);
+                input.splice(pos..end, bytes);
+            }
+            // Splice in a part of another input.
+            _ => {
+                let other = &corpus[rng.gen_range(0..corpus.len())];
+                if other.is_empty() {
+                    return;
+                }
+                let start = rng.gen_range(0..other.len());
+                let end = rng.gen_range(start..=other.len());
+                input.truncate(pos);
+                input.extend_from_slice(&other[start..end]);
+            }
+        }
+    }
+}
+
+/// Returns a name for `input` in the corpus directory that only depends on its contents.
+fn input_name(input: &[u8]) -> String {
+    let mut hasher = FxHasher::default();
+    input.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Reads the seed inputs from the corpus directory, in a deterministic order.
+fn read_corpus(dir: &Path) -> io::Result<Vec<Vec<u8>>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    paths.into_iter().map(fs::read).collect()
+}
+
+/// The main thread's stack became empty, i.e. the fuzz target returned.
+fn on_main_stack_empty<'tcx>(_this: &mut MiriInterpCx<'tcx>) -> InterpResult<'tcx, Poll<()>> {
+    throw_machine_stop!(TerminationInfo::FuzzInputDone)
+}
+
+/// Calls the fuzz target once with `input`. Returns the result of the call.
+fn run_input<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    target: ty::Instance<'tcx>,
+    ret_place: &MPlaceTy<'tcx, Provenance>,
+    input: &[u8],
+) -> InterpResult<'tcx, i32> {
+    // The input is freshly allocated each time, so out-of-bounds accesses are detected.
+    let len = u64::try_from(input.len()).unwrap();
+    let ptr = ecx.allocate_ptr(Size::from_bytes(len), Align::ONE, MiriMemoryKind::Machine.into())?;
+    let ptr = Pointer::from(ptr);
+    ecx.write_bytes_ptr(ptr, input.iter().copied())?;
+
+    // `libfuzzer-sys` exports the target with the C ABI, but we accept any ABI.
+    let abi = ecx.tcx.fn_sig(target.def_id()).skip_binder().abi();
+    let args = [
+        Scalar::from_maybe_pointer(ptr, ecx).into(),
+        Scalar::from_target_usize(len, ecx).into(),
+    ];
+    ecx.call_function(
+        target,
+        abi,
+        &args,
+        Some(ret_place),
+        StackPopCleanup::Root { cleanup: true },
+    )?;
+
+    let res: std::thread::Result<InterpResult<'_, !>> =
+        panic::catch_unwind(AssertUnwindSafe(|| ecx.run_threads()));
+    let res = res.unwrap_or_else(|panic_payload| {
+        ecx.handle_ice();
+        panic::resume_unwind(panic_payload)
+    });
+    let err = match res {
+        Err(err) => err,
+        // `Ok` can never happen
+        Ok(never) => match never {},
+    };
+    // Returning from the target is the only way the execution should stop.
+    let done = match err.kind() {
+        InterpError::MachineStop(info) =>
+            matches!(info.downcast_ref::<TerminationInfo>(), Some(TerminationInfo::FuzzInputDone)),
+        _ => false,
+    };
+    if !done {
+        return Err(err);
+    }
+
+    ecx.deallocate_ptr(ptr, None, MiriMemoryKind::Machine.into())?;
+    ecx.read_scalar(ret_place)?.to_i32()
+}
+
+/// Fuzzes the target selected with `-Zmiri-fuzz` until it fails or `-Zmiri-fuzz-runs` inputs
+/// were tried.
+pub fn fuzz_entry<'tcx>(tcx: TyCtxt<'tcx>, config: &MiriConfig, target: &str) -> Option<i64> {
+    let mut ecx = match init_ecx(tcx, config, Box::new(on_main_stack_empty)) {
+        Ok((ecx, _argc, _argv)) => ecx,
+        Err(err) => {
+            let (kind, backtrace) = err.into_parts();
+            backtrace.print_backtrace();
+            panic!("Miri initialization error: {kind:?}")
+        }
+    };
+    ecx.machine.coverage = Some(Default::default());
+
+    let target = match ecx.lookup_exported_symbol(Symbol::intern(target)) {
+        Ok(Some((_body, instance))) => instance,
+        Ok(None) =>
+            tcx.dcx().fatal(format!(
+                "could not find the fuzz target `{target}`; it must be a function exported with \
+                `#[no_mangle]` or `#[export_name]`, as done by `libfuzzer_sys::fuzz_target!`"
+            )),
+        Err(err) => {
+            report_error(&ecx, err);
+            return None;
+        }
+    };
+    let sig = tcx.fn_sig(target.def_id()).instantiate_identity().skip_binder();
+    let data_ty = Ty::new_imm_ptr(tcx, tcx.types.u8);
+    if sig.inputs() != [data_ty, tcx.types.usize] || sig.output() != tcx.types.i32 {
+        tcx.dcx().fatal(format!(
+            "the fuzz target `{}` must have the signature `fn(*const u8, usize) -> i32`",
+            tcx.def_path_str(target.def_id())
+        ));
+    }
+    let ret_place = ecx.allocate(ecx.machine.layouts.i32, MiriMemoryKind::Machine.into()).unwrap();
+
+    let mut corpus = match &config.fuzz_corpus {
+        Some(dir) =>
+            read_corpus(dir).unwrap_or_else(|err| {
+                tcx.dcx().fatal(format!("failed to read the corpus `{}`: {err}", dir.display()))
+            }),
+        None => Vec::new(),
+    };
+    // Start with the seeds, then continue with mutations of the corpus.
+    let mut pending = corpus.clone();
+    pending.reverse();
+    if corpus.is_empty() {
+        corpus.push(Vec::new());
+        pending.push(Vec::new());
+    }
+    let mut mutator = Mutator::new(config.seed.unwrap_or(0), config.fuzz_max_len);
+    let mut runs = 0;
+    let mut mutations = 0;
+    loop {
+        let input = match pending.pop() {
+            Some(input) => input,
+            None if mutations < config.fuzz_runs => {
+                mutations += 1;
+                mutator.mutate(&corpus)
+            }
+            None => break,
+        };
+        runs += 1;
+
+        let covered = ecx.machine.coverage.as_ref().unwrap().len();
+        if let Err(err) = run_input(&mut ecx, target, &ret_place, &input) {
+            report_error(&ecx, err);
+            tcx.dcx().note(format!(
+                "this error was found after {runs} runs, with the input b\"{}\"",
+                input.escape_ascii()
+            ));
+            return None;
+        }
+        if ecx.machine.coverage.as_ref().unwrap().len() > covered {
+            // The input reached new basic blocks, so mutations of it are promising.
+            if let Some(dir) = &config.fuzz_corpus {
+                let path = dir.join(input_name(&input));
+                if let Err(err) = fs::write(&path, &input) {
+                    tcx.dcx().warn(format!("failed to write `{}`: {err}", path.display()));
+                }
+            }
+            corpus.push(input);
+        }
+    }
+
+    tcx.dcx().note(format!(
+        "fuzzed {runs} inputs without finding an error: {} basic blocks covered, {} inputs in the corpus",
+        ecx.machine.coverage.as_ref().unwrap().len(),
+        corpus.len()
+    ));
+    Some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutations_are_deterministic() {
+        let corpus = vec![b"hello".to_vec(), Vec::new()];
+        let mut a = Mutator::new(42, 16);
+        let mut b = Mutator::new(42, 16);
+        for _ in 0..100 {
+            assert_eq!(a.mutate(&corpus), b.mutate(&corpus));
+        }
+    }
+
+    #[test]
+    fn mutations_respect_max_len() {
+        let corpus = vec![vec![0; 16]];
+        let mut mutator = Mutator::new(0, 16);
+        for _ in 0..1000 {
+            assert!(mutator.mutate(&corpus).len() <= 16);
+        }
+    }
+
+    #[test]
+    fn empty_inputs_grow() {
+        let mut mutator = Mutator::new(0, 16);
+        assert!(!mutator.mutate(&[Vec::new()]).is_empty());
+    }
+}
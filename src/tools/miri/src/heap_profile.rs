@@ -0,0 +1,340 @@
+//! Heap profiling, for `-Zmiri-heap-profile`.
+//!
+//! Every heap allocation is recorded together with the stacktrace of the interpreted program at
+//! the point where it was created (its allocation site). The profiler tracks how many bytes are
+//! live per allocation site, and regularly takes a snapshot of the live heap bytes. Time is
+//! measured in executed basic blocks, so profiles are deterministic.
+//!
+//! At the end of the execution, the profile is written in the output format of Valgrind's massif
+//! tool, so it can be inspected with `ms_print` or `massif-visualizer`. Like massif, we keep at
+//! most `MAX_SNAPSHOTS` snapshots by dropping every other snapshot and halving the snapshot rate
+//! whenever there would be more. Every `DETAILED_FREQ`-th snapshot is "detailed", i.e., it also
+//! records the tree of allocation sites the live bytes come from. The peak is always recorded in
+//! detail, which requires cloning the per-site counters; to avoid doing that on every allocation
+//! that grows the heap, the peak is only captured when memory is freed after reaching it.
+
+use std::fmt::Write as _;
+use std::hash::Hash;
+use std::path::Path;
+
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap, FxIndexSet};
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::Span;
+
+use crate::*;
+
+/// The maximal number of snapshots taken at regular intervals.
+const MAX_SNAPSHOTS: usize = 100;
+/// How many snapshots are taken for each detailed snapshot.
+const DETAILED_FREQ: usize = 10;
+
+/// A frame of an allocation site, as recorded by the machine.
+pub type ProfileFrame<'tcx> = (ty::Instance<'tcx>, Span);
+
+struct Snapshot {
+    /// The number of basic blocks executed when the snapshot was taken.
+    time: u64,
+    /// The number of live heap bytes.
+    heap_bytes: u64,
+    /// For detailed snapshots, the number of live bytes of each allocation site.
+    sites: Option<Vec<u64>>,
+    /// Whether this is the snapshot of the peak.
+    peak: bool,
+}
+
+pub struct HeapProfiler<F> {
+    /// All frames that occur in allocation sites.
+    frames: FxIndexSet<F>,
+    /// All allocation sites, as indices into `frames`, innermost frame first.
+    sites: FxIndexSet<Vec<usize>>,
+    /// The number of live bytes of each allocation site.
+    live: Vec<u64>,
+    /// The allocation site and size of each live heap allocation.
+    allocs: FxHashMap<AllocId, (usize, u64)>,
+    /// The number of live heap bytes.
+    heap_bytes: u64,
+    /// The maximal number of live heap bytes so far.
+    peak_bytes: u64,
+    /// The time of the peak, if it has not been captured in `peak` yet.
+    pending_peak: Option<u64>,
+    /// The snapshot of the last captured peak.
+    peak: Option<Snapshot>,
+    /// The snapshots taken at regular intervals.
+    snapshots: Vec<Snapshot>,
+    /// The minimal number of basic blocks between two regular snapshots.
+    interval: u64,
+}
+
+impl<F> Default for HeapProfiler<F> {
+    fn default() -> Self {
+        HeapProfiler {
+            frames: Default::default(),
+            sites: Default::default(),
+            live: Vec::new(),
+            allocs: Default::default(),
+            heap_bytes: 0,
+            peak_bytes: 0,
+            pending_peak: None,
+            peak: None,
+            snapshots: Vec::new(),
+            interval: 1,
+        }
+    }
+}
+
+impl<F: Hash + Eq> HeapProfiler<F> {
+    /// Records a new heap allocation created at the allocation site `stacktrace`.
+    pub fn allocated(
+        &mut self,
+        id: AllocId,
+        size: u64,
+        stacktrace: impl IntoIterator<Item = F>,
+        time: u64,
+    ) {
+        let site: Vec<usize> =
+            stacktrace.into_iter().map(|frame| self.frames.insert_full(frame).0).collect();
+        let (site, new) = self.sites.insert_full(site);
+        if new {
+            self.live.push(0);
+        }
+        self.live[site] += size;
+        self.allocs.insert(id, (site, size));
+        self.heap_bytes += size;
+        if self.heap_bytes > self.peak_bytes {
+            self.peak_bytes = self.heap_bytes;
+            self.pending_peak = Some(time);
+        }
+        self.maybe_take_snapshot(time);
+    }
+
+    /// Records that an allocation was freed. Allocations that are not on the heap are ignored.
+    pub fn deallocated(&mut self, id: AllocId, time: u64) {
+        let Some((site, size)) = self.allocs.remove(&id) else { return };
+        // This is the first time memory is freed since the peak was reached, so the current state
+        // is the peak.
+        self.capture_peak();
+        self.live[site] -= size;
+        self.heap_bytes -= size;
+        self.maybe_take_snapshot(time);
+    }
+
+    /// Records the final state when the program has finished.
+    pub fn finish(&mut self, time: u64) {
+        self.capture_peak();
+        let sites = Some(self.live.clone());
+        self.snapshots.push(Snapshot { time, heap_bytes: self.heap_bytes, sites, peak: false });
+    }
+
+    /// The maximal number of live heap bytes, and the time at which it was reached.
+    pub fn peak(&self) -> (u64, u64) {
+        let time = self.pending_peak.or(self.peak.as_ref().map(|peak| peak.time)).unwrap_or(0);
+        (self.peak_bytes, time)
+    }
+
+    fn capture_peak(&mut self) {
+        if let Some(time) = self.pending_peak.take() {
+            let sites = Some(self.live.clone());
+            self.peak = Some(Snapshot { time, heap_bytes: self.heap_bytes, sites, peak: true });
+        }
+    }
+
+    fn maybe_take_snapshot(&mut self, time: u64) {
+        if let Some(last) = self.snapshots.last() {
+            if time < last.time + self.interval {
+                return;
+            }
+        }
+        let detailed = self.snapshots.len() % DETAILED_FREQ == 0;
+        let sites = detailed.then(|| self.live.clone());
+        self.snapshots.push(Snapshot { time, heap_bytes: self.heap_bytes, sites, peak: false });
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            // Drop every other snapshot, keeping the first one.
+            let mut idx = 0;
+            self.snapshots.retain(|_| {
+                idx += 1;
+                idx % 2 == 1
+            });
+            self.interval *= 2;
+        }
+    }
+
+    /// Returns the profile in the output format of massif. `describe` renders a frame as
+    /// `function (file:line)`.
+    pub fn to_massif(&self, cmd: &str, describe: impl Fn(&F) -> String) -> String {
+        let mut out = String::new();
+        writeln!(out, "desc: (none)").unwrap();
+        writeln!(out, "cmd: {cmd}").unwrap();
+        // Basic blocks are the closest thing to instructions that we have.
+        writeln!(out, "time_unit: i").unwrap();
+
+        let mut snapshots: Vec<&Snapshot> = self.snapshots.iter().collect();
+        if let Some(peak) = &self.peak {
+            let pos = snapshots.partition_point(|snapshot| snapshot.time <= peak.time);
+            snapshots.insert(pos, peak);
+        }
+        for (idx, snapshot) in snapshots.into_iter().enumerate() {
+            writeln!(out, "#-----------").unwrap();
+            writeln!(out, "snapshot={idx}").unwrap();
+            writeln!(out, "#-----------").unwrap();
+            writeln!(out, "time={}", snapshot.time).unwrap();
+            writeln!(out, "mem_heap_B={}", snapshot.heap_bytes).unwrap();
+            writeln!(out, "mem_heap_extra_B=0").unwrap();
+            writeln!(out, "mem_stacks_B=0").unwrap();
+            match &snapshot.sites {
+                None => writeln!(out, "heap_tree=empty").unwrap(),
+                Some(sites) => {
+                    let kind = if snapshot.peak { "peak" } else { "detailed" };
+                    writeln!(out, "heap_tree={kind}").unwrap();
+                    self.write_tree(&mut out, sites, &describe);
+                }
+            }
+        }
+        out
+    }
+
+    /// Writes the tree of allocation sites: the root stands for all heap allocations, and the
+    /// children of a node are the callers of its frame.
+    fn write_tree(&self, out: &mut String, live: &[u64], describe: &impl Fn(&F) -> String) {
+        #[derive(Default)]
+        struct Node {
+            bytes: u64,
+            children: FxIndexMap<usize, Node>,
+        }
+
+        let mut root = Node::default();
+        for (site, &bytes) in self.sites.iter().zip(live) {
+            if bytes == 0 {
+                continue;
+            }
+            let mut node = &mut root;
+            node.bytes += bytes;
+            for &frame in site {
+                node = node.children.entry(frame).or_default();
+                node.bytes += bytes;
+            }
+        }
+
+        fn write_node<F>(
+            out: &mut String,
+            frames: &FxIndexSet<F>,
+            describe: &impl Fn(&F) -> String,
+            depth: usize,
+            desc: &str,
+            node: &Node,
+        ) {
+            let indent = " ".repeat(depth);
+            writeln!(out, "{indent}n{}: {} {desc}", node.children.len(), node.bytes).unwrap();
+            let mut children: Vec<_> = node.children.iter().collect();
+            // The largest children come first, as in massif's output.
+            children.sort_by(|(_, a), (_, b)| b.bytes.cmp(&a.bytes));
+            for (&frame, child) in children {
+                let desc = format!("0x{frame:X}: {}", describe(&frames[frame]));
+                write_node(out, frames, describe, depth + 1, &desc, child);
+            }
+        }
+
+        write_node(
+            out,
+            &self.frames,
+            describe,
+            0,
+            "(heap allocation functions) malloc/new/new[], --alloc-fns, etc.",
+            &root,
+        );
+    }
+}
+
+/// The heap profile of a finished execution.
+pub struct HeapProfile {
+    /// The profile in massif's format.
+    massif: String,
+    peak_bytes: u64,
+    peak_time: u64,
+}
+
+/// Finishes the heap profile at the end of the execution.
+pub fn finish_heap_profile<'tcx>(ecx: &MiriInterpCx<'tcx>, args: &[String]) -> Option<HeapProfile> {
+    let mut profiler = ecx.machine.heap_profiler.as_ref()?.borrow_mut();
+    profiler.finish(ecx.machine.basic_block_count);
+
+    let tcx = *ecx.tcx;
+    let source_map = tcx.sess.source_map();
+    let massif = profiler.to_massif(&args.join(" "), |(instance, span)| {
+        format!(
+            "{} ({})",
+            tcx.def_path_str(instance.def_id()),
+            source_map.span_to_embeddable_string(*span)
+        )
+    });
+    let (peak_bytes, peak_time) = profiler.peak();
+    Some(HeapProfile { massif, peak_bytes, peak_time })
+}
+
+/// Writes the heap profile, and reports the peak heap usage. This is done once, after all
+/// executions: with `-Zmiri-explore`, the profile is the one of the last execution.
+pub fn write_heap_profile(tcx: TyCtxt<'_>, path: &Path, profile: &HeapProfile) {
+    if let Err(err) = std::fs::write(path, &profile.massif) {
+        tcx.dcx().err(format!("failed to write the heap profile `{}`: {err}", path.display()));
+        return;
+    }
+    let HeapProfile { peak_bytes, peak_time, .. } = profile;
+    tcx.dcx().note(format!(
+        "the peak heap usage was {peak_bytes} bytes, reached after {peak_time} basic blocks; \
+        the heap profile was written to `{}`",
+        path.display()
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alloc_id(id: u64) -> AllocId {
+        AllocId(std::num::NonZero::new(id).unwrap())
+    }
+
+    #[test]
+    fn peak_is_captured_when_memory_is_freed() {
+        let mut profiler = HeapProfiler::default();
+        profiler.allocated(alloc_id(1), 10, ["f", "main"], 1);
+        profiler.allocated(alloc_id(2), 20, ["g", "main"], 2);
+        profiler.deallocated(alloc_id(1), 3);
+        profiler.allocated(alloc_id(3), 5, ["f", "main"], 4);
+        profiler.deallocated(alloc_id(2), 5);
+        profiler.finish(6);
+        assert_eq!(profiler.peak(), (30, 2));
+        let peak = profiler.peak.as_ref().unwrap();
+        assert_eq!(peak.heap_bytes, 30);
+        assert_eq!(peak.sites.as_deref(), Some(&[10, 20][..]));
+    }
+
+    #[test]
+    fn snapshots_are_bounded() {
+        let mut profiler = HeapProfiler::default();
+        for time in 1..10_000 {
+            profiler.allocated(alloc_id(time), 1, ["main"], time);
+        }
+        assert!(profiler.snapshots.len() <= MAX_SNAPSHOTS);
+        assert_eq!(profiler.snapshots[0].time, 1);
+    }
+
+    #[test]
+    fn massif_tree_aggregates_callers() {
+        let mut profiler = HeapProfiler::default();
+        profiler.allocated(alloc_id(1), 10, ["f", "main"], 1);
+        profiler.allocated(alloc_id(2), 20, ["g", "main"], 1);
+        profiler.allocated(alloc_id(3), 5, ["f", "h"], 1);
+        profiler.finish(2);
+        let massif = profiler.to_massif("prog", |frame| format!("{frame} (a.rs:1)"));
+        let tree = massif.split("heap_tree=detailed\n").last().unwrap();
+        assert_eq!(
+            tree,
+            "n2: 35 (heap allocation functions) malloc/new/new[], --alloc-fns, etc.\n\
+             \x20n1: 20 0x2: g (a.rs:1)\n\
+             \x20\x20n0: 20 0x1: main (a.rs:1)\n\
+             \x20n2: 15 0x0: f (a.rs:1)\n\
+             \x20\x20n0: 10 0x1: main (a.rs:1)\n\
+             \x20\x20n0: 5 0x3: h (a.rs:1)\n"
+        );
+    }
+}
@@ -90,8 +90,29 @@ pub enum AccessKind {
     // FIXME: this is still incomplete.
     &[
         ("ERROR_ACCESS_DENIED", PermissionDenied),
+        ("ERROR_ALREADY_EXISTS", AlreadyExists),
+        ("ERROR_FILE_EXISTS", AlreadyExists),
+        ("ERROR_BROKEN_PIPE", BrokenPipe),
         ("ERROR_FILE_NOT_FOUND", NotFound),
+        ("ERROR_PATH_NOT_FOUND", NotFound),
+        ("ERROR_NO_DATA", BrokenPipe),
+        ("ERROR_INVALID_NAME", InvalidFilename),
         ("ERROR_INVALID_PARAMETER", InvalidInput),
+        ("ERROR_NOT_ENOUGH_MEMORY", OutOfMemory),
+        ("ERROR_TIMEOUT", TimedOut),
+        ("ERROR_CALL_NOT_IMPLEMENTED", Unsupported),
+        ("ERROR_DIRECTORY", NotADirectory),
+        ("ERROR_DIRECTORY_NOT_SUPPORTED", IsADirectory),
+        ("ERROR_DIR_NOT_EMPTY", DirectoryNotEmpty),
+        ("ERROR_WRITE_PROTECT", ReadOnlyFilesystem),
+        ("ERROR_DISK_FULL", StorageFull),
+        ("ERROR_SEEK_ON_DEVICE", NotSeekable),
+        ("ERROR_DISK_QUOTA_EXCEEDED", FilesystemQuotaExceeded),
+        ("ERROR_FILE_TOO_LARGE", FileTooLarge),
+        ("ERROR_BUSY", ResourceBusy),
+        ("ERROR_POSSIBLE_DEADLOCK", Deadlock),
+        ("ERROR_NOT_SAME_DEVICE", CrossesDevices),
+        ("ERROR_TOO_MANY_LINKS", TooManyLinks),
     ]
 };
 
@@ -433,8 +454,13 @@ fn gen_random(&mut self, ptr: Pointer<Option<Provenance>>, len: u64) -> InterpRe
 
         if this.machine.communicate() {
             // Fill the buffer using the host's rng.
-            getrandom::getrandom(&mut data)
-                .map_err(|err| err_unsup_format!("host getrandom failed: {}", err))?;
+            let host_data = this.machine.decide("entropy", || -> std::io::Result<_> {
+                getrandom::getrandom(&mut data).map_err(std::io::Error::from)?;
+                Ok(data.clone())
+            });
+            data = host_data.map_err(|err| err_unsup_format!("host getrandom failed: {}", err))?;
+            // A replay that diverged might have the wrong amount of data.
+            data.resize(usize::try_from(len).unwrap(), 0);
         } else {
             let rng = this.machine.rng.get_mut();
             rng.fill_bytes(&mut data);
@@ -81,6 +81,8 @@
 mod concurrency;
 mod diagnostics;
 mod eval;
+mod fuzz;
+mod heap_profile;
 mod helpers;
 mod intrinsics;
 mod machine;
@@ -88,6 +90,7 @@
 mod operator;
 mod provenance_gc;
 mod range_map;
+mod recorder;
 mod shims;
 
 // Establish a "crate-wide prelude": we often import `crate::*`.
@@ -106,7 +109,7 @@
 pub use crate::shims::panic::{CatchUnwindData, EvalContextExt as _};
 pub use crate::shims::time::EvalContextExt as _;
 pub use crate::shims::tls::TlsData;
-pub use crate::shims::EmulateItemResult;
+pub use crate::shims::{EmulateItemResult, InlineAsmEvalContextExt as _};
 
 pub use crate::alloc_addresses::{EvalContextExt as _, ProvenanceMode};
 pub use crate::alloc_bytes::MiriAllocBytes;
@@ -120,6 +123,7 @@
 pub use crate::clock::{Clock, Instant};
 pub use crate::concurrency::{
     data_race::{AtomicFenceOrd, AtomicReadOrd, AtomicRwOrd, AtomicWriteOrd, EvalContextExt as _},
+    explore::{ExploreStrategy, Schedule},
     init_once::{EvalContextExt as _, InitOnceId},
     sync::{CondvarId, EvalContextExt as _, MutexId, RwLockId, SynchronizationObjects},
     thread::{
@@ -131,8 +135,10 @@
     report_error, EvalContextExt as _, NonHaltingDiagnostic, TerminationInfo,
 };
 pub use crate::eval::{
-    create_ecx, eval_entry, AlignmentCheck, BacktraceStyle, IsolatedOp, MiriConfig, RejectOpWith,
+    create_ecx, eval_entry, init_ecx, AlignmentCheck, BacktraceStyle, ErrorFormat, IsolatedOp,
+    MiriConfig, RejectOpWith,
 };
+pub use crate::fuzz::{fuzz_entry, DEFAULT_FUZZ_TARGET};
 pub use crate::helpers::{AccessKind, EvalContextExt as _};
 pub use crate::machine::{
     AllocExtra, FrameExtra, MemoryKind, MiriInterpCx, MiriInterpCxExt, MiriMachine, MiriMemoryKind,
@@ -142,6 +148,7 @@
 pub use crate::operator::EvalContextExt as _;
 pub use crate::provenance_gc::{EvalContextExt as _, LiveAllocs, VisitProvenance, VisitWith};
 pub use crate::range_map::RangeMap;
+pub use crate::recorder::{RecordReplay, RecordedRng, Recorder};
 
 /// Insert rustc arguments at the beginning of the argument list that Miri wants to be
 /// set per default, for maximal validation power.
@@ -6,11 +6,13 @@
 use std::fmt;
 use std::path::Path;
 use std::process;
+use std::rc::Rc;
 
 use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
 
+use rustc_ast::{InlineAsmOptions, InlineAsmTemplatePiece};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 #[allow(unused)]
 use rustc_data_structures::static_assert_size;
@@ -33,6 +35,8 @@
         data_race::{self, NaReadType, NaWriteType},
         weak_memory,
     },
+    diagnostics::prune_stacktrace,
+    heap_profile::{HeapProfiler, ProfileFrame},
     *,
 };
 
@@ -152,6 +156,15 @@ fn may_leak(self) -> bool {
 }
 
 impl MiriMemoryKind {
+    /// Whether this memory is allocated on the heap, for `-Zmiri-heap-profile`.
+    fn is_heap(self) -> bool {
+        use self::MiriMemoryKind::*;
+        match self {
+            Rust | Miri | C | WinHeap | WinLocal | Mmap => true,
+            Machine | Global | ExternStatic | Tls | Runtime => false,
+        }
+    }
+
     /// Whether we have a useful allocation span for an allocation of this kind.
     fn should_save_allocation_span(self) -> bool {
         use self::MiriMemoryKind::*;
@@ -465,6 +478,14 @@ pub struct MiriMachine<'tcx> {
     pub(crate) fds: shims::FdTable,
     /// The table of directory descriptors.
     pub(crate) dirs: shims::DirTable,
+    /// The loopback network the program's sockets are attached to.
+    pub(crate) network: Rc<RefCell<shims::VirtualNetwork>>,
+    /// The child processes of the program.
+    pub(crate) processes: shims::ProcessTable,
+    /// The allocations created by `mmap` for files.
+    pub(crate) file_mappings: FxHashMap<AllocId, shims::FileMapping>,
+    /// The table of Windows file and directory search handles.
+    pub(crate) windows_files: shims::WindowsFileTable,
 
     /// This machine's monotone clock.
     pub(crate) clock: Clock,
@@ -499,6 +520,9 @@ pub struct MiriMachine<'tcx> {
     /// Equivalent setting as RUST_BACKTRACE on encountering an error.
     pub(crate) backtrace_style: BacktraceStyle,
 
+    /// Whether reports are rendered for humans or printed as JSON.
+    pub(crate) error_format: ErrorFormat,
+
     /// Crates which are considered local for the purposes of error reporting.
     pub(crate) local_crates: Vec<CrateNum>,
 
@@ -507,7 +531,13 @@ pub struct MiriMachine<'tcx> {
 
     /// The random number generator used for resolving non-determinism.
     /// Needs to be queried by ptr_to_int, hence needs interior mutability.
-    pub(crate) rng: RefCell<StdRng>,
+    pub(crate) rng: RefCell<RecordedRng>,
+
+    /// Records or replays all non-deterministic decisions, if requested.
+    pub(crate) recorder: Option<Rc<Recorder>>,
+
+    /// Records the live heap bytes per allocation site, if `-Zmiri-heap-profile` is set.
+    pub(crate) heap_profiler: Option<RefCell<HeapProfiler<ProfileFrame<'tcx>>>>,
 
     /// The allocation IDs to report when they are being allocated
     /// (helps for debugging memory leaks and use after free bugs).
@@ -534,6 +564,9 @@ pub struct MiriMachine<'tcx> {
     pub(crate) report_progress: Option<u32>,
     // The total number of blocks that have been executed.
     pub(crate) basic_block_count: u64,
+    /// If `Some`, the basic blocks of the local crates that have been executed. Used to guide
+    /// `-Zmiri-fuzz`.
+    pub(crate) coverage: Option<FxHashSet<(DefId, mir::BasicBlock)>>,
 
     /// Handle of the optional shared object file for native functions.
     #[cfg(target_os = "linux")]
@@ -598,7 +631,22 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             let path = Path::new(out).join(filename);
             measureme::Profiler::new(path).expect("Couldn't create `measureme` profiler")
         });
+        let recorder = config.record_replay.as_ref().map(|mode| {
+            Rc::new(Recorder::new(mode).unwrap_or_else(|err| {
+                tcx.dcx().fatal(format!("failed to open the recording: {err}"))
+            }))
+        });
+        let process_stubs = config.process_stubs.as_ref().map_or_else(Vec::new, |path| {
+            std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|stubs| shims::parse_process_stubs(&stubs))
+                .unwrap_or_else(|err| {
+                    tcx.dcx().fatal(format!("failed to read `{}`: {err}", path.display()))
+                })
+        });
         let rng = StdRng::seed_from_u64(config.seed.unwrap_or(0));
+        let rng = RecordedRng::new(rng, recorder.clone());
+        let clock = Clock::new(config.isolated_op == IsolatedOp::Allow, recorder.clone());
         let borrow_tracker = config.borrow_tracker.map(|bt| bt.instantiate_global_state(config));
         let data_race = config.data_race_detector.then(|| data_race::GlobalState::new(config));
         // Determine page size, stack address, and stack size.
@@ -643,6 +691,10 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             validate: config.validate,
             fds: shims::FdTable::new(config.mute_stdout_stderr),
             dirs: Default::default(),
+            network: Default::default(),
+            processes: shims::ProcessTable::new(process_stubs),
+            file_mappings: FxHashMap::default(),
+            windows_files: Default::default(),
             layouts,
             threads: ThreadManager::default(),
             sync: SynchronizationObjects::default(),
@@ -652,9 +704,12 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             exported_symbols_cache: FxHashMap::default(),
             panic_on_unsupported: config.panic_on_unsupported,
             backtrace_style: config.backtrace_style,
+            error_format: config.error_format,
             local_crates,
             extern_statics: FxHashMap::default(),
             rng: RefCell::new(rng),
+            recorder,
+            heap_profiler: config.heap_profile.as_ref().map(|_| Default::default()),
             tracked_alloc_ids: config.tracked_alloc_ids.clone(),
             track_alloc_accesses: config.track_alloc_accesses,
             check_alignment: config.check_alignment,
@@ -664,7 +719,8 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             preemption_rate: config.preemption_rate,
             report_progress: config.report_progress,
             basic_block_count: 0,
-            clock: Clock::new(config.isolated_op == IsolatedOp::Allow),
+            coverage: None,
+            clock,
             #[cfg(target_os = "linux")]
             native_lib: config.native_lib.as_ref().map(|lib_file_path| {
                 let target_triple = layout_cx.tcx.sess.opts.target_triple.triple();
@@ -782,6 +838,10 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             data_race,
             alloc_addresses,
             fds,
+            network: _,
+            processes: _,
+            file_mappings: _,
+            windows_files: _,
             tcx: _,
             isolated_op: _,
             validate: _,
@@ -793,8 +853,11 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             exported_symbols_cache: _,
             panic_on_unsupported: _,
             backtrace_style: _,
+            error_format: _,
             local_crates: _,
             rng: _,
+            recorder: _,
+            heap_profiler: _,
             tracked_alloc_ids: _,
             track_alloc_accesses: _,
             check_alignment: _,
@@ -804,6 +867,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             preemption_rate: _,
             report_progress: _,
             basic_block_count: _,
+            coverage: _,
             native_lib: _,
             gc_interval: _,
             since_gc: _,
@@ -1005,6 +1069,16 @@ fn panic_nounwind(ecx: &mut InterpCx<'tcx, Self>, msg: &str) -> InterpResult<'tc
         ecx.start_panic_nounwind(msg)
     }
 
+    fn eval_inline_asm(
+        ecx: &mut InterpCx<'tcx, Self>,
+        template: &'tcx [InlineAsmTemplatePiece],
+        operands: &[mir::InlineAsmOperand<'tcx>],
+        options: InlineAsmOptions,
+        targets: &[mir::BasicBlock],
+    ) -> InterpResult<'tcx> {
+        ecx.emulate_inline_asm(template, operands, options, targets)
+    }
+
     fn unwind_terminate(
         ecx: &mut InterpCx<'tcx, Self>,
         reason: mir::UnwindTerminateReason,
@@ -1122,6 +1196,18 @@ fn init_alloc_extra(
             Some(ecx.generate_stacktrace())
         };
 
+        if let Some(heap_profiler) = &ecx.machine.heap_profiler
+            && matches!(kind, MemoryKind::Machine(kind) if kind.is_heap())
+        {
+            let (stacktrace, _) = prune_stacktrace(ecx.generate_stacktrace(), &ecx.machine);
+            heap_profiler.borrow_mut().allocated(
+                id,
+                size.bytes(),
+                stacktrace.into_iter().map(|frame| (frame.instance, frame.span)),
+                ecx.machine.basic_block_count,
+            );
+        }
+
         if matches!(kind, MemoryKind::Machine(kind) if kind.should_save_allocation_span()) {
             ecx.machine
                 .allocation_spans
@@ -1246,6 +1332,13 @@ fn before_memory_write(
             machine
                 .emit_diagnostic(NonHaltingDiagnostic::AccessedAlloc(alloc_id, AccessKind::Write));
         }
+        if let Some(mapping) = machine.file_mappings.get(&alloc_id) {
+            if !mapping.is_writable() {
+                throw_ub_format!(
+                    "writing to {alloc_id:?}, which is a memory mapping without PROT_WRITE"
+                );
+            }
+        }
         if let Some(data_race) = &mut alloc_extra.data_race {
             data_race.write(alloc_id, range, NaWriteType::Write, None, machine)?;
         }
@@ -1287,6 +1380,9 @@ fn before_memory_deallocation(
         {
             *deallocated_at = Some(machine.current_span());
         }
+        if let Some(heap_profiler) = &machine.heap_profiler {
+            heap_profiler.borrow_mut().deallocated(alloc_id, machine.basic_block_count);
+        }
         machine.free_alloc_id(alloc_id, size, align, kind);
         Ok(())
     }
@@ -1403,6 +1499,22 @@ fn before_terminator(ecx: &mut InterpCx<'tcx, Self>) -> InterpResult<'tcx> {
             ecx.run_provenance_gc();
         }
 
+        // Report if the execution diverged from the one being replayed.
+        if let Some(recorder) = &ecx.machine.recorder {
+            recorder.check()?;
+        }
+
+        // Record which blocks of the local crates are covered.
+        if ecx.machine.coverage.is_some() {
+            let frame = ecx.frame();
+            let def_id = frame.instance.def_id();
+            if let Some(loc) = frame.loc.left()
+                && (def_id.is_local() || ecx.machine.local_crates.contains(&def_id.krate))
+            {
+                ecx.machine.coverage.as_mut().unwrap().insert((def_id, loc.block));
+            }
+        }
+
         // These are our preemption points.
         ecx.maybe_preempt_active_thread();
 
@@ -0,0 +1,394 @@
+//! Recording and replaying of the non-deterministic decisions of an execution, for
+//! `-Zmiri-record` and `-Zmiri-replay`.
+//!
+//! The recording is a text file with one decision per line, in the order in which the decisions
+//! were made. Each line consists of the kind of the decision and its value. There are two sources
+//! of non-determinism:
+//! - Miri's RNG, which is used for thread scheduling, base addresses of allocations, the loads
+//!   returned by the weak memory emulation, spurious failures of `compare_exchange_weak`, and so
+//!   on. Every value drawn from it is recorded.
+//! - The host, when isolation is disabled: the monotone clock, the system time, the host's
+//!   entropy source, everything read from files and stdin, and the environment variables. When
+//!   replaying, the host is not asked at all.
+//!
+//! Other host state, like file metadata, file positions and directory listings, is not recorded,
+//! so replaying an execution that depends on it requires the file system to be unchanged.
+//!
+//! When the replayed execution asks for a different kind of decision than the recording contains
+//! next, it has diverged from the recorded one; this is reported as an error at the next basic
+//! block.
+
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write as _};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use rand::rngs::StdRng;
+use rand::RngCore;
+
+use crate::*;
+
+/// The first line of every recording.
+const HEADER: &str = "miri-recording 1";
+
+/// Whether to record or replay an execution, and the file to use for that.
+#[derive(Clone, Debug)]
+pub enum RecordReplay {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// A value that can be written to a recording.
+pub trait Decision: Sized {
+    fn encode(&self) -> String;
+    fn decode(value: &str) -> Option<Self>;
+}
+
+impl Decision for u32 {
+    fn encode(&self) -> String {
+        format!("{self:x}")
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        u32::from_str_radix(value, 16).ok()
+    }
+}
+
+impl Decision for u64 {
+    fn encode(&self) -> String {
+        format!("{self:x}")
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        u64::from_str_radix(value, 16).ok()
+    }
+}
+
+impl Decision for Vec<u8> {
+    fn encode(&self) -> String {
+        let mut value = String::with_capacity(2 * self.len());
+        for byte in self {
+            write!(value, "{byte:02x}").unwrap();
+        }
+        value
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        if value.len() % 2 != 0 {
+            return None;
+        }
+        (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+}
+
+impl Decision for Duration {
+    fn encode(&self) -> String {
+        self.as_nanos().to_string()
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        Some(Duration::from_nanos(value.parse().ok()?))
+    }
+}
+
+impl Decision for SystemTime {
+    fn encode(&self) -> String {
+        match self.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => after.encode(),
+            Err(before) => format!("-{}", before.duration().encode()),
+        }
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        match value.strip_prefix('-') {
+            Some(before) => SystemTime::UNIX_EPOCH.checked_sub(Duration::decode(before)?),
+            None => SystemTime::UNIX_EPOCH.checked_add(Duration::decode(value)?),
+        }
+    }
+}
+
+impl Decision for io::Result<Vec<u8>> {
+    fn encode(&self) -> String {
+        match self {
+            Ok(data) => format!("ok {}", data.encode()),
+            Err(err) =>
+                match err.raw_os_error() {
+                    Some(code) => format!("err {code}"),
+                    None => "err".to_owned(),
+                },
+        }
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        if let Some(data) = value.strip_prefix("ok ") {
+            return Some(Ok(Vec::decode(data)?));
+        }
+        match value.strip_prefix("err")? {
+            "" => Some(Err(io::ErrorKind::Other.into())),
+            code => Some(Err(io::Error::from_raw_os_error(code.trim_start().parse().ok()?))),
+        }
+    }
+}
+
+impl Decision for Vec<(OsString, OsString)> {
+    fn encode(&self) -> String {
+        let encode = |s: &OsString| s.as_encoded_bytes().to_vec().encode();
+        self.iter()
+            .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let decode = |s: &str| bytes_to_os_string(Vec::decode(s)?);
+        value
+            .split(' ')
+            .filter(|var| !var.is_empty())
+            .map(|var| {
+                let (name, value) = var.split_once('=')?;
+                Some((decode(name)?, decode(value)?))
+            })
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn bytes_to_os_string(bytes: Vec<u8>) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+    Some(OsString::from_vec(bytes))
+}
+#[cfg(not(unix))]
+fn bytes_to_os_string(bytes: Vec<u8>) -> Option<OsString> {
+    // We cannot use `from_encoded_bytes_unchecked` here since we can't trust the recording.
+    Some(OsString::from(String::from_utf8(bytes).ok()?))
+}
+
+#[derive(Debug)]
+enum Log {
+    Record(BufWriter<File>),
+    Replay(io::Lines<BufReader<File>>),
+}
+
+/// The recording of the current execution, shared by everything that makes decisions.
+#[derive(Debug)]
+pub struct Recorder {
+    log: RefCell<Log>,
+    /// The first error that occurred while recording or replaying.
+    error: RefCell<Option<String>>,
+}
+
+impl Recorder {
+    pub fn new(mode: &RecordReplay) -> io::Result<Self> {
+        let log = match mode {
+            RecordReplay::Record(path) => {
+                let mut file = BufWriter::new(File::create(path)?);
+                writeln!(file, "{HEADER}")?;
+                Log::Record(file)
+            }
+            RecordReplay::Replay(path) => {
+                let mut lines = BufReader::new(File::open(path)?).lines();
+                if lines.next().transpose()?.as_deref() != Some(HEADER) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "the file is not a recording made by `-Zmiri-record`",
+                    ));
+                }
+                Log::Replay(lines)
+            }
+        };
+        Ok(Recorder { log: RefCell::new(log), error: RefCell::new(None) })
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(*self.log.borrow(), Log::Replay(_))
+    }
+
+    /// When recording, makes the decision by calling `decide` and records it. When replaying,
+    /// returns the recorded decision instead.
+    ///
+    /// If the replayed execution diverged from the recorded one, `decide` is called so that the
+    /// execution can continue until the error is reported by `check`.
+    pub fn decide<T: Decision>(&self, kind: &str, decide: impl FnOnce() -> T) -> T {
+        if self.error.borrow().is_some() {
+            return decide();
+        }
+        let result = match &mut *self.log.borrow_mut() {
+            Log::Record(file) => {
+                let value = decide();
+                if let Err(err) = writeln!(file, "{kind} {}", value.encode()) {
+                    let err = format!("failed to write the recording: {err}");
+                    *self.error.borrow_mut() = Some(err);
+                }
+                return value;
+            }
+            Log::Replay(lines) => replay(lines, kind),
+        };
+        match result {
+            Ok(value) => value,
+            Err(err) => {
+                *self.error.borrow_mut() = Some(err);
+                decide()
+            }
+        }
+    }
+
+    /// Reports the first error that occurred while recording or replaying.
+    pub fn check<'tcx>(&self) -> InterpResult<'tcx> {
+        if let Some(err) = &*self.error.borrow() {
+            throw_machine_stop!(TerminationInfo::RecordReplay(err.clone()));
+        }
+        Ok(())
+    }
+
+    /// Writes everything that was recorded to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        match &mut *self.log.borrow_mut() {
+            Log::Record(file) => file.flush(),
+            Log::Replay(_) => Ok(()),
+        }
+    }
+}
+
+/// Reads the next decision from the recording, which must be of the given kind.
+fn replay<T: Decision>(lines: &mut io::Lines<BufReader<File>>, kind: &str) -> Result<T, String> {
+    let line = match lines.next() {
+        Some(line) => line.map_err(|err| format!("failed to read the recording: {err}"))?,
+        None => return Err(format!("asked for a `{kind}` decision, but the recording ended")),
+    };
+    let (recorded_kind, value) = line.split_once(' ').unwrap_or((&line, ""));
+    if recorded_kind != kind {
+        return Err(format!(
+            "asked for a `{kind}` decision, but the recording contains a `{recorded_kind}` decision"
+        ));
+    }
+    T::decode(value)
+        .ok_or_else(|| format!("the recording contains an invalid `{kind}` decision: `{value}`"))
+}
+
+/// Miri's RNG, which records all values drawn from it when there is a recorder.
+#[derive(Debug)]
+pub struct RecordedRng {
+    rng: StdRng,
+    recorder: Option<Rc<Recorder>>,
+}
+
+impl RecordedRng {
+    pub fn new(rng: StdRng, recorder: Option<Rc<Recorder>>) -> Self {
+        RecordedRng { rng, recorder }
+    }
+}
+
+impl RngCore for RecordedRng {
+    fn next_u32(&mut self) -> u32 {
+        match &self.recorder {
+            Some(recorder) => recorder.decide("rng32", || self.rng.next_u32()),
+            None => self.rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match &self.recorder {
+            Some(recorder) => recorder.decide("rng64", || self.rng.next_u64()),
+            None => self.rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match &self.recorder {
+            Some(recorder) => {
+                let bytes = recorder.decide("rng-bytes", || {
+                    let mut bytes = vec![0; dest.len()];
+                    self.rng.fill_bytes(&mut bytes);
+                    bytes
+                });
+                if bytes.len() == dest.len() {
+                    dest.copy_from_slice(&bytes);
+                } else {
+                    // The execution diverged, which will be reported at the next basic block.
+                    self.rng.fill_bytes(dest);
+                }
+            }
+            None => self.rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<'tcx> MiriMachine<'tcx> {
+    /// Makes a decision that depends on the host, recording or replaying it if requested.
+    pub(crate) fn decide<T: Decision>(&self, kind: &str, decide: impl FnOnce() -> T) -> T {
+        match &self.recorder {
+            Some(recorder) => recorder.decide(kind, decide),
+            None => decide(),
+        }
+    }
+
+    /// Reads from the host into `bytes` using `read`, recording or replaying the data if
+    /// requested.
+    pub(crate) fn decide_read(
+        &self,
+        bytes: &mut [u8],
+        read: impl FnOnce(&mut [u8]) -> io::Result<usize>,
+    ) -> io::Result<usize> {
+        let data = self.decide("read", || {
+            let len = read(bytes)?;
+            Ok(bytes[..len].to_vec())
+        })?;
+        // A diverged replay could return more data than was asked for.
+        let len = data.len().min(bytes.len());
+        bytes[..len].copy_from_slice(&data[..len]);
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: Decision>(value: T) -> T {
+        T::decode(&value.encode()).unwrap()
+    }
+
+    #[test]
+    fn decisions_roundtrip() {
+        assert_eq!(roundtrip(0xdead_beef_u64), 0xdead_beef);
+        assert_eq!(roundtrip(vec![0u8, 1, 0xff]), [0, 1, 0xff]);
+        assert_eq!(roundtrip(Vec::<u8>::new()), []);
+        assert_eq!(roundtrip(Duration::new(3, 7)), Duration::new(3, 7));
+        let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(roundtrip(before_epoch), before_epoch);
+        assert_eq!(roundtrip(io::Result::Ok(vec![42u8])).unwrap(), [42]);
+        let err = roundtrip(io::Result::<Vec<u8>>::Err(io::Error::from_raw_os_error(2)));
+        assert_eq!(err.unwrap_err().raw_os_error(), Some(2));
+        let env = vec![
+            (OsString::from("A"), OsString::from("b=c")),
+            (OsString::new(), OsString::new()),
+        ];
+        assert_eq!(roundtrip(env.clone()), env);
+        assert_eq!(roundtrip(Vec::<(OsString, OsString)>::new()), []);
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            let env = vec![(OsString::from("A"), OsString::from_vec(vec![b'b', 0xff, 0x80]))];
+            assert_eq!(roundtrip(env.clone()), env);
+        }
+    }
+
+    #[test]
+    fn invalid_decisions() {
+        assert_eq!(<Vec<u8>>::decode("abc"), None);
+        assert_eq!(<Vec<u8>>::decode("zz"), None);
+        assert!(<io::Result<Vec<u8>>>::decode("maybe").is_none());
+    }
+}
@@ -32,13 +32,18 @@ pub(crate) fn init(
         // Skip the loop entirely if we don't want to forward anything.
         let mut env_vars = FxHashMap::default();
         if ecx.machine.communicate() || !config.forwarded_env_vars.is_empty() {
-            for (name, value) in &config.env {
-                let forward = ecx.machine.communicate()
-                    || config.forwarded_env_vars.iter().any(|v| **v == *name);
-                if forward {
-                    env_vars.insert(OsString::from(name), OsString::from(value));
-                }
-            }
+            let forwarded = ecx.machine.decide("env", || {
+                config
+                    .env
+                    .iter()
+                    .filter(|(name, _)| {
+                        ecx.machine.communicate()
+                            || config.forwarded_env_vars.iter().any(|v| **v == *name)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+            });
+            env_vars.extend(forwarded);
         }
 
         for (name, value) in &config.set_env_vars {
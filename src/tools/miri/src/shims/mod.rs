@@ -17,7 +17,11 @@
 pub mod time;
 pub mod tls;
 
-pub use unix::{DirTable, FdTable};
+pub use unix::{
+    parse_process_stubs, DirTable, FdTable, FileMapping, ProcessTable, VirtualNetwork,
+};
+pub use windows::WindowsFileTable;
+pub use x86::asm::EvalContextExt as InlineAsmEvalContextExt;
 
 /// What needs to be done after emulating an item (a shim or an intrinsic) is done.
 pub enum EmulateItemResult {
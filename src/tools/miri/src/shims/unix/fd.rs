@@ -5,13 +5,18 @@
 use std::cell::{Ref, RefCell, RefMut};
 use std::collections::BTreeMap;
 use std::io::{self, ErrorKind, IsTerminal, Read, SeekFrom, Write};
+use std::mem;
 use std::rc::Rc;
+use std::time::Duration;
 
 use rustc_target::abi::Size;
 
 use crate::shims::unix::*;
 use crate::*;
 
+/// How often `poll` checks file descriptors that a host process might make ready.
+const HOST_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
 /// Represents an open file descriptor.
 pub trait FileDescription: std::fmt::Debug + Any {
     fn name(&self) -> &'static str;
@@ -58,6 +63,43 @@ fn is_tty(&self, _communicate_allowed: bool) -> bool {
         // so we use a default impl here.
         false
     }
+
+    /// Returns which operations would currently not block, for `poll` and `epoll`.
+    fn readiness<'tcx>(&self) -> InterpResult<'tcx, Readiness> {
+        throw_unsup_format!("cannot poll {}", self.name());
+    }
+
+    /// Makes reads and writes return `EWOULDBLOCK` instead of blocking, for `FIONBIO`.
+    fn set_nonblocking<'tcx>(&mut self, _nonblocking: bool) -> InterpResult<'tcx> {
+        throw_unsup_format!("cannot make {} non-blocking", self.name());
+    }
+
+    /// Whether a `read` that returns `EWOULDBLOCK` should instead block the thread until the file
+    /// description becomes readable.
+    fn blocks_on_read(&self) -> bool {
+        false
+    }
+
+    /// How long a blocking `read` waits at most, or `None` to wait forever.
+    fn read_timeout(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Which operations on a file description would currently not block.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Readiness {
+    /// Reading would not block: there is data, or reading returns end-of-file.
+    pub readable: bool,
+    /// Writing would not block.
+    pub writable: bool,
+    /// The other end shut down writing, so reading returns end-of-file once all data was read.
+    pub read_closed: bool,
+    /// Both directions were shut down.
+    pub hangup: bool,
+    /// Nothing is ready, but a host process might make this ready at any time, without the
+    /// program calling `wake_fd_waiters`. Threads waiting for this must check it periodically.
+    pub host_pending: bool,
 }
 
 impl dyn FileDescription {
@@ -81,13 +123,13 @@ fn read<'tcx>(
         &mut self,
         communicate_allowed: bool,
         bytes: &mut [u8],
-        _ecx: &mut MiriInterpCx<'tcx>,
+        ecx: &mut MiriInterpCx<'tcx>,
     ) -> InterpResult<'tcx, io::Result<usize>> {
         if !communicate_allowed {
             // We want isolation mode to be deterministic, so we have to disallow all reads, even stdin.
             helpers::isolation_abort_error("`read` from stdin")?;
         }
-        Ok(Read::read(self, bytes))
+        Ok(ecx.machine.decide_read(bytes, |bytes| Read::read(self, bytes)))
     }
 
     fn is_tty(&self, communicate_allowed: bool) -> bool {
@@ -164,6 +206,53 @@ fn write<'tcx>(
     }
 }
 
+/// `/dev/null`, which can be opened even with isolation enabled.
+#[derive(Debug)]
+pub struct DevNull;
+
+impl FileDescription for DevNull {
+    fn name(&self) -> &'static str {
+        "/dev/null"
+    }
+
+    fn read<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        _bytes: &mut [u8],
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        Ok(Ok(0))
+    }
+
+    fn write<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &[u8],
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        Ok(Ok(bytes.len()))
+    }
+
+    fn seek<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        _offset: SeekFrom,
+    ) -> InterpResult<'tcx, io::Result<u64>> {
+        Ok(Ok(0))
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        Ok(Ok(()))
+    }
+
+    fn readiness<'tcx>(&self) -> InterpResult<'tcx, Readiness> {
+        Ok(Readiness { readable: true, writable: true, ..Readiness::default() })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FileDescriptor(Rc<RefCell<Box<dyn FileDescription>>>);
 
@@ -180,6 +269,11 @@ pub fn borrow_mut(&self) -> RefMut<'_, dyn FileDescription> {
         RefMut::map(self.0.borrow_mut(), |fd| fd.as_mut())
     }
 
+    /// Whether both refer to the same file description.
+    pub fn ptr_eq(&self, other: &FileDescriptor) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
     pub fn close<'ctx>(self, communicate_allowed: bool) -> InterpResult<'ctx, io::Result<()>> {
         // Destroy this `Rc` using `into_inner` so we can call `close` instead of
         // implicitly running the destructor of the file description.
@@ -194,6 +288,11 @@ pub fn close<'ctx>(self, communicate_allowed: bool) -> InterpResult<'ctx, io::Re
 #[derive(Debug)]
 pub struct FdTable {
     pub fds: BTreeMap<i32, FileDescriptor>,
+    /// The threads blocked until some file descriptor becomes ready, see `block_on_fds`.
+    waiters: Vec<ThreadId>,
+    /// Counts the calls to `wake_fd_waiters`, i.e. the times some file descriptor might have
+    /// become ready. Used to implement edge-triggered `epoll`.
+    pub generation: u64,
 }
 
 impl VisitProvenance for FdTable {
@@ -213,7 +312,7 @@ pub(crate) fn new(mute_stdout_stderr: bool) -> FdTable {
             fds.insert(1i32, FileDescriptor::new(io::stdout()));
             fds.insert(2i32, FileDescriptor::new(io::stderr()));
         }
-        FdTable { fds }
+        FdTable { fds, waiters: Vec::new(), generation: 0 }
     }
 
     pub fn insert_fd(&mut self, file_handle: FileDescriptor) -> i32 {
@@ -336,6 +435,8 @@ fn close(&mut self, fd_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, Scalar
 
         Ok(Scalar::from_i32(if let Some(file_descriptor) = this.machine.fds.remove(fd) {
             let result = file_descriptor.close(this.machine.communicate())?;
+            // Closing a file descriptor can make the other end of a socket ready.
+            this.wake_fd_waiters()?;
             // return `0` if close is successful
             let result = result.map(|()| 0i32);
             this.try_unwrap_io_result(result)?
@@ -360,7 +461,26 @@ fn read(
         fd: i32,
         buf: Pointer<Option<Provenance>>,
         count: u64,
-    ) -> InterpResult<'tcx, i64> {
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let timeout = this.machine.fds.get(fd).and_then(|file_descriptor| {
+            let duration = file_descriptor.read_timeout()?;
+            Some(Timeout::Monotonic(this.machine.clock.now().checked_add(duration).unwrap()))
+        });
+        this.read_or_block(fd, buf, count, timeout, dest)
+    }
+
+    /// Reads from `fd`, or blocks until it becomes readable or `timeout` elapsed if the file
+    /// description asks for that.
+    fn read_or_block(
+        &mut self,
+        fd: i32,
+        buf: Pointer<Option<Provenance>>,
+        count: u64,
+        timeout: Option<Timeout>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
 
         // Isolation check is done via `FileDescriptor` trait.
@@ -380,7 +500,8 @@ fn read(
         // We temporarily dup the FD to be able to retain mutable access to `this`.
         let Some(file_descriptor) = this.machine.fds.dup(fd) else {
             trace!("read: FD not found");
-            return this.fd_not_found();
+            let result: i64 = this.fd_not_found()?;
+            return this.write_int(result, dest);
         };
 
         trace!("read: FD mapped to {:?}", file_descriptor);
@@ -394,17 +515,48 @@ fn read(
             .borrow_mut()
             .read(communicate, &mut bytes, this)?
             .map(|c| i64::try_from(c).unwrap());
-        drop(file_descriptor);
+        let blocks = file_descriptor.borrow().blocks_on_read();
 
         match result {
             Ok(read_bytes) => {
                 // If reading to `bytes` did not fail, we write those bytes to the buffer.
                 this.write_bytes_ptr(buf, bytes)?;
-                Ok(read_bytes)
+                this.write_int(read_bytes, dest)
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock && blocks => {
+                let host_pending = file_descriptor.borrow().readiness()?.host_pending;
+                drop(file_descriptor);
+                let (wait_timeout, rechecking) = this.fd_wait_timeout(host_pending, &timeout);
+                let dest = dest.clone();
+                this.block_on_fds(
+                    wait_timeout,
+                    callback!(
+                        @capture<'tcx> {
+                            fd: i32,
+                            buf: Pointer<Option<Provenance>>,
+                            count: u64,
+                            timeout: Option<Timeout>,
+                            rechecking: bool,
+                            dest: MPlaceTy<'tcx, Provenance>,
+                        }
+                        @unblock = |this| {
+                            this.read_or_block(fd, buf, count, timeout, &dest)
+                        }
+                        @timeout = |this| {
+                            this.fd_wait_timed_out();
+                            if rechecking {
+                                return this.read_or_block(fd, buf, count, timeout, &dest);
+                            }
+                            this.set_last_error_from_io_error(ErrorKind::WouldBlock.into())?;
+                            this.write_int(-1, &dest)
+                        }
+                    ),
+                );
+                Ok(())
             }
             Err(e) => {
                 this.set_last_error_from_io_error(e)?;
-                Ok(-1)
+                this.write_int(-1, dest)
             }
         }
     }
@@ -441,6 +593,158 @@ fn write(
             .map(|c| i64::try_from(c).unwrap());
         drop(file_descriptor);
 
+        // Writing can make the file descriptor (or, for sockets, its other end) ready for reading.
+        this.wake_fd_waiters()?;
+
         this.try_unwrap_io_result(result)
     }
+
+    /// Blocks the active thread until any file descriptor might have become ready, or until the
+    /// timeout elapsed. The callback is then expected to check whether the operation the thread
+    /// is waiting for can proceed, and otherwise block again.
+    ///
+    /// Whenever an operation might make a file descriptor ready, it must call `wake_fd_waiters`.
+    fn block_on_fds(
+        &mut self,
+        timeout: Option<Timeout>,
+        callback: impl UnblockCallback<'tcx> + 'tcx,
+    ) {
+        let this = self.eval_context_mut();
+        let thread = this.active_thread();
+        this.machine.fds.waiters.push(thread);
+        this.block_thread(BlockReason::Fd, timeout, callback);
+    }
+
+    /// Returns the timeout to pass to `block_on_fds` for a wait that ends at `timeout`, and whether
+    /// it is an earlier one for checking again. Host processes do not wake us up, so if one of them
+    /// might make a file descriptor ready (`host_pending`), we check again in a bit.
+    fn fd_wait_timeout(
+        &self,
+        host_pending: bool,
+        timeout: &Option<Timeout>,
+    ) -> (Option<Timeout>, bool) {
+        let this = self.eval_context_ref();
+        let recheck = host_pending
+            .then(|| this.machine.clock.now().checked_add(HOST_POLL_INTERVAL).unwrap())
+            .filter(|recheck| {
+                match timeout {
+                    Some(Timeout::Monotonic(end)) =>
+                        end.duration_since(recheck.clone()) > Duration::ZERO,
+                    Some(Timeout::RealTime(_)) | None => true,
+                }
+            });
+        let rechecking = recheck.is_some();
+        (recheck.map(Timeout::Monotonic).or(timeout.clone()), rechecking)
+    }
+
+    /// Removes the active thread from the threads blocked in `block_on_fds`, after it timed out.
+    fn fd_wait_timed_out(&mut self) {
+        let this = self.eval_context_mut();
+        let thread = this.active_thread();
+        this.machine.fds.waiters.retain(|&waiter| waiter != thread);
+    }
+
+    /// Unblocks all threads blocked in `block_on_fds`.
+    fn wake_fd_waiters(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.machine.fds.generation += 1;
+        for thread in mem::take(&mut this.machine.fds.waiters) {
+            this.unblock_thread(thread, BlockReason::Fd)?;
+        }
+        Ok(())
+    }
+
+    /// Turns a `poll` or `epoll_wait` timeout in milliseconds into a `Timeout`. Returns `None` for
+    /// negative timeouts, which mean to wait forever.
+    fn fd_timeout(&self, timeout_ms: i32) -> Option<Timeout> {
+        let this = self.eval_context_ref();
+        let duration = Duration::from_millis(u64::try_from(timeout_ms).ok()?);
+        Some(Timeout::Monotonic(this.machine.clock.now().checked_add(duration).unwrap()))
+    }
+
+    /// Waits until one of the given file descriptors is ready.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/poll.2.html>
+    fn poll(
+        &mut self,
+        fds: Pointer<Option<Provenance>>,
+        nfds: u64,
+        timeout: Option<Timeout>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let pollfd_layout = this.libc_ty_layout("pollfd");
+        let pollin = this.eval_libc("POLLIN").to_i16()?;
+        let pollout = this.eval_libc("POLLOUT").to_i16()?;
+        let pollhup = this.eval_libc("POLLHUP").to_i16()?;
+        let pollnval = this.eval_libc("POLLNVAL").to_i16()?;
+
+        let mut ready = 0;
+        let mut host_pending = false;
+        for i in 0..nfds {
+            let offset = pollfd_layout.size.checked_mul(i, this).unwrap();
+            let pollfd =
+                this.ptr_to_mplace(fds, pollfd_layout).offset(offset, pollfd_layout, this)?;
+            let fd = this.read_scalar(&this.project_field_named(&pollfd, "fd")?)?.to_i32()?;
+            let events =
+                this.read_scalar(&this.project_field_named(&pollfd, "events")?)?.to_i16()?;
+            let revents = if fd < 0 {
+                // Negative file descriptors are ignored.
+                0
+            } else if let Some(file_descriptor) = this.machine.fds.get(fd) {
+                let readiness = file_descriptor.readiness()?;
+                host_pending |= readiness.host_pending;
+                let mut revents = 0;
+                if readiness.readable {
+                    revents |= events & pollin;
+                }
+                if readiness.writable {
+                    revents |= events & pollout;
+                }
+                if readiness.hangup {
+                    revents |= pollhup;
+                }
+                revents
+            } else {
+                pollnval
+            };
+            if revents != 0 {
+                ready += 1;
+            }
+            let revents_place = this.project_field_named(&pollfd, "revents")?;
+            this.write_int(revents, &revents_place)?;
+        }
+
+        if ready > 0 {
+            this.write_int(ready, dest)?;
+            return Ok(());
+        }
+        // Nothing is ready, so wait. A timeout of 0 fires right away.
+        let (wait_timeout, rechecking) = this.fd_wait_timeout(host_pending, &timeout);
+        let dest = dest.clone();
+        this.block_on_fds(
+            wait_timeout,
+            callback!(
+                @capture<'tcx> {
+                    fds: Pointer<Option<Provenance>>,
+                    nfds: u64,
+                    timeout: Option<Timeout>,
+                    rechecking: bool,
+                    dest: MPlaceTy<'tcx, Provenance>,
+                }
+                @unblock = |this| {
+                    this.poll(fds, nfds, timeout, &dest)
+                }
+                @timeout = |this| {
+                    this.fd_wait_timed_out();
+                    if rechecking {
+                        return this.poll(fds, nfds, timeout, &dest);
+                    }
+                    this.write_int(0, &dest)
+                }
+            ),
+        );
+        Ok(())
+    }
 }
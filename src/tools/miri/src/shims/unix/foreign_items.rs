@@ -1,8 +1,10 @@
 use std::ffi::OsStr;
+use std::iter;
 use std::str;
 
 use rustc_middle::ty::layout::LayoutOf;
 use rustc_span::Symbol;
+use rustc_target::abi::Size;
 use rustc_target::spec::abi::Abi;
 
 use crate::shims::alloc::EvalContextExt as _;
@@ -24,6 +26,8 @@ pub fn is_dyn_sym(name: &str, target_os: &str) -> bool {
         "signal" => true,
         // needed at least on macOS to avoid file-based fallback in getrandom
         "getentropy" | "getrandom" => true,
+        // `std::process::Command::current_dir` looks it up with `dlsym`.
+        "posix_spawn_file_actions_addchdir_np" => true,
         // Give specific OSes a chance to allow their symbols.
         _ =>
             match target_os {
@@ -85,8 +89,7 @@ fn emulate_foreign_item_inner(
                 let fd = this.read_scalar(fd)?.to_i32()?;
                 let buf = this.read_pointer(buf)?;
                 let count = this.read_target_usize(count)?;
-                let result = this.read(fd, buf, count)?;
-                this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
+                this.read(fd, buf, count, dest)?;
             }
             "write" => {
                 let [fd, buf, n] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
@@ -229,6 +232,232 @@ fn emulate_foreign_item_inner(
                 let result = this.socketpair(domain, type_, protocol, sv)?;
                 this.write_scalar(result, dest)?;
             }
+            "socket" => {
+                let [domain, type_, protocol] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.socket(domain, type_, protocol)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "bind" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.bind(socket, address, address_len)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "listen" => {
+                let [socket, backlog] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.listen(socket, backlog)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "connect" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.connect(socket, address, address_len)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "accept" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let socket = this.read_scalar(socket)?.to_i32()?;
+                let address = this.read_pointer(address)?;
+                let address_len = this.read_pointer(address_len)?;
+                this.accept4(socket, address, address_len, 0, dest)?;
+            }
+            "accept4" => {
+                let [socket, address, address_len, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let socket = this.read_scalar(socket)?.to_i32()?;
+                let address = this.read_pointer(address)?;
+                let address_len = this.read_pointer(address_len)?;
+                let flags = this.read_scalar(flags)?.to_i32()?;
+                this.accept4(socket, address, address_len, flags, dest)?;
+            }
+            "getsockname" | "getpeername" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let peer = link_name.as_str() == "getpeername";
+                let result = this.getsockname(socket, address, address_len, peer)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "send" => {
+                let [socket, buf, len, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sendto(socket, buf, len, flags, None)?;
+                this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
+            }
+            "sendto" => {
+                let [socket, buf, len, flags, address, address_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sendto(socket, buf, len, flags, Some((address, address_len)))?;
+                this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
+            }
+            "recv" | "recvfrom" => {
+                let (socket, buf, len, flags, address, address_len) = if link_name.as_str() == "recv" {
+                    let [socket, buf, len, flags] =
+                        this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                    (socket, buf, len, flags, Pointer::null(), Pointer::null())
+                } else {
+                    let [socket, buf, len, flags, address, address_len] =
+                        this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                    let address = this.read_pointer(address)?;
+                    let address_len = this.read_pointer(address_len)?;
+                    (socket, buf, len, flags, address, address_len)
+                };
+                let socket = this.read_scalar(socket)?.to_i32()?;
+                let buf = this.read_pointer(buf)?;
+                let len = this.read_target_usize(len)?;
+                let flags = this.read_scalar(flags)?.to_i32()?;
+                this.recvfrom(socket, buf, len, flags, address, address_len, dest)?;
+            }
+            "shutdown" => {
+                let [socket, how] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.shutdown(socket, how)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "setsockopt" => {
+                let [socket, level, name, value, value_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.setsockopt(socket, level, name, value, value_len)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "getsockopt" => {
+                let [socket, level, name, value, value_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getsockopt(socket, level, name, value, value_len)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "ioctl" => {
+                // `ioctl` is variadic. The argument count is checked based on the request in
+                // `this.ioctl()`, so we do not use `check_shim` here.
+                this.check_abi_and_shim_symbol_clash(abi, Abi::C { unwind: false }, link_name)?;
+                let result = this.ioctl(args)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "poll" => {
+                let [fds, nfds, timeout] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let fds = this.read_pointer(fds)?;
+                let nfds = this.read_target_usize(nfds)?;
+                let timeout = this.read_scalar(timeout)?.to_i32()?;
+                let timeout = this.fd_timeout(timeout);
+                this.poll(fds, nfds, timeout, dest)?;
+            }
+
+            // Processes
+            "pipe" => {
+                let [fds] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.pipe2(fds, None)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pipe2" => {
+                let [fds, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.pipe2(fds, Some(flags))?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn" | "posix_spawnp" => {
+                let [pid, path, file_actions, attrp, argv, envp] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                // The attributes are ignored.
+                let _attrp = this.read_pointer(attrp)?;
+                let search_path = link_name.as_str() == "posix_spawnp";
+                let result = this.posix_spawn(pid, path, file_actions, argv, envp, search_path)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_init" => {
+                let [file_actions] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.posix_spawn_file_actions_init(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_destroy" => {
+                let [file_actions] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.posix_spawn_file_actions_destroy(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_adddup2" => {
+                let [file_actions, fd, new_fd] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.posix_spawn_file_actions_adddup2(file_actions, fd, new_fd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_addclose" => {
+                let [file_actions, fd] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.posix_spawn_file_actions_addclose(file_actions, fd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_addchdir_np" => {
+                let [file_actions, path] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.posix_spawn_file_actions_addchdir_np(file_actions, path)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawnattr_init"
+            | "posix_spawnattr_destroy"
+            | "posix_spawnattr_setflags"
+            | "posix_spawnattr_setsigdefault"
+            | "posix_spawnattr_setsigmask"
+            | "posix_spawnattr_setpgroup" => {
+                // The attributes only affect the child, in ways we do not support anyway, so they
+                // are not even stored.
+                this.check_abi_and_shim_symbol_clash(abi, Abi::C { unwind: false }, link_name)?;
+                let _attr = this.read_pointer(&args[0])?;
+                this.write_null(dest)?;
+            }
+            "sigemptyset" => {
+                let [set] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+                let zeros = iter::repeat(0u8).take(set.layout.size.bytes_usize());
+                this.write_bytes_ptr(set.ptr(), zeros)?;
+                this.write_null(dest)?;
+            }
+            "sigaddset" => {
+                let [set, signo] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+                let signo = this.read_scalar(signo)?.to_i32()?;
+                let bit = u64::try_from(signo.wrapping_sub(1)).unwrap_or(u64::MAX);
+                if bit >= set.layout.size.bits() {
+                    let einval = this.eval_libc("EINVAL");
+                    this.set_last_error(einval)?;
+                    this.write_int(-1, dest)?;
+                } else {
+                    // Signal `n` is bit `n - 1`, counting from the least significant bit of the
+                    // first byte.
+                    let offset = Size::from_bytes(bit / 8);
+                    let byte = set.offset(offset, this.machine.layouts.u8, this)?;
+                    let value = this.read_scalar(&byte)?.to_u8()? | (1 << (bit % 8));
+                    this.write_scalar(Scalar::from_u8(value), &byte)?;
+                    this.write_null(dest)?;
+                }
+            }
+            "waitpid" => {
+                let [pid, status, options] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let pid = this.read_scalar(pid)?.to_i32()?;
+                let status = this.read_pointer(status)?;
+                let options = this.read_scalar(options)?.to_i32()?;
+                this.waitpid(pid, status, options, dest)?;
+            }
+            "kill" => {
+                let [pid, sig] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let pid = this.read_scalar(pid)?.to_i32()?;
+                let sig = this.read_scalar(sig)?.to_i32()?;
+                let result = this.kill(pid, sig)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "fork" | "vfork" | "execve" | "execv" | "execvp" => {
+                // These would duplicate or replace the interpreter, so they fail, and programs
+                // like `std::process::Command` report the error. Only `posix_spawn` can create
+                // child processes.
+                this.check_abi_and_shim_symbol_clash(abi, Abi::C { unwind: false }, link_name)?;
+                let enosys = this.eval_libc("ENOSYS");
+                this.set_last_error(enosys)?;
+                this.write_int(-1, dest)?;
+            }
 
             // Time
             "gettimeofday" => {
@@ -266,6 +495,11 @@ fn emulate_foreign_item_inner(
                 let result = this.munmap(addr, length)?;
                 this.write_scalar(result, dest)?;
             }
+            "msync" => {
+                let [addr, length, flags] = this.check_shim(abi, Abi::C {unwind: false}, link_name, args)?;
+                let result = this.msync(addr, length, flags)?;
+                this.write_scalar(result, dest)?;
+            }
 
             "reallocarray" => {
                 // Currently this function does not exist on all Unixes, e.g. on macOS.
@@ -16,12 +16,12 @@
 use crate::*;
 use shims::time::system_time_to_duration;
 
-use self::fd::FileDescriptor;
+use self::fd::{DevNull, FileDescriptor, Readiness};
 
 #[derive(Debug)]
-struct FileHandle {
-    file: File,
-    writable: bool,
+pub struct FileHandle {
+    pub file: File,
+    pub writable: bool,
 }
 
 impl FileDescription for FileHandle {
@@ -33,10 +33,10 @@ fn read<'tcx>(
         &mut self,
         communicate_allowed: bool,
         bytes: &mut [u8],
-        _ecx: &mut MiriInterpCx<'tcx>,
+        ecx: &mut MiriInterpCx<'tcx>,
     ) -> InterpResult<'tcx, io::Result<usize>> {
         assert!(communicate_allowed, "isolation should have prevented even opening a file");
-        Ok(self.file.read(bytes))
+        Ok(ecx.machine.decide_read(bytes, |bytes| self.file.read(bytes)))
     }
 
     fn write<'tcx>(
@@ -58,6 +58,11 @@ fn seek<'tcx>(
         Ok(self.file.seek(offset))
     }
 
+    fn readiness<'tcx>(&self) -> InterpResult<'tcx, Readiness> {
+        // Regular files never block.
+        Ok(Readiness { readable: true, writable: true, ..Readiness::default() })
+    }
+
     fn close<'tcx>(
         self: Box<Self>,
         communicate_allowed: bool,
@@ -376,6 +381,10 @@ fn open(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            // `/dev/null` is deterministic, and `Stdio::null` needs it.
+            if path == Path::new("/dev/null") {
+                return Ok(this.machine.fds.insert_fd(FileDescriptor::new(DevNull)));
+            }
             this.reject_in_isolation("`open`", reject_with)?;
             this.set_last_error_from_io_error(ErrorKind::PermissionDenied.into())?;
             return Ok(-1);
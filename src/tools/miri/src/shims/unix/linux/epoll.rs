@@ -1,7 +1,6 @@
+use std::collections::BTreeMap;
 use std::io;
 
-use rustc_data_structures::fx::FxHashMap;
-
 use crate::shims::unix::*;
 use crate::*;
 
@@ -11,11 +10,10 @@
 #[derive(Clone, Debug, Default)]
 struct Epoll {
     /// The file descriptors we are watching, and what we are watching for.
-    file_descriptors: FxHashMap<i32, EpollEvent>,
+    file_descriptors: BTreeMap<i32, EpollEvent>,
 }
 
 /// Epoll Events associate events with data.
-/// These fields are currently unused by miri.
 /// This matches the `epoll_event` struct defined
 /// by the epoll_ctl man page. For more information
 /// see the man page:
@@ -23,12 +21,13 @@ struct Epoll {
 /// <https://man7.org/linux/man-pages/man2/epoll_ctl.2.html>
 #[derive(Clone, Debug)]
 struct EpollEvent {
-    #[allow(dead_code)]
     events: u32,
     /// `Scalar<Provenance>` is used to represent the
     /// `epoll_data` type union.
-    #[allow(dead_code)]
     data: Scalar<Provenance>,
+    /// For edge-triggered (`EPOLLET`) events, the `FdTable::generation` in which the event was
+    /// last reported. It is reported again only once some file descriptor changed.
+    reported: Option<u64>,
 }
 
 impl FileDescription for Epoll {
@@ -108,7 +107,16 @@ fn epoll_ctl(
             let events = this.read_scalar(&events)?.to_u32()?;
             let data = this.project_field(&event, 1)?;
             let data = this.read_scalar(&data)?;
-            let event = EpollEvent { events, data };
+            let event = EpollEvent { events, data, reported: None };
+
+            // Like on Linux, an epoll instance cannot watch itself.
+            let epoll = this.machine.fds.dup(epfd);
+            let target = this.machine.fds.dup(fd);
+            if epoll.zip(target).is_some_and(|(epoll, target)| epoll.ptr_eq(&target)) {
+                let einval = this.eval_libc("EINVAL");
+                this.set_last_error(einval)?;
+                return Ok(Scalar::from_i32(-1));
+            }
 
             let Some(mut epfd) = this.machine.fds.get_mut(epfd) else {
                 return Ok(Scalar::from_i32(this.fd_not_found()?));
@@ -167,26 +175,117 @@ fn epoll_ctl(
     /// <https://man7.org/linux/man-pages/man2/epoll_wait.2.html>
     fn epoll_wait(
         &mut self,
-        epfd: &OpTy<'tcx, Provenance>,
-        events: &OpTy<'tcx, Provenance>,
-        maxevents: &OpTy<'tcx, Provenance>,
-        timeout: &OpTy<'tcx, Provenance>,
-    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        epfd: i32,
+        events: Pointer<Option<Provenance>>,
+        maxevents: i32,
+        timeout: Option<Timeout>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
 
-        let epfd = this.read_scalar(epfd)?.to_i32()?;
-        let _events = this.read_scalar(events)?.to_pointer(this)?;
-        let _maxevents = this.read_scalar(maxevents)?.to_i32()?;
-        let _timeout = this.read_scalar(timeout)?.to_i32()?;
+        let Ok(max_ready @ 1..) = usize::try_from(maxevents) else {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return this.write_int(-1, dest);
+        };
+
+        let epollin = this.eval_libc("EPOLLIN").to_u32()?;
+        let epollout = this.eval_libc("EPOLLOUT").to_u32()?;
+        let epollrdhup = this.eval_libc("EPOLLRDHUP").to_u32()?;
+        let epollhup = this.eval_libc("EPOLLHUP").to_u32()?;
+        let epollet = this.eval_libc("EPOLLET").to_u32()?;
 
-        let Some(mut epfd) = this.machine.fds.get_mut(epfd) else {
-            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        // We temporarily dup the FD to be able to retain access to the other FDs.
+        let Some(epoll_fd) = this.machine.fds.dup(epfd) else {
+            let result: i32 = this.fd_not_found()?;
+            return this.write_int(result, dest);
         };
-        let _epfd = epfd
-            .downcast_mut::<Epoll>()
-            .ok_or_else(|| err_unsup_format!("non-epoll FD passed to `epoll_wait`"))?;
+        // Collect the interests first, so that the epoll instance is not borrowed while the file
+        // descriptors are asked for their readiness: one of them may refer to it as well.
+        let interests: Vec<(i32, u32)> = {
+            let epoll_description = epoll_fd.borrow();
+            let epoll = epoll_description
+                .downcast_ref::<Epoll>()
+                .ok_or_else(|| err_unsup_format!("non-epoll FD passed to `epoll_wait`"))?;
+            epoll.file_descriptors.iter().map(|(&fd, interest)| (fd, interest.events)).collect()
+        };
+        let mut pending = Vec::new();
+        for (fd, interest_events) in interests {
+            // Closed file descriptors never report events.
+            let Some(file_descriptor) = this.machine.fds.get(fd) else {
+                continue;
+            };
+            let readiness = file_descriptor.readiness()?;
+            let mut events = 0;
+            if readiness.readable {
+                events |= interest_events & epollin;
+            }
+            if readiness.writable {
+                events |= interest_events & epollout;
+            }
+            if readiness.read_closed {
+                events |= interest_events & epollrdhup;
+            }
+            if readiness.hangup {
+                events |= epollhup;
+            }
+            if events != 0 {
+                pending.push((fd, events));
+            }
+        }
+
+        let generation = this.machine.fds.generation;
+        let mut epoll_description = epoll_fd.borrow_mut();
+        let epoll = epoll_description.downcast_mut::<Epoll>().unwrap();
+        let mut ready = Vec::new();
+        for (fd, events) in pending {
+            if ready.len() == max_ready {
+                break;
+            }
+            let interest = epoll.file_descriptors.get_mut(&fd).unwrap();
+            if interest.events & epollet != 0 {
+                if interest.reported == Some(generation) {
+                    continue;
+                }
+                interest.reported = Some(generation);
+            }
+            ready.push((events, interest.data));
+        }
+        drop(epoll_description);
+
+        if ready.is_empty() {
+            // Nothing is ready, so wait. A timeout of 0 fires right away.
+            let dest = dest.clone();
+            this.block_on_fds(
+                timeout.clone(),
+                callback!(
+                    @capture<'tcx> {
+                        epfd: i32,
+                        events: Pointer<Option<Provenance>>,
+                        maxevents: i32,
+                        timeout: Option<Timeout>,
+                        dest: MPlaceTy<'tcx, Provenance>,
+                    }
+                    @unblock = |this| {
+                        this.epoll_wait(epfd, events, maxevents, timeout, &dest)
+                    }
+                    @timeout = |this| {
+                        this.fd_wait_timed_out();
+                        this.write_int(0, &dest)
+                    }
+                ),
+            );
+            return Ok(());
+        }
 
-        // FIXME return number of events ready when scheme for marking events ready exists
-        throw_unsup_format!("returning ready events from epoll_wait is not yet implemented");
+        let event_layout = this.libc_ty_layout("epoll_event");
+        for (i, &(events_ready, data)) in ready.iter().enumerate() {
+            let offset = event_layout.size.checked_mul(u64::try_from(i).unwrap(), this).unwrap();
+            let event =
+                this.ptr_to_mplace(events, event_layout).offset(offset, event_layout, this)?;
+            this.write_int(events_ready, &this.project_field(&event, 0)?)?;
+            this.write_scalar(data, &this.project_field(&event, 1)?)?;
+        }
+        this.write_int(u64::try_from(ready.len()).unwrap(), dest)
     }
 }
@@ -7,7 +7,7 @@
 use crate::shims::unix::*;
 use crate::*;
 
-use self::shims::unix::fd::FileDescriptor;
+use self::shims::unix::fd::{FileDescriptor, Readiness};
 
 /// A kind of file descriptor created by `eventfd`.
 /// The `Event` type isn't currently written to by `eventfd`.
@@ -64,6 +64,10 @@ fn write<'tcx>(
         self.val = self.val.checked_add(num).unwrap();
         Ok(Ok(8))
     }
+
+    fn readiness<'tcx>(&self) -> InterpResult<'tcx, Readiness> {
+        Ok(Readiness { readable: self.val > 0, writable: true, ..Readiness::default() })
+    }
 }
 
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
@@ -1,3 +1,5 @@
+use std::ffi::OsStr;
+
 use rustc_span::Symbol;
 use rustc_target::spec::abi::Abi;
 
@@ -62,8 +64,12 @@ fn emulate_foreign_item_inner(
             "epoll_wait" => {
                 let [epfd, events, maxevents, timeout] =
                     this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
-                let result = this.epoll_wait(epfd, events, maxevents, timeout)?;
-                this.write_scalar(result, dest)?;
+                let epfd = this.read_scalar(epfd)?.to_i32()?;
+                let events = this.read_pointer(events)?;
+                let maxevents = this.read_scalar(maxevents)?.to_i32()?;
+                let timeout = this.read_scalar(timeout)?.to_i32()?;
+                let timeout = this.fd_timeout(timeout);
+                this.epoll_wait(epfd, events, maxevents, timeout, dest)?;
             }
             "eventfd" => {
                 let [val, flag] =
@@ -178,6 +184,14 @@ fn emulate_foreign_item_inner(
 
                 this.write_scalar(Scalar::from_i32(SIGRTMAX), dest)?;
             }
+            "gnu_get_libc_version" => {
+                let [] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                // `std` checks this before using `posix_spawn`. We pretend to be a version that
+                // reports all errors of `posix_spawn` directly.
+                let version = this
+                    .alloc_os_str_as_c_str(OsStr::new("2.35"), MiriMemoryKind::Machine.into())?;
+                this.write_pointer(version, dest)?;
+            }
             "sched_getaffinity" => {
                 // This shim isn't useful, aside from the fact that it makes `num_cpus`
                 // fall back to `sysconf` where it will successfully determine the number of CPUs.
@@ -114,7 +114,9 @@ pub fn futex<'tcx>(
                 } else {
                     // FUTEX_WAIT uses a relative timestamp.
                     if realtime {
-                        Timeout::RealTime(SystemTime::now().checked_add(duration).unwrap())
+                        Timeout::RealTime(
+                            this.machine.clock.system_time().checked_add(duration).unwrap(),
+                        )
                     } else {
                         Timeout::Monotonic(this.machine.clock.now().checked_add(duration).unwrap())
                     }
@@ -1,11 +1,17 @@
 //! This is an incomplete implementation of mmap/munmap which is restricted in order to be
 //! implementable on top of the existing memory system. The point of these function as-written is
-//! to allow memory allocators written entirely in Rust to be executed by Miri. This implementation
-//! does not support other uses of mmap such as file mappings.
+//! to allow memory allocators written entirely in Rust, and programs that map files (e.g. with
+//! `memmap2`), to be executed by Miri.
 //!
 //! mmap/munmap behave a lot like alloc/dealloc, and for simple use they are exactly
-//! equivalent. That is the only part we support: no MAP_FIXED or MAP_SHARED or anything
-//! else that goes beyond a basic allocation API.
+//! equivalent. That is the only part we support: no MAP_FIXED or anything else that goes beyond a
+//! basic allocation API.
+//!
+//! File mappings are allocations that are initialized with the contents of the file. Changes to a
+//! `MAP_SHARED` mapping are written back to the file by `msync` and `munmap`, so other mappings of
+//! the same file and `read`s from it only see them after that. Since a mapping is just an
+//! allocation, accesses to it are checked by the aliasing model like any other memory accesses,
+//! and writing to a mapping without `PROT_WRITE` is UB.
 //!
 //! Note that in addition to only supporting malloc-like calls to mmap, we only support free-like
 //! calls to munmap, but for a very different reason. In principle, according to the man pages, it
@@ -14,9 +20,189 @@
 //! munmap shim which would partially unmap a region of address space previously mapped by mmap will
 //! report UB.
 
-use crate::*;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
 use rustc_target::abi::Size;
 
+use crate::shims::unix::*;
+use crate::*;
+
+use self::fd::FileDescriptor;
+use self::fs::FileHandle;
+
+/// A memory mapping of a file.
+#[derive(Debug)]
+pub struct FileMapping {
+    /// The mapped file, which stays open as long as it is mapped.
+    file: FileDescriptor,
+    /// The offset of the mapping in the file.
+    offset: u64,
+    /// How many bytes at the start of the mapping are backed by the file. The rest of the mapping
+    /// is beyond the end of the file.
+    file_len: u64,
+    /// Whether changes are written back to the file, i.e. whether this is a `MAP_SHARED` mapping.
+    shared: bool,
+    /// Whether the mapping was created with `PROT_WRITE`.
+    writable: bool,
+}
+
+impl FileMapping {
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+}
+
+/// Runs `f` with the position of `file` set to `offset`, and restores the position afterwards.
+/// The file description might be used by `read` and `write` as well, so they must not notice.
+fn at_offset<T>(
+    file: &mut File,
+    offset: u64,
+    f: impl FnOnce(&mut File) -> io::Result<T>,
+) -> io::Result<T> {
+    let position = file.stream_position()?;
+    file.seek(SeekFrom::Start(offset))?;
+    let result = f(file);
+    file.seek(SeekFrom::Start(position))?;
+    result
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Maps `map_length` bytes of the file `fd`, starting at `offset`.
+    fn mmap_file(
+        &mut self,
+        map_length: u64,
+        prot: i32,
+        flags: i32,
+        fd: i32,
+        offset: i128,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let prot_read = this.eval_libc_i32("PROT_READ");
+        let prot_write = this.eval_libc_i32("PROT_WRITE");
+        let map_shared = this.eval_libc_i32("MAP_SHARED");
+        let map_private = this.eval_libc_i32("MAP_PRIVATE");
+
+        // Miri only supports readable mappings.
+        if prot != prot_read && prot != prot_read | prot_write {
+            throw_unsup_format!(
+                "Miri does not support file mappings with protections other than PROT_READ or \
+                 PROT_READ|PROT_WRITE",
+            );
+        }
+        let writable = prot & prot_write != 0;
+        let mut mapping_flags = flags;
+        if this.tcx.sess.target.os == "linux" {
+            // Pre-faulting the mapping has no observable effect.
+            mapping_flags &= !this.eval_libc_i32("MAP_POPULATE");
+        }
+        if mapping_flags != map_shared && mapping_flags != map_private {
+            throw_unsup_format!(
+                "Miri only supports file mappings which set the flags argument to MAP_SHARED or \
+                 MAP_PRIVATE",
+            );
+        }
+        let shared = mapping_flags == map_shared;
+
+        let Ok(offset) = u64::try_from(offset) else {
+            this.set_last_error(this.eval_libc("EINVAL"))?;
+            return Ok(this.eval_libc("MAP_FAILED"));
+        };
+        #[allow(clippy::arithmetic_side_effects)] // PAGE_SIZE is nonzero
+        if offset % this.machine.page_size != 0 {
+            this.set_last_error(this.eval_libc("EINVAL"))?;
+            return Ok(this.eval_libc("MAP_FAILED"));
+        }
+
+        let Some(file) = this.machine.fds.dup(fd) else {
+            this.set_last_error(this.eval_libc("EBADF"))?;
+            return Ok(this.eval_libc("MAP_FAILED"));
+        };
+        // Read the mapped part of the file.
+        let contents = {
+            let mut file_handle = file.borrow_mut();
+            let Some(file_handle) = file_handle.downcast_mut::<FileHandle>() else {
+                // Only regular files can be mapped.
+                this.set_last_error(this.eval_libc("ENODEV"))?;
+                return Ok(this.eval_libc("MAP_FAILED"));
+            };
+            if shared && writable && !file_handle.writable {
+                this.set_last_error(this.eval_libc("EACCES"))?;
+                return Ok(this.eval_libc("MAP_FAILED"));
+            }
+            at_offset(&mut file_handle.file, offset, |file| {
+                let mut contents = Vec::new();
+                file.take(map_length).read_to_end(&mut contents)?;
+                Ok(contents)
+            })
+        };
+        let contents = match contents {
+            Ok(contents) => contents,
+            Err(err) => {
+                this.set_last_error_from_io_error(err)?;
+                return Ok(this.eval_libc("MAP_FAILED"));
+            }
+        };
+
+        let align = this.machine.page_align();
+        let ptr =
+            this.allocate_ptr(Size::from_bytes(map_length), align, MiriMemoryKind::Mmap.into())?;
+        // The part of the mapping beyond the end of the file is zero-init.
+        let file_len = u64::try_from(contents.len()).unwrap();
+        let zeros = std::iter::repeat(0u8).take(usize::try_from(map_length - file_len).unwrap());
+        this.write_bytes_ptr(ptr.into(), contents.into_iter().chain(zeros)).unwrap();
+
+        // Only register the mapping now, so that the writes above are not rejected for read-only
+        // mappings.
+        let mapping = FileMapping { file, offset, file_len, shared, writable };
+        this.machine.file_mappings.insert(ptr.provenance.get_alloc_id().unwrap(), mapping);
+
+        Ok(Scalar::from_pointer(ptr, this))
+    }
+
+    /// Writes the changes to the `length` bytes of a file mapping at `addr` back to the file.
+    /// Does nothing if `addr` is not in a shared and writable file mapping.
+    fn flush_file_mapping(
+        &mut self,
+        addr: Pointer<Option<Provenance>>,
+        length: u64,
+        sync: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        let this = self.eval_context_mut();
+
+        let Ok((alloc_id, offset_in_mapping, _)) = this.ptr_try_get_alloc_id(addr) else {
+            return Ok(Ok(()));
+        };
+        let Some(mapping) = this.machine.file_mappings.get(&alloc_id) else {
+            return Ok(Ok(()));
+        };
+        if !mapping.shared || !mapping.writable {
+            return Ok(Ok(()));
+        }
+        // Only the part of the range that is backed by the file is written back.
+        let start = offset_in_mapping.bytes();
+        let end = start.saturating_add(length).min(mapping.file_len);
+        if start >= end {
+            return Ok(Ok(()));
+        }
+        let file = mapping.file.clone();
+        let file_offset = mapping.offset.checked_add(start).unwrap();
+        // This is a read of the mapping, so the aliasing model checks it like the kernel was just
+        // another thread reading the memory through `addr`.
+        let bytes = this.read_bytes_ptr_strip_provenance(addr, Size::from_bytes(end - start))?;
+        let bytes = bytes.to_vec();
+
+        let mut file_handle = file.borrow_mut();
+        let file_handle = file_handle.downcast_mut::<FileHandle>().unwrap();
+        Ok(at_offset(&mut file_handle.file, file_offset, |file| {
+            file.write_all(&bytes)?;
+            if sync { file.sync_data() } else { Ok(()) }
+        }))
+    }
+}
+
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
 pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
     fn mmap(
@@ -64,13 +250,6 @@ fn mmap(
             return Ok(this.eval_libc("MAP_FAILED"));
         }
 
-        // If a user tries to map a file, we want to loudly inform them that this is not going
-        // to work. It is possible that POSIX gives us enough leeway to return an error, but the
-        // outcome for the user (I need to add cfg(miri)) is the same, just more frustrating.
-        if fd != -1 {
-            throw_unsup_format!("Miri does not support file-backed memory mappings");
-        }
-
         // Miri doesn't support MAP_FIXED.
         if flags & map_fixed != 0 {
             throw_unsup_format!(
@@ -78,6 +257,19 @@ fn mmap(
             );
         }
 
+        let Some(map_length) = length.checked_next_multiple_of(this.machine.page_size) else {
+            this.set_last_error(this.eval_libc("EINVAL"))?;
+            return Ok(this.eval_libc("MAP_FAILED"));
+        };
+        if map_length > this.target_usize_max() {
+            this.set_last_error(this.eval_libc("EINVAL"))?;
+            return Ok(this.eval_libc("MAP_FAILED"));
+        }
+
+        if flags & map_anonymous == 0 {
+            return this.mmap_file(map_length, prot, flags, fd, offset);
+        }
+
         // Miri doesn't support protections other than PROT_READ|PROT_WRITE.
         if prot != prot_read | prot_write {
             throw_unsup_format!(
@@ -95,21 +287,12 @@ fn mmap(
             );
         }
 
-        // This is only used for file mappings, which we don't support anyway.
+        // This is only used for file mappings.
         if offset != 0 {
-            throw_unsup_format!("Miri does not support non-zero offsets to mmap");
+            throw_unsup_format!("Miri does not support non-zero offsets to anonymous mmap");
         }
 
         let align = this.machine.page_align();
-        let Some(map_length) = length.checked_next_multiple_of(this.machine.page_size) else {
-            this.set_last_error(this.eval_libc("EINVAL"))?;
-            return Ok(this.eval_libc("MAP_FAILED"));
-        };
-        if map_length > this.target_usize_max() {
-            this.set_last_error(this.eval_libc("EINVAL"))?;
-            return Ok(this.eval_libc("MAP_FAILED"));
-        }
-
         let ptr =
             this.allocate_ptr(Size::from_bytes(map_length), align, MiriMemoryKind::Mmap.into())?;
         // We just allocated this, the access is definitely in-bounds and fits into our address space.
@@ -150,13 +333,59 @@ fn munmap(
             return Ok(this.eval_libc("MAP_FAILED"));
         }
 
+        // Changes to shared file mappings must not get lost. The kernel would write them back even
+        // if that fails, so we ignore errors.
+        let _ = this.flush_file_mapping(addr, length, /* sync */ false)?;
+        let alloc_id = this.ptr_try_get_alloc_id(addr).ok().map(|(alloc_id, _, _)| alloc_id);
+
         let length = Size::from_bytes(length);
         this.deallocate_ptr(
             addr,
             Some((length, this.machine.page_align())),
             MemoryKind::Machine(MiriMemoryKind::Mmap),
         )?;
+        if let Some(alloc_id) = alloc_id {
+            this.machine.file_mappings.remove(&alloc_id);
+        }
 
         Ok(Scalar::from_i32(0))
     }
+
+    fn msync(
+        &mut self,
+        addr: &OpTy<'tcx, Provenance>,
+        length: &OpTy<'tcx, Provenance>,
+        flags: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(addr)?;
+        let length = this.read_target_usize(length)?;
+        let flags = this.read_scalar(flags)?.to_i32()?;
+
+        let ms_async = this.eval_libc_i32("MS_ASYNC");
+        let ms_sync = this.eval_libc_i32("MS_SYNC");
+        let ms_invalidate = this.eval_libc_i32("MS_INVALIDATE");
+        #[allow(clippy::arithmetic_side_effects)] // PAGE_SIZE is nonzero
+        if addr.addr().bytes() % this.machine.page_size != 0
+            || flags & !(ms_async | ms_sync | ms_invalidate) != 0
+            || (flags & ms_async != 0 && flags & ms_sync != 0)
+        {
+            this.set_last_error(this.eval_libc("EINVAL"))?;
+            return Ok(Scalar::from_i32(-1));
+        }
+        // The range must be mapped.
+        let in_bounds = this.ptr_try_get_alloc_id(addr).is_ok_and(|(alloc_id, offset, _)| {
+            let (size, _, _) = this.get_alloc_info(alloc_id);
+            offset.bytes().checked_add(length).is_some_and(|end| end <= size.bytes())
+        });
+        if !in_bounds {
+            this.set_last_error(this.eval_libc("ENOMEM"))?;
+            return Ok(Scalar::from_i32(-1));
+        }
+
+        // `MS_ASYNC` just schedules the write-back, which we do right away anyway.
+        let result = this.flush_file_mapping(addr, length, flags & ms_sync != 0)?;
+        Ok(Scalar::from_i32(this.try_unwrap_io_result(result.map(|()| 0))?))
+    }
 }
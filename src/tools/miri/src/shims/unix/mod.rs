@@ -4,6 +4,7 @@
 mod fd;
 mod fs;
 mod mem;
+mod process;
 mod socket;
 mod sync;
 mod thread;
@@ -17,11 +18,15 @@
 pub use env::UnixEnvVars;
 pub use fd::{FdTable, FileDescription};
 pub use fs::DirTable;
+pub use mem::FileMapping;
+pub use process::{parse_process_stubs, ProcessTable};
+pub use socket::VirtualNetwork;
 // All the Unix-specific extension traits
 pub use env::EvalContextExt as _;
 pub use fd::EvalContextExt as _;
 pub use fs::EvalContextExt as _;
 pub use mem::EvalContextExt as _;
+pub use process::EvalContextExt as _;
 pub use socket::EvalContextExt as _;
 pub use sync::EvalContextExt as _;
 pub use thread::EvalContextExt as _;
@@ -0,0 +1,939 @@
+//! Pipes and child processes.
+//!
+//! Child processes can only be created with `posix_spawn`, which is what `std::process::Command`
+//! uses unless it is configured with e.g. `uid`, `gid`, `groups` or `pre_exec`. `fork` and `execve`
+//! would duplicate or replace the interpreter, so they fail with `ENOSYS`, and so does
+//! `Command::spawn` with these options. The child only inherits the standard streams of the
+//! program (after applying the file actions of `posix_spawn`) and its working directory (which
+//! `posix_spawn_file_actions_addchdir_np` can change); it is created in one of two ways:
+//! - If the command line matches one of the stubs given with `-Zmiri-process-stubs`, the child
+//!   "runs" right away: its canned output is written to its stdout and stderr, it consumes all
+//!   input, and it exits with the canned status. This works even with isolation enabled.
+//! - Otherwise, if isolation is disabled, the child is a real process on the host. When one of
+//!   its standard streams is a pipe created by the program, a host thread copies the child's
+//!   output into the pipe, and writes to the pipe are forwarded to the child.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::concurrency::VClock;
+use crate::shims::unix::*;
+use crate::*;
+
+use self::fd::{DevNull, FileDescriptor, NullOutput, Readiness};
+use self::fs::FileHandle;
+
+/// How often the status of a host child process is checked while a thread waits for it.
+const HOST_WAIT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The output of a host child process, copied by a host thread.
+#[derive(Debug, Default)]
+struct HostOutput {
+    /// The data read so far, and whether the child closed its end.
+    state: Mutex<(VecDeque<u8>, bool)>,
+}
+
+impl HostOutput {
+    /// Starts copying everything `reader` outputs.
+    fn spawn(mut reader: impl Read + Send + 'static) -> Arc<HostOutput> {
+        let output = Arc::new(HostOutput::default());
+        let copy = output.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0; 4096];
+            loop {
+                let len = reader.read(&mut buf).unwrap_or(0);
+                let mut state = copy.state.lock().unwrap();
+                if len == 0 {
+                    state.1 = true;
+                } else {
+                    state.0.extend(&buf[..len]);
+                }
+                if len == 0 {
+                    break;
+                }
+            }
+        });
+        output
+    }
+
+    /// Moves the data read so far into `data`. Returns whether the child closed its end.
+    fn take(&self, data: &mut VecDeque<u8>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        data.extend(state.0.drain(..));
+        state.1
+    }
+}
+
+/// Where the data written to a pipe goes, if it does not stay in the pipe.
+#[derive(Debug)]
+enum PipeSink {
+    /// The pipe is the stdin of a host child process.
+    Host(ChildStdin),
+    /// The pipe is the stdin of a stubbed child process, which consumes all input.
+    Discard,
+}
+
+/// The buffer shared by both ends of a pipe.
+#[derive(Debug, Default)]
+struct PipeBuffer {
+    data: VecDeque<u8>,
+    /// The write end was closed.
+    write_closed: bool,
+    /// The read end was closed.
+    read_closed: bool,
+    /// The clock of the last write, which the reader acquires.
+    clock: VClock,
+    /// The output of the host child process that writes to this pipe.
+    host_output: Option<Arc<HostOutput>>,
+    sink: Option<PipeSink>,
+}
+
+impl PipeBuffer {
+    /// Moves the output of the host child into the buffer. Returns whether reading would return
+    /// end-of-file if the buffer is empty.
+    fn fetch_host_output(&mut self) -> bool {
+        match &self.host_output {
+            Some(host_output) => host_output.take(&mut self.data) && self.write_closed,
+            None => self.write_closed,
+        }
+    }
+}
+
+/// The read end of a pipe.
+#[derive(Debug)]
+struct PipeReader {
+    buffer: Rc<RefCell<PipeBuffer>>,
+    nonblocking: bool,
+}
+
+/// The write end of a pipe.
+#[derive(Debug)]
+struct PipeWriter {
+    buffer: Rc<RefCell<PipeBuffer>>,
+    nonblocking: bool,
+}
+
+impl FileDescription for PipeReader {
+    fn name(&self) -> &'static str {
+        "pipe"
+    }
+
+    fn read<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &mut [u8],
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        let mut buffer = self.buffer.borrow_mut();
+        let eof = buffer.fetch_host_output();
+        if buffer.data.is_empty() && !eof && !bytes.is_empty() {
+            // Unless the pipe is non-blocking, the thread blocks until there is data. If a host
+            // child writes to the pipe, the thread checks for its output periodically.
+            return Ok(Err(io::ErrorKind::WouldBlock.into()));
+        }
+        let len = bytes.len().min(buffer.data.len());
+        for (dest, byte) in bytes.iter_mut().zip(buffer.data.drain(..len)) {
+            *dest = byte;
+        }
+        ecx.acquire_clock(&buffer.clock);
+        Ok(Ok(len))
+    }
+
+    fn set_nonblocking<'tcx>(&mut self, nonblocking: bool) -> InterpResult<'tcx> {
+        self.nonblocking = nonblocking;
+        Ok(())
+    }
+
+    fn blocks_on_read(&self) -> bool {
+        !self.nonblocking
+    }
+
+    fn readiness<'tcx>(&self) -> InterpResult<'tcx, Readiness> {
+        let mut buffer = self.buffer.borrow_mut();
+        let eof = buffer.fetch_host_output();
+        Ok(Readiness {
+            readable: !buffer.data.is_empty() || eof,
+            read_closed: eof,
+            hangup: eof,
+            host_pending: !eof && buffer.host_output.is_some(),
+            ..Readiness::default()
+        })
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        self.buffer.borrow_mut().read_closed = true;
+        Ok(Ok(()))
+    }
+}
+
+impl FileDescription for PipeWriter {
+    fn name(&self) -> &'static str {
+        "pipe"
+    }
+
+    fn write<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &[u8],
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        let mut buffer = self.buffer.borrow_mut();
+        match &mut buffer.sink {
+            Some(PipeSink::Host(stdin)) => return Ok(stdin.write(bytes)),
+            Some(PipeSink::Discard) => return Ok(Ok(bytes.len())),
+            None => {}
+        }
+        if buffer.read_closed {
+            // Rust programs ignore `SIGPIPE`, so the write just fails.
+            return Ok(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        buffer.data.extend(bytes);
+        if let Some(clock) = ecx.release_clock() {
+            buffer.clock.join(&clock);
+        }
+        Ok(Ok(bytes.len()))
+    }
+
+    fn set_nonblocking<'tcx>(&mut self, nonblocking: bool) -> InterpResult<'tcx> {
+        // Pipes never fill up, so writing never blocks anyway.
+        self.nonblocking = nonblocking;
+        Ok(())
+    }
+
+    fn readiness<'tcx>(&self) -> InterpResult<'tcx, Readiness> {
+        let buffer = self.buffer.borrow();
+        Ok(Readiness { writable: true, hangup: buffer.read_closed, ..Readiness::default() })
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.write_closed = true;
+        // Closing the stdin of a host child signals end-of-file to it.
+        buffer.sink = None;
+        Ok(Ok(()))
+    }
+}
+
+/// A child process started with `posix_spawn`.
+#[derive(Debug)]
+enum ChildProcess {
+    /// A process running on the host.
+    Host(Child),
+    /// A stubbed process, which exited with this wait status.
+    Exited(i32),
+}
+
+/// The canned result of a command, from `-Zmiri-process-stubs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessStub {
+    /// The command line, including the program.
+    argv: Vec<String>,
+    /// The exit code.
+    status: u8,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// An action of a `posix_spawn_file_actions_t`.
+#[derive(Clone, Debug)]
+enum FileAction {
+    Dup2 { fd: i32, new_fd: i32 },
+    Close { fd: i32 },
+    /// Changes the working directory of a host child process; stubbed processes ignore it.
+    Chdir { path: PathBuf },
+}
+
+/// The child processes of the program, and the state of the `posix_spawn` APIs.
+#[derive(Debug)]
+pub struct ProcessTable {
+    stubs: Vec<ProcessStub>,
+    /// The children that have not been waited for yet, by process ID.
+    children: BTreeMap<i32, ChildProcess>,
+    next_pid: i32,
+    /// The actions of each `posix_spawn_file_actions_t`. The ID stored in a
+    /// `posix_spawn_file_actions_t` is the index in this list plus 1, so that zeroed memory is
+    /// not a valid ID. Destroyed lists are `None`.
+    file_actions: Vec<Option<Vec<FileAction>>>,
+}
+
+impl ProcessTable {
+    pub fn new(stubs: Vec<ProcessStub>) -> Self {
+        // The process IDs are made up, like the ID of the program itself in isolation mode.
+        ProcessTable { stubs, children: BTreeMap::new(), next_pid: 1001, file_actions: Vec::new() }
+    }
+
+    fn add_child(&mut self, child: ChildProcess) -> i32 {
+        let pid = self.next_pid;
+        self.next_pid = self.next_pid.checked_add(1).unwrap();
+        self.children.insert(pid, child);
+        pid
+    }
+}
+
+/// Parses the stubs given with `-Zmiri-process-stubs`. The file consists of entries like this:
+///
+/// ```text
+/// # Lines starting with `#` are comments.
+/// command: git rev-parse "HEAD~1"
+/// status: 0
+/// stdout: "4d2c1f0\n"
+/// stderr: ""
+/// ```
+///
+/// The command consists of words separated by whitespace, which can be quoted. Quoted strings
+/// support the escapes `\n`, `\r`, `\t`, `\0`, `\\`, `\"` and `\xNN`. Everything but the command
+/// is optional; the default status is 0, and the default output is empty.
+pub fn parse_process_stubs(input: &str) -> Result<Vec<ProcessStub>, String> {
+    let mut stubs: Vec<ProcessStub> = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| format!("line {}: {msg}", idx + 1);
+        let (key, value) = line.split_once(':').ok_or_else(|| err("expected `key: value`"))?;
+        let value = value.trim();
+        if key == "command" {
+            let words = parse_words(value).map_err(|msg| err(&msg))?;
+            let argv = words
+                .into_iter()
+                .map(String::from_utf8)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| err("the command is not valid UTF-8"))?;
+            if argv.is_empty() {
+                return Err(err("the command is empty"));
+            }
+            stubs.push(ProcessStub { argv, status: 0, stdout: Vec::new(), stderr: Vec::new() });
+            continue;
+        }
+        let stub = stubs.last_mut().ok_or_else(|| err("expected `command:` first"))?;
+        match key {
+            "status" =>
+                stub.status =
+                    value.parse().map_err(|_| err("the status must be a number from 0 to 255"))?,
+            "stdout" | "stderr" => {
+                let output = match parse_words(value).map_err(|msg| err(&msg))?.as_mut_slice() {
+                    [output] if value.starts_with('"') => std::mem::take(output),
+                    _ => return Err(err("expected a single quoted string")),
+                };
+                if key == "stdout" {
+                    stub.stdout = output;
+                } else {
+                    stub.stderr = output;
+                }
+            }
+            _ => return Err(err(&format!("unknown key `{key}`"))),
+        }
+    }
+    Ok(stubs)
+}
+
+/// Splits `input` into words separated by whitespace, which can be quoted.
+fn parse_words(input: &str) -> Result<Vec<Vec<u8>>, String> {
+    let mut words = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = Vec::new();
+        if c != '"' {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            words.push(word);
+            continue;
+        }
+        chars.next();
+        loop {
+            match chars.next() {
+                None => return Err("unterminated string".to_owned()),
+                Some('"') => break,
+                Some('\\') => {
+                    let byte = match chars.next() {
+                        Some('n') => b'\n',
+                        Some('r') => b'\r',
+                        Some('t') => b'\t',
+                        Some('0') => b'\0',
+                        Some('\\') => b'\\',
+                        Some('"') => b'"',
+                        Some('x') => {
+                            let digits: String = chars.by_ref().take(2).collect();
+                            u8::from_str_radix(&digits, 16)
+                                .map_err(|_| format!("invalid escape `\\x{digits}`"))?
+                        }
+                        c => return Err(format!("invalid escape `\\{}`", c.unwrap_or(' '))),
+                    };
+                    word.push(byte);
+                }
+                Some(c) => word.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Reads a null-terminated array of C strings, like `argv`.
+    fn read_c_str_array(
+        &self,
+        ptr: Pointer<Option<Provenance>>,
+    ) -> InterpResult<'tcx, Vec<OsString>> {
+        let this = self.eval_context_ref();
+        let ptr_layout = this.machine.layouts.const_raw_ptr;
+        let mut strings = Vec::new();
+        if this.ptr_is_null(ptr)? {
+            return Ok(strings);
+        }
+        for idx in 0.. {
+            let offset = ptr_layout.size.checked_mul(idx, this).unwrap();
+            let entry = this.ptr_to_mplace(ptr, ptr_layout).offset(offset, ptr_layout, this)?;
+            let str_ptr = this.read_pointer(&entry)?;
+            if this.ptr_is_null(str_ptr)? {
+                break;
+            }
+            strings.push(this.read_os_str_from_c_str(str_ptr)?.to_owned());
+        }
+        Ok(strings)
+    }
+
+    /// Returns the index into `file_actions` of the `posix_spawn_file_actions_t` at `ptr`.
+    fn file_actions_index(&self, ptr: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, usize> {
+        let this = self.eval_context_ref();
+        let id = this.deref_pointer_as(ptr, this.machine.layouts.u32)?;
+        let id = this.read_scalar(&id)?.to_u32()?;
+        let idx = usize::try_from(id).unwrap().wrapping_sub(1);
+        if !matches!(this.machine.processes.file_actions.get(idx), Some(Some(_))) {
+            throw_ub_format!("`posix_spawn_file_actions_t` is not initialized or was destroyed");
+        }
+        Ok(idx)
+    }
+
+    /// Runs a stubbed child process with the given standard streams.
+    fn run_stub(
+        &mut self,
+        stub: &ProcessStub,
+        stdio: &[Option<FileDescriptor>; 3],
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let communicate = this.machine.communicate();
+        if let Some(stdin) = &stdio[0] {
+            if let Some(reader) = stdin.borrow().downcast_ref::<PipeReader>() {
+                reader.buffer.borrow_mut().sink = Some(PipeSink::Discard);
+            }
+        }
+        for (fd, output) in [(&stdio[1], &stub.stdout), (&stdio[2], &stub.stderr)] {
+            let Some(fd) = fd else { continue };
+            let mut written = 0;
+            while written < output.len() {
+                match fd.borrow_mut().write(communicate, &output[written..], this)? {
+                    Ok(0) | Err(_) => break,
+                    Ok(len) => written += len,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how a standard stream of the program is passed to a host child process.
+    fn host_stdio(&self, fd: &Option<FileDescriptor>) -> InterpResult<'tcx, Stdio> {
+        let Some(fd) = fd else { return Ok(Stdio::null()) };
+        let fd = fd.borrow();
+        let stdio = if fd.downcast_ref::<io::Stdin>().is_some()
+            || fd.downcast_ref::<io::Stdout>().is_some()
+            || fd.downcast_ref::<io::Stderr>().is_some()
+        {
+            Stdio::inherit()
+        } else if fd.downcast_ref::<NullOutput>().is_some()
+            || fd.downcast_ref::<DevNull>().is_some()
+        {
+            Stdio::null()
+        } else if let Some(file) = fd.downcast_ref::<FileHandle>() {
+            match file.file.try_clone() {
+                Ok(file) => Stdio::from(file),
+                Err(err) => throw_unsup_format!("cannot pass a file to a child process: {err}"),
+            }
+        } else if fd.downcast_ref::<PipeReader>().is_some()
+            || fd.downcast_ref::<PipeWriter>().is_some()
+        {
+            Stdio::piped()
+        } else {
+            throw_unsup_format!("cannot pass a {} to a child process", fd.name())
+        };
+        Ok(stdio)
+    }
+
+    /// Starts a child process on the host. Returns the error number if that fails.
+    fn spawn_host_child(
+        &mut self,
+        program: OsString,
+        argv: Vec<OsString>,
+        envp: Vec<OsString>,
+        cwd: Option<PathBuf>,
+        stdio: &[Option<FileDescriptor>; 3],
+    ) -> InterpResult<'tcx, Result<Child, Scalar<Provenance>>> {
+        let this = self.eval_context_mut();
+
+        let mut command = Command::new(program);
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+        if let Some((arg0, args)) = argv.split_first() {
+            command.args(args);
+            #[cfg(unix)]
+            std::os::unix::process::CommandExt::arg0(&mut command, arg0);
+            #[cfg(not(unix))]
+            let _ = arg0;
+        }
+        command.env_clear();
+        for var in envp {
+            let var = var.to_string_lossy();
+            if let Some((name, value)) = var.split_once('=') {
+                command.env(name, value);
+            }
+        }
+        command.stdin(this.host_stdio(&stdio[0])?);
+        command.stdout(this.host_stdio(&stdio[1])?);
+        command.stderr(this.host_stdio(&stdio[2])?);
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => return Ok(Err(this.io_error_to_errnum(err)?)),
+        };
+        // Connect the pipes of the program to the child.
+        let buffer_of =
+            |fd: &Option<FileDescriptor>| fd.as_ref().and_then(|fd| pipe_buffer(&*fd.borrow()));
+        if let (Some(stdin), Some(buffer)) = (child.stdin.take(), buffer_of(&stdio[0])) {
+            buffer.borrow_mut().sink = Some(PipeSink::Host(stdin));
+        }
+        if let (Some(stdout), Some(buffer)) = (child.stdout.take(), buffer_of(&stdio[1])) {
+            buffer.borrow_mut().host_output = Some(HostOutput::spawn(stdout));
+        }
+        if let (Some(stderr), Some(buffer)) = (child.stderr.take(), buffer_of(&stdio[2])) {
+            buffer.borrow_mut().host_output = Some(HostOutput::spawn(stderr));
+        }
+        Ok(Ok(child))
+    }
+}
+
+/// Returns the buffer of a pipe end.
+fn pipe_buffer(fd: &dyn FileDescription) -> Option<Rc<RefCell<PipeBuffer>>> {
+    if let Some(reader) = fd.downcast_ref::<PipeReader>() {
+        return Some(reader.buffer.clone());
+    }
+    fd.downcast_ref::<PipeWriter>().map(|writer| writer.buffer.clone())
+}
+
+/// Turns the exit status of a host process into a wait status.
+fn host_wait_status(status: std::process::ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return (code & 0xff) << 8;
+    }
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return signal & 0x7f;
+    }
+    0
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn pipe2(
+        &mut self,
+        fds: &OpTy<'tcx, Provenance>,
+        flags: Option<&OpTy<'tcx, Provenance>>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fds = this.deref_pointer_as(fds, this.machine.layouts.i32)?;
+        let mut flags = match flags {
+            Some(flags) => this.read_scalar(flags)?.to_i32()?,
+            None => 0,
+        };
+        // `O_CLOEXEC` has no effect since child processes only inherit the standard streams.
+        let o_cloexec = this.eval_libc_i32("O_CLOEXEC");
+        let o_nonblock = this.eval_libc_i32("O_NONBLOCK");
+        let nonblocking = flags & o_nonblock != 0;
+        flags &= !(o_cloexec | o_nonblock);
+        if flags != 0 {
+            throw_unsup_format!("unsupported flags {flags:#x} for `pipe2`");
+        }
+
+        let buffer = Rc::new(RefCell::new(PipeBuffer::default()));
+        let reader = PipeReader { buffer: buffer.clone(), nonblocking };
+        let writer = PipeWriter { buffer, nonblocking };
+        let reader = this.machine.fds.insert_fd(FileDescriptor::new(reader));
+        let writer = this.machine.fds.insert_fd(FileDescriptor::new(writer));
+        this.write_scalar(Scalar::from_i32(reader), &fds)?;
+        this.write_scalar(
+            Scalar::from_i32(writer),
+            &fds.offset(fds.layout.size, fds.layout, this)?,
+        )?;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_init(
+        &mut self,
+        file_actions: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let place = this.deref_pointer_as(file_actions, this.machine.layouts.u32)?;
+        let lists = &mut this.machine.processes.file_actions;
+        lists.push(Some(Vec::new()));
+        let id = u32::try_from(lists.len()).unwrap();
+        this.write_scalar(Scalar::from_u32(id), &place)?;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_destroy(
+        &mut self,
+        file_actions: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let idx = this.file_actions_index(file_actions)?;
+        this.machine.processes.file_actions[idx] = None;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_adddup2(
+        &mut self,
+        file_actions: &OpTy<'tcx, Provenance>,
+        fd: &OpTy<'tcx, Provenance>,
+        new_fd: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let idx = this.file_actions_index(file_actions)?;
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let new_fd = this.read_scalar(new_fd)?.to_i32()?;
+        if fd < 0 || new_fd < 0 {
+            return Ok(this.eval_libc("EBADF"));
+        }
+        let list = this.machine.processes.file_actions[idx].as_mut().unwrap();
+        list.push(FileAction::Dup2 { fd, new_fd });
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_addclose(
+        &mut self,
+        file_actions: &OpTy<'tcx, Provenance>,
+        fd: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let idx = this.file_actions_index(file_actions)?;
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        if fd < 0 {
+            return Ok(this.eval_libc("EBADF"));
+        }
+        let list = this.machine.processes.file_actions[idx].as_mut().unwrap();
+        list.push(FileAction::Close { fd });
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// This is what `std::process::Command::current_dir` uses, found with `dlsym`.
+    fn posix_spawn_file_actions_addchdir_np(
+        &mut self,
+        file_actions: &OpTy<'tcx, Provenance>,
+        path: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let idx = this.file_actions_index(file_actions)?;
+        let path = this.read_path_from_c_str(this.read_pointer(path)?)?.into_owned();
+        let list = this.machine.processes.file_actions[idx].as_mut().unwrap();
+        list.push(FileAction::Chdir { path });
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Creates a child process. With `search_path` set, this is `posix_spawnp`.
+    /// The attributes in `attrp` are ignored, since the child does not share anything they
+    /// could affect with the program.
+    ///
+    /// <https://man7.org/linux/man-pages/man3/posix_spawn.3.html>
+    fn posix_spawn(
+        &mut self,
+        pid: &OpTy<'tcx, Provenance>,
+        path: &OpTy<'tcx, Provenance>,
+        file_actions: &OpTy<'tcx, Provenance>,
+        argv: &OpTy<'tcx, Provenance>,
+        envp: &OpTy<'tcx, Provenance>,
+        search_path: bool,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let pid_ptr = this.read_pointer(pid)?;
+        let path = this.read_os_str_from_c_str(this.read_pointer(path)?)?.to_owned();
+        let actions = if this.ptr_is_null(this.read_pointer(file_actions)?)? {
+            Vec::new()
+        } else {
+            let idx = this.file_actions_index(file_actions)?;
+            this.machine.processes.file_actions[idx].clone().unwrap()
+        };
+        let argv = this.read_c_str_array(this.read_pointer(argv)?)?;
+        let envp = this.read_c_str_array(this.read_pointer(envp)?)?;
+
+        // Determine the standard streams and the working directory of the child. A relative
+        // directory is relative to the previous one.
+        let mut child_fds: BTreeMap<i32, Option<FileDescriptor>> = BTreeMap::new();
+        let mut cwd: Option<PathBuf> = None;
+        for action in actions {
+            match action {
+                FileAction::Dup2 { fd, new_fd } => {
+                    let file_descriptor = match child_fds.get(&fd) {
+                        Some(file_descriptor) => file_descriptor.clone(),
+                        None => this.machine.fds.dup(fd),
+                    };
+                    if file_descriptor.is_none() {
+                        return Ok(this.eval_libc("EBADF"));
+                    }
+                    child_fds.insert(new_fd, file_descriptor);
+                }
+                FileAction::Close { fd } => {
+                    child_fds.insert(fd, None);
+                }
+                FileAction::Chdir { path } =>
+                    cwd = Some(match cwd {
+                        Some(cwd) => cwd.join(path),
+                        None => path,
+                    }),
+            }
+        }
+        let stdio = [0, 1, 2].map(|fd| match child_fds.remove(&fd) {
+            Some(file_descriptor) => file_descriptor,
+            None => this.machine.fds.dup(fd),
+        });
+
+        let command_line: Vec<String> =
+            argv.iter().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        let stub = this.machine.processes.stubs.iter().find(|stub| stub.argv == command_line);
+        let child = if let Some(stub) = stub.cloned() {
+            this.run_stub(&stub, &stdio)?;
+            ChildProcess::Exited(i32::from(stub.status) << 8)
+        } else if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            let op_name = format!(
+                "`{}` of `{}`, which has no stub in `-Zmiri-process-stubs`,",
+                if search_path { "posix_spawnp" } else { "posix_spawn" },
+                command_line.join(" ")
+            );
+            this.reject_in_isolation(&op_name, reject_with)?;
+            return Ok(this.eval_libc("EACCES"));
+        } else {
+            // `Command` searches `PATH` for programs without a slash, which `posix_spawn` does not.
+            let program = if search_path || path.to_string_lossy().contains('/') {
+                path
+            } else {
+                let mut program = OsString::from("./");
+                program.push(path);
+                program
+            };
+            match this.spawn_host_child(program, argv, envp, cwd, &stdio)? {
+                Ok(child) => ChildProcess::Host(child),
+                Err(errno) => return Ok(errno),
+            }
+        };
+
+        let child_pid = this.machine.processes.add_child(child);
+        if !this.ptr_is_null(pid_ptr)? {
+            let pid_place = this.ptr_to_mplace(pid_ptr, this.libc_ty_layout("pid_t"));
+            this.write_int(child_pid, &pid_place)?;
+        }
+        // The child might have written to a pipe.
+        this.wake_fd_waiters()?;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Waits for a child process to exit.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/waitpid.2.html>
+    fn waitpid(
+        &mut self,
+        pid: i32,
+        status: Pointer<Option<Provenance>>,
+        options: i32,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let wnohang = this.eval_libc_i32("WNOHANG");
+        if options & !wnohang != 0 {
+            throw_unsup_format!("unsupported options {options:#x} for `waitpid`");
+        }
+        if pid == 0 || pid < -1 {
+            throw_unsup_format!("`waitpid` on process groups is not supported");
+        }
+
+        let candidates: Vec<i32> = if pid == -1 {
+            this.machine.processes.children.keys().copied().collect()
+        } else if this.machine.processes.children.contains_key(&pid) {
+            vec![pid]
+        } else {
+            vec![]
+        };
+        if candidates.is_empty() {
+            let echild = this.eval_libc("ECHILD");
+            this.set_last_error(echild)?;
+            return this.write_int(-1, dest);
+        }
+
+        for child_pid in candidates {
+            let exited = match this.machine.processes.children.get_mut(&child_pid).unwrap() {
+                ChildProcess::Exited(wait_status) => Ok(Some(*wait_status)),
+                ChildProcess::Host(child) =>
+                    child.try_wait().map(|status| status.map(host_wait_status)),
+            };
+            let wait_status = match exited {
+                Ok(Some(wait_status)) => wait_status,
+                Ok(None) => continue,
+                Err(err) => {
+                    this.set_last_error_from_io_error(err)?;
+                    return this.write_int(-1, dest);
+                }
+            };
+            this.machine.processes.children.remove(&child_pid);
+            if !this.ptr_is_null(status)? {
+                let status_place = this.ptr_to_mplace(status, this.machine.layouts.i32);
+                this.write_int(wait_status, &status_place)?;
+            }
+            return this.write_int(child_pid, dest);
+        }
+
+        if options & wnohang != 0 {
+            return this.write_int(0, dest);
+        }
+        // Some host children are still running. Let the other threads of the program run, and
+        // check again in a bit.
+        let timeout = this.machine.clock.now().checked_add(HOST_WAIT_INTERVAL).unwrap();
+        let dest = dest.clone();
+        this.block_thread(
+            BlockReason::Sleep,
+            Some(Timeout::Monotonic(timeout)),
+            callback!(
+                @capture<'tcx> {
+                    pid: i32,
+                    status: Pointer<Option<Provenance>>,
+                    options: i32,
+                    dest: MPlaceTy<'tcx, Provenance>,
+                }
+                @unblock = |_this| { panic!("`waitpid` unblocked before its timeout") }
+                @timeout = |this| { this.waitpid(pid, status, options, &dest) }
+            ),
+        );
+        Ok(())
+    }
+
+    /// Sends a signal to a child process. Only `SIGKILL`, `SIGTERM` and the null signal are
+    /// supported.
+    fn kill(&mut self, pid: i32, sig: i32) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        if !this.machine.processes.children.contains_key(&pid) {
+            if pid <= 0 {
+                throw_unsup_format!("`kill` is only supported for child processes");
+            }
+            let esrch = this.eval_libc("ESRCH");
+            this.set_last_error(esrch)?;
+            return Ok(-1);
+        }
+        if sig != 0 && sig != this.eval_libc_i32("SIGKILL") && sig != this.eval_libc_i32("SIGTERM")
+        {
+            throw_unsup_format!("`kill` with signal {sig} is not supported");
+        }
+        let result = match this.machine.processes.children.get_mut(&pid).unwrap() {
+            // Signals to processes that already exited are ignored.
+            ChildProcess::Exited(_) => Ok(()),
+            ChildProcess::Host(_) if sig == 0 => Ok(()),
+            // We cannot send `SIGTERM` portably, so we treat it like `SIGKILL`.
+            ChildProcess::Host(child) => child.kill(),
+        };
+        if let Err(err) = result {
+            this.set_last_error_from_io_error(err)?;
+            return Ok(-1);
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_stubs() {
+        let stubs = parse_process_stubs(
+            "# A comment.\n\
+             command: git rev-parse \"HEAD~1\" \"with space\"\n\
+             status: 0\n\
+             stdout: \"4d2c1f0\\n\"\n\
+             \n\
+             command: false\n\
+             status: 1\n\
+             stderr: \"\\x00\\\"\"\n",
+        )
+        .unwrap();
+        assert_eq!(stubs, vec![
+            ProcessStub {
+                argv: vec![
+                    "git".to_owned(),
+                    "rev-parse".to_owned(),
+                    "HEAD~1".to_owned(),
+                    "with space".to_owned(),
+                ],
+                status: 0,
+                stdout: b"4d2c1f0\n".to_vec(),
+                stderr: Vec::new(),
+            },
+            ProcessStub {
+                argv: vec!["false".to_owned()],
+                status: 1,
+                stdout: Vec::new(),
+                stderr: b"\0\"".to_vec(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_stubs_errors() {
+        assert_eq!(
+            parse_process_stubs("status: 0").unwrap_err(),
+            "line 1: expected `command:` first"
+        );
+        assert_eq!(
+            parse_process_stubs("command: ls\nstdout: abc").unwrap_err(),
+            "line 2: expected a single quoted string"
+        );
+        assert_eq!(
+            parse_process_stubs("command: ls\nstatus: 256").unwrap_err(),
+            "line 2: the status must be a number from 0 to 255"
+        );
+        assert_eq!(
+            parse_process_stubs("command: \"ls").unwrap_err(),
+            "line 1: unterminated string"
+        );
+    }
+}
@@ -1,9 +1,23 @@
+//! Sockets, backed by a virtual loopback network that only connects the sockets of the
+//! interpreted program with each other.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::io;
+use std::iter;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::time::Duration;
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_target::abi::Size;
 
+use crate::concurrency::VClock;
 use crate::shims::unix::*;
 use crate::*;
 
-use self::fd::FileDescriptor;
+use self::fd::{FileDescriptor, Readiness};
 
 /// Pair of connected sockets.
 ///
@@ -24,6 +38,827 @@ fn close<'tcx>(
     }
 }
 
+/// The ports handed out to sockets that are bound to port 0.
+const EPHEMERAL_PORTS: RangeInclusive<u16> = 49152..=65535;
+
+/// The largest payload of a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65507;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum SocketKind {
+    /// `SOCK_STREAM`, i.e. TCP.
+    Stream,
+    /// `SOCK_DGRAM`, i.e. UDP.
+    Datagram,
+}
+
+/// One direction of a stream connection.
+#[derive(Debug, Default)]
+struct Pipe {
+    data: VecDeque<u8>,
+    /// The sending end shut down writing.
+    write_closed: bool,
+    /// The receiving end shut down reading.
+    read_closed: bool,
+    /// The clock of the last write, which the reader acquires.
+    clock: VClock,
+}
+
+/// One end of a stream connection.
+#[derive(Debug)]
+struct Connection {
+    local: SocketAddr,
+    peer: SocketAddr,
+    incoming: Rc<RefCell<Pipe>>,
+    outgoing: Rc<RefCell<Pipe>>,
+}
+
+impl Connection {
+    /// Creates the two ends of a connection from `client` to `server`.
+    fn pair(client: SocketAddr, server: SocketAddr) -> (Connection, Connection) {
+        let upstream = Rc::new(RefCell::new(Pipe::default()));
+        let downstream = Rc::new(RefCell::new(Pipe::default()));
+        let client_end = Connection {
+            local: client,
+            peer: server,
+            incoming: downstream.clone(),
+            outgoing: upstream.clone(),
+        };
+        let server_end =
+            Connection { local: server, peer: client, incoming: upstream, outgoing: downstream };
+        (client_end, server_end)
+    }
+
+    fn shutdown(&self, read: bool, write: bool) {
+        if read {
+            self.incoming.borrow_mut().read_closed = true;
+        }
+        if write {
+            self.outgoing.borrow_mut().write_closed = true;
+        }
+    }
+}
+
+/// The datagrams received by a bound datagram socket.
+#[derive(Debug, Default)]
+struct Mailbox {
+    /// The address set with `connect`: datagrams are sent there by default, and datagrams from
+    /// other addresses are dropped.
+    peer: Option<SocketAddr>,
+    datagrams: VecDeque<(SocketAddr, Vec<u8>, VClock)>,
+}
+
+/// The loopback network all sockets of the program are attached to.
+///
+/// Nothing ever reaches the host's network, so sockets work under isolation.
+#[derive(Debug, Default)]
+pub struct VirtualNetwork {
+    /// The addresses that sockets are bound to.
+    bound: FxHashSet<(SocketKind, SocketAddr)>,
+    /// The connections to listening stream sockets that were not accepted yet.
+    backlogs: FxHashMap<SocketAddr, VecDeque<Connection>>,
+    /// The datagrams sent to bound datagram sockets.
+    mailboxes: FxHashMap<SocketAddr, Mailbox>,
+    /// The last port handed out from `EPHEMERAL_PORTS`.
+    last_port: u16,
+}
+
+impl VirtualNetwork {
+    /// Reserves `addr` for a socket. Port 0 picks a free ephemeral port.
+    fn bind(&mut self, kind: SocketKind, mut addr: SocketAddr) -> Result<SocketAddr, &'static str> {
+        if !addr.ip().is_loopback() && !addr.ip().is_unspecified() {
+            // The only interface is the loopback interface.
+            return Err("EADDRNOTAVAIL");
+        }
+        if addr.port() == 0 {
+            let port = self.free_port(kind, addr.ip()).ok_or("EADDRINUSE")?;
+            addr.set_port(port);
+        } else if self.in_use(kind, addr) {
+            return Err("EADDRINUSE");
+        }
+        self.bound.insert((kind, addr));
+        if kind == SocketKind::Datagram {
+            self.mailboxes.insert(addr, Mailbox::default());
+        }
+        Ok(addr)
+    }
+
+    /// Releases an address reserved with `bind`, refusing all connections that were not accepted.
+    fn unbind(&mut self, kind: SocketKind, addr: SocketAddr) {
+        self.bound.remove(&(kind, addr));
+        match kind {
+            SocketKind::Stream => {
+                for connection in self.backlogs.remove(&addr).into_iter().flatten() {
+                    connection.shutdown(true, true);
+                }
+            }
+            SocketKind::Datagram => {
+                self.mailboxes.remove(&addr);
+            }
+        }
+    }
+
+    fn in_use(&self, kind: SocketKind, addr: SocketAddr) -> bool {
+        self.bound.iter().any(|&(other_kind, other)| {
+            other_kind == kind
+                && other.is_ipv6() == addr.is_ipv6()
+                && other.port() == addr.port()
+                && (other.ip() == addr.ip()
+                    || other.ip().is_unspecified()
+                    || addr.ip().is_unspecified())
+        })
+    }
+
+    fn free_port(&mut self, kind: SocketKind, ip: IpAddr) -> Option<u16> {
+        for _ in EPHEMERAL_PORTS {
+            self.last_port = if EPHEMERAL_PORTS.contains(&self.last_port)
+                && self.last_port != *EPHEMERAL_PORTS.end()
+            {
+                self.last_port + 1
+            } else {
+                *EPHEMERAL_PORTS.start()
+            };
+            if !self.in_use(kind, SocketAddr::new(ip, self.last_port)) {
+                return Some(self.last_port);
+            }
+        }
+        None
+    }
+
+    /// Finds the key under which a socket reachable at `addr` is stored in `map`: either `addr`
+    /// itself, or the unspecified address with the same port.
+    fn resolve<T>(map: &FxHashMap<SocketAddr, T>, addr: SocketAddr) -> Option<SocketAddr> {
+        if map.contains_key(&addr) {
+            return Some(addr);
+        }
+        let unspecified = SocketAddr::new(unspecified_ip(addr.is_ipv6()), addr.port());
+        map.contains_key(&unspecified).then_some(unspecified)
+    }
+}
+
+fn unspecified_ip(ipv6: bool) -> IpAddr {
+    if ipv6 { Ipv6Addr::UNSPECIFIED.into() } else { Ipv4Addr::UNSPECIFIED.into() }
+}
+
+/// Replaces the unspecified address with the loopback address, which is where packets sent to
+/// and from the unspecified address end up.
+fn loopback_if_unspecified(mut addr: SocketAddr) -> SocketAddr {
+    if addr.ip().is_unspecified() {
+        addr.set_ip(if addr.is_ipv6() {
+            Ipv6Addr::LOCALHOST.into()
+        } else {
+            Ipv4Addr::LOCALHOST.into()
+        });
+    }
+    addr
+}
+
+/// A socket created by `socket` or `accept`. Errors are returned as the name of the `errno`
+/// value to set.
+#[derive(Debug)]
+struct Socket {
+    kind: SocketKind,
+    ipv6: bool,
+    /// The address this socket reserved, by `bind` or implicitly by `connect`, `listen` or
+    /// sending a datagram. Accepted sockets share the address of their listener and do not
+    /// reserve one.
+    binding: Option<SocketAddr>,
+    listening: bool,
+    /// The connection of a connected stream socket.
+    connection: Option<Connection>,
+    nonblocking: bool,
+    /// `SO_RCVTIMEO`.
+    read_timeout: Option<Duration>,
+    /// Socket options that are remembered but have no effect.
+    options: FxHashMap<(i32, i32), i32>,
+    network: Rc<RefCell<VirtualNetwork>>,
+}
+
+impl Socket {
+    fn new(kind: SocketKind, ipv6: bool, network: Rc<RefCell<VirtualNetwork>>) -> Self {
+        Socket {
+            kind,
+            ipv6,
+            binding: None,
+            listening: false,
+            connection: None,
+            nonblocking: false,
+            read_timeout: None,
+            options: FxHashMap::default(),
+            network,
+        }
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        match (&self.connection, self.binding) {
+            (Some(connection), _) => connection.local,
+            (None, Some(binding)) => binding,
+            (None, None) => SocketAddr::new(unspecified_ip(self.ipv6), 0),
+        }
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr, &'static str> {
+        match (self.kind, &self.connection, self.binding) {
+            (SocketKind::Stream, Some(connection), _) => Ok(connection.peer),
+            (SocketKind::Datagram, _, Some(binding)) =>
+                self.network.borrow().mailboxes[&binding].peer.ok_or("ENOTCONN"),
+            _ => Err("ENOTCONN"),
+        }
+    }
+
+    fn check_family(&self, addr: SocketAddr) -> Result<(), &'static str> {
+        if addr.is_ipv6() == self.ipv6 { Ok(()) } else { Err("EAFNOSUPPORT") }
+    }
+
+    fn bind(&mut self, addr: SocketAddr) -> Result<(), &'static str> {
+        self.check_family(addr)?;
+        if self.binding.is_some() || self.connection.is_some() {
+            return Err("EINVAL");
+        }
+        self.binding = Some(self.network.borrow_mut().bind(self.kind, addr)?);
+        Ok(())
+    }
+
+    /// Binds the socket to an ephemeral port if it is not bound yet.
+    fn autobind(&mut self) -> Result<SocketAddr, &'static str> {
+        if let Some(binding) = self.binding {
+            return Ok(binding);
+        }
+        let addr = SocketAddr::new(unspecified_ip(self.ipv6), 0);
+        let binding = self.network.borrow_mut().bind(self.kind, addr)?;
+        self.binding = Some(binding);
+        Ok(binding)
+    }
+
+    fn listen(&mut self) -> Result<(), &'static str> {
+        if self.kind != SocketKind::Stream {
+            return Err("EOPNOTSUPP");
+        }
+        if self.connection.is_some() {
+            return Err("EINVAL");
+        }
+        let binding = self.autobind()?;
+        self.network.borrow_mut().backlogs.entry(binding).or_default();
+        self.listening = true;
+        Ok(())
+    }
+
+    fn connect(&mut self, addr: SocketAddr) -> Result<(), &'static str> {
+        self.check_family(addr)?;
+        let local = self.autobind()?;
+        let peer = loopback_if_unspecified(addr);
+        let mut network = self.network.borrow_mut();
+        match self.kind {
+            SocketKind::Stream => {
+                if self.connection.is_some() {
+                    return Err("EISCONN");
+                }
+                if self.listening {
+                    return Err("EINVAL");
+                }
+                let listener =
+                    VirtualNetwork::resolve(&network.backlogs, peer).ok_or("ECONNREFUSED")?;
+                let (client, server) = Connection::pair(loopback_if_unspecified(local), peer);
+                network.backlogs.get_mut(&listener).unwrap().push_back(server);
+                self.connection = Some(client);
+            }
+            SocketKind::Datagram => {
+                network.mailboxes.get_mut(&local).unwrap().peer = Some(peer);
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes a connection from the backlog. Returns `None` if there is none yet.
+    fn accept(&mut self) -> Result<Option<Connection>, &'static str> {
+        let (true, Some(binding)) = (self.listening, self.binding) else {
+            return Err("EINVAL");
+        };
+        Ok(self.network.borrow_mut().backlogs.get_mut(&binding).unwrap().pop_front())
+    }
+
+    /// Receives data, together with the address it came from. Returns `None` if that would
+    /// block.
+    fn recv<'tcx>(
+        &mut self,
+        bytes: &mut [u8],
+        peek: bool,
+        ecx: &MiriInterpCx<'tcx>,
+    ) -> Result<Option<(usize, SocketAddr)>, &'static str> {
+        match self.kind {
+            SocketKind::Stream => {
+                let Some(connection) = &self.connection else {
+                    return Err("ENOTCONN");
+                };
+                let mut pipe = connection.incoming.borrow_mut();
+                if pipe.data.is_empty() && !bytes.is_empty() {
+                    return if pipe.write_closed || pipe.read_closed {
+                        // End of file.
+                        Ok(Some((0, connection.peer)))
+                    } else {
+                        Ok(None)
+                    };
+                }
+                let len = bytes.len().min(pipe.data.len());
+                for (dest, src) in bytes.iter_mut().zip(pipe.data.iter()) {
+                    *dest = *src;
+                }
+                if !peek {
+                    pipe.data.drain(..len);
+                }
+                ecx.acquire_clock(&pipe.clock);
+                Ok(Some((len, connection.peer)))
+            }
+            SocketKind::Datagram => {
+                let Some(binding) = self.binding else {
+                    // Nobody can send anything to an unbound socket.
+                    return Ok(None);
+                };
+                let mut network = self.network.borrow_mut();
+                let mailbox = network.mailboxes.get_mut(&binding).unwrap();
+                let Some((from, data, clock)) = mailbox.datagrams.front() else {
+                    return Ok(None);
+                };
+                let from = *from;
+                let len = bytes.len().min(data.len());
+                bytes[..len].copy_from_slice(&data[..len]);
+                ecx.acquire_clock(clock);
+                if !peek {
+                    mailbox.datagrams.pop_front();
+                }
+                Ok(Some((len, from)))
+            }
+        }
+    }
+
+    /// Sends data, to `to` or the connected peer. Sending never blocks.
+    fn send<'tcx>(
+        &mut self,
+        bytes: &[u8],
+        to: Option<SocketAddr>,
+        ecx: &MiriInterpCx<'tcx>,
+    ) -> Result<usize, &'static str> {
+        match self.kind {
+            SocketKind::Stream => {
+                let Some(connection) = &self.connection else {
+                    return Err("ENOTCONN");
+                };
+                let mut pipe = connection.outgoing.borrow_mut();
+                if pipe.write_closed || pipe.read_closed {
+                    return Err("EPIPE");
+                }
+                pipe.data.extend(bytes);
+                if let Some(clock) = ecx.release_clock() {
+                    pipe.clock.join(&clock);
+                }
+                Ok(bytes.len())
+            }
+            SocketKind::Datagram => {
+                let to = match to {
+                    Some(to) => {
+                        self.check_family(to)?;
+                        loopback_if_unspecified(to)
+                    }
+                    None => self.peer_addr().map_err(|_| "EDESTADDRREQ")?,
+                };
+                if bytes.len() > MAX_DATAGRAM_SIZE {
+                    return Err("EMSGSIZE");
+                }
+                let from = loopback_if_unspecified(self.autobind()?);
+                let mut network = self.network.borrow_mut();
+                // Datagrams to addresses nobody listens on are lost.
+                if let Some(receiver) = VirtualNetwork::resolve(&network.mailboxes, to) {
+                    let mailbox = network.mailboxes.get_mut(&receiver).unwrap();
+                    if mailbox.peer.map_or(true, |peer| peer == from) {
+                        let clock = ecx.release_clock().map(|clock| clock.clone());
+                        let clock = clock.unwrap_or_default();
+                        mailbox.datagrams.push_back((from, bytes.to_vec(), clock));
+                    }
+                }
+                Ok(bytes.len())
+            }
+        }
+    }
+
+    fn shutdown(&mut self, read: bool, write: bool) -> Result<(), &'static str> {
+        let Some(connection) = &self.connection else {
+            return Err("ENOTCONN");
+        };
+        connection.shutdown(read, write);
+        Ok(())
+    }
+}
+
+impl FileDescription for Socket {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn read<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &mut [u8],
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        match self.recv(bytes, false, ecx) {
+            Ok(Some((len, _from))) => Ok(Ok(len)),
+            // Unless the socket is non-blocking, the thread blocks until there is data.
+            Ok(None) => Ok(Err(io::ErrorKind::WouldBlock.into())),
+            Err("ENOTCONN") => Ok(Err(io::ErrorKind::NotConnected.into())),
+            Err(errno) => throw_unsup_format!("`read` on a socket failed with {errno}"),
+        }
+    }
+
+    fn write<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &[u8],
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        match self.send(bytes, None, ecx) {
+            Ok(len) => Ok(Ok(len)),
+            Err("ENOTCONN") => Ok(Err(io::ErrorKind::NotConnected.into())),
+            Err("EPIPE") => Ok(Err(io::ErrorKind::BrokenPipe.into())),
+            Err(errno) => throw_unsup_format!("`write` on a socket failed with {errno}"),
+        }
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        if let Some(connection) = &self.connection {
+            connection.shutdown(true, true);
+        }
+        if let Some(binding) = self.binding {
+            self.network.borrow_mut().unbind(self.kind, binding);
+        }
+        Ok(Ok(()))
+    }
+
+    fn set_nonblocking<'tcx>(&mut self, nonblocking: bool) -> InterpResult<'tcx> {
+        self.nonblocking = nonblocking;
+        Ok(())
+    }
+
+    fn blocks_on_read(&self) -> bool {
+        !self.nonblocking
+    }
+
+    fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    fn readiness<'tcx>(&self) -> InterpResult<'tcx, Readiness> {
+        let network = self.network.borrow();
+        Ok(match (self.kind, &self.connection, self.binding) {
+            (SocketKind::Stream, None, Some(binding)) if self.listening =>
+                Readiness {
+                    readable: !network.backlogs[&binding].is_empty(),
+                    ..Readiness::default()
+                },
+            (SocketKind::Stream, Some(connection), _) => {
+                let incoming = connection.incoming.borrow();
+                let outgoing = connection.outgoing.borrow();
+                Readiness {
+                    readable: !incoming.data.is_empty()
+                        || incoming.write_closed
+                        || incoming.read_closed,
+                    // Writing never blocks.
+                    writable: true,
+                    read_closed: incoming.write_closed,
+                    hangup: incoming.write_closed && outgoing.read_closed,
+                    ..Readiness::default()
+                }
+            }
+            // Like on Linux, a stream socket that is not connected reports a hangup.
+            (SocketKind::Stream, None, _) =>
+                Readiness { writable: true, hangup: true, ..Readiness::default() },
+            (SocketKind::Datagram, _, binding) =>
+                Readiness {
+                    readable: binding
+                        .is_some_and(|binding| !network.mailboxes[&binding].datagrams.is_empty()),
+                    writable: true,
+                    ..Readiness::default()
+                },
+        })
+    }
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Sets `errno` to the given value and returns -1.
+    fn socket_error<T: From<i32>>(&mut self, errno: &str) -> InterpResult<'tcx, T> {
+        let this = self.eval_context_mut();
+        let errno = this.eval_libc(errno);
+        this.set_last_error(errno)?;
+        Ok((-1).into())
+    }
+
+    /// Runs `f` on the socket behind `fd`, and sets `errno` if that fails.
+    fn with_socket<T>(
+        &mut self,
+        fd: i32,
+        f: impl FnOnce(
+            &mut Socket,
+            &mut MiriInterpCx<'tcx>,
+        ) -> InterpResult<'tcx, Result<T, &'static str>>,
+    ) -> InterpResult<'tcx, Result<T, i32>> {
+        let this = self.eval_context_mut();
+        // We temporarily dup the FD to be able to retain mutable access to `this`.
+        let Some(file_descriptor) = this.machine.fds.dup(fd) else {
+            return Ok(Err(this.fd_not_found()?));
+        };
+        let mut file_description = file_descriptor.borrow_mut();
+        let Some(socket) = file_description.downcast_mut::<Socket>() else {
+            drop(file_description);
+            return Ok(Err(this.socket_error("ENOTSOCK")?));
+        };
+        let result = f(socket, this)?;
+        drop(file_description);
+        match result {
+            Ok(value) => Ok(Ok(value)),
+            Err(errno) => Ok(Err(this.socket_error(errno)?)),
+        }
+    }
+
+    /// Returns the values of `SOCK_NONBLOCK` and `SOCK_CLOEXEC` on targets that have them, and
+    /// zeros otherwise.
+    fn sock_flags(&self) -> (i32, i32) {
+        let this = self.eval_context_ref();
+        let os = &*this.tcx.sess.target.os;
+        if matches!(os, "linux" | "android" | "freebsd" | "solaris" | "illumos") {
+            (this.eval_libc_i32("SOCK_NONBLOCK"), this.eval_libc_i32("SOCK_CLOEXEC"))
+        } else {
+            (0, 0)
+        }
+    }
+
+    /// Reads a `sockaddr_in` or `sockaddr_in6` of length `len`.
+    fn read_socket_addr(
+        &self,
+        ptr: Pointer<Option<Provenance>>,
+        len: u32,
+    ) -> InterpResult<'tcx, Result<SocketAddr, &'static str>> {
+        let this = self.eval_context_ref();
+        let sockaddr = this.libc_ty_layout("sockaddr");
+        if u64::from(len) < sockaddr.size.bytes() {
+            return Ok(Err("EINVAL"));
+        }
+        let family = this.project_field_named(&this.ptr_to_mplace(ptr, sockaddr), "sa_family")?;
+        let family = this.read_scalar(&family)?.to_int(family.layout.size)?;
+
+        let (layout, prefix) = if family == i128::from(this.eval_libc_i32("AF_INET")) {
+            (this.libc_ty_layout("sockaddr_in"), "sin")
+        } else if family == i128::from(this.eval_libc_i32("AF_INET6")) {
+            (this.libc_ty_layout("sockaddr_in6"), "sin6")
+        } else {
+            return Ok(Err("EAFNOSUPPORT"));
+        };
+        if u64::from(len) < layout.size.bytes() {
+            return Ok(Err("EINVAL"));
+        }
+        let place = this.ptr_to_mplace(ptr, layout);
+        // Ports and addresses are stored in network byte order.
+        let read_field = |name: &str| -> InterpResult<'tcx, Vec<u8>> {
+            let field = this.project_field_named(&place, &format!("{prefix}_{name}"))?;
+            Ok(this.read_bytes_ptr_strip_provenance(field.ptr(), field.layout.size)?.to_vec())
+        };
+        let port = u16::from_be_bytes(read_field("port")?.try_into().unwrap());
+        let ip: IpAddr = match prefix {
+            "sin" => <[u8; 4]>::try_from(read_field("addr")?).unwrap().into(),
+            _ => <[u8; 16]>::try_from(read_field("addr")?).unwrap().into(),
+        };
+        Ok(Ok(SocketAddr::new(ip, port)))
+    }
+
+    /// Writes `addr` as a `sockaddr_in` or `sockaddr_in6` to the buffer at `ptr`, whose length
+    /// is stored in the `socklen_t` at `len_ptr`. The length is then updated to the size of the
+    /// written address. Does nothing if `ptr` is null.
+    fn write_socket_addr(
+        &mut self,
+        addr: SocketAddr,
+        ptr: Pointer<Option<Provenance>>,
+        len_ptr: Pointer<Option<Provenance>>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        if this.ptr_is_null(ptr)? {
+            return Ok(());
+        }
+        let (family, layout, prefix) = match addr {
+            SocketAddr::V4(_) =>
+                (this.eval_libc_i32("AF_INET"), this.libc_ty_layout("sockaddr_in"), "sin"),
+            SocketAddr::V6(_) =>
+                (this.eval_libc_i32("AF_INET6"), this.libc_ty_layout("sockaddr_in6"), "sin6"),
+        };
+        let len_place = this.ptr_to_mplace(len_ptr, this.libc_ty_layout("socklen_t"));
+        let len = this.read_scalar(&len_place)?.to_u32()?;
+        if u64::from(len) < layout.size.bytes() {
+            throw_unsup_format!("truncating socket addresses to {len} bytes is not supported");
+        }
+
+        let place = this.ptr_to_mplace(ptr, layout);
+        this.write_bytes_ptr(place.ptr(), iter::repeat(0u8).take(layout.size.bytes_usize()))?;
+        this.write_int(family, &this.project_field_named(&place, &format!("{prefix}_family"))?)?;
+        // BSDs store the length of the address in the address.
+        if this.projectable_has_field(&place, &format!("{prefix}_len")) {
+            let len_field = this.project_field_named(&place, &format!("{prefix}_len"))?;
+            this.write_int(layout.size.bytes(), &len_field)?;
+        }
+        let port = this.project_field_named(&place, &format!("{prefix}_port"))?;
+        this.write_bytes_ptr(port.ptr(), addr.port().to_be_bytes())?;
+        let ip = this.project_field_named(&place, &format!("{prefix}_addr"))?;
+        match addr.ip() {
+            IpAddr::V4(ip_addr) => this.write_bytes_ptr(ip.ptr(), ip_addr.octets())?,
+            IpAddr::V6(ip_addr) => this.write_bytes_ptr(ip.ptr(), ip_addr.octets())?,
+        }
+
+        this.write_int(layout.size.bytes(), &len_place)
+    }
+
+    /// The timeout for a blocking receive on `fd`, from `SO_RCVTIMEO`.
+    fn socket_read_timeout(&self, fd: i32) -> Option<Timeout> {
+        let this = self.eval_context_ref();
+        let file_descriptor = this.machine.fds.get(fd)?;
+        let duration = file_descriptor.downcast_ref::<Socket>()?.read_timeout?;
+        Some(Timeout::Monotonic(this.machine.clock.now().checked_add(duration).unwrap()))
+    }
+
+    /// The socket options that are remembered but have no effect, as `(level, name)` pairs.
+    fn inert_socket_options(&self) -> Vec<(i32, i32)> {
+        let this = self.eval_context_ref();
+        let sol_socket = this.eval_libc_i32("SOL_SOCKET");
+        let mut options = vec![
+            (sol_socket, this.eval_libc_i32("SO_REUSEADDR")),
+            (sol_socket, this.eval_libc_i32("SO_REUSEPORT")),
+            (sol_socket, this.eval_libc_i32("SO_KEEPALIVE")),
+            (sol_socket, this.eval_libc_i32("SO_BROADCAST")),
+            (sol_socket, this.eval_libc_i32("SO_RCVBUF")),
+            (sol_socket, this.eval_libc_i32("SO_SNDBUF")),
+            (this.eval_libc_i32("IPPROTO_TCP"), this.eval_libc_i32("TCP_NODELAY")),
+            (this.eval_libc_i32("IPPROTO_IP"), this.eval_libc_i32("IP_TTL")),
+            (this.eval_libc_i32("IPPROTO_IPV6"), this.eval_libc_i32("IPV6_V6ONLY")),
+        ];
+        if matches!(&*this.tcx.sess.target.os, "macos" | "ios" | "freebsd") {
+            // Sockets never raise `SIGPIPE` anyway.
+            options.push((sol_socket, this.eval_libc_i32("SO_NOSIGPIPE")));
+        }
+        options
+    }
+
+    /// Accepts a connection, or blocks until there is one or `timeout` elapsed.
+    fn accept_or_block(
+        &mut self,
+        socket: i32,
+        address: Pointer<Option<Provenance>>,
+        address_len: Pointer<Option<Provenance>>,
+        flags: i32,
+        timeout: Option<Timeout>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let (sock_nonblock, sock_cloexec) =
+            if flags == 0 { (0, 0) } else { this.sock_flags() };
+        if flags & !(sock_nonblock | sock_cloexec) != 0 {
+            throw_unsup_format!("`accept4` flags {flags:#x} are not supported");
+        }
+
+        let result = this.with_socket(socket, |socket, _this| {
+            let nonblocking = socket.nonblocking;
+            let ipv6 = socket.ipv6;
+            let network = socket.network.clone();
+            Ok(socket.accept().map(|connection| (connection, nonblocking, ipv6, network)))
+        })?;
+        match result {
+            Err(err) => this.write_int(err, dest),
+            Ok((Some(connection), _, ipv6, network)) => {
+                let peer = connection.peer;
+                let mut socket = Socket::new(SocketKind::Stream, ipv6, network);
+                socket.connection = Some(connection);
+                socket.nonblocking = flags & sock_nonblock != 0;
+                let fd = this.machine.fds.insert_fd(FileDescriptor::new(socket));
+                this.write_socket_addr(peer, address, address_len)?;
+                this.write_int(fd, dest)
+            }
+            Ok((None, true, _, _)) => {
+                let err: i32 = this.socket_error("EWOULDBLOCK")?;
+                this.write_int(err, dest)
+            }
+            Ok((None, false, _, _)) => {
+                let dest = dest.clone();
+                this.block_on_fds(
+                    timeout.clone(),
+                    callback!(
+                        @capture<'tcx> {
+                            socket: i32,
+                            address: Pointer<Option<Provenance>>,
+                            address_len: Pointer<Option<Provenance>>,
+                            flags: i32,
+                            timeout: Option<Timeout>,
+                            dest: MPlaceTy<'tcx, Provenance>,
+                        }
+                        @unblock = |this| {
+                            this.accept_or_block(
+                                socket,
+                                address,
+                                address_len,
+                                flags,
+                                timeout,
+                                &dest,
+                            )
+                        }
+                        @timeout = |this| {
+                            this.fd_wait_timed_out();
+                            let err: i32 = this.socket_error("EWOULDBLOCK")?;
+                            this.write_int(err, &dest)
+                        }
+                    ),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Receives data, or blocks until there is some or `timeout` elapsed.
+    #[allow(clippy::too_many_arguments)]
+    fn recv_or_block(
+        &mut self,
+        socket: i32,
+        buf: Pointer<Option<Provenance>>,
+        len: u64,
+        flags: i32,
+        address: Pointer<Option<Provenance>>,
+        address_len: Pointer<Option<Provenance>>,
+        timeout: Option<Timeout>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let msg_peek = this.eval_libc_i32("MSG_PEEK");
+        let msg_dontwait = this.eval_libc_i32("MSG_DONTWAIT");
+        if flags & !(msg_peek | msg_dontwait) != 0 {
+            throw_unsup_format!("`recv` flags {flags:#x} are not supported");
+        }
+
+        // Check that the *entire* buffer is actually valid memory.
+        this.check_ptr_access(buf, Size::from_bytes(len), CheckInAllocMsg::MemoryAccessTest)?;
+        let mut bytes = vec![0; usize::try_from(len).unwrap()];
+        let result = this.with_socket(socket, |socket, this| {
+            let nonblocking = socket.nonblocking || flags & msg_dontwait != 0;
+            Ok(socket.recv(&mut bytes, flags & msg_peek != 0, this).map(|read| (read, nonblocking)))
+        })?;
+        match result {
+            Err(err) => this.write_int(err, dest),
+            Ok((Some((read, from)), _)) => {
+                this.write_bytes_ptr(buf, bytes[..read].iter().copied())?;
+                this.write_socket_addr(from, address, address_len)?;
+                this.write_int(u64::try_from(read).unwrap(), dest)
+            }
+            Ok((None, true)) => {
+                let err: i32 = this.socket_error("EWOULDBLOCK")?;
+                this.write_int(err, dest)
+            }
+            Ok((None, false)) => {
+                let dest = dest.clone();
+                this.block_on_fds(
+                    timeout.clone(),
+                    callback!(
+                        @capture<'tcx> {
+                            socket: i32,
+                            buf: Pointer<Option<Provenance>>,
+                            len: u64,
+                            flags: i32,
+                            address: Pointer<Option<Provenance>>,
+                            address_len: Pointer<Option<Provenance>>,
+                            timeout: Option<Timeout>,
+                            dest: MPlaceTy<'tcx, Provenance>,
+                        }
+                        @unblock = |this| {
+                            this.recv_or_block(
+                                socket,
+                                buf,
+                                len,
+                                flags,
+                                address,
+                                address_len,
+                                timeout,
+                                &dest,
+                            )
+                        }
+                        @timeout = |this| {
+                            this.fd_wait_timed_out();
+                            let err: i32 = this.socket_error("EWOULDBLOCK")?;
+                            this.write_int(err, &dest)
+                        }
+                    ),
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
 pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
     /// Currently this function this function is a stub. Eventually we need to
@@ -60,4 +895,417 @@ fn socketpair(
 
         Ok(Scalar::from_i32(0))
     }
+
+    /// Creates a TCP or UDP socket on the virtual loopback network.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/socket.2.html>
+    fn socket(
+        &mut self,
+        domain: &OpTy<'tcx, Provenance>,
+        type_: &OpTy<'tcx, Provenance>,
+        protocol: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let domain = this.read_scalar(domain)?.to_i32()?;
+        let mut type_ = this.read_scalar(type_)?.to_i32()?;
+        let protocol = this.read_scalar(protocol)?.to_i32()?;
+
+        let ipv6 = if domain == this.eval_libc_i32("AF_INET") {
+            false
+        } else if domain == this.eval_libc_i32("AF_INET6") {
+            true
+        } else {
+            throw_unsup_format!("socket domain {domain} is not supported, only IPv4 and IPv6 are");
+        };
+
+        let (sock_nonblock, sock_cloexec) = this.sock_flags();
+        let nonblocking = type_ & sock_nonblock != 0;
+        // Miri does not support exec, so `SOCK_CLOEXEC` has no effect.
+        type_ &= !(sock_nonblock | sock_cloexec);
+
+        let (kind, default_protocol) = if type_ == this.eval_libc_i32("SOCK_STREAM") {
+            (SocketKind::Stream, this.eval_libc_i32("IPPROTO_TCP"))
+        } else if type_ == this.eval_libc_i32("SOCK_DGRAM") {
+            (SocketKind::Datagram, this.eval_libc_i32("IPPROTO_UDP"))
+        } else {
+            throw_unsup_format!("socket type {type_} is not supported");
+        };
+        if protocol != 0 && protocol != default_protocol {
+            return this.socket_error("EPROTONOSUPPORT");
+        }
+
+        let mut socket = Socket::new(kind, ipv6, this.machine.network.clone());
+        socket.nonblocking = nonblocking;
+        Ok(this.machine.fds.insert_fd(FileDescriptor::new(socket)))
+    }
+
+    /// <https://man7.org/linux/man-pages/man2/bind.2.html>
+    fn bind(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        address: &OpTy<'tcx, Provenance>,
+        address_len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_scalar(address_len)?.to_u32()?;
+
+        let address = match this.read_socket_addr(address, address_len)? {
+            Ok(address) => address,
+            Err(errno) => return this.socket_error(errno),
+        };
+        let result = this.with_socket(socket, |socket, _this| Ok(socket.bind(address)))?;
+        Ok(result.map_or_else(|err| err, |()| 0))
+    }
+
+    /// <https://man7.org/linux/man-pages/man2/listen.2.html>
+    fn listen(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        backlog: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        // The backlog of the virtual network is unbounded.
+        let _backlog = this.read_scalar(backlog)?.to_i32()?;
+
+        let result = this.with_socket(socket, |socket, _this| Ok(socket.listen()))?;
+        Ok(result.map_or_else(|err| err, |()| 0))
+    }
+
+    /// Connects a socket. For stream sockets, this succeeds right away if some socket listens on
+    /// the address, even if that socket has not accepted the connection yet.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/connect.2.html>
+    fn connect(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        address: &OpTy<'tcx, Provenance>,
+        address_len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_scalar(address_len)?.to_u32()?;
+
+        let address = match this.read_socket_addr(address, address_len)? {
+            Ok(address) => address,
+            Err(errno) => return this.socket_error(errno),
+        };
+        let result = this.with_socket(socket, |socket, _this| Ok(socket.connect(address)))?;
+        // The listener became ready.
+        this.wake_fd_waiters()?;
+        Ok(result.map_or_else(|err| err, |()| 0))
+    }
+
+    /// Implements `accept` and `accept4`; `accept` passes 0 as `flags`. If there is no
+    /// connection yet, the thread blocks (unless the socket is nonblocking).
+    ///
+    /// <https://man7.org/linux/man-pages/man2/accept.2.html>
+    fn accept4(
+        &mut self,
+        socket: i32,
+        address: Pointer<Option<Provenance>>,
+        address_len: Pointer<Option<Provenance>>,
+        flags: i32,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let timeout = this.socket_read_timeout(socket);
+        this.accept_or_block(socket, address, address_len, flags, timeout, dest)
+    }
+
+    /// Implements `getsockname` and `getpeername`.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/getsockname.2.html>
+    fn getsockname(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        address: &OpTy<'tcx, Provenance>,
+        address_len: &OpTy<'tcx, Provenance>,
+        peer: bool,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+
+        let result = this.with_socket(socket, |socket, _this| {
+            Ok(if peer { socket.peer_addr() } else { Ok(socket.local_addr()) })
+        })?;
+        match result {
+            Ok(addr) => {
+                this.write_socket_addr(addr, address, address_len)?;
+                Ok(0)
+            }
+            Err(err) => Ok(err),
+        }
+    }
+
+    /// Implements `send` and `sendto`; `send` passes `None` as the address. Sending never
+    /// blocks, as the buffers of the virtual network are unbounded.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/send.2.html>
+    fn sendto(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        buf: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+        flags: &OpTy<'tcx, Provenance>,
+        address: Option<(&OpTy<'tcx, Provenance>, &OpTy<'tcx, Provenance>)>,
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        let buf = this.read_pointer(buf)?;
+        let len = this.read_target_usize(len)?;
+        let flags = this.read_scalar(flags)?.to_i32()?;
+
+        let mut supported_flags = this.eval_libc_i32("MSG_DONTWAIT");
+        if matches!(&*this.tcx.sess.target.os, "linux" | "android" | "freebsd") {
+            // Sockets never raise `SIGPIPE` anyway.
+            supported_flags |= this.eval_libc_i32("MSG_NOSIGNAL");
+        }
+        if flags & !supported_flags != 0 {
+            throw_unsup_format!("`send` flags {flags:#x} are not supported");
+        }
+
+        let to = match address {
+            Some((address, address_len)) => {
+                let address = this.read_pointer(address)?;
+                let address_len = this.read_scalar(address_len)?.to_u32()?;
+                if this.ptr_is_null(address)? {
+                    None
+                } else {
+                    match this.read_socket_addr(address, address_len)? {
+                        Ok(address) => Some(address),
+                        Err(errno) => return this.socket_error(errno),
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let bytes = this.read_bytes_ptr_strip_provenance(buf, Size::from_bytes(len))?.to_owned();
+        let result = this.with_socket(socket, |socket, this| Ok(socket.send(&bytes, to, this)))?;
+        // The receiving end became ready.
+        this.wake_fd_waiters()?;
+        Ok(result.map_or_else(i64::from, |len| i64::try_from(len).unwrap()))
+    }
+
+    /// Implements `recv` and `recvfrom`; `recv` passes null as the address. If there is no data
+    /// yet, the thread blocks (unless the socket is nonblocking or `MSG_DONTWAIT` is passed).
+    ///
+    /// <https://man7.org/linux/man-pages/man2/recv.2.html>
+    #[allow(clippy::too_many_arguments)]
+    fn recvfrom(
+        &mut self,
+        socket: i32,
+        buf: Pointer<Option<Provenance>>,
+        len: u64,
+        flags: i32,
+        address: Pointer<Option<Provenance>>,
+        address_len: Pointer<Option<Provenance>>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let timeout = this.socket_read_timeout(socket);
+        this.recv_or_block(socket, buf, len, flags, address, address_len, timeout, dest)
+    }
+
+    /// <https://man7.org/linux/man-pages/man2/shutdown.2.html>
+    fn shutdown(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        how: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        let how = this.read_scalar(how)?.to_i32()?;
+
+        let (read, write) = if how == this.eval_libc_i32("SHUT_RD") {
+            (true, false)
+        } else if how == this.eval_libc_i32("SHUT_WR") {
+            (false, true)
+        } else if how == this.eval_libc_i32("SHUT_RDWR") {
+            (true, true)
+        } else {
+            return this.socket_error("EINVAL");
+        };
+        let result = this.with_socket(socket, |socket, _this| Ok(socket.shutdown(read, write)))?;
+        // The other end might see end-of-file now.
+        this.wake_fd_waiters()?;
+        Ok(result.map_or_else(|err| err, |()| 0))
+    }
+
+    /// Supports `SO_RCVTIMEO`, and accepts some options that have no effect on the virtual
+    /// network.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/setsockopt.2.html>
+    fn setsockopt(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        level: &OpTy<'tcx, Provenance>,
+        name: &OpTy<'tcx, Provenance>,
+        value: &OpTy<'tcx, Provenance>,
+        value_len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let name = this.read_scalar(name)?.to_i32()?;
+        let value_len = this.read_scalar(value_len)?.to_u32()?;
+
+        let sol_socket = this.eval_libc_i32("SOL_SOCKET");
+        if level == sol_socket
+            && (name == this.eval_libc_i32("SO_RCVTIMEO")
+                || name == this.eval_libc_i32("SO_SNDTIMEO"))
+        {
+            let timeval = this.libc_ty_layout("timeval");
+            if u64::from(value_len) < timeval.size.bytes() {
+                return this.socket_error("EINVAL");
+            }
+            let timeval = this.deref_pointer_as(value, timeval)?;
+            let seconds = this.project_field_named(&timeval, "tv_sec")?;
+            let seconds = this.read_scalar(&seconds)?.to_int(seconds.layout.size)?;
+            let micros = this.project_field_named(&timeval, "tv_usec")?;
+            let micros = this.read_scalar(&micros)?.to_int(micros.layout.size)?;
+            let (Ok(seconds), Ok(micros @ 0..1_000_000)) =
+                (u64::try_from(seconds), u32::try_from(micros))
+            else {
+                return this.socket_error("EDOM");
+            };
+            let timeout = Duration::new(seconds, micros * 1000);
+            let is_read_timeout = name == this.eval_libc_i32("SO_RCVTIMEO");
+            let result = this.with_socket(socket, |socket, _this| {
+                // Sending never blocks, so only the receive timeout matters.
+                if is_read_timeout {
+                    socket.read_timeout = (!timeout.is_zero()).then_some(timeout);
+                }
+                Ok(Ok(()))
+            })?;
+            return Ok(result.map_or_else(|err| err, |()| 0));
+        }
+
+        if !this.inert_socket_options().contains(&(level, name)) {
+            throw_unsup_format!("socket option {name} at level {level} is not supported");
+        }
+        if value_len < 4 {
+            return this.socket_error("EINVAL");
+        }
+        let value = this.deref_pointer_as(value, this.machine.layouts.i32)?;
+        let value = this.read_scalar(&value)?.to_i32()?;
+        let result = this.with_socket(socket, |socket, _this| {
+            socket.options.insert((level, name), value);
+            Ok(Ok(()))
+        })?;
+        Ok(result.map_or_else(|err| err, |()| 0))
+    }
+
+    /// Supports `SO_ERROR`, `SO_TYPE`, and the options supported by `setsockopt` other than
+    /// timeouts.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/getsockopt.2.html>
+    fn getsockopt(
+        &mut self,
+        socket: &OpTy<'tcx, Provenance>,
+        level: &OpTy<'tcx, Provenance>,
+        name: &OpTy<'tcx, Provenance>,
+        value: &OpTy<'tcx, Provenance>,
+        value_len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let socket = this.read_scalar(socket)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let name = this.read_scalar(name)?.to_i32()?;
+        let value = this.read_pointer(value)?;
+        let value_len = this.deref_pointer_as(value_len, this.libc_ty_layout("socklen_t"))?;
+
+        let sol_socket = this.eval_libc_i32("SOL_SOCKET");
+        let so_error = this.eval_libc_i32("SO_ERROR");
+        let so_type = this.eval_libc_i32("SO_TYPE");
+        let sock_stream = this.eval_libc_i32("SOCK_STREAM");
+        let sock_dgram = this.eval_libc_i32("SOCK_DGRAM");
+        if !(level == sol_socket && (name == so_error || name == so_type))
+            && !this.inert_socket_options().contains(&(level, name))
+        {
+            throw_unsup_format!("socket option {name} at level {level} is not supported");
+        }
+        if this.read_scalar(&value_len)?.to_u32()? < 4 {
+            return this.socket_error("EINVAL");
+        }
+
+        let result = this.with_socket(socket, |socket, _this| {
+            Ok(Ok(if level == sol_socket && name == so_error {
+                // Connecting either succeeds or fails right away, so there are no pending errors.
+                0
+            } else if level == sol_socket && name == so_type {
+                match socket.kind {
+                    SocketKind::Stream => sock_stream,
+                    SocketKind::Datagram => sock_dgram,
+                }
+            } else {
+                socket.options.get(&(level, name)).copied().unwrap_or(0)
+            }))
+        })?;
+        match result {
+            Ok(option) => {
+                this.write_int(option, &this.ptr_to_mplace(value, this.machine.layouts.i32))?;
+                this.write_int(4, &value_len)?;
+                Ok(0)
+            }
+            Err(err) => Ok(err),
+        }
+    }
+
+    /// Supports `FIONBIO` on sockets and pipes, and `FIOCLEX` on all file descriptors.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/ioctl.2.html>
+    fn ioctl(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        if args.len() < 2 {
+            throw_ub_format!(
+                "incorrect number of arguments for ioctl: got {}, expected at least 2",
+                args.len()
+            );
+        }
+        let fd = this.read_scalar(&args[0])?.to_i32()?;
+        let request = this.read_scalar(&args[1])?.to_uint(args[1].layout.size)?;
+
+        let fioclex = this.eval_libc("FIOCLEX");
+        let fionbio = this.eval_libc("FIONBIO");
+        if request == fioclex.to_uint(fioclex.size())? {
+            // Child processes only inherit the standard streams, so this has no effect.
+            if !this.machine.fds.is_fd(fd) {
+                return this.fd_not_found();
+            }
+            Ok(0)
+        } else if request == fionbio.to_uint(fionbio.size())? {
+            if args.len() < 3 {
+                throw_ub_format!(
+                    "incorrect number of arguments for ioctl with request=`FIONBIO`: got {}, expected at least 3",
+                    args.len()
+                );
+            }
+            let value = this.deref_pointer_as(&args[2], this.machine.layouts.i32)?;
+            let nonblocking = this.read_scalar(&value)?.to_i32()? != 0;
+            let Some(mut file_descriptor) = this.machine.fds.get_mut(fd) else {
+                return this.fd_not_found();
+            };
+            file_descriptor.set_nonblocking(nonblocking)?;
+            Ok(0)
+        } else {
+            throw_unsup_format!("ioctl request {request:#x} is not supported");
+        }
+    }
 }
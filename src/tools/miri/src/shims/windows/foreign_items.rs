@@ -140,17 +140,64 @@ fn emulate_foreign_item_inner(
             }
 
             // File related shims
-            "NtWriteFile" => {
-                if !this.frame_in_std() {
-                    throw_unsup_format!(
-                        "`NtWriteFile` support is crude and just enough for stdout to work"
-                    );
+            "CreateFileW" => {
+                let [
+                    file_name,
+                    desired_access,
+                    share_mode,
+                    security_attributes,
+                    creation_disposition,
+                    flags_and_attributes,
+                    template_file,
+                ] = this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let handle = this.CreateFileW(
+                    file_name,
+                    desired_access,
+                    share_mode,
+                    security_attributes,
+                    creation_disposition,
+                    flags_and_attributes,
+                    template_file,
+                )?;
+                this.write_scalar(handle, dest)?;
+            }
+            "ReadFile" => {
+                let [handle, buf, n, bytes_read, overlapped] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.ReadFile(handle, buf, n, bytes_read, overlapped)?;
+                this.write_scalar(result, dest)?;
+            }
+            "WriteFile" => {
+                let [handle, buf, n, bytes_written, overlapped] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.WriteFile(handle, buf, n, bytes_written, overlapped)?;
+                this.write_scalar(result, dest)?;
+            }
+            "NtReadFile" => {
+                let [
+                    handle,
+                    event,
+                    apc_routine,
+                    _apc_context,
+                    io_status_block,
+                    buf,
+                    n,
+                    byte_offset,
+                    _key,
+                ] = this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                if !this.ptr_is_null(this.read_pointer(event)?)?
+                    || !this.ptr_is_null(this.read_pointer(apc_routine)?)?
+                {
+                    throw_unsup_format!("`NtReadFile`: asynchronous I/O is not supported");
                 }
-
+                let status = this.NtReadFile(handle, io_status_block, buf, n, byte_offset)?;
+                this.write_scalar(status, dest)?;
+            }
+            "NtWriteFile" => {
                 let [
                     handle,
-                    _event,
-                    _apc_routine,
+                    event,
+                    apc_routine,
                     _apc_context,
                     io_status_block,
                     buf,
@@ -158,54 +205,108 @@ fn emulate_foreign_item_inner(
                     byte_offset,
                     _key,
                 ] = this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
-                let handle = this.read_target_isize(handle)?;
-                let buf = this.read_pointer(buf)?;
-                let n = this.read_scalar(n)?.to_u32()?;
-                let byte_offset = this.read_target_usize(byte_offset)?; // is actually a pointer
-                let io_status_block = this
-                    .deref_pointer_as(io_status_block, this.windows_ty_layout("IO_STATUS_BLOCK"))?;
-
-                if byte_offset != 0 {
-                    throw_unsup_format!(
-                        "`NtWriteFile` `ByteOffset` parameter is non-null, which is unsupported"
-                    );
+                if !this.ptr_is_null(this.read_pointer(event)?)?
+                    || !this.ptr_is_null(this.read_pointer(apc_routine)?)?
+                {
+                    throw_unsup_format!("`NtWriteFile`: asynchronous I/O is not supported");
                 }
-
-                let written = if handle == -11 || handle == -12 {
-                    // stdout/stderr
-                    use io::Write;
-
-                    let buf_cont =
-                        this.read_bytes_ptr_strip_provenance(buf, Size::from_bytes(u64::from(n)))?;
-                    let res = if this.machine.mute_stdout_stderr {
-                        Ok(buf_cont.len())
-                    } else if handle == -11 {
-                        io::stdout().write(buf_cont)
-                    } else {
-                        io::stderr().write(buf_cont)
-                    };
-                    // We write at most `n` bytes, which is a `u32`, so we cannot have written more than that.
-                    res.ok().map(|n| u32::try_from(n).unwrap())
+                let status = this.NtWriteFile(handle, io_status_block, buf, n, byte_offset)?;
+                this.write_scalar(status, dest)?;
+            }
+            "RtlNtStatusToDosError" => {
+                let [status] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let status = this.read_scalar(status)?.to_u32()?;
+                // We only ever produce `NTSTATUS` values that wrap a Win32 error code (see
+                // `NtReadFile`), plus a few well-known ones.
+                let error = if status & 0xFFFF_0000 == 0xC007_0000 {
+                    Scalar::from_u32(status & 0xFFFF)
+                } else if status == 0 {
+                    Scalar::from_u32(0) // STATUS_SUCCESS -> ERROR_SUCCESS
+                } else if status == this.eval_windows("c", "STATUS_END_OF_FILE").to_u32()? {
+                    this.eval_windows("c", "ERROR_HANDLE_EOF")
+                } else if status == 0xC0000185 {
+                    // STATUS_IO_DEVICE_ERROR
+                    this.eval_windows("c", "ERROR_IO_DEVICE")
                 } else {
-                    throw_unsup_format!(
-                        "on Windows, writing to anything except stdout/stderr is not supported"
-                    )
+                    // This is what Windows returns for unknown status codes.
+                    this.eval_windows("c", "ERROR_MR_MID_NOT_FOUND")
                 };
-                // We have to put the result into io_status_block.
-                if let Some(n) = written {
-                    let io_status_information =
-                        this.project_field_named(&io_status_block, "Information")?;
-                    this.write_scalar(
-                        Scalar::from_target_usize(n.into(), this),
-                        &io_status_information,
-                    )?;
-                }
-                // Return whether this was a success. >= 0 is success.
-                // For the error code we arbitrarily pick 0xC0000185, STATUS_IO_DEVICE_ERROR.
-                this.write_scalar(
-                    Scalar::from_u32(if written.is_some() { 0 } else { 0xC0000185u32 }),
-                    dest,
-                )?;
+                this.write_scalar(error, dest)?;
+            }
+            "GetFileInformationByHandle" => {
+                let [handle, info] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.GetFileInformationByHandle(handle, info)?;
+                this.write_scalar(result, dest)?;
+            }
+            "GetFileInformationByHandleEx" => {
+                let [handle, class, info, size] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.GetFileInformationByHandleEx(handle, class, info, size)?;
+                this.write_scalar(result, dest)?;
+            }
+            "SetFileInformationByHandle" => {
+                let [handle, class, info, size] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.SetFileInformationByHandle(handle, class, info, size)?;
+                this.write_scalar(result, dest)?;
+            }
+            "SetFilePointerEx" => {
+                let [handle, distance, new_pointer, move_method] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.SetFilePointerEx(handle, distance, new_pointer, move_method)?;
+                this.write_scalar(result, dest)?;
+            }
+            "FlushFileBuffers" => {
+                let [handle] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.FlushFileBuffers(handle)?;
+                this.write_scalar(result, dest)?;
+            }
+            "FindFirstFileW" => {
+                let [file_name, find_data] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let handle = this.FindFirstFileW(file_name, find_data)?;
+                this.write_scalar(handle, dest)?;
+            }
+            "FindNextFileW" => {
+                let [handle, find_data] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.FindNextFileW(handle, find_data)?;
+                this.write_scalar(result, dest)?;
+            }
+            "FindClose" => {
+                let [handle] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+
+                this.FindClose(handle)?;
+
+                this.write_scalar(Scalar::from_u32(1), dest)?;
+            }
+            "DeleteFileW" => {
+                let [file_name] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.DeleteFileW(file_name)?;
+                this.write_scalar(result, dest)?;
+            }
+            "CreateDirectoryW" => {
+                let [path, security_attributes] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.CreateDirectoryW(path, security_attributes)?;
+                this.write_scalar(result, dest)?;
+            }
+            "RemoveDirectoryW" => {
+                let [path] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.RemoveDirectoryW(path)?;
+                this.write_scalar(result, dest)?;
+            }
+            "MoveFileExW" => {
+                let [existing_file_name, new_file_name, flags] =
+                    this.check_shim(abi, Abi::System { unwind: false }, link_name, args)?;
+                let result = this.MoveFileExW(existing_file_name, new_file_name, flags)?;
+                this.write_scalar(result, dest)?;
             }
             "GetFullPathNameW" => {
                 let [filename, size, buffer, filepart] =
@@ -613,7 +714,7 @@ fn emulate_foreign_item_inner(
                 // We just make this the identity function, so we know later in `NtWriteFile` which
                 // one it is. This is very fake, but libtest needs it so we cannot make it a
                 // std-only shim.
-                // FIXME: this should return real HANDLEs when io support is added
+                // FIXME: this should return real HANDLEs, like the ones of files
                 this.write_scalar(Scalar::from_target_isize(which.into(), this), dest)?;
             }
             "CloseHandle" => {
@@ -0,0 +1,1011 @@
+//! File and file system access, mirroring `shims/unix/fs.rs` for Windows targets.
+
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_target::abi::Size;
+
+use crate::*;
+use shims::time::system_time_to_duration;
+use shims::windows::handle::{EvalContextExt as _, Handle};
+
+/// A file or directory opened with `CreateFileW`.
+#[derive(Debug)]
+struct FileHandle {
+    /// The host file. This is `None` for directories and for symbolic links opened with
+    /// `FILE_FLAG_OPEN_REPARSE_POINT`, as well as for handles without read or write access; such
+    /// handles can only be used to query metadata.
+    file: Option<File>,
+    path: PathBuf,
+    /// Whether the handle refers to a symbolic link itself rather than to its target.
+    link: bool,
+    readable: bool,
+    writable: bool,
+}
+
+impl FileHandle {
+    fn metadata(&self) -> io::Result<Metadata> {
+        match &self.file {
+            _ if self.link => fs::symlink_metadata(&self.path),
+            Some(file) => file.metadata(),
+            None => fs::metadata(&self.path),
+        }
+    }
+}
+
+/// A directory entry returned by `FindFirstFileW` or `FindNextFileW`.
+#[derive(Debug)]
+struct FindEntry {
+    name: OsString,
+    metadata: Metadata,
+    attributes: u32,
+    reparse_tag: u32,
+}
+
+/// The table of file handles and directory searches.
+///
+/// Both kinds of handles are indexed by an ID that is stored in the corresponding [`Handle`]
+/// variant. Closing a file handle drops the host file; closing a search drops its remaining
+/// entries.
+#[derive(Debug, Default)]
+pub struct WindowsFileTable {
+    files: FxHashMap<u32, FileHandle>,
+    /// The entries of each search started by `FindFirstFileW` that were not returned yet.
+    searches: FxHashMap<u32, std::vec::IntoIter<FindEntry>>,
+    /// ID number to be used by the next handle.
+    next_id: u32,
+}
+
+impl WindowsFileTable {
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap();
+        id
+    }
+}
+
+/// Returns the volume serial number, file index and number of links of a file.
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> (u32, u64, u32) {
+    use std::os::unix::fs::MetadataExt;
+    let volume = u32::try_from(metadata.dev() & 0xFFFF_FFFF).unwrap();
+    (volume, metadata.ino(), u32::try_from(metadata.nlink()).unwrap_or(u32::MAX))
+}
+
+/// Returns the volume serial number, file index and number of links of a file.
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> (u32, u64, u32) {
+    // The host does not tell us, so we pretend every file is on its own with a single link.
+    (0, 0, 1)
+}
+
+/// Splits a 64-bit value into the high and low halves used by many Windows structs.
+fn split_u64(value: u64) -> (i128, i128) {
+    ((value >> 32).into(), (value & 0xFFFF_FFFF).into())
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Returns `true` if `op_name` has to be rejected because isolation is enabled, after setting
+    /// the last error to `ERROR_ACCESS_DENIED`.
+    fn reject_fs_op(&mut self, op_name: &str) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            this.reject_in_isolation(op_name, reject_with)?;
+            this.set_last_error_from_io_error(ErrorKind::PermissionDenied.into())?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Turns the result of an operation into the `BOOL` returned by most file system functions,
+    /// setting the last error on failure.
+    fn io_result_to_bool(
+        &mut self,
+        result: io::Result<()>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+        match result {
+            Ok(()) => Ok(Scalar::from_i32(1)),
+            Err(err) => {
+                this.set_last_error_from_io_error(err)?;
+                Ok(Scalar::from_i32(0))
+            }
+        }
+    }
+
+    /// Returns the ID of the open file `handle`, aborting if it is anything else.
+    fn file_handle_id(
+        &mut self,
+        handle: Scalar<Provenance>,
+        function_name: &str,
+    ) -> InterpResult<'tcx, u32> {
+        let this = self.eval_context_mut();
+        match Handle::from_scalar(handle, this)? {
+            Some(Handle::File(id)) if this.machine.windows_files.files.contains_key(&id) => Ok(id),
+            _ => this.invalid_handle(function_name)?,
+        }
+    }
+
+    /// Computes the `FILE_ATTRIBUTE_*` flags and the reparse tag of the file at `path`.
+    fn file_attributes(&self, path: &Path, metadata: &Metadata) -> (u32, u32) {
+        let this = self.eval_context_ref();
+        let mut attributes = 0;
+        let mut reparse_tag = 0;
+        if metadata.is_symlink() {
+            attributes |= this.eval_windows_u32("c", "FILE_ATTRIBUTE_REPARSE_POINT");
+            reparse_tag = this.eval_windows_u32("c", "IO_REPARSE_TAG_SYMLINK");
+            // On Windows, links to directories are directories themselves.
+            if fs::metadata(path).is_ok_and(|target| target.is_dir()) {
+                attributes |= this.eval_windows_u32("c", "FILE_ATTRIBUTE_DIRECTORY");
+            }
+        } else if metadata.is_dir() {
+            attributes |= this.eval_windows_u32("c", "FILE_ATTRIBUTE_DIRECTORY");
+        }
+        if metadata.permissions().readonly() {
+            attributes |= this.eval_windows_u32("c", "FILE_ATTRIBUTE_READONLY");
+        }
+        if attributes == 0 {
+            // This attribute is only valid on its own.
+            attributes = this.eval_windows_u32("c", "FILE_ATTRIBUTE_NORMAL");
+        }
+        (attributes, reparse_tag)
+    }
+
+    /// Writes `time` to the `FILETIME` at `dest`, or zero if the host does not provide it.
+    #[allow(non_snake_case)]
+    fn write_filetime(
+        &mut self,
+        time: io::Result<SystemTime>,
+        dest: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let ticks = match time {
+            Ok(time) => {
+                let NANOS_PER_SEC = this.eval_windows_u64("time", "NANOS_PER_SEC");
+                let INTERVALS_PER_SEC = this.eval_windows_u64("time", "INTERVALS_PER_SEC");
+                let INTERVALS_TO_UNIX_EPOCH =
+                    this.eval_windows_u64("time", "INTERVALS_TO_UNIX_EPOCH");
+                #[allow(clippy::arithmetic_side_effects)] // the divisor is a non-zero constant
+                let NANOS_PER_INTERVAL = NANOS_PER_SEC / INTERVALS_PER_SEC;
+
+                let nanos = system_time_to_duration(&time)?.as_nanos();
+                #[allow(clippy::arithmetic_side_effects)] // the divisor is a non-zero constant
+                let ticks = nanos / u128::from(NANOS_PER_INTERVAL);
+                u64::try_from(ticks)
+                    .ok()
+                    .and_then(|ticks| ticks.checked_add(INTERVALS_TO_UNIX_EPOCH))
+                    .ok_or_else(|| {
+                        err_unsup_format!(
+                            "file times more than 2^64 Windows ticks after the Windows epoch are not supported"
+                        )
+                    })?
+            }
+            Err(_) => 0,
+        };
+        let (high, low) = split_u64(ticks);
+        this.write_int_fields_named(&[("dwLowDateTime", low), ("dwHighDateTime", high)], dest)
+    }
+
+    /// Reads the optional `ByteOffset` argument of `NtReadFile` and `NtWriteFile`.
+    fn read_byte_offset(
+        &mut self,
+        byte_offset_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Option<u64>> {
+        let this = self.eval_context_mut();
+        if this.ptr_is_null(this.read_pointer(byte_offset_op)?)? {
+            return Ok(None);
+        }
+        let byte_offset = this.deref_pointer_as(byte_offset_op, this.machine.layouts.i64)?;
+        let byte_offset = this.read_scalar(&byte_offset)?.to_i64()?;
+        // Negative offsets select special positions like the end of the file.
+        let byte_offset = u64::try_from(byte_offset).map_err(|_| {
+            err_unsup_format!("negative `ByteOffset` values are not supported: {byte_offset}")
+        })?;
+        Ok(Some(byte_offset))
+    }
+
+    /// Reads up to `len` bytes from `handle` into `buf`, starting at `offset` if one is given.
+    /// Returns the number of bytes read.
+    fn read_from_handle(
+        &mut self,
+        handle: Scalar<Provenance>,
+        buf: Pointer<Option<Provenance>>,
+        len: u32,
+        offset: Option<u64>,
+        function_name: &str,
+    ) -> InterpResult<'tcx, io::Result<u32>> {
+        let this = self.eval_context_mut();
+
+        if handle.to_target_isize(this)? == -10 {
+            throw_unsup_format!("on Windows, reading from stdin is not supported");
+        }
+        let id = this.file_handle_id(handle, function_name)?;
+
+        let handle = &this.machine.windows_files.files[&id];
+        let Some(file) = handle.file.as_ref().filter(|_| handle.readable) else {
+            return Ok(Err(ErrorKind::PermissionDenied.into()));
+        };
+        let mut bytes = vec![0; usize::try_from(len).unwrap()];
+        let result = this.machine.decide_read(&mut bytes, |bytes| {
+            let mut file = file;
+            if let Some(offset) = offset {
+                file.seek(SeekFrom::Start(offset))?;
+            }
+            file.read(bytes)
+        });
+        match result {
+            Ok(read) => {
+                this.write_bytes_ptr(buf, bytes[..read].iter().copied())?;
+                // We read at most `len` bytes, which is a `u32`.
+                Ok(Ok(u32::try_from(read).unwrap()))
+            }
+            Err(err) => Ok(Err(err)),
+        }
+    }
+
+    /// Writes up to `len` bytes from `buf` to `handle`, starting at `offset` if one is given.
+    /// Returns the number of bytes written.
+    fn write_to_handle(
+        &mut self,
+        handle: Scalar<Provenance>,
+        buf: Pointer<Option<Provenance>>,
+        len: u32,
+        offset: Option<u64>,
+        function_name: &str,
+    ) -> InterpResult<'tcx, io::Result<u32>> {
+        let this = self.eval_context_mut();
+
+        let bytes = this.read_bytes_ptr_strip_provenance(buf, Size::from_bytes(u64::from(len)))?;
+        // `GetStdHandle` returns its argument, so these are stdout and stderr.
+        let std_handle = handle.to_target_isize(this)?;
+        let result = if std_handle == -11 || std_handle == -12 {
+            if offset.is_some() {
+                throw_unsup_format!(
+                    "`{function_name}` with an offset on stdout/stderr is not supported"
+                );
+            }
+            if this.machine.mute_stdout_stderr {
+                Ok(bytes.len())
+            } else if std_handle == -11 {
+                io::stdout().write(bytes)
+            } else {
+                io::stderr().write(bytes)
+            }
+        } else {
+            let bytes = bytes.to_vec();
+            let id = this.file_handle_id(handle, function_name)?;
+            let handle = &this.machine.windows_files.files[&id];
+            match handle.file.as_ref().filter(|_| handle.writable) {
+                Some(mut file) => {
+                    if let Some(offset) = offset {
+                        file.seek(SeekFrom::Start(offset)).and_then(|_| file.write(&bytes))
+                    } else {
+                        file.write(&bytes)
+                    }
+                }
+                None => Err(ErrorKind::PermissionDenied.into()),
+            }
+        };
+        // We write at most `len` bytes, which is a `u32`.
+        Ok(result.map(|written| u32::try_from(written).unwrap()))
+    }
+
+    /// Stores the number of transferred bytes in `io_status_block` and computes the `NTSTATUS`
+    /// returned by `NtReadFile` and `NtWriteFile`.
+    fn nt_status(
+        &mut self,
+        result: io::Result<u32>,
+        io_status_block: &MPlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+        match result {
+            Ok(n) => {
+                let information = this.project_field_named(io_status_block, "Information")?;
+                this.write_scalar(Scalar::from_target_usize(n.into(), this), &information)?;
+                Ok(Scalar::from_u32(0)) // STATUS_SUCCESS
+            }
+            Err(err) => {
+                // Wrap the Win32 error code into an `NTSTATUS` of the `FACILITY_NTWIN32` facility,
+                // so that `RtlNtStatusToDosError` can recover it.
+                let code = this.io_error_to_errnum(err)?.to_u32()?;
+                Ok(Scalar::from_u32(0xC007_0000 | (code & 0xFFFF)))
+            }
+        }
+    }
+
+    /// Lists the entries matching `pattern`, which is either a path or a directory followed by
+    /// `*`. Like on Windows, listing a directory includes `.` and `..`.
+    fn find_entries(&mut self, pattern: &Path) -> InterpResult<'tcx, io::Result<Vec<FindEntry>>> {
+        let this = self.eval_context_mut();
+
+        let (Some(name), Some(dir)) = (pattern.file_name(), pattern.parent()) else {
+            return Ok(Err(ErrorKind::NotFound.into()));
+        };
+        let names = if name == "*" {
+            let read_dir = match fs::read_dir(dir) {
+                Ok(read_dir) => read_dir,
+                Err(err) => return Ok(Err(err)),
+            };
+            let mut names = vec![OsString::from("."), OsString::from("..")];
+            for entry in read_dir {
+                match entry {
+                    Ok(entry) => names.push(entry.file_name()),
+                    Err(err) => return Ok(Err(err)),
+                }
+            }
+            names
+        } else if name.as_encoded_bytes().iter().any(|&b| b == b'*' || b == b'?') {
+            throw_unsup_format!(
+                "`FindFirstFileW` only supports `*` and plain file names as patterns, not {name:?}"
+            );
+        } else {
+            vec![name.to_owned()]
+        };
+
+        let mut entries = Vec::with_capacity(names.len());
+        for name in names {
+            let path = dir.join(&name);
+            let metadata = match fs::symlink_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(err) => return Ok(Err(err)),
+            };
+            let (attributes, reparse_tag) = this.file_attributes(&path, &metadata);
+            entries.push(FindEntry { name, metadata, attributes, reparse_tag });
+        }
+        Ok(Ok(entries))
+    }
+
+    /// Writes `entry` to the `WIN32_FIND_DATAW` at `find_data_op`.
+    fn write_find_data(
+        &mut self,
+        entry: &FindEntry,
+        find_data_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let find_data =
+            this.deref_pointer_as(find_data_op, this.windows_ty_layout("WIN32_FIND_DATAW"))?;
+        let metadata = &entry.metadata;
+        this.write_filetime(
+            metadata.created(),
+            &this.project_field_named(&find_data, "ftCreationTime")?,
+        )?;
+        this.write_filetime(
+            metadata.accessed(),
+            &this.project_field_named(&find_data, "ftLastAccessTime")?,
+        )?;
+        this.write_filetime(
+            metadata.modified(),
+            &this.project_field_named(&find_data, "ftLastWriteTime")?,
+        )?;
+        let (size_high, size_low) = split_u64(metadata.len());
+        this.write_int_fields_named(
+            &[
+                ("dwFileAttributes", entry.attributes.into()),
+                ("nFileSizeHigh", size_high),
+                ("nFileSizeLow", size_low),
+                ("dwReserved0", entry.reparse_tag.into()),
+                ("dwReserved1", 0),
+            ],
+            &find_data,
+        )?;
+
+        let file_name = this.project_field_named(&find_data, "cFileName")?;
+        let (complete, _) =
+            this.write_os_str_to_wide_str(&entry.name, file_name.ptr(), file_name.len(this)?)?;
+        if !complete {
+            throw_unsup_format!(
+                "`FindFirstFileW`: file names longer than `MAX_PATH` are not supported: {:?}",
+                entry.name
+            );
+        }
+        // We do not make up 8.3 names.
+        let alternate_file_name = this.project_field_named(&find_data, "cAlternateFileName")?;
+        this.write_os_str_to_wide_str(
+            OsStr::new(""),
+            alternate_file_name.ptr(),
+            alternate_file_name.len(this)?,
+        )?;
+        Ok(())
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+#[allow(non_snake_case)]
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn CreateFileW(
+        &mut self,
+        file_name_op: &OpTy<'tcx, Provenance>,
+        desired_access_op: &OpTy<'tcx, Provenance>,
+        share_mode_op: &OpTy<'tcx, Provenance>,
+        security_attributes_op: &OpTy<'tcx, Provenance>,
+        creation_disposition_op: &OpTy<'tcx, Provenance>,
+        flags_and_attributes_op: &OpTy<'tcx, Provenance>,
+        template_file_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let file_name = this.read_path_from_wide_str(this.read_pointer(file_name_op)?)?;
+        let desired_access = this.read_scalar(desired_access_op)?.to_u32()?;
+        // Miri never shares files with other processes.
+        let _share_mode = this.read_scalar(share_mode_op)?.to_u32()?;
+        // Security descriptors are not supported, so we ignore them.
+        let _security_attributes = this.read_pointer(security_attributes_op)?;
+        let creation_disposition = this.read_scalar(creation_disposition_op)?.to_u32()?;
+        let flags_and_attributes = this.read_scalar(flags_and_attributes_op)?.to_u32()?;
+        let template_file = this.read_target_isize(template_file_op)?;
+
+        // INVALID_HANDLE_VALUE
+        let invalid_handle = Scalar::from_target_isize(-1, this);
+
+        if template_file != 0 {
+            throw_unsup_format!("`CreateFileW`: template files are not supported");
+        }
+        let unsupported_flags = this.eval_windows_u32("c", "FILE_FLAG_OVERLAPPED")
+            | this.eval_windows_u32("c", "FILE_FLAG_DELETE_ON_CLOSE");
+        if flags_and_attributes & unsupported_flags != 0 {
+            throw_unsup_format!("`CreateFileW`: unsupported flags {flags_and_attributes:#x}");
+        }
+
+        let create_new = this.eval_windows_u32("c", "CREATE_NEW");
+        let create_always = this.eval_windows_u32("c", "CREATE_ALWAYS");
+        let open_existing = this.eval_windows_u32("c", "OPEN_EXISTING");
+        let open_always = this.eval_windows_u32("c", "OPEN_ALWAYS");
+        let truncate_existing = this.eval_windows_u32("c", "TRUNCATE_EXISTING");
+        if ![create_new, create_always, open_existing, open_always, truncate_existing]
+            .contains(&creation_disposition)
+        {
+            this.set_last_error_from_io_error(ErrorKind::InvalidInput.into())?;
+            return Ok(invalid_handle);
+        }
+        let create = creation_disposition == create_new
+            || creation_disposition == create_always
+            || creation_disposition == open_always;
+        let truncate = creation_disposition == create_always
+            || creation_disposition == truncate_existing;
+
+        if this.reject_fs_op("`CreateFileW`")? {
+            return Ok(invalid_handle);
+        }
+
+        let readable = desired_access
+            & (this.eval_windows_u32("c", "GENERIC_READ")
+                | this.eval_windows_u32("c", "FILE_READ_DATA"))
+            != 0;
+        let write_data = desired_access
+            & (this.eval_windows_u32("c", "GENERIC_WRITE")
+                | this.eval_windows_u32("c", "FILE_WRITE_DATA"))
+            != 0;
+        // This is how `std` requests append mode.
+        let append =
+            !write_data && desired_access & this.eval_windows_u32("c", "FILE_APPEND_DATA") != 0;
+        let writable = write_data || append;
+        let backup_semantics =
+            flags_and_attributes & this.eval_windows_u32("c", "FILE_FLAG_BACKUP_SEMANTICS") != 0;
+        let link = flags_and_attributes & this.eval_windows_u32("c", "FILE_FLAG_OPEN_REPARSE_POINT")
+            != 0
+            && fs::symlink_metadata(&file_name).is_ok_and(|metadata| metadata.is_symlink());
+
+        let existing =
+            if link { fs::symlink_metadata(&file_name) } else { fs::metadata(&file_name) }.ok();
+        let result = match &existing {
+            Some(_) if creation_disposition == create_new => Err(ErrorKind::AlreadyExists.into()),
+            None if !create => Err(ErrorKind::NotFound.into()),
+            // Directories can only be opened with `FILE_FLAG_BACKUP_SEMANTICS`, and only to query
+            // their metadata.
+            Some(metadata) if metadata.is_dir() || link =>
+                if backup_semantics {
+                    Ok(None)
+                } else {
+                    Err(ErrorKind::PermissionDenied.into())
+                },
+            Some(_) if !readable && !writable && !truncate => Ok(None),
+            Some(_) =>
+                OpenOptions::new()
+                    .read(readable)
+                    .write(write_data)
+                    .append(append)
+                    .truncate(truncate)
+                    .open(&file_name)
+                    .map(Some),
+            // Unlike on Windows, the host needs write access to create a file.
+            None =>
+                OpenOptions::new()
+                    .read(readable)
+                    .write(write_data || !append)
+                    .append(append)
+                    .create_new(true)
+                    .open(&file_name)
+                    .map(Some),
+        };
+
+        match result {
+            Ok(file) => {
+                // These dispositions report whether the file already existed.
+                if creation_disposition == create_always || creation_disposition == open_always {
+                    if existing.is_some() {
+                        let already_exists = this.eval_windows("c", "ERROR_ALREADY_EXISTS");
+                        this.set_last_error(already_exists)?;
+                    } else {
+                        this.set_last_error(Scalar::from_u32(0))?;
+                    }
+                }
+                let files = &mut this.machine.windows_files;
+                let id = files.next_id();
+                files
+                    .files
+                    .try_insert(id, FileHandle { file, path: file_name, link, readable, writable })
+                    .unwrap();
+                Ok(Handle::File(id).to_scalar(this))
+            }
+            Err(err) => {
+                this.set_last_error_from_io_error(err)?;
+                Ok(invalid_handle)
+            }
+        }
+    }
+
+    /// Closes the file handle with the given ID, for `CloseHandle`.
+    fn close_file(&mut self, id: u32) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        // Dropping the host file closes it; like `close`, we ignore errors on the way.
+        if this.machine.windows_files.files.remove(&id).is_none() {
+            this.invalid_handle("CloseHandle")?;
+        }
+        Ok(())
+    }
+
+    fn ReadFile(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        buf_op: &OpTy<'tcx, Provenance>,
+        len_op: &OpTy<'tcx, Provenance>,
+        bytes_read_op: &OpTy<'tcx, Provenance>,
+        overlapped_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let buf = this.read_pointer(buf_op)?;
+        let len = this.read_scalar(len_op)?.to_u32()?;
+        if !this.ptr_is_null(this.read_pointer(overlapped_op)?)? {
+            throw_unsup_format!("`ReadFile`: overlapped I/O is not supported");
+        }
+
+        let result = this.read_from_handle(handle, buf, len, None, "ReadFile")?;
+        let bytes_read = *result.as_ref().unwrap_or(&0);
+        if !this.ptr_is_null(this.read_pointer(bytes_read_op)?)? {
+            let bytes_read_place = this.deref_pointer_as(bytes_read_op, this.machine.layouts.u32)?;
+            this.write_scalar(Scalar::from_u32(bytes_read), &bytes_read_place)?;
+        }
+        this.io_result_to_bool(result.map(|_| ()))
+    }
+
+    fn WriteFile(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        buf_op: &OpTy<'tcx, Provenance>,
+        len_op: &OpTy<'tcx, Provenance>,
+        bytes_written_op: &OpTy<'tcx, Provenance>,
+        overlapped_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let buf = this.read_pointer(buf_op)?;
+        let len = this.read_scalar(len_op)?.to_u32()?;
+        if !this.ptr_is_null(this.read_pointer(overlapped_op)?)? {
+            throw_unsup_format!("`WriteFile`: overlapped I/O is not supported");
+        }
+
+        let result = this.write_to_handle(handle, buf, len, None, "WriteFile")?;
+        let bytes_written = *result.as_ref().unwrap_or(&0);
+        if !this.ptr_is_null(this.read_pointer(bytes_written_op)?)? {
+            let bytes_written_place =
+                this.deref_pointer_as(bytes_written_op, this.machine.layouts.u32)?;
+            this.write_scalar(Scalar::from_u32(bytes_written), &bytes_written_place)?;
+        }
+        this.io_result_to_bool(result.map(|_| ()))
+    }
+
+    fn NtReadFile(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        io_status_block_op: &OpTy<'tcx, Provenance>,
+        buf_op: &OpTy<'tcx, Provenance>,
+        len_op: &OpTy<'tcx, Provenance>,
+        byte_offset_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let buf = this.read_pointer(buf_op)?;
+        let len = this.read_scalar(len_op)?.to_u32()?;
+        let byte_offset = this.read_byte_offset(byte_offset_op)?;
+        let io_status_block =
+            this.deref_pointer_as(io_status_block_op, this.windows_ty_layout("IO_STATUS_BLOCK"))?;
+
+        let result = this.read_from_handle(handle, buf, len, byte_offset, "NtReadFile")?;
+        // Unlike `ReadFile`, this reports the end of the file as an error.
+        let end_of_file = matches!(result, Ok(0)) && len > 0;
+        let status = this.nt_status(result, &io_status_block)?;
+        if end_of_file {
+            return Ok(this.eval_windows("c", "STATUS_END_OF_FILE"));
+        }
+        Ok(status)
+    }
+
+    fn NtWriteFile(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        io_status_block_op: &OpTy<'tcx, Provenance>,
+        buf_op: &OpTy<'tcx, Provenance>,
+        len_op: &OpTy<'tcx, Provenance>,
+        byte_offset_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let buf = this.read_pointer(buf_op)?;
+        let len = this.read_scalar(len_op)?.to_u32()?;
+        let byte_offset = this.read_byte_offset(byte_offset_op)?;
+        let io_status_block =
+            this.deref_pointer_as(io_status_block_op, this.windows_ty_layout("IO_STATUS_BLOCK"))?;
+
+        let result = this.write_to_handle(handle, buf, len, byte_offset, "NtWriteFile")?;
+        this.nt_status(result, &io_status_block)
+    }
+
+    fn GetFileInformationByHandle(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        info_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let id = this.file_handle_id(handle, "GetFileInformationByHandle")?;
+        let info =
+            this.deref_pointer_as(info_op, this.windows_ty_layout("BY_HANDLE_FILE_INFORMATION"))?;
+
+        let handle = &this.machine.windows_files.files[&id];
+        let path = handle.path.clone();
+        let metadata = match handle.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => return this.io_result_to_bool(Err(err)),
+        };
+        let (attributes, _) = this.file_attributes(&path, &metadata);
+
+        this.write_filetime(
+            metadata.created(),
+            &this.project_field_named(&info, "ftCreationTime")?,
+        )?;
+        this.write_filetime(
+            metadata.accessed(),
+            &this.project_field_named(&info, "ftLastAccessTime")?,
+        )?;
+        this.write_filetime(
+            metadata.modified(),
+            &this.project_field_named(&info, "ftLastWriteTime")?,
+        )?;
+        let (volume, index, links) = file_id(&metadata);
+        let (size_high, size_low) = split_u64(metadata.len());
+        let (index_high, index_low) = split_u64(index);
+        this.write_int_fields_named(
+            &[
+                ("dwFileAttributes", attributes.into()),
+                ("dwVolumeSerialNumber", volume.into()),
+                ("nFileSizeHigh", size_high),
+                ("nFileSizeLow", size_low),
+                ("nNumberOfLinks", links.into()),
+                ("nFileIndexHigh", index_high),
+                ("nFileIndexLow", index_low),
+            ],
+            &info,
+        )?;
+
+        Ok(Scalar::from_i32(1))
+    }
+
+    fn GetFileInformationByHandleEx(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        class_op: &OpTy<'tcx, Provenance>,
+        info_op: &OpTy<'tcx, Provenance>,
+        size_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let id = this.file_handle_id(handle, "GetFileInformationByHandleEx")?;
+        let class = this.read_scalar(class_op)?.to_i32()?;
+        let _size = this.read_scalar(size_op)?.to_u32()?;
+
+        if class != this.eval_windows("c", "FileAttributeTagInfo").to_i32()? {
+            throw_unsup_format!("`GetFileInformationByHandleEx`: unsupported class {class}");
+        }
+        let info =
+            this.deref_pointer_as(info_op, this.windows_ty_layout("FILE_ATTRIBUTE_TAG_INFO"))?;
+
+        let handle = &this.machine.windows_files.files[&id];
+        let path = handle.path.clone();
+        let metadata = match handle.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => return this.io_result_to_bool(Err(err)),
+        };
+        let (attributes, reparse_tag) = this.file_attributes(&path, &metadata);
+        this.write_int_fields_named(
+            &[("FileAttributes", attributes.into()), ("ReparseTag", reparse_tag.into())],
+            &info,
+        )?;
+
+        Ok(Scalar::from_i32(1))
+    }
+
+    fn SetFileInformationByHandle(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        class_op: &OpTy<'tcx, Provenance>,
+        info_op: &OpTy<'tcx, Provenance>,
+        size_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let id = this.file_handle_id(handle, "SetFileInformationByHandle")?;
+        let class = this.read_scalar(class_op)?.to_i32()?;
+        let _size = this.read_scalar(size_op)?.to_u32()?;
+
+        if class != this.eval_windows("c", "FileEndOfFileInfo").to_i32()? {
+            throw_unsup_format!("`SetFileInformationByHandle`: unsupported class {class}");
+        }
+        let info =
+            this.deref_pointer_as(info_op, this.windows_ty_layout("FILE_END_OF_FILE_INFO"))?;
+        let end_of_file = this.read_scalar(&this.project_field_named(&info, "EndOfFile")?)?;
+        let end_of_file = end_of_file.to_i64()?;
+
+        let handle = &this.machine.windows_files.files[&id];
+        let result = match (u64::try_from(end_of_file), handle.file.as_ref()) {
+            (Err(_), _) => Err(ErrorKind::InvalidInput.into()),
+            (Ok(len), Some(file)) if handle.writable => file.set_len(len),
+            (Ok(_), _) => Err(ErrorKind::PermissionDenied.into()),
+        };
+        this.io_result_to_bool(result)
+    }
+
+    fn SetFilePointerEx(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        distance_op: &OpTy<'tcx, Provenance>,
+        new_pointer_op: &OpTy<'tcx, Provenance>,
+        move_method_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let id = this.file_handle_id(handle, "SetFilePointerEx")?;
+        let distance = this.read_scalar(distance_op)?.to_i64()?;
+        let move_method = this.read_scalar(move_method_op)?.to_u32()?;
+
+        let seek_from = if move_method == this.eval_windows_u32("c", "FILE_BEGIN") {
+            u64::try_from(distance)
+                .map(SeekFrom::Start)
+                .map_err(|_| io::Error::from(ErrorKind::InvalidInput))
+        } else if move_method == this.eval_windows_u32("c", "FILE_CURRENT") {
+            Ok(SeekFrom::Current(distance))
+        } else if move_method == this.eval_windows_u32("c", "FILE_END") {
+            Ok(SeekFrom::End(distance))
+        } else {
+            Err(io::Error::from(ErrorKind::InvalidInput))
+        };
+        let handle = &this.machine.windows_files.files[&id];
+        let result = seek_from.and_then(|seek_from| {
+            match handle.file.as_ref() {
+                Some(mut file) => file.seek(seek_from),
+                None => Err(ErrorKind::PermissionDenied.into()),
+            }
+        });
+
+        match result {
+            Ok(position) => {
+                if !this.ptr_is_null(this.read_pointer(new_pointer_op)?)? {
+                    let new_pointer =
+                        this.deref_pointer_as(new_pointer_op, this.machine.layouts.i64)?;
+                    let position = i64::try_from(position).unwrap();
+                    this.write_scalar(Scalar::from_i64(position), &new_pointer)?;
+                }
+                Ok(Scalar::from_i32(1))
+            }
+            Err(err) => this.io_result_to_bool(Err(err)),
+        }
+    }
+
+    fn FlushFileBuffers(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let id = this.file_handle_id(handle, "FlushFileBuffers")?;
+
+        let handle = &this.machine.windows_files.files[&id];
+        let result = match handle.file.as_ref() {
+            Some(file) if handle.writable => file.sync_all(),
+            _ => Err(ErrorKind::PermissionDenied.into()),
+        };
+        this.io_result_to_bool(result)
+    }
+
+    fn FindFirstFileW(
+        &mut self,
+        file_name_op: &OpTy<'tcx, Provenance>,
+        find_data_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let pattern = this.read_path_from_wide_str(this.read_pointer(file_name_op)?)?;
+        // INVALID_HANDLE_VALUE
+        let invalid_handle = Scalar::from_target_isize(-1, this);
+
+        if this.reject_fs_op("`FindFirstFileW`")? {
+            return Ok(invalid_handle);
+        }
+
+        let mut entries = match this.find_entries(&pattern)? {
+            Ok(entries) => entries.into_iter(),
+            // `std` relies on Windows telling apart a missing file and a missing directory.
+            Err(err)
+                if err.kind() == ErrorKind::NotFound
+                    && !pattern.parent().is_some_and(|dir| dir.is_dir()) =>
+            {
+                let path_not_found = this.eval_windows("c", "ERROR_PATH_NOT_FOUND");
+                this.set_last_error(path_not_found)?;
+                return Ok(invalid_handle);
+            }
+            Err(err) => {
+                this.set_last_error_from_io_error(err)?;
+                return Ok(invalid_handle);
+            }
+        };
+        let Some(first) = entries.next() else {
+            let file_not_found = this.eval_windows("c", "ERROR_FILE_NOT_FOUND");
+            this.set_last_error(file_not_found)?;
+            return Ok(invalid_handle);
+        };
+        this.write_find_data(&first, find_data_op)?;
+
+        let files = &mut this.machine.windows_files;
+        let id = files.next_id();
+        files.searches.try_insert(id, entries).unwrap();
+        Ok(Handle::FindFile(id).to_scalar(this))
+    }
+
+    fn FindNextFileW(
+        &mut self,
+        handle_op: &OpTy<'tcx, Provenance>,
+        find_data_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let Some(Handle::FindFile(id)) = Handle::from_scalar(handle, this)? else {
+            this.invalid_handle("FindNextFileW")?
+        };
+        let Some(entries) = this.machine.windows_files.searches.get_mut(&id) else {
+            this.invalid_handle("FindNextFileW")?
+        };
+
+        match entries.next() {
+            Some(entry) => {
+                this.write_find_data(&entry, find_data_op)?;
+                Ok(Scalar::from_i32(1))
+            }
+            None => {
+                let no_more_files = this.eval_windows("c", "ERROR_NO_MORE_FILES");
+                this.set_last_error(no_more_files)?;
+                Ok(Scalar::from_i32(0))
+            }
+        }
+    }
+
+    fn FindClose(&mut self, handle_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let handle = this.read_scalar(handle_op)?;
+        let Some(Handle::FindFile(id)) = Handle::from_scalar(handle, this)? else {
+            this.invalid_handle("FindClose")?
+        };
+        if this.machine.windows_files.searches.remove(&id).is_none() {
+            this.invalid_handle("FindClose")?;
+        }
+
+        Ok(())
+    }
+
+    fn DeleteFileW(
+        &mut self,
+        file_name_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let file_name = this.read_path_from_wide_str(this.read_pointer(file_name_op)?)?;
+        if this.reject_fs_op("`DeleteFileW`")? {
+            return Ok(Scalar::from_i32(0));
+        }
+
+        let result = fs::remove_file(file_name);
+        this.io_result_to_bool(result)
+    }
+
+    fn CreateDirectoryW(
+        &mut self,
+        path_op: &OpTy<'tcx, Provenance>,
+        security_attributes_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let path = this.read_path_from_wide_str(this.read_pointer(path_op)?)?;
+        // Security descriptors are not supported, so we ignore them.
+        let _security_attributes = this.read_pointer(security_attributes_op)?;
+        if this.reject_fs_op("`CreateDirectoryW`")? {
+            return Ok(Scalar::from_i32(0));
+        }
+
+        let result = fs::create_dir(path);
+        this.io_result_to_bool(result)
+    }
+
+    fn RemoveDirectoryW(
+        &mut self,
+        path_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let path = this.read_path_from_wide_str(this.read_pointer(path_op)?)?;
+        if this.reject_fs_op("`RemoveDirectoryW`")? {
+            return Ok(Scalar::from_i32(0));
+        }
+
+        let result = fs::remove_dir(path);
+        this.io_result_to_bool(result)
+    }
+
+    fn MoveFileExW(
+        &mut self,
+        existing_file_name_op: &OpTy<'tcx, Provenance>,
+        new_file_name_op: &OpTy<'tcx, Provenance>,
+        flags_op: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let existing_file_name =
+            this.read_path_from_wide_str(this.read_pointer(existing_file_name_op)?)?;
+        let new_file_name = this.read_path_from_wide_str(this.read_pointer(new_file_name_op)?)?;
+        let flags = this.read_scalar(flags_op)?.to_u32()?;
+
+        let replace_existing = this.eval_windows_u32("c", "MOVEFILE_REPLACE_EXISTING");
+        if flags & !replace_existing != 0 {
+            throw_unsup_format!("`MoveFileExW`: unsupported flags {flags:#x}");
+        }
+        if this.reject_fs_op("`MoveFileExW`")? {
+            return Ok(Scalar::from_i32(0));
+        }
+
+        // Unlike `rename`, Windows does not replace files unless asked to.
+        let result = if flags & replace_existing == 0
+            && fs::symlink_metadata(&new_file_name).is_ok()
+        {
+            Err(ErrorKind::AlreadyExists.into())
+        } else {
+            fs::rename(existing_file_name, new_file_name)
+        };
+        this.io_result_to_bool(result)
+    }
+}
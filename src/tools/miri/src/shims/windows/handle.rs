@@ -2,6 +2,7 @@
 use std::mem::variant_count;
 
 use crate::*;
+use shims::windows::fs::EvalContextExt as _;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PseudoHandle {
@@ -14,6 +15,10 @@ pub enum Handle {
     Null,
     Pseudo(PseudoHandle),
     Thread(ThreadId),
+    /// A file or directory opened with `CreateFileW`.
+    File(u32),
+    /// A directory search started with `FindFirstFileW`.
+    FindFile(u32),
 }
 
 impl PseudoHandle {
@@ -37,12 +42,16 @@ impl Handle {
     const NULL_DISCRIMINANT: u32 = 0;
     const PSEUDO_DISCRIMINANT: u32 = 1;
     const THREAD_DISCRIMINANT: u32 = 2;
+    const FILE_DISCRIMINANT: u32 = 3;
+    const FIND_FILE_DISCRIMINANT: u32 = 4;
 
     fn discriminant(self) -> u32 {
         match self {
             Self::Null => Self::NULL_DISCRIMINANT,
             Self::Pseudo(_) => Self::PSEUDO_DISCRIMINANT,
             Self::Thread(_) => Self::THREAD_DISCRIMINANT,
+            Self::File(_) => Self::FILE_DISCRIMINANT,
+            Self::FindFile(_) => Self::FIND_FILE_DISCRIMINANT,
         }
     }
 
@@ -51,6 +60,7 @@ fn data(self) -> u32 {
             Self::Null => 0,
             Self::Pseudo(pseudo_handle) => pseudo_handle.value(),
             Self::Thread(thread) => thread.to_u32(),
+            Self::File(id) | Self::FindFile(id) => id,
         }
     }
 
@@ -96,6 +106,8 @@ fn new(discriminant: u32, data: u32) -> Option<Self> {
             Self::NULL_DISCRIMINANT if data == 0 => Some(Self::Null),
             Self::PSEUDO_DISCRIMINANT => Some(Self::Pseudo(PseudoHandle::from_value(data)?)),
             Self::THREAD_DISCRIMINANT => Some(Self::Thread(data.into())),
+            Self::FILE_DISCRIMINANT => Some(Self::File(data)),
+            Self::FIND_FILE_DISCRIMINANT => Some(Self::FindFile(data)),
             _ => None,
         }
     }
@@ -163,6 +175,7 @@ fn CloseHandle(&mut self, handle_op: &OpTy<'tcx, Provenance>) -> InterpResult<'t
         match Handle::from_scalar(handle, this)? {
             Some(Handle::Thread(thread)) =>
                 this.detach_thread(thread, /*allow_terminated_joined*/ true)?,
+            Some(Handle::File(id)) => this.close_file(id)?,
             _ => this.invalid_handle("CloseHandle")?,
         }
 
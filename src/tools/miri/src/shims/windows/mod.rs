@@ -1,13 +1,16 @@
 pub mod foreign_items;
 
 mod env;
+mod fs;
 mod handle;
 mod sync;
 mod thread;
 
 pub use env::WindowsEnvVars;
+pub use fs::WindowsFileTable;
 // All the Windows-specific extension traits
 pub use env::EvalContextExt as _;
+pub use fs::EvalContextExt as _;
 pub use handle::EvalContextExt as _;
 pub use sync::EvalContextExt as _;
 pub use thread::EvalContextExt as _;
@@ -0,0 +1,1945 @@
+//! An interpreter for x86_64 inline assembly.
+//!
+//! The template of an `asm!` block is rendered into Intel-syntax text, parsed into a list of
+//! instructions, and executed against a virtual register file. Memory operands go through
+//! Miri's memory, so every access is checked like any other: registers holding pointers carry
+//! their provenance, plain loads and stores are non-atomic accesses, and `lock`-prefixed
+//! instructions (as well as `xchg` with memory) are sequentially consistent atomic
+//! read-modify-write operations.
+//!
+//! On top of that, the contract of `asm!` itself is checked: registers that are not declared as
+//! outputs must hold their original value when the block is done, `options(preserves_flags)`
+//! forbids changing the flags, `options(nomem)` forbids accessing memory, `options(readonly)`
+//! forbids writing it, and `options(noreturn)` blocks must not fall through. Registers that are
+//! not inputs start out uninitialized: reading them is UB, and an output the block never wrote
+//! is uninitialized as well.
+//!
+//! Only a small subset of the instruction set is supported: the instructions that commonly show
+//! up in inline assembly for arithmetic, data movement, atomics, `cpuid` and timestamps.
+
+use rand::Rng as _;
+
+use rustc_ast::{InlineAsmOptions, InlineAsmTemplatePiece};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::mir;
+use rustc_middle::ty::layout::TyAndLayout;
+use rustc_span::Symbol;
+use rustc_target::abi::Size;
+use rustc_target::asm::InlineAsmRegOrRegClass;
+
+use crate::*;
+
+/// The number of instructions a single `asm!` block may execute before we give up on it.
+/// Miri cannot switch threads in the middle of an `asm!` block, so a spin loop waiting for
+/// another thread would otherwise never terminate.
+const STEP_LIMIT: u64 = 1_000_000;
+
+/// The prefix of the labels that `label` operands are rendered as.
+const BLOCK_LABEL_PREFIX: &str = "__miri_asm_label_";
+
+/// The names of the general-purpose registers, indexed by size (1, 2, 4, 8 bytes) and register
+/// number.
+const REG_NAMES: [[&str; 16]; 4] = [
+    [
+        "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil", "r8b", "r9b", "r10b", "r11b", "r12b",
+        "r13b", "r14b", "r15b",
+    ],
+    [
+        "ax", "cx", "dx", "bx", "sp", "bp", "si", "di", "r8w", "r9w", "r10w", "r11w", "r12w",
+        "r13w", "r14w", "r15w",
+    ],
+    [
+        "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi", "r8d", "r9d", "r10d", "r11d",
+        "r12d", "r13d", "r14d", "r15d",
+    ],
+    [
+        "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12",
+        "r13", "r14", "r15",
+    ],
+];
+
+/// The names of the high byte registers.
+const HIGH_BYTE_NAMES: [&str; 4] = ["ah", "ch", "dh", "bh"];
+
+const RAX: usize = 0;
+const RCX: usize = 1;
+const RDX: usize = 2;
+const RBX: usize = 3;
+const RSP: usize = 4;
+const RBP: usize = 5;
+const RSI: usize = 6;
+const RDI: usize = 7;
+
+/// The registers that the `reg` and `reg_byte` classes are allocated from, in order.
+const ALLOCATABLE: [usize; 13] = [RAX, RCX, RDX, RSI, RDI, 8, 9, 10, 11, 12, 13, 14, 15];
+/// The registers that the `reg_abcd` class is allocated from. `rbx` is reserved by LLVM.
+const ALLOCATABLE_ABCD: [usize; 3] = [RAX, RCX, RDX];
+
+/// Instructions that accept a `lock` prefix.
+const LOCKABLE: &[&str] = &[
+    "add", "adc", "sub", "sbb", "and", "or", "xor", "inc", "dec", "neg", "not", "xadd", "cmpxchg",
+    "xchg", "bts", "btr", "btc",
+];
+
+fn mask(size: u8) -> u64 {
+    match size {
+        1 => 0xff,
+        2 => 0xffff,
+        4 => 0xffff_ffff,
+        _ => u64::MAX,
+    }
+}
+
+fn sign_bit(size: u8) -> u64 {
+    match size {
+        1 => 0x80,
+        2 => 0x8000,
+        4 => 0x8000_0000,
+        _ => 0x8000_0000_0000_0000,
+    }
+}
+
+fn bits(size: u8) -> u32 {
+    match size {
+        1 => 8,
+        2 => 16,
+        4 => 32,
+        _ => 64,
+    }
+}
+
+/// Sign-extends the low `size` bytes of `value` to 64 bits.
+fn sign_extend(value: u64, size: u8) -> u64 {
+    let value = value & mask(size);
+    if value & sign_bit(size) != 0 { value | !mask(size) } else { value }
+}
+
+fn to_signed(value: u64, size: u8) -> i64 {
+    i64::from_ne_bytes(sign_extend(value, size).to_ne_bytes())
+}
+
+fn as_unsigned(value: i128) -> u128 {
+    u128::from_ne_bytes(value.to_ne_bytes())
+}
+
+fn low_half(value: u128) -> u64 {
+    u64::try_from(value & u128::from(u64::MAX)).unwrap()
+}
+
+/// A general-purpose register, accessed with a particular size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Reg {
+    /// The register number, see `REG_NAMES`.
+    index: usize,
+    /// The size of the access in bytes.
+    size: u8,
+    /// Whether this is one of `ah`, `ch`, `dh` and `bh`.
+    high: bool,
+}
+
+impl Reg {
+    fn full(index: usize) -> Self {
+        Reg { index, size: 8, high: false }
+    }
+
+    fn with_size(self, size: u8) -> Self {
+        Reg { index: self.index, size, high: self.high && size == 1 }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        if let Some(index) = HIGH_BYTE_NAMES.iter().position(|&n| n == name) {
+            return Some(Reg { index, size: 1, high: true });
+        }
+        REG_NAMES.iter().zip([1, 2, 4, 8]).find_map(|(names, size)| {
+            let index = names.iter().position(|&n| n == name)?;
+            Some(Reg { index, size, high: false })
+        })
+    }
+
+    fn name(self) -> &'static str {
+        if self.high {
+            return HIGH_BYTE_NAMES[self.index];
+        }
+        let row = match self.size {
+            1 => 0,
+            2 => 1,
+            4 => 2,
+            _ => 3,
+        };
+        REG_NAMES[row][self.index]
+    }
+}
+
+/// A memory operand `[base + index*scale + disp]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Mem {
+    /// The size given by a `byte ptr`, `word ptr`, ... annotation.
+    size: Option<u8>,
+    base: Option<Reg>,
+    index: Option<(Reg, u8)>,
+    /// The displacement, as a two's complement bit pattern.
+    disp: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Operand {
+    Reg(Reg),
+    /// An immediate, as a two's complement bit pattern.
+    Imm(u64),
+    Mem(Mem),
+    Label(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Prefix {
+    None,
+    Lock,
+    Rep,
+}
+
+#[derive(Clone, Debug)]
+struct Instr {
+    prefix: Prefix,
+    mnemonic: String,
+    operands: Vec<Operand>,
+    /// The source text, for error messages.
+    text: String,
+}
+
+/// Where a jump goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Jump {
+    /// To an instruction of the same `asm!` block.
+    Instr(usize),
+    /// To one of the MIR targets of the `asm!` block, i.e. a `label` operand.
+    Block(usize),
+}
+
+#[derive(Debug, Default)]
+struct Program {
+    instrs: Vec<Instr>,
+    /// Named labels and the index of the instruction they precede.
+    labels: FxHashMap<String, usize>,
+    /// Numeric local labels (`1:`), referred to as `1b` and `1f`.
+    local_labels: Vec<(u64, usize)>,
+}
+
+impl Program {
+    /// Resolves a label used by the instruction at `pc`.
+    fn resolve(&self, label: &str, pc: usize) -> Option<Jump> {
+        if let Some(index) = label.strip_prefix(BLOCK_LABEL_PREFIX) {
+            return index.parse().ok().map(Jump::Block);
+        }
+        if let Some(num) = label.strip_suffix('b').and_then(|n| n.parse::<u64>().ok()) {
+            return self
+                .local_labels
+                .iter()
+                .rev()
+                .find(|&&(n, pos)| n == num && pos <= pc)
+                .map(|&(_, pos)| Jump::Instr(pos));
+        }
+        if let Some(num) = label.strip_suffix('f').and_then(|n| n.parse::<u64>().ok()) {
+            return self
+                .local_labels
+                .iter()
+                .find(|&&(n, pos)| n == num && pos > pc)
+                .map(|&(_, pos)| Jump::Instr(pos));
+        }
+        self.labels.get(label).map(|&pos| Jump::Instr(pos))
+    }
+}
+
+fn is_label_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$'))
+}
+
+/// Parses an integer literal in any of the forms the assembler accepts.
+fn parse_int(s: &str) -> Option<u64> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits.trim_start()),
+        None => (false, s),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok()?
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        u64::from_str_radix(bin, 2).ok()?
+    } else if let Some(hex) = digits.strip_suffix('h')
+        && digits.starts_with(|c: char| c.is_ascii_digit())
+    {
+        u64::from_str_radix(hex, 16).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if negative { value.wrapping_neg() } else { value })
+}
+
+fn parse_mem(s: &str) -> Result<Mem, String> {
+    let (open, close) = (s.find('[').unwrap(), s.rfind(']'));
+    let Some(close) = close.filter(|&close| close > open && s[close..].trim() == "]") else {
+        return Err(format!("malformed memory operand `{s}`"));
+    };
+    let annotation = s[..open].trim();
+    let size = match annotation.strip_suffix("ptr").map(str::trim) {
+        _ if annotation.is_empty() => None,
+        Some("byte") => Some(1),
+        Some("word") => Some(2),
+        Some("dword") => Some(4),
+        Some("qword") => Some(8),
+        _ => return Err(format!("unsupported memory operand `{s}`")),
+    };
+    let mut mem = Mem { size, base: None, index: None, disp: 0 };
+    let body = s[open..close].trim_start_matches('[').replace('-', "+-");
+    for term in body.split('+') {
+        let term: String = term.chars().filter(|c| !c.is_whitespace()).collect();
+        if term.is_empty() {
+            continue;
+        }
+        let address_reg = |name: &str| {
+            Reg::parse(name)
+                .filter(|reg| reg.size == 8 && !reg.high)
+                .ok_or_else(|| format!("unsupported address `{s}`"))
+        };
+        if let Some((a, b)) = term.split_once('*') {
+            let (reg, scale) = if Reg::parse(a).is_some() { (a, b) } else { (b, a) };
+            let scale = match parse_int(scale) {
+                Some(scale @ (1 | 2 | 4 | 8)) => u8::try_from(scale).unwrap(),
+                _ => return Err(format!("invalid scale in memory operand `{s}`")),
+            };
+            if mem.index.is_some() {
+                return Err(format!("invalid memory operand `{s}`"));
+            }
+            mem.index = Some((address_reg(reg)?, scale));
+        } else if let Some(disp) = parse_int(&term) {
+            mem.disp = mem.disp.wrapping_add(disp);
+        } else if mem.base.is_none() {
+            mem.base = Some(address_reg(&term)?);
+        } else if mem.index.is_none() {
+            mem.index = Some((address_reg(&term)?, 1));
+        } else {
+            return Err(format!("invalid memory operand `{s}`"));
+        }
+    }
+    Ok(mem)
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let s = s.trim();
+    if let Some(reg) = Reg::parse(s) {
+        if reg.index == RSP {
+            return Err(format!("accessing the stack pointer `{s}` is not supported"));
+        }
+        return Ok(Operand::Reg(reg));
+    }
+    if let Some(imm) = parse_int(s) {
+        return Ok(Operand::Imm(imm));
+    }
+    if s.contains('[') {
+        return parse_mem(s).map(Operand::Mem);
+    }
+    if is_label_name(s) {
+        return Ok(Operand::Label(s.to_owned()));
+    }
+    Err(format!("unsupported operand `{s}`"))
+}
+
+fn parse_instr(stmt: &str) -> Result<Instr, String> {
+    fn split_word(s: &str) -> (&str, &str) {
+        let s = s.trim();
+        s.split_once(char::is_whitespace).unwrap_or((s, ""))
+    }
+
+    let (mut mnemonic, mut rest) = split_word(stmt);
+    let prefix = match mnemonic {
+        "lock" => Prefix::Lock,
+        "rep" => Prefix::Rep,
+        _ => Prefix::None,
+    };
+    if prefix != Prefix::None {
+        (mnemonic, rest) = split_word(rest);
+    }
+    if mnemonic.starts_with('.') {
+        return Err(format!("assembler directive `{stmt}` is not supported"));
+    }
+    let operands = if rest.trim().is_empty() {
+        Vec::new()
+    } else {
+        rest.split(',').map(parse_operand).collect::<Result<_, _>>()?
+    };
+    Ok(Instr { prefix, mnemonic: mnemonic.to_owned(), operands, text: stmt.to_owned() })
+}
+
+/// Parses the rendered template of an `asm!` block.
+fn parse(text: &str) -> Result<Program, String> {
+    let mut program = Program::default();
+    for line in text.to_ascii_lowercase().lines() {
+        let line = line.split_once("//").map_or(line, |(code, _)| code);
+        let line = line.split_once('#').map_or(line, |(code, _)| code);
+        for stmt in line.split(';') {
+            let mut stmt = stmt.trim();
+            while let Some((label, rest)) = stmt.split_once(':') {
+                let label = label.trim();
+                if !is_label_name(label) {
+                    break;
+                }
+                let pos = program.instrs.len();
+                if let Ok(num) = label.parse() {
+                    program.local_labels.push((num, pos));
+                } else if program.labels.insert(label.to_owned(), pos).is_some() {
+                    return Err(format!("label `{label}` is defined more than once"));
+                }
+                stmt = rest.trim();
+            }
+            if !stmt.is_empty() {
+                program.instrs.push(parse_instr(stmt)?);
+            }
+        }
+    }
+    Ok(program)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Flags {
+    cf: bool,
+    pf: bool,
+    zf: bool,
+    sf: bool,
+    of: bool,
+}
+
+impl Flags {
+    /// Updates the flags that only depend on the result of an operation.
+    fn with_result(self, result: u64, size: u8) -> Self {
+        Flags {
+            zf: result & mask(size) == 0,
+            sf: result & sign_bit(size) != 0,
+            pf: (result & 0xff).count_ones() & 1 == 0,
+            ..self
+        }
+    }
+}
+
+/// Evaluates a condition code, as used by `jcc`, `setcc` and `cmovcc`.
+fn condition(cc: &str, flags: Flags) -> Option<bool> {
+    let Flags { cf, pf, zf, sf, of } = flags;
+    Some(match cc {
+        "o" => of,
+        "no" => !of,
+        "b" | "c" | "nae" => cf,
+        "ae" | "nb" | "nc" => !cf,
+        "e" | "z" => zf,
+        "ne" | "nz" => !zf,
+        "be" | "na" => cf || zf,
+        "a" | "nbe" => !cf && !zf,
+        "s" => sf,
+        "ns" => !sf,
+        "p" | "pe" => pf,
+        "np" | "po" => !pf,
+        "l" | "nge" => sf != of,
+        "ge" | "nl" => sf == of,
+        "le" | "ng" => zf || sf != of,
+        "g" | "nle" => !zf && sf == of,
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AluOp {
+    Add,
+    Adc,
+    Sub,
+    Sbb,
+    And,
+    Or,
+    Xor,
+}
+
+impl AluOp {
+    fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        Some(match mnemonic {
+            "add" => AluOp::Add,
+            "adc" => AluOp::Adc,
+            "sub" | "cmp" => AluOp::Sub,
+            "sbb" => AluOp::Sbb,
+            "and" | "test" => AluOp::And,
+            "or" => AluOp::Or,
+            "xor" => AluOp::Xor,
+            _ => return None,
+        })
+    }
+}
+
+/// Computes a two-operand arithmetic or logic operation on `size`-byte values.
+fn alu(op: AluOp, a: u64, b: u64, size: u8, flags: Flags) -> (u64, Flags) {
+    let (a, b) = (a & mask(size), b & mask(size));
+    let sign = sign_bit(size);
+    let (result, cf, of) = match op {
+        AluOp::Add | AluOp::Adc => {
+            let carry = u64::from(op == AluOp::Adc && flags.cf);
+            let (sum, c1) = a.overflowing_add(b);
+            let (sum, c2) = sum.overflowing_add(carry);
+            let cf = if size == 8 { c1 || c2 } else { sum > mask(size) };
+            let result = sum & mask(size);
+            (result, cf, (a ^ result) & (b ^ result) & sign != 0)
+        }
+        AluOp::Sub | AluOp::Sbb => {
+            let borrow = op == AluOp::Sbb && flags.cf;
+            let result = a.wrapping_sub(b).wrapping_sub(u64::from(borrow)) & mask(size);
+            let cf = a < b || (borrow && a == b);
+            (result, cf, (a ^ b) & (a ^ result) & sign != 0)
+        }
+        AluOp::And => (a & b, false, false),
+        AluOp::Or => (a | b, false, false),
+        AluOp::Xor => (a ^ b, false, false),
+    };
+    (result, Flags { cf, of, ..flags }.with_result(result, size))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ShiftOp {
+    Shl,
+    Shr,
+    Sar,
+    Rol,
+    Ror,
+}
+
+/// Computes a shift or rotation of a `size`-byte value.
+fn shift(op: ShiftOp, a: u64, count: u64, size: u8, flags: Flags) -> (u64, Flags) {
+    let a = a & mask(size);
+    let width = bits(size);
+    let count = u32::try_from(count & if size == 8 { 63 } else { 31 }).unwrap();
+    if count == 0 {
+        return (a, flags);
+    }
+    let msb = |value: u64| value & sign_bit(size) != 0;
+    let bit = |value: u64, n: u32| value.checked_shr(n).unwrap_or(0) & 1 != 0;
+    match op {
+        ShiftOp::Shl => {
+            let result = a.checked_shl(count).unwrap_or(0) & mask(size);
+            let cf = count <= width && bit(a, width.wrapping_sub(count));
+            (result, Flags { cf, of: msb(result) != cf, ..flags }.with_result(result, size))
+        }
+        ShiftOp::Shr => {
+            let result = a.checked_shr(count).unwrap_or(0);
+            let cf = bit(a, count.wrapping_sub(1));
+            (result, Flags { cf, of: msb(a), ..flags }.with_result(result, size))
+        }
+        ShiftOp::Sar => {
+            let signed = sign_extend(a, size);
+            let result = i64::from_ne_bytes(signed.to_ne_bytes()).wrapping_shr(count.min(63));
+            let result = u64::from_ne_bytes(result.to_ne_bytes()) & mask(size);
+            let cf = bit(signed, count.wrapping_sub(1).min(63));
+            (result, Flags { cf, of: false, ..flags }.with_result(result, size))
+        }
+        ShiftOp::Rol | ShiftOp::Ror => {
+            let count = count & width.strict_sub(1);
+            let (left, right) = match op {
+                ShiftOp::Rol => (count, width.wrapping_sub(count)),
+                _ => (width.wrapping_sub(count), count),
+            };
+            let result = (a.checked_shl(left).unwrap_or(0) | a.checked_shr(right).unwrap_or(0))
+                & mask(size);
+            let cf = if op == ShiftOp::Rol { result & 1 != 0 } else { msb(result) };
+            let of = if op == ShiftOp::Rol {
+                msb(result) != cf
+            } else {
+                msb(result) != bit(result, width.wrapping_sub(2))
+            };
+            (result, Flags { cf, of, ..flags })
+        }
+    }
+}
+
+/// The contents of a general-purpose register.
+#[derive(Clone, Copy, Default)]
+struct Value {
+    bits: u64,
+    /// Only registers written as a whole can hold a pointer.
+    prov: Option<Provenance>,
+}
+
+impl Value {
+    fn int(bits: u64) -> Self {
+        Value { bits, prov: None }
+    }
+}
+
+/// The provenance of the result of `a op b`, which is the provenance of the only operand that
+/// has one.
+fn arith_provenance(op: AluOp, a: Value, b: Value) -> Option<Provenance> {
+    match (a.prov, b.prov) {
+        (Some(prov), None) => Some(prov),
+        (None, Some(prov)) if !matches!(op, AluOp::Sub | AluOp::Sbb) => Some(prov),
+        _ => None,
+    }
+}
+
+/// The state of the virtual CPU executing an `asm!` block.
+struct Cpu {
+    regs: [Value; 16],
+    /// Which bytes of each register are initialized, as a bit mask. Registers which are not
+    /// inputs of the block start out uninitialized, and reading them is UB.
+    init: [u8; 16],
+    flags: Flags,
+    flags_written: bool,
+    /// The values pushed with `push`, with their initialized bytes. The stack does not live in
+    /// Miri's memory, so `rsp` cannot be accessed directly.
+    stack: Vec<(Value, u8)>,
+    options: InlineAsmOptions,
+}
+
+/// The bytes of the full register that `reg` consists of, as a bit mask.
+fn reg_bytes(reg: Reg) -> u8 {
+    match (reg.size, reg.high) {
+        (_, true) => 0b10,
+        (1, false) => 0b1,
+        (2, false) => 0b11,
+        (4, false) => 0b1111,
+        _ => u8::MAX,
+    }
+}
+
+impl Cpu {
+    fn new(options: InlineAsmOptions) -> Self {
+        let mut init = [0; 16];
+        // The reserved registers hold the values of the surrounding code, which the block may
+        // save and restore.
+        init[RBX] = u8::MAX;
+        init[RSP] = u8::MAX;
+        init[RBP] = u8::MAX;
+        Cpu {
+            regs: [Value::default(); 16],
+            init,
+            flags: Flags::default(),
+            flags_written: false,
+            stack: Vec::new(),
+            options,
+        }
+    }
+
+    fn is_init(&self, reg: Reg) -> bool {
+        let bytes = reg_bytes(reg);
+        self.init[reg.index] & bytes == bytes
+    }
+
+    fn get<'tcx>(&self, reg: Reg) -> InterpResult<'tcx, Value> {
+        if !self.is_init(reg) {
+            throw_ub_format!(
+                "inline assembly read register `{}`, which is uninitialized",
+                reg.name()
+            );
+        }
+        let full = self.regs[reg.index];
+        Ok(match (reg.size, reg.high) {
+            (8, _) => full,
+            (_, true) => Value::int(full.bits.wrapping_shr(8) & 0xff),
+            (size, false) => Value::int(full.bits & mask(size)),
+        })
+    }
+
+    fn set(&mut self, reg: Reg, value: Value) {
+        let full = &mut self.regs[reg.index];
+        *full = match (reg.size, reg.high) {
+            (8, _) => value,
+            // 32-bit writes zero the upper half of the register.
+            (4, _) => Value::int(value.bits & mask(4)),
+            (_, true) => Value::int((full.bits & !0xff00) | (value.bits & 0xff).wrapping_shl(8)),
+            (size, false) => Value::int((full.bits & !mask(size)) | (value.bits & mask(size))),
+        };
+        self.init[reg.index] =
+            if reg.size >= 4 { u8::MAX } else { self.init[reg.index] | reg_bytes(reg) };
+    }
+
+    /// Writes `edx:eax`, as `rdtsc` and `xgetbv` do.
+    fn set_edx_eax(&mut self, value: u64) {
+        self.set(Reg::full(RAX).with_size(4), Value::int(value & mask(4)));
+        self.set(Reg::full(RDX).with_size(4), Value::int(value.wrapping_shr(32)));
+    }
+
+    fn set_flags(&mut self, flags: Flags) {
+        self.flags = flags;
+        self.flags_written = true;
+    }
+}
+
+/// What an operand of the `asm!` block is rendered as in the template.
+enum AsmOperand {
+    Reg(Reg),
+    Const(String),
+    Label(usize),
+    /// Operands that cannot be used in the template: explicit registers and clobbers.
+    None,
+}
+
+fn operands<'a, 'tcx, const N: usize>(instr: &'a Instr) -> InterpResult<'tcx, &'a [Operand; N]> {
+    <&[Operand; N]>::try_from(instr.operands.as_slice()).map_err(|_| {
+        err_unsup_format!("invalid number of operands in inline assembly: `{}`", instr.text)
+            .into()
+    })
+}
+
+/// The size of the operation performed by an instruction, as given by its operands.
+fn operand_size<'tcx>(instr: &Instr) -> InterpResult<'tcx, u8> {
+    let size = instr.operands.iter().find_map(|op| {
+        match op {
+            Operand::Reg(reg) => Some(reg.size),
+            Operand::Mem(mem) => mem.size,
+            _ => None,
+        }
+    });
+    let Some(size) = size else {
+        throw_unsup_format!(
+            "the operand size of `{}` is ambiguous, use `byte ptr`, `word ptr`, ... to specify it",
+            instr.text
+        );
+    };
+    Ok(size)
+}
+
+/// The size of a source operand whose size differs from the destination.
+fn source_size<'tcx>(instr: &Instr, op: &Operand, default: Option<u8>) -> InterpResult<'tcx, u8> {
+    match op {
+        Operand::Reg(reg) => Ok(reg.size),
+        Operand::Mem(mem) =>
+            match mem.size.or(default) {
+                Some(size) => Ok(size),
+                None =>
+                    throw_unsup_format!(
+                        "the source size of `{}` is ambiguous, use `byte ptr` or `word ptr`",
+                        instr.text
+                    ),
+            },
+        _ => throw_unsup_format!("invalid operand in inline assembly: `{}`", instr.text),
+    }
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Assigns registers to the operands of the `asm!` block and loads the inputs into them.
+    /// Returns how each operand is rendered, the outputs to write back, and which registers
+    /// may be modified.
+    #[allow(clippy::type_complexity)]
+    fn asm_operands(
+        &mut self,
+        operands: &[mir::InlineAsmOperand<'tcx>],
+        cpu: &mut Cpu,
+    ) -> InterpResult<'tcx, (Vec<AsmOperand>, Vec<(Reg, PlaceTy<'tcx, Provenance>)>, [bool; 16])>
+    {
+        let this = self.eval_context_mut();
+
+        // `rbx`, `rsp` and `rbp` are reserved and never allocated.
+        let mut used = [false; 16];
+        used[RBX] = true;
+        used[RSP] = true;
+        used[RBP] = true;
+        for operand in operands {
+            if let mir::InlineAsmOperand::In { reg: InlineAsmRegOrRegClass::Reg(reg), .. }
+            | mir::InlineAsmOperand::Out { reg: InlineAsmRegOrRegClass::Reg(reg), .. }
+            | mir::InlineAsmOperand::InOut { reg: InlineAsmRegOrRegClass::Reg(reg), .. } =
+                operand
+                && let Some(reg) = Reg::parse(reg.name())
+            {
+                used[reg.index] = true;
+            }
+        }
+
+        let mut rendered = Vec::with_capacity(operands.len());
+        let mut outputs = Vec::new();
+        let mut clobbered = [false; 16];
+        for operand in operands {
+            let (reg, input, output) = match operand {
+                mir::InlineAsmOperand::In { reg, value } => (reg, Some(value), None),
+                mir::InlineAsmOperand::Out { reg, late: _, place } => (reg, None, Some(place)),
+                mir::InlineAsmOperand::InOut { reg, late: _, in_value, out_place } =>
+                    (reg, Some(in_value), Some(out_place)),
+                mir::InlineAsmOperand::Const { value } => {
+                    let value = this.eval_mir_constant(&value.const_, value.span, None)?;
+                    let value = this.read_immediate(&value)?;
+                    let size = value.layout.size;
+                    let text = if value.layout.ty.is_signed() {
+                        value.to_scalar().to_int(size)?.to_string()
+                    } else {
+                        value.to_scalar().to_uint(size)?.to_string()
+                    };
+                    rendered.push(AsmOperand::Const(text));
+                    continue;
+                }
+                mir::InlineAsmOperand::Label { target_index } => {
+                    rendered.push(AsmOperand::Label(*target_index));
+                    continue;
+                }
+                mir::InlineAsmOperand::SymFn { .. } | mir::InlineAsmOperand::SymStatic { .. } =>
+                    throw_unsup_format!("`sym` operands in inline assembly are not supported"),
+            };
+            // Clobbers of registers we do not model need no further treatment.
+            let is_clobber = input.is_none() && matches!(output, Some(None));
+
+            let (reg, template) = match reg {
+                InlineAsmRegOrRegClass::Reg(reg) =>
+                    match Reg::parse(reg.name()) {
+                        Some(reg) => (reg, None),
+                        None if is_clobber => {
+                            rendered.push(AsmOperand::None);
+                            continue;
+                        }
+                        None =>
+                            throw_unsup_format!(
+                                "register `{}` is not supported in inline assembly",
+                                reg.name()
+                            ),
+                    },
+                InlineAsmRegOrRegClass::RegClass(class) => {
+                    let (candidates, size) = match class.name().as_str() {
+                        "reg" => (&ALLOCATABLE[..], 8),
+                        "reg_abcd" => (&ALLOCATABLE_ABCD[..], 8),
+                        "reg_byte" => (&ALLOCATABLE[..], 1),
+                        _ if is_clobber => {
+                            rendered.push(AsmOperand::None);
+                            continue;
+                        }
+                        name =>
+                            throw_unsup_format!(
+                                "register class `{name}` is not supported in inline assembly"
+                            ),
+                    };
+                    let Some(&index) = candidates.iter().find(|&&index| !used[index]) else {
+                        throw_unsup_format!("inline assembly uses too many registers");
+                    };
+                    used[index] = true;
+                    let reg = Reg { index, size, high: false };
+                    (reg, Some(reg))
+                }
+            };
+            rendered.push(template.map_or(AsmOperand::None, AsmOperand::Reg));
+
+            if let Some(value) = input {
+                let value = this.eval_operand(value, None)?;
+                if !value.layout.abi.is_scalar() || value.layout.size.bytes() > 8 {
+                    throw_unsup_format!(
+                        "inline assembly operand of type `{}` is not supported",
+                        value.layout.ty
+                    );
+                }
+                let value = this.read_scalar(&value)?;
+                let value = this.asm_scalar_to_value(value)?;
+                // Inputs are zero-extended to the full register.
+                cpu.set(if reg.size == 1 { reg } else { Reg::full(reg.index) }, value);
+            }
+            if let Some(place) = output {
+                clobbered[reg.index] = true;
+                if let Some(place) = place {
+                    let place = this.eval_place(*place)?;
+                    let size = place.layout.size.bytes();
+                    if !place.layout.abi.is_scalar() || size > 8 {
+                        throw_unsup_format!(
+                            "inline assembly operand of type `{}` is not supported",
+                            place.layout.ty
+                        );
+                    }
+                    outputs.push((reg.with_size(u8::try_from(size).unwrap()), place));
+                }
+            }
+        }
+        Ok((rendered, outputs, clobbered))
+    }
+
+    fn asm_scalar_to_value(&self, scalar: Scalar<Provenance>) -> InterpResult<'tcx, Value> {
+        let this = self.eval_context_ref();
+        if scalar.size() == this.tcx.data_layout.pointer_size {
+            let ptr = scalar.to_pointer(this)?;
+            Ok(Value { bits: ptr.addr().bytes(), prov: ptr.provenance })
+        } else {
+            let size = scalar.size();
+            Ok(Value::int(u64::try_from(scalar.to_bits(size)?).unwrap()))
+        }
+    }
+
+    fn asm_value_to_scalar(&self, value: Value, size: u8) -> Scalar<Provenance> {
+        let this = self.eval_context_ref();
+        if size == 8 {
+            Scalar::from_maybe_pointer(Pointer::new(value.prov, Size::from_bytes(value.bits)), this)
+        } else {
+            Scalar::from_uint(value.bits & mask(size), Size::from_bytes(size))
+        }
+    }
+
+    /// The layout used for memory accesses of the given size. 8-byte accesses use a pointer type
+    /// so that provenance is preserved.
+    fn asm_layout(&self, size: u8) -> TyAndLayout<'tcx> {
+        let this = self.eval_context_ref();
+        match size {
+            1 => this.machine.layouts.u8,
+            2 => this.machine.layouts.u16,
+            4 => this.machine.layouts.u32,
+            _ => this.machine.layouts.mut_raw_ptr,
+        }
+    }
+
+    fn asm_uint_layout(&self, size: u8) -> TyAndLayout<'tcx> {
+        let this = self.eval_context_ref();
+        if size == 8 { this.machine.layouts.u64 } else { this.asm_layout(size) }
+    }
+
+    fn asm_address(
+        &self,
+        cpu: &Cpu,
+        mem: &Mem,
+    ) -> InterpResult<'tcx, Pointer<Option<Provenance>>> {
+        let mut addr = mem.disp;
+        let mut prov = None;
+        if let Some(base) = mem.base {
+            let base = cpu.get(base)?;
+            addr = addr.wrapping_add(base.bits);
+            prov = base.prov;
+        }
+        if let Some((index, scale)) = mem.index {
+            let index = cpu.get(index)?;
+            addr = addr.wrapping_add(index.bits.wrapping_mul(u64::from(scale)));
+            if prov.is_none() && scale == 1 {
+                prov = index.prov;
+            }
+        }
+        Ok(Pointer::new(prov, Size::from_bytes(addr)))
+    }
+
+    fn asm_check_memory_access(&self, cpu: &Cpu, write: bool) -> InterpResult<'tcx> {
+        if cpu.options.contains(InlineAsmOptions::NOMEM) {
+            throw_ub_format!("inline assembly with `options(nomem)` accessed memory");
+        }
+        if write && cpu.options.contains(InlineAsmOptions::READONLY) {
+            throw_ub_format!("inline assembly with `options(readonly)` wrote to memory");
+        }
+        Ok(())
+    }
+
+    /// Loads a value from memory. x86 allows unaligned accesses, so the data goes through an
+    /// aligned temporary.
+    fn asm_load(
+        &mut self,
+        cpu: &Cpu,
+        ptr: Pointer<Option<Provenance>>,
+        size: u8,
+    ) -> InterpResult<'tcx, Value> {
+        let this = self.eval_context_mut();
+        this.asm_check_memory_access(cpu, false)?;
+        let tmp = this.allocate(this.asm_layout(size), MiriMemoryKind::Machine.into())?;
+        this.mem_copy(ptr, tmp.ptr(), Size::from_bytes(size), /*nonoverlapping*/ true)?;
+        let scalar = this.read_scalar(&tmp)?;
+        this.deallocate_ptr(tmp.ptr(), None, MiriMemoryKind::Machine.into())?;
+        this.asm_scalar_to_value(scalar)
+    }
+
+    fn asm_store(
+        &mut self,
+        cpu: &Cpu,
+        ptr: Pointer<Option<Provenance>>,
+        size: u8,
+        value: Value,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.asm_check_memory_access(cpu, true)?;
+        let tmp = this.allocate(this.asm_layout(size), MiriMemoryKind::Machine.into())?;
+        this.write_scalar(this.asm_value_to_scalar(value, size), &tmp)?;
+        this.mem_copy(tmp.ptr(), ptr, Size::from_bytes(size), /*nonoverlapping*/ true)?;
+        this.deallocate_ptr(tmp.ptr(), None, MiriMemoryKind::Machine.into())
+    }
+
+    fn asm_read(&mut self, cpu: &Cpu, op: &Operand, size: u8) -> InterpResult<'tcx, Value> {
+        let this = self.eval_context_mut();
+        match op {
+            Operand::Reg(reg) => cpu.get(reg.with_size(size)),
+            Operand::Imm(imm) => Ok(Value::int(imm & mask(size))),
+            Operand::Mem(mem) => {
+                let ptr = this.asm_address(cpu, mem)?;
+                this.asm_load(cpu, ptr, size)
+            }
+            Operand::Label(label) =>
+                throw_unsup_format!("label `{label}` cannot be used as a value in inline assembly"),
+        }
+    }
+
+    fn asm_write(
+        &mut self,
+        cpu: &mut Cpu,
+        op: &Operand,
+        size: u8,
+        value: Value,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        match op {
+            Operand::Reg(reg) => cpu.set(reg.with_size(size), value),
+            Operand::Mem(mem) => {
+                let ptr = this.asm_address(cpu, mem)?;
+                this.asm_store(cpu, ptr, size, value)?;
+            }
+            Operand::Imm(_) | Operand::Label(_) =>
+                throw_unsup_format!("invalid destination operand in inline assembly"),
+        }
+        Ok(())
+    }
+
+    /// The place for an atomic access to a memory operand.
+    fn asm_atomic_place(
+        &self,
+        cpu: &Cpu,
+        op: &Operand,
+        layout: TyAndLayout<'tcx>,
+    ) -> InterpResult<'tcx, MPlaceTy<'tcx, Provenance>> {
+        let this = self.eval_context_ref();
+        let Operand::Mem(mem) = op else {
+            throw_unsup_format!("atomic operations in inline assembly need a memory operand");
+        };
+        this.asm_check_memory_access(cpu, true)?;
+        Ok(this.ptr_to_mplace(this.asm_address(cpu, mem)?, layout))
+    }
+
+    /// Performs a `lock`-prefixed read-modify-write and returns the old value.
+    fn asm_atomic_rmw(
+        &mut self,
+        cpu: &Cpu,
+        op: &Operand,
+        size: u8,
+        bin_op: mir::BinOp,
+        rhs: u64,
+    ) -> InterpResult<'tcx, u64> {
+        let this = self.eval_context_mut();
+        let layout = this.asm_uint_layout(size);
+        let place = this.asm_atomic_place(cpu, op, layout)?;
+        let rhs = ImmTy::from_uint(rhs & mask(size), layout);
+        let old = this.atomic_rmw_op_immediate(&place, &rhs, bin_op, false, AtomicRwOrd::SeqCst)?;
+        Ok(u64::try_from(old.to_scalar().to_bits(Size::from_bytes(size))?).unwrap())
+    }
+
+    fn asm_cpuid(&self, leaf: u32, subleaf: u32) -> [u32; 4] {
+        let this = self.eval_context_ref();
+        let feature = |name: &str, bit: u32| {
+            if this.tcx.sess.unstable_target_features.contains(&Symbol::intern(name)) {
+                bit
+            } else {
+                0
+            }
+        };
+        match leaf {
+            // The highest basic leaf and the vendor string "MiriMiriMiri".
+            0 => {
+                let vendor = u32::from_le_bytes(*b"Miri");
+                [7, vendor, vendor, vendor]
+            }
+            1 => {
+                let ecx = feature("sse3", 0x1)
+                    | feature("pclmulqdq", 0x2)
+                    | feature("ssse3", 0x200)
+                    | feature("fma", 0x1000)
+                    | feature("cmpxchg16b", 0x2000)
+                    | feature("sse4.1", 0x8_0000)
+                    | feature("sse4.2", 0x10_0000)
+                    | feature("movbe", 0x40_0000)
+                    | feature("popcnt", 0x80_0000)
+                    | feature("aes", 0x200_0000)
+                    | feature("xsave", 0x400_0000)
+                    // OSXSAVE, which tells the program that `xgetbv` can be used.
+                    | feature("avx", 0x800_0000)
+                    | feature("avx", 0x1000_0000)
+                    | feature("f16c", 0x2000_0000)
+                    | feature("rdrand", 0x4000_0000);
+                // TSC, CX8, CMOV, MMX and FXSR are always available on x86_64.
+                let edx = 0x10 | 0x100 | 0x8000 | 0x80_0000 | 0x100_0000
+                    | feature("sse", 0x200_0000)
+                    | feature("sse2", 0x400_0000);
+                [0, 0, ecx, edx]
+            }
+            7 if subleaf == 0 => {
+                let ebx = feature("bmi1", 0x8)
+                    | feature("avx2", 0x20)
+                    | feature("bmi2", 0x100)
+                    | feature("avx512f", 0x1_0000)
+                    | feature("rdseed", 0x4_0000)
+                    | feature("adx", 0x8_0000)
+                    | feature("sha", 0x2000_0000);
+                [0, ebx, 0, 0]
+            }
+            // The highest extended leaf.
+            0x8000_0000 => [0x8000_0001, 0, 0, 0],
+            0x8000_0001 => {
+                let ecx = feature("lzcnt", 0x20) | feature("sse4a", 0x40);
+                // Long mode.
+                let edx = 0x2000_0000;
+                [0, 0, ecx, edx]
+            }
+            _ => [0; 4],
+        }
+    }
+
+    /// Executes a single instruction and returns where to jump to, if anywhere.
+    fn asm_exec(
+        &mut self,
+        cpu: &mut Cpu,
+        program: &Program,
+        pc: usize,
+    ) -> InterpResult<'tcx, Option<Jump>> {
+        let this = self.eval_context_mut();
+        let instr = &program.instrs[pc];
+        let mnemonic = instr.mnemonic.as_str();
+        let lock = instr.prefix == Prefix::Lock;
+        if lock
+            && (!LOCKABLE.contains(&mnemonic)
+                || !matches!(instr.operands.first(), Some(Operand::Mem(_))))
+        {
+            throw_unsup_format!("invalid `lock` prefix in inline assembly: `{}`", instr.text);
+        }
+        if instr.prefix == Prefix::Rep
+            && !matches!(mnemonic, "movsb" | "movsw" | "movsd" | "movsq")
+            && !matches!(mnemonic, "stosb" | "stosw" | "stosd" | "stosq")
+        {
+            throw_unsup_format!("invalid `rep` prefix in inline assembly: `{}`", instr.text);
+        }
+
+        match mnemonic {
+            // A spin loop hint; there is nothing for it to do here since we cannot switch
+            // threads in the middle of an `asm!` block.
+            "nop" | "pause" => {}
+            "mfence" => this.atomic_fence(AtomicFenceOrd::SeqCst)?,
+            "lfence" => this.atomic_fence(AtomicFenceOrd::Acquire)?,
+            "sfence" => this.atomic_fence(AtomicFenceOrd::Release)?,
+
+            "mov" | "movabs" => {
+                let [dst, src] = operands(instr)?;
+                let size = operand_size(instr)?;
+                let value = this.asm_read(cpu, src, size)?;
+                this.asm_write(cpu, dst, size, value)?;
+            }
+            "movzx" | "movsx" | "movsxd" => {
+                let [Operand::Reg(dst), src] = operands(instr)? else {
+                    throw_unsup_format!("invalid operands in inline assembly: `{}`", instr.text);
+                };
+                let src_size = source_size(instr, src, (mnemonic == "movsxd").then_some(4))?;
+                let value = this.asm_read(cpu, src, src_size)?.bits;
+                let value = if mnemonic == "movzx" { value } else { sign_extend(value, src_size) };
+                cpu.set(*dst, Value::int(value & mask(dst.size)));
+            }
+            "lea" => {
+                let [Operand::Reg(dst), Operand::Mem(mem)] = operands(instr)? else {
+                    throw_unsup_format!("invalid operands in inline assembly: `{}`", instr.text);
+                };
+                let ptr = this.asm_address(cpu, mem)?;
+                cpu.set(*dst, Value { bits: ptr.addr().bytes(), prov: ptr.provenance });
+            }
+            "xchg" => {
+                let [a, b] = operands(instr)?;
+                let size = operand_size(instr)?;
+                let (mem, reg) = match (a, b) {
+                    (Operand::Mem(_), Operand::Reg(reg)) => (a, *reg),
+                    (Operand::Reg(reg), Operand::Mem(_)) => (b, *reg),
+                    (Operand::Reg(a), Operand::Reg(b)) => {
+                        let (va, vb) = (cpu.get(*a)?, cpu.get(*b)?);
+                        cpu.set(*a, vb);
+                        cpu.set(*b, va);
+                        return Ok(None);
+                    }
+                    _ =>
+                        throw_unsup_format!(
+                            "invalid operands in inline assembly: `{}`",
+                            instr.text
+                        ),
+                };
+                // `xchg` with memory is always atomic.
+                let place = this.asm_atomic_place(cpu, mem, this.asm_layout(size))?;
+                let new = this.asm_value_to_scalar(cpu.get(reg)?, size);
+                let old = this.atomic_exchange_scalar(&place, new, AtomicRwOrd::SeqCst)?;
+                let old = this.asm_scalar_to_value(old)?;
+                cpu.set(reg, old);
+            }
+
+            "add" | "adc" | "sub" | "sbb" | "and" | "or" | "xor" | "cmp" | "test" => {
+                let [dst, src] = operands(instr)?;
+                let size = operand_size(instr)?;
+                let op = AluOp::from_mnemonic(mnemonic).unwrap();
+                // `xor reg, reg` and `sub reg, reg` zero the register without depending on its
+                // value, so they are fine on uninitialized registers.
+                let zeroing = matches!(op, AluOp::Xor | AluOp::Sub)
+                    && matches!((dst, src), (Operand::Reg(a), Operand::Reg(b)) if a == b);
+                let rhs = if zeroing { Value::int(0) } else { this.asm_read(cpu, src, size)? };
+                if lock {
+                    let bin_op = match op {
+                        AluOp::Add => mir::BinOp::Add,
+                        AluOp::Sub => mir::BinOp::Sub,
+                        AluOp::And => mir::BinOp::BitAnd,
+                        AluOp::Or => mir::BinOp::BitOr,
+                        AluOp::Xor => mir::BinOp::BitXor,
+                        AluOp::Adc | AluOp::Sbb =>
+                            throw_unsup_format!("`lock {mnemonic}` is not supported"),
+                    };
+                    let old = this.asm_atomic_rmw(cpu, dst, size, bin_op, rhs.bits)?;
+                    let (_, flags) = alu(op, old, rhs.bits, size, cpu.flags);
+                    cpu.set_flags(flags);
+                } else {
+                    let lhs = if zeroing { rhs } else { this.asm_read(cpu, dst, size)? };
+                    let (bits, flags) = alu(op, lhs.bits, rhs.bits, size, cpu.flags);
+                    cpu.set_flags(flags);
+                    if !matches!(mnemonic, "cmp" | "test") {
+                        let prov = if size == 8 { arith_provenance(op, lhs, rhs) } else { None };
+                        this.asm_write(cpu, dst, size, Value { bits, prov })?;
+                    }
+                }
+            }
+            "inc" | "dec" => {
+                let [dst] = operands(instr)?;
+                let size = operand_size(instr)?;
+                let op = if mnemonic == "inc" { AluOp::Add } else { AluOp::Sub };
+                let old = if lock {
+                    let bin_op = if mnemonic == "inc" { mir::BinOp::Add } else { mir::BinOp::Sub };
+                    Value::int(this.asm_atomic_rmw(cpu, dst, size, bin_op, 1)?)
+                } else {
+                    this.asm_read(cpu, dst, size)?
+                };
+                let (bits, flags) = alu(op, old.bits, 1, size, cpu.flags);
+                // `inc` and `dec` leave the carry flag alone.
+                cpu.set_flags(Flags { cf: cpu.flags.cf, ..flags });
+                if !lock {
+                    this.asm_write(cpu, dst, size, Value { bits, prov: old.prov })?;
+                }
+            }
+            "neg" | "not" => {
+                let [dst] = operands(instr)?;
+                if lock {
+                    throw_unsup_format!("`lock {mnemonic}` is not supported");
+                }
+                let size = operand_size(instr)?;
+                let value = this.asm_read(cpu, dst, size)?.bits;
+                let bits = if mnemonic == "neg" {
+                    let (bits, flags) = alu(AluOp::Sub, 0, value, size, cpu.flags);
+                    cpu.set_flags(flags);
+                    bits
+                } else {
+                    !value & mask(size)
+                };
+                this.asm_write(cpu, dst, size, Value::int(bits))?;
+            }
+            "shl" | "sal" | "shr" | "sar" | "rol" | "ror" => {
+                let [dst, count] = operands(instr)?;
+                let size = match dst {
+                    Operand::Reg(reg) => reg.size,
+                    Operand::Mem(Mem { size: Some(size), .. }) => *size,
+                    _ => operand_size(instr)?,
+                };
+                let count = match count {
+                    Operand::Imm(imm) => *imm,
+                    Operand::Reg(reg) if reg.index == RCX && reg.size == 1 && !reg.high =>
+                        cpu.get(*reg)?.bits,
+                    _ =>
+                        throw_unsup_format!(
+                            "invalid shift count in inline assembly: `{}`",
+                            instr.text
+                        ),
+                };
+                let op = match mnemonic {
+                    "shl" | "sal" => ShiftOp::Shl,
+                    "shr" => ShiftOp::Shr,
+                    "sar" => ShiftOp::Sar,
+                    "rol" => ShiftOp::Rol,
+                    _ => ShiftOp::Ror,
+                };
+                let value = this.asm_read(cpu, dst, size)?.bits;
+                let (bits, flags) = shift(op, value, count, size, cpu.flags);
+                if flags != cpu.flags {
+                    cpu.set_flags(flags);
+                }
+                this.asm_write(cpu, dst, size, Value::int(bits))?;
+            }
+
+            "mul" | "imul" if instr.operands.len() == 1 => {
+                let [src] = operands(instr)?;
+                let size = operand_size(instr)?;
+                let a = cpu.get(Reg::full(RAX).with_size(size))?.bits;
+                let b = this.asm_read(cpu, src, size)?.bits;
+                let (product, fits) = if mnemonic == "mul" {
+                    let product = u128::from(a).wrapping_mul(u128::from(b));
+                    (product, product <= u128::from(mask(size)))
+                } else {
+                    let product =
+                        i128::from(to_signed(a, size)).wrapping_mul(i128::from(to_signed(b, size)));
+                    let low = low_half(as_unsigned(product));
+                    let fits = product == i128::from(to_signed(low, size));
+                    (as_unsigned(product), fits)
+                };
+                let high = low_half(product.wrapping_shr(bits(size)));
+                if size == 1 {
+                    cpu.set(Reg::full(RAX).with_size(2), Value::int(low_half(product)));
+                } else {
+                    cpu.set(Reg::full(RAX).with_size(size), Value::int(low_half(product)));
+                    cpu.set(Reg::full(RDX).with_size(size), Value::int(high));
+                }
+                let flags = cpu.flags.with_result(low_half(product), size);
+                cpu.set_flags(Flags { cf: !fits, of: !fits, ..flags });
+            }
+            "imul" => {
+                let (dst, a, b) = match instr.operands.as_slice() {
+                    [dst @ Operand::Reg(_), src] => (dst, dst, src),
+                    [dst @ Operand::Reg(_), src, imm @ Operand::Imm(_)] => (dst, src, imm),
+                    _ =>
+                        throw_unsup_format!(
+                            "invalid operands in inline assembly: `{}`",
+                            instr.text
+                        ),
+                };
+                let size = operand_size(instr)?;
+                let a = to_signed(this.asm_read(cpu, a, size)?.bits, size);
+                let b = to_signed(this.asm_read(cpu, b, size)?.bits, size);
+                let product = i128::from(a).wrapping_mul(i128::from(b));
+                let low = low_half(as_unsigned(product)) & mask(size);
+                let fits = product == i128::from(to_signed(low, size));
+                let flags = cpu.flags.with_result(low, size);
+                cpu.set_flags(Flags { cf: !fits, of: !fits, ..flags });
+                this.asm_write(cpu, dst, size, Value::int(low))?;
+            }
+            "div" | "idiv" => {
+                let [src] = operands(instr)?;
+                let size = operand_size(instr)?;
+                let (low, high) = if size == 1 {
+                    let ax = cpu.get(Reg::full(RAX).with_size(2))?.bits;
+                    (ax & 0xff, ax.wrapping_shr(8))
+                } else {
+                    (
+                        cpu.get(Reg::full(RAX).with_size(size))?.bits,
+                        cpu.get(Reg::full(RDX).with_size(size))?.bits,
+                    )
+                };
+                let dividend = u128::from(high).wrapping_shl(bits(size)) | u128::from(low);
+                let divisor = this.asm_read(cpu, src, size)?.bits;
+                let result = if mnemonic == "div" {
+                    let divisor = u128::from(divisor);
+                    dividend.checked_div(divisor).and_then(|quotient| {
+                        let remainder = dividend.checked_rem(divisor)?;
+                        (quotient <= u128::from(mask(size)))
+                            .then(|| (low_half(quotient), low_half(remainder)))
+                    })
+                } else {
+                    let dividend = if size == 8 {
+                        i128::from_ne_bytes(dividend.to_ne_bytes())
+                    } else {
+                        i128::from(to_signed(low_half(dividend), size.wrapping_mul(2)))
+                    };
+                    let divisor = i128::from(to_signed(divisor, size));
+                    dividend.checked_div(divisor).and_then(|quotient| {
+                        let remainder = dividend.checked_rem(divisor)?;
+                        let truncate = |value: i128| low_half(as_unsigned(value)) & mask(size);
+                        (quotient == i128::from(to_signed(truncate(quotient), size)))
+                            .then(|| (truncate(quotient), truncate(remainder)))
+                    })
+                };
+                let Some((quotient, remainder)) = result else {
+                    throw_machine_stop!(TerminationInfo::Abort(format!(
+                        "inline assembly raised a divide error: `{}`",
+                        instr.text
+                    )));
+                };
+                if size == 1 {
+                    let ax = quotient | remainder.wrapping_shl(8);
+                    cpu.set(Reg::full(RAX).with_size(2), Value::int(ax));
+                } else {
+                    cpu.set(Reg::full(RAX).with_size(size), Value::int(quotient));
+                    cpu.set(Reg::full(RDX).with_size(size), Value::int(remainder));
+                }
+            }
+            "cbw" | "cwde" | "cdqe" => {
+                let size = match mnemonic {
+                    "cbw" => 1,
+                    "cwde" => 2,
+                    _ => 4,
+                };
+                let value = sign_extend(cpu.get(Reg::full(RAX).with_size(size))?.bits, size);
+                let dst_size = size.wrapping_mul(2);
+                cpu.set(Reg::full(RAX).with_size(dst_size), Value::int(value & mask(dst_size)));
+            }
+            "cwd" | "cdq" | "cqo" => {
+                let size = match mnemonic {
+                    "cwd" => 2,
+                    "cdq" => 4,
+                    _ => 8,
+                };
+                let rax = cpu.get(Reg::full(RAX).with_size(size))?;
+                let negative = rax.bits & sign_bit(size) != 0;
+                let value = if negative { mask(size) } else { 0 };
+                cpu.set(Reg::full(RDX).with_size(size), Value::int(value));
+            }
+
+            "bswap" => {
+                let [Operand::Reg(reg)] = operands(instr)? else {
+                    throw_unsup_format!("invalid operands in inline assembly: `{}`", instr.text);
+                };
+                let value = cpu.get(*reg)?.bits;
+                let value = match reg.size {
+                    4 => u64::from(u32::try_from(value).unwrap().swap_bytes()),
+                    8 => value.swap_bytes(),
+                    _ =>
+                        throw_unsup_format!(
+                            "invalid operands in inline assembly: `{}`",
+                            instr.text
+                        ),
+                };
+                cpu.set(*reg, Value::int(value));
+            }
+            "bsf" | "bsr" | "tzcnt" | "lzcnt" | "popcnt" => {
+                let [Operand::Reg(dst), src] = operands(instr)? else {
+                    throw_unsup_format!("invalid operands in inline assembly: `{}`", instr.text);
+                };
+                let size = dst.size;
+                let value = this.asm_read(cpu, src, size)?.bits & mask(size);
+                let width = u64::from(bits(size));
+                let leading_zeros =
+                    u64::from(value.leading_zeros()).strict_sub(64u64.strict_sub(width));
+                let trailing_zeros = u64::from(value.trailing_zeros()).min(width);
+                match mnemonic {
+                    "bsf" | "bsr" => {
+                        // The destination is left alone for a zero source.
+                        if value != 0 {
+                            let index = if mnemonic == "bsf" {
+                                trailing_zeros
+                            } else {
+                                width.wrapping_sub(1).wrapping_sub(leading_zeros)
+                            };
+                            cpu.set(*dst, Value::int(index));
+                        }
+                        cpu.set_flags(Flags { zf: value == 0, ..cpu.flags });
+                    }
+                    "tzcnt" | "lzcnt" => {
+                        let count =
+                            if mnemonic == "tzcnt" { trailing_zeros } else { leading_zeros };
+                        cpu.set(*dst, Value::int(count));
+                        cpu.set_flags(Flags { cf: value == 0, zf: count == 0, ..cpu.flags });
+                    }
+                    _ => {
+                        cpu.set(*dst, Value::int(u64::from(value.count_ones())));
+                        cpu.set_flags(Flags {
+                            cf: false,
+                            pf: false,
+                            zf: value == 0,
+                            sf: false,
+                            of: false,
+                        });
+                    }
+                }
+            }
+            "bt" | "bts" | "btr" | "btc" => {
+                let [dst, offset] = operands(instr)?;
+                let size = operand_size(instr)?;
+                let offset = match offset {
+                    Operand::Imm(imm) => *imm,
+                    Operand::Reg(reg) => cpu.get(*reg)?.bits,
+                    _ =>
+                        throw_unsup_format!(
+                            "invalid operands in inline assembly: `{}`",
+                            instr.text
+                        ),
+                };
+                if offset >= u64::from(bits(size)) {
+                    throw_unsup_format!(
+                        "bit offsets outside of the operand are not supported in inline assembly: `{}`",
+                        instr.text
+                    );
+                }
+                let bit = 1u64.wrapping_shl(u32::try_from(offset).unwrap());
+                let old = if lock {
+                    let (bin_op, rhs) = match mnemonic {
+                        "bts" => (mir::BinOp::BitOr, bit),
+                        "btr" => (mir::BinOp::BitAnd, !bit),
+                        _ => (mir::BinOp::BitXor, bit),
+                    };
+                    this.asm_atomic_rmw(cpu, dst, size, bin_op, rhs)?
+                } else {
+                    let old = this.asm_read(cpu, dst, size)?.bits;
+                    let new = match mnemonic {
+                        "bt" => None,
+                        "bts" => Some(old | bit),
+                        "btr" => Some(old & !bit),
+                        _ => Some(old ^ bit),
+                    };
+                    if let Some(new) = new {
+                        this.asm_write(cpu, dst, size, Value::int(new))?;
+                    }
+                    old
+                };
+                cpu.set_flags(Flags { cf: old & bit != 0, ..cpu.flags });
+            }
+            "xadd" => {
+                let [dst, Operand::Reg(src)] = operands(instr)? else {
+                    throw_unsup_format!("invalid operands in inline assembly: `{}`", instr.text);
+                };
+                let size = src.size;
+                let rhs = cpu.get(*src)?.bits;
+                let old = if lock {
+                    this.asm_atomic_rmw(cpu, dst, size, mir::BinOp::Add, rhs)?
+                } else {
+                    this.asm_read(cpu, dst, size)?.bits
+                };
+                let (sum, flags) = alu(AluOp::Add, old, rhs, size, cpu.flags);
+                cpu.set_flags(flags);
+                cpu.set(*src, Value::int(old));
+                if !lock {
+                    this.asm_write(cpu, dst, size, Value::int(sum))?;
+                }
+            }
+            "cmpxchg" => {
+                let [dst, Operand::Reg(src)] = operands(instr)? else {
+                    throw_unsup_format!("invalid operands in inline assembly: `{}`", instr.text);
+                };
+                let size = src.size;
+                let acc = Reg::full(RAX).with_size(size);
+                let expected = cpu.get(acc)?;
+                let new = cpu.get(*src)?;
+                let old = if lock {
+                    let layout = this.asm_uint_layout(size);
+                    let place = this.asm_atomic_place(cpu, dst, layout)?;
+                    let expected = ImmTy::from_uint(expected.bits, layout);
+                    let new = Scalar::from_uint(new.bits & mask(size), Size::from_bytes(size));
+                    let (old, _) = this
+                        .atomic_compare_exchange_scalar(
+                            &place,
+                            &expected,
+                            new,
+                            AtomicRwOrd::SeqCst,
+                            AtomicReadOrd::SeqCst,
+                            /* can_fail_spuriously */ false,
+                        )?
+                        .to_scalar_pair();
+                    this.asm_scalar_to_value(old)?
+                } else {
+                    let old = this.asm_read(cpu, dst, size)?;
+                    if old.bits == expected.bits {
+                        this.asm_write(cpu, dst, size, new)?;
+                    }
+                    old
+                };
+                let (_, flags) = alu(AluOp::Sub, expected.bits, old.bits, size, cpu.flags);
+                cpu.set_flags(flags);
+                if !flags.zf {
+                    cpu.set(acc, old);
+                }
+            }
+
+            "push" => {
+                let [src] = operands(instr)?;
+                if cpu.options.contains(InlineAsmOptions::NOSTACK) {
+                    throw_ub_format!("inline assembly with `options(nostack)` pushed to the stack");
+                }
+                let value = match src {
+                    Operand::Imm(imm) => (Value::int(*imm), u8::MAX),
+                    // Saving a register is fine even if it is uninitialized.
+                    Operand::Reg(reg) => (cpu.regs[reg.index], cpu.init[reg.index]),
+                    _ => (this.asm_read(cpu, src, 8)?, u8::MAX),
+                };
+                cpu.stack.push(value);
+            }
+            "pop" => {
+                let [dst] = operands(instr)?;
+                let Some((value, init)) = cpu.stack.pop() else {
+                    throw_unsup_format!(
+                        "inline assembly popped a value that it did not push: `{}`",
+                        instr.text
+                    );
+                };
+                match dst {
+                    Operand::Reg(reg) => {
+                        cpu.regs[reg.index] = value;
+                        cpu.init[reg.index] = init;
+                    }
+                    _ if init != u8::MAX =>
+                        throw_ub_format!(
+                            "inline assembly popped an uninitialized register to memory"
+                        ),
+                    _ => this.asm_write(cpu, dst, 8, value)?,
+                }
+            }
+
+            "clc" => cpu.set_flags(Flags { cf: false, ..cpu.flags }),
+            "stc" => cpu.set_flags(Flags { cf: true, ..cpu.flags }),
+            "cmc" => cpu.set_flags(Flags { cf: !cpu.flags.cf, ..cpu.flags }),
+
+            "rdtsc" | "rdtscp" => {
+                // Use the nanoseconds since startup as timestamp counter. This is a virtual
+                // clock when isolation is enabled.
+                let duration = this.machine.clock.now().duration_since(this.machine.clock.anchor());
+                let ticks = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+                cpu.set_edx_eax(ticks);
+                if mnemonic == "rdtscp" {
+                    // The processor ID.
+                    cpu.set(Reg::full(RCX).with_size(4), Value::int(0));
+                }
+            }
+            "cpuid" => {
+                let leaf = u32::try_from(cpu.get(Reg::full(RAX).with_size(4))?.bits).unwrap();
+                // Only some leaves have subleaves, the others ignore `ecx`.
+                let subleaf = if leaf == 7 {
+                    u32::try_from(cpu.get(Reg::full(RCX).with_size(4))?.bits).unwrap()
+                } else {
+                    0
+                };
+                let [eax, ebx, ecx, edx] = this.asm_cpuid(leaf, subleaf);
+                for (index, value) in [(RAX, eax), (RBX, ebx), (RCX, ecx), (RDX, edx)] {
+                    cpu.set(Reg::full(index).with_size(4), Value::int(u64::from(value)));
+                }
+            }
+            "xgetbv" => {
+                if cpu.get(Reg::full(RCX).with_size(4))?.bits != 0 {
+                    throw_unsup_format!("`xgetbv` is only supported for `XCR0`");
+                }
+                // x87 and SSE state are always enabled, AVX state if AVX is available.
+                let avx = this.tcx.sess.unstable_target_features.contains(&Symbol::intern("avx"));
+                cpu.set_edx_eax(if avx { 0b111 } else { 0b011 });
+            }
+            "rdrand" | "rdseed" => {
+                let [Operand::Reg(dst)] = operands(instr)? else {
+                    throw_unsup_format!("invalid operands in inline assembly: `{}`", instr.text);
+                };
+                let value = this.machine.rng.get_mut().gen::<u64>();
+                cpu.set(*dst, Value::int(value & mask(dst.size)));
+                // Indicate success.
+                cpu.set_flags(Flags { cf: true, ..Flags::default() });
+            }
+
+            "movsb" | "movsw" | "movsd" | "movsq" | "stosb" | "stosw" | "stosd" | "stosq"
+                if instr.operands.is_empty() =>
+            {
+                let size = match mnemonic.as_bytes()[4] {
+                    b'b' => 1,
+                    b'w' => 2,
+                    b'd' => 4,
+                    _ => 8,
+                };
+                let mut count = if instr.prefix == Prefix::Rep {
+                    cpu.get(Reg::full(RCX))?.bits
+                } else {
+                    1
+                };
+                // Elements are copied one by one since this is what happens for overlapping
+                // ranges. The direction flag is always clear.
+                while count > 0 {
+                    let dst =
+                        Mem { size: Some(size), base: Some(Reg::full(RDI)), index: None, disp: 0 };
+                    let value = if mnemonic.starts_with("movs") {
+                        let src = Mem { base: Some(Reg::full(RSI)), ..dst.clone() };
+                        let value = this.asm_read(cpu, &Operand::Mem(src), size)?;
+                        let rsi = cpu.get(Reg::full(RSI))?;
+                        cpu.set(
+                            Reg::full(RSI),
+                            Value { bits: rsi.bits.wrapping_add(u64::from(size)), ..rsi },
+                        );
+                        value
+                    } else {
+                        cpu.get(Reg::full(RAX).with_size(size))?
+                    };
+                    this.asm_write(cpu, &Operand::Mem(dst), size, value)?;
+                    let rdi = cpu.get(Reg::full(RDI))?;
+                    cpu.set(
+                        Reg::full(RDI),
+                        Value { bits: rdi.bits.wrapping_add(u64::from(size)), ..rdi },
+                    );
+                    count = count.wrapping_sub(1);
+                    if instr.prefix == Prefix::Rep {
+                        cpu.set(Reg::full(RCX), Value::int(count));
+                    }
+                }
+            }
+
+            "jmp" => {
+                let [Operand::Label(label)] = operands(instr)? else {
+                    throw_unsup_format!("indirect jumps in inline assembly are not supported");
+                };
+                let Some(target) = program.resolve(label, pc) else {
+                    throw_unsup_format!("unknown label `{label}` in inline assembly");
+                };
+                return Ok(Some(target));
+            }
+            "call" | "ret" | "syscall" | "int" | "int3" | "ud2" | "hlt" =>
+                throw_unsup_format!(
+                    "`{mnemonic}` is not supported in inline assembly by Miri"
+                ),
+
+            _ => {
+                let (kind, cond) = if let Some(cc) = mnemonic.strip_prefix("cmov") {
+                    ("cmov", condition(cc, cpu.flags))
+                } else if let Some(cc) = mnemonic.strip_prefix("set") {
+                    ("set", condition(cc, cpu.flags))
+                } else if let Some(cc) = mnemonic.strip_prefix('j') {
+                    ("j", condition(cc, cpu.flags))
+                } else {
+                    ("", None)
+                };
+                let Some(cond) = cond else {
+                    throw_unsup_format!(
+                        "unsupported instruction in inline assembly: `{}`",
+                        instr.text
+                    );
+                };
+                match kind {
+                    "cmov" => {
+                        let [dst @ Operand::Reg(_), src] = operands(instr)? else {
+                            throw_unsup_format!(
+                                "invalid operands in inline assembly: `{}`",
+                                instr.text
+                            );
+                        };
+                        let size = operand_size(instr)?;
+                        // The source is read (and the upper half of a 32-bit destination
+                        // cleared) even if the condition is false.
+                        let value = this.asm_read(cpu, src, size)?;
+                        let value = if cond { value } else { this.asm_read(cpu, dst, size)? };
+                        this.asm_write(cpu, dst, size, value)?;
+                    }
+                    "set" => {
+                        let [dst] = operands(instr)?;
+                        this.asm_write(cpu, dst, 1, Value::int(u64::from(cond)))?;
+                    }
+                    _ => {
+                        let [Operand::Label(label)] = operands(instr)? else {
+                            throw_unsup_format!(
+                                "invalid operands in inline assembly: `{}`",
+                                instr.text
+                            );
+                        };
+                        let Some(target) = program.resolve(label, pc) else {
+                            throw_unsup_format!("unknown label `{label}` in inline assembly");
+                        };
+                        if cond {
+                            return Ok(Some(target));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Executes an `asm!` block and jumps to the next basic block.
+    fn emulate_inline_asm(
+        &mut self,
+        template: &'tcx [InlineAsmTemplatePiece],
+        operands: &[mir::InlineAsmOperand<'tcx>],
+        options: InlineAsmOptions,
+        targets: &[mir::BasicBlock],
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        if this.tcx.sess.target.arch != "x86_64" {
+            throw_unsup_format!("inline assembly is only supported on x86_64 targets");
+        }
+        if options.contains(InlineAsmOptions::ATT_SYNTAX) {
+            throw_unsup_format!("inline assembly using AT&T syntax is not supported");
+        }
+
+        let mut cpu = Cpu::new(options);
+        let (rendered, outputs, clobbered) = this.asm_operands(operands, &mut cpu)?;
+        let initial = (cpu.regs, cpu.init);
+
+        let mut text = String::new();
+        for piece in template {
+            match piece {
+                InlineAsmTemplatePiece::String(s) => text.push_str(s),
+                InlineAsmTemplatePiece::Placeholder { operand_idx, modifier, span: _ } =>
+                    match &rendered[*operand_idx] {
+                        AsmOperand::Reg(reg) => {
+                            let reg = match modifier {
+                                None => *reg,
+                                Some('l') => reg.with_size(1),
+                                Some('h') if reg.index <= RBX =>
+                                    Reg { index: reg.index, size: 1, high: true },
+                                Some('x') => reg.with_size(2),
+                                Some('e') => reg.with_size(4),
+                                Some('r') => reg.with_size(8),
+                                Some(modifier) =>
+                                    throw_unsup_format!(
+                                        "template modifier `{modifier}` is not supported in inline assembly"
+                                    ),
+                            };
+                            text.push_str(reg.name());
+                        }
+                        AsmOperand::Const(value) => text.push_str(value),
+                        AsmOperand::Label(index) => {
+                            text.push_str(BLOCK_LABEL_PREFIX);
+                            text.push_str(&index.to_string());
+                        }
+                        AsmOperand::None =>
+                            throw_unsup_format!(
+                                "this operand cannot be used in the inline assembly template"
+                            ),
+                    },
+            }
+        }
+        let program = parse(&text).map_err(|msg| err_unsup_format!("{msg}"))?;
+
+        let mut pc = 0;
+        let mut steps = 0u64;
+        let exit = loop {
+            if pc == program.instrs.len() {
+                break None;
+            }
+            steps = steps.wrapping_add(1);
+            if steps > STEP_LIMIT {
+                throw_unsup_format!(
+                    "inline assembly executed more than {STEP_LIMIT} instructions; \
+                    Miri cannot switch threads while executing inline assembly, so spin loops never finish"
+                );
+            }
+            match this.asm_exec(&mut cpu, &program, pc)? {
+                None => pc = pc.strict_add(1),
+                Some(Jump::Instr(target)) => pc = target,
+                Some(Jump::Block(index)) => break Some(index),
+            }
+        };
+
+        // Check that the block kept its promises.
+        if !cpu.stack.is_empty() {
+            throw_ub_format!("inline assembly did not pop all the values it pushed to the stack");
+        }
+        let before = initial.0.iter().zip(&initial.1);
+        let after = cpu.regs.iter().zip(&cpu.init);
+        for (index, ((before, before_init), (after, after_init))) in before.zip(after).enumerate() {
+            if !clobbered[index] && (before.bits != after.bits || before_init != after_init) {
+                throw_ub_format!(
+                    "inline assembly modified register `{}` without declaring it as an output",
+                    REG_NAMES[3][index]
+                );
+            }
+        }
+        if options.contains(InlineAsmOptions::PRESERVES_FLAGS) && cpu.flags_written {
+            throw_ub_format!("inline assembly with `options(preserves_flags)` modified the flags");
+        }
+
+        // Outputs the block did not write are uninitialized.
+        for (reg, place) in outputs {
+            if cpu.is_init(reg) {
+                let value = this.asm_value_to_scalar(cpu.get(reg)?, reg.size);
+                this.write_scalar(value, &place)?;
+            } else {
+                this.write_uninit(&place)?;
+            }
+        }
+        match exit {
+            Some(index) => this.go_to_block(targets[index]),
+            None if options.contains(InlineAsmOptions::NORETURN) =>
+                throw_ub_format!("inline assembly with `options(noreturn)` fell through"),
+            None => this.go_to_block(targets[0]),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reg(name: &str) -> Operand {
+        Operand::Reg(Reg::parse(name).unwrap())
+    }
+
+    #[test]
+    fn parse_registers() {
+        assert_eq!(Reg::parse("rax"), Some(Reg { index: RAX, size: 8, high: false }));
+        assert_eq!(Reg::parse("r10d"), Some(Reg { index: 10, size: 4, high: false }));
+        assert_eq!(Reg::parse("bh"), Some(Reg { index: RBX, size: 1, high: true }));
+        assert_eq!(Reg::parse("sil").unwrap().name(), "sil");
+        assert_eq!(Reg::parse("xmm0"), None);
+    }
+
+    #[test]
+    fn parse_instructions() {
+        let program = parse(
+            "mov rax, qword ptr [rdi + rcx*8 - 0x10]\n\
+             2: lock add dword ptr [rsi], -1 // comment\n\
+             jnz 2b; xor eax, eax # comment",
+        )
+        .unwrap();
+        assert_eq!(program.instrs.len(), 4);
+        assert_eq!(program.instrs[0].operands, [
+            reg("rax"),
+            Operand::Mem(Mem {
+                size: Some(8),
+                base: Reg::parse("rdi"),
+                index: Some((Reg::parse("rcx").unwrap(), 8)),
+                disp: 0u64.wrapping_sub(0x10),
+            }),
+        ]);
+        assert_eq!(program.instrs[1].prefix, Prefix::Lock);
+        assert_eq!(program.instrs[1].operands[1], Operand::Imm(u64::MAX));
+        assert_eq!(program.resolve("2b", 2), Some(Jump::Instr(1)));
+        assert_eq!(program.resolve("2f", 2), None);
+        assert_eq!(program.instrs[3].operands, [reg("eax"), reg("eax")]);
+    }
+
+    #[test]
+    fn parse_labels() {
+        let program = parse("jmp done\nloop:\nnop\ndone: jmp __miri_asm_label_1").unwrap();
+        assert_eq!(program.resolve("loop", 0), Some(Jump::Instr(1)));
+        assert_eq!(program.resolve("done", 0), Some(Jump::Instr(2)));
+        assert_eq!(program.resolve("__miri_asm_label_1", 2), Some(Jump::Block(1)));
+        assert!(parse("a: nop\na: nop").is_err());
+        assert!(parse(".p2align 4").is_err());
+        assert!(parse("mov rax, [rsp]").is_err());
+        assert!(parse("mov rsp, rax").is_err());
+    }
+
+    #[test]
+    fn parse_immediates() {
+        assert_eq!(parse_int("42"), Some(42));
+        assert_eq!(parse_int("0x2a"), Some(42));
+        assert_eq!(parse_int("2ah"), None);
+        assert_eq!(parse_int("02ah"), Some(42));
+        assert_eq!(parse_int("0b101010"), Some(42));
+        assert_eq!(parse_int("-1"), Some(u64::MAX));
+        assert_eq!(parse_int("1b"), None);
+    }
+
+    #[test]
+    fn arithmetic_flags() {
+        let flags = Flags::default();
+        let (result, flags) = alu(AluOp::Add, 0xff, 1, 1, flags);
+        assert_eq!(result, 0);
+        assert!(flags.cf && flags.zf && !flags.of);
+        let (result, flags) = alu(AluOp::Adc, 0x7f, 0, 1, flags);
+        assert_eq!(result, 0x80);
+        assert!(!flags.cf && flags.of && flags.sf);
+        let (result, flags) = alu(AluOp::Sub, 1, 2, 8, flags);
+        assert_eq!(result, u64::MAX);
+        assert!(flags.cf && flags.sf && !flags.of);
+        assert_eq!(condition("b", flags), Some(true));
+        assert_eq!(condition("l", flags), Some(true));
+        assert_eq!(condition("g", flags), Some(false));
+        let (_, flags) = alu(AluOp::Sub, 0x8000_0000, 1, 4, flags);
+        assert!(flags.of && !flags.cf);
+        assert_eq!(condition("ge", flags), Some(true));
+        let (result, flags) = alu(AluOp::Xor, 5, 5, 8, flags);
+        assert!(result == 0 && flags.zf && flags.pf && !flags.cf && !flags.of);
+    }
+
+    #[test]
+    fn shifts() {
+        let flags = Flags::default();
+        assert_eq!(shift(ShiftOp::Shl, 0x81, 1, 1, flags).0, 0x02);
+        assert!(shift(ShiftOp::Shl, 0x81, 1, 1, flags).1.cf);
+        assert_eq!(shift(ShiftOp::Shr, 0x81, 1, 1, flags).0, 0x40);
+        assert_eq!(shift(ShiftOp::Sar, 0x80, 3, 1, flags).0, 0xf0);
+        assert_eq!(shift(ShiftOp::Rol, 0x8000_0001, 4, 4, flags).0, 0x18);
+        assert_eq!(shift(ShiftOp::Ror, 1, 1, 8, flags).0, 0x8000_0000_0000_0000);
+        // Shifting by zero leaves everything alone.
+        assert_eq!(shift(ShiftOp::Shl, 3, 64, 8, flags), (3, flags));
+    }
+
+    #[test]
+    fn registers() {
+        let mut cpu = Cpu::new(InlineAsmOptions::empty());
+        cpu.set(Reg::full(RAX), Value::int(u64::MAX));
+        cpu.set(Reg::parse("ah").unwrap(), Value::int(0x12));
+        assert_eq!(cpu.get(Reg::parse("ax").unwrap()).unwrap().bits, 0x12ff);
+        cpu.set(Reg::parse("al").unwrap(), Value::int(0x34));
+        assert_eq!(cpu.get(Reg::full(RAX)).unwrap().bits, 0xffff_ffff_ffff_1234);
+        cpu.set(Reg::parse("eax").unwrap(), Value::int(1));
+        assert_eq!(cpu.get(Reg::full(RAX)).unwrap().bits, 1);
+        // Writing part of an uninitialized register leaves the rest of it uninitialized.
+        cpu.set(Reg::parse("cl").unwrap(), Value::int(1));
+        assert_eq!(cpu.get(Reg::parse("cl").unwrap()).unwrap().bits, 1);
+        assert!(cpu.get(Reg::parse("cx").unwrap()).is_err());
+        assert!(cpu.get(Reg::full(RDX)).is_err());
+    }
+}
@@ -12,6 +12,7 @@
 use helpers::bool_to_simd_element;
 
 mod aesni;
+pub mod asm;
 mod avx;
 mod avx2;
 mod sse;
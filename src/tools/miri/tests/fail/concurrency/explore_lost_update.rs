@@ -0,0 +1,27 @@
+//@compile-flags: -Zmiri-explore=preemption-bound=1 -Zmiri-disable-weak-memory-emulation
+//@normalize-stderr-test: "after exploring [0-9]+ schedules" -> "after exploring N schedules"
+//@normalize-stderr-test: "-Zmiri-explore-replay=[0-9:,]*" -> "-Zmiri-explore-replay=SCHEDULE"
+
+// Without a preemption between the load and the store of a thread, the increments are never lost,
+// so the bug is not found with the default schedule, but exploring one preemption finds it.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::thread;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn increment() {
+    let value = COUNTER.load(SeqCst);
+    COUNTER.store(value + 1, SeqCst);
+}
+
+fn main() {
+    let t1 = thread::spawn(increment);
+    let t2 = thread::spawn(increment);
+    t1.join().unwrap();
+    t2.join().unwrap();
+    if COUNTER.load(SeqCst) != 2 {
+        unsafe { std::hint::unreachable_unchecked() } //~ERROR: entering unreachable code
+    }
+}
@@ -0,0 +1,8 @@
+//@compile-flags: -Zmiri-fuzz
+//@error-in-other-file: must have the signature `fn(*const u8, usize) -> i32`
+#![no_main]
+
+#[no_mangle]
+pub extern "C" fn LLVMFuzzerTestOneInput(_data: usize, _size: usize) -> i32 {
+    0
+}
@@ -0,0 +1,13 @@
+//@compile-flags: -Zmiri-fuzz
+//@normalize-stderr-test: "after [0-9]+ runs, with the input b\".*\"" -> "after N runs, with the input INPUT"
+#![no_main]
+
+// The empty input does not trigger the bug, so it must be found by mutating inputs.
+#[no_mangle]
+pub extern "C" fn LLVMFuzzerTestOneInput(data: *const u8, size: usize) -> i32 {
+    let data = unsafe { std::slice::from_raw_parts(data, size) };
+    if data.len() >= 2 && data[0] % 2 == 1 {
+        unsafe { std::hint::unreachable_unchecked() } //~ERROR: entering unreachable code
+    }
+    0
+}
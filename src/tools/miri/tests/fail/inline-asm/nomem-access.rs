@@ -0,0 +1,12 @@
+//@only-target-x86_64: Miri only interprets x86_64 inline assembly
+
+use std::arch::asm;
+
+fn main() {
+    let x = 0u64;
+    let y: u64;
+    unsafe {
+        asm!("mov {}, qword ptr [{}]", out(reg) y, in(reg) &x, options(nomem)); //~ ERROR: `options(nomem)` accessed memory
+    }
+    assert_eq!(y, 0);
+}
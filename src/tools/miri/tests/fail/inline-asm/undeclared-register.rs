@@ -0,0 +1,10 @@
+//@only-target-x86_64: Miri only interprets x86_64 inline assembly
+
+use std::arch::asm;
+
+fn main() {
+    unsafe {
+        // `rcx` is modified, but not declared as an output.
+        asm!("mov rcx, 1", out("rax") _); //~ ERROR: modified register `rcx` without declaring it as an output
+    }
+}
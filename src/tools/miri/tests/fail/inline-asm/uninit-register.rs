@@ -0,0 +1,12 @@
+//@only-target-x86_64: Miri only interprets x86_64 inline assembly
+
+use std::arch::asm;
+
+fn main() {
+    let x: u64;
+    unsafe {
+        // `{1}` is only an output, so its value is not defined when it is read.
+        asm!("mov {0}, {1}", out(reg) x, out(reg) _); //~ ERROR: read register `rcx`, which is uninitialized
+    }
+    println!("{x}");
+}
@@ -0,0 +1,15 @@
+//@compile-flags: -Zmiri-error-format=json
+// The report is a regular JSON diagnostic, with the span of every frame and the involved
+// allocations as notes.
+
+fn read(p: *const i32) -> i32 {
+    unsafe { *p } //~ ERROR: has been freed
+}
+
+fn main() {
+    let p = {
+        let b = Box::new(42);
+        &*b as *const i32
+    };
+    read(p);
+}
@@ -0,0 +1,47 @@
+//@only-target-linux
+
+use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+
+fn main() {
+    test_epoll_socket();
+    test_epoll_watch_itself();
+}
+
+fn test_epoll_socket() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+
+    unsafe {
+        let epfd = libc::epoll_create1(0);
+        assert!(epfd >= 0);
+        let mut event = libc::epoll_event { events: libc::EPOLLIN as u32, u64: 42 };
+        assert_eq!(libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, stream.as_raw_fd(), &mut event), 0);
+
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 4];
+        assert_eq!(libc::epoll_wait(epfd, events.as_mut_ptr(), 4, 0), 0);
+        client.write_all(b"data").unwrap();
+        assert_eq!(libc::epoll_wait(epfd, events.as_mut_ptr(), 4, 0), 1);
+        let (ready, data) = (events[0].events, events[0].u64);
+        assert_eq!((ready, data), (libc::EPOLLIN as u32, 42));
+        assert_eq!(libc::close(epfd), 0);
+    }
+}
+
+/// An epoll instance cannot watch itself, not even through another file descriptor.
+fn test_epoll_watch_itself() {
+    unsafe {
+        let epfd = libc::epoll_create1(0);
+        let dup = libc::fcntl(epfd, libc::F_DUPFD_CLOEXEC, 0);
+        assert!(dup >= 0);
+        let mut event = libc::epoll_event { events: libc::EPOLLIN as u32, u64: 0 };
+        assert_eq!(libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, dup, &mut event), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 1];
+        assert_eq!(libc::epoll_wait(epfd, events.as_mut_ptr(), 1, 0), 0);
+        assert_eq!(libc::close(dup), 0);
+        assert_eq!(libc::close(epfd), 0);
+    }
+}
@@ -35,6 +35,7 @@ fn main() {
     #[cfg(target_os = "linux")]
     test_sync_file_range();
     test_isatty();
+    test_mmap_file();
 }
 
 fn test_file_open_unix_allow_two_args() {
@@ -362,3 +363,87 @@ fn test_isatty() {
         remove_file(&path).unwrap();
     }
 }
+
+fn test_mmap_file() {
+    use std::fs::{read, OpenOptions};
+    use std::slice;
+
+    let page_size = usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap();
+    let mut contents = vec![b'a'; page_size];
+    contents.extend_from_slice(b"second page");
+    let path = utils::prepare_with_content("miri_test_libc_mmap_file.txt", &contents);
+    let file = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+
+    unsafe {
+        // A read-only mapping of the second page, which extends beyond the end of the file.
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            page_size,
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            page_size.try_into().unwrap(),
+        );
+        assert_ne!(ptr, libc::MAP_FAILED);
+        let mapped = slice::from_raw_parts(ptr.cast::<u8>(), page_size);
+        assert_eq!(&mapped[..11], b"second page");
+        // The rest of the page is zeroed.
+        assert!(mapped[11..].iter().all(|&b| b == 0));
+        assert_eq!(libc::munmap(ptr, page_size), 0);
+
+        // Changes to a private mapping are not written back.
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            page_size,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE,
+            file.as_raw_fd(),
+            0,
+        );
+        assert_ne!(ptr, libc::MAP_FAILED);
+        *ptr.cast::<u8>() = b'p';
+        assert_eq!(libc::munmap(ptr, page_size), 0);
+        assert_eq!(read(&path).unwrap(), contents);
+
+        // Changes to a shared mapping are written back by `msync` and `munmap`.
+        let len = contents.len();
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        );
+        assert_ne!(ptr, libc::MAP_FAILED);
+        let mapped = slice::from_raw_parts_mut(ptr.cast::<u8>(), len);
+        mapped[0] = b's';
+        assert_eq!(libc::msync(ptr, len, libc::MS_SYNC), 0);
+        assert_eq!(read(&path).unwrap()[0], b's');
+        mapped[len - 1] = b'E';
+        // `msync` needs a page-aligned address.
+        assert_eq!(libc::msync(ptr.wrapping_add(1), 1, libc::MS_SYNC), -1);
+        assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+        assert_eq!(libc::munmap(ptr, len), 0);
+        let written = read(&path).unwrap();
+        // Writing back does not change the length of the file.
+        assert_eq!(written.len(), len);
+        assert_eq!(written[len - 1], b'E');
+
+        // Mapping a file that is not open for writing as shared and writable is not allowed.
+        let read_only = File::open(&path).unwrap();
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            read_only.as_raw_fd(),
+            0,
+        );
+        assert_eq!(ptr, libc::MAP_FAILED);
+        assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::EACCES));
+    }
+
+    drop(file);
+    remove_file(&path).unwrap();
+}
@@ -0,0 +1,43 @@
+//@ignore-target-windows: No libc pipe on Windows
+
+use std::thread;
+use std::time::Duration;
+
+fn main() {
+    test_blocking_read();
+    #[cfg(target_os = "linux")]
+    test_nonblocking_read();
+}
+
+/// A blocking `read` on an empty pipe waits until another thread writes to it.
+fn test_blocking_read() {
+    let mut fds = [-1, -1];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    let [read_fd, write_fd] = fds;
+
+    let writer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        let data = b"data";
+        assert_eq!(unsafe { libc::write(write_fd, data.as_ptr().cast(), data.len()) }, 4);
+        assert_eq!(unsafe { libc::close(write_fd) }, 0);
+    });
+    let mut buf = [0u8; 8];
+    assert_eq!(unsafe { libc::read(read_fd, buf.as_mut_ptr().cast(), buf.len()) }, 4);
+    assert_eq!(&buf[..4], b"data");
+    writer.join().unwrap();
+    // The write end is closed, so reading returns end-of-file.
+    assert_eq!(unsafe { libc::read(read_fd, buf.as_mut_ptr().cast(), buf.len()) }, 0);
+    assert_eq!(unsafe { libc::close(read_fd) }, 0);
+}
+
+#[cfg(target_os = "linux")]
+fn test_nonblocking_read() {
+    let mut fds = [-1, -1];
+    assert_eq!(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK) }, 0);
+    let mut buf = [0u8; 8];
+    assert_eq!(unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) }, -1);
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EAGAIN));
+    for fd in fds {
+        assert_eq!(unsafe { libc::close(fd) }, 0);
+    }
+}
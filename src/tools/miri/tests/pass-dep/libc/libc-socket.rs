@@ -0,0 +1,41 @@
+//@ignore-target-windows: No libc socket on Windows
+
+use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+use std::thread;
+use std::time::Duration;
+
+fn main() {
+    test_blocking_read();
+    test_read_timeout();
+}
+
+/// `read` on a socket without data blocks until the peer writes.
+fn test_blocking_read() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+
+    let writer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        client.write_all(b"data").unwrap();
+    });
+    let mut buf = [0u8; 8];
+    let len = unsafe { libc::read(stream.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(len, 4);
+    assert_eq!(&buf[..4], b"data");
+    writer.join().unwrap();
+}
+
+/// A blocking `read` gives up after the `SO_RCVTIMEO` timeout.
+fn test_read_timeout() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+    stream.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+    let mut buf = [0u8; 8];
+    let len = unsafe { libc::read(stream.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(len, -1);
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EAGAIN));
+}
@@ -1,7 +1,5 @@
-//@compile-flags: -Zmiri-permissive-provenance -Zmiri-backtrace=full
+//@compile-flags: -Zmiri-permissive-provenance
 //@only-target-x86_64-unknown-linux: support for tokio only on linux and x86
-//@error-in-other-file: returning ready events from epoll_wait is not yet implemented
-//@normalize-stderr-test: " += note:.*\n" -> ""
 
 use tokio::time::{sleep, Duration, Instant};
 
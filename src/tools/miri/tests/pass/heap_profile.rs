@@ -0,0 +1,12 @@
+//@ignore-host-windows: the profile is written to `/dev/null`
+//@compile-flags: -Zmiri-heap-profile=/dev/null
+//@normalize-stderr-test: "was [0-9]+ bytes, reached after [0-9]+ basic blocks" -> "was N bytes, reached after M basic blocks"
+
+fn main() {
+    let mut v = Vec::new();
+    for i in 0..100u64 {
+        v.push(Box::new(i));
+    }
+    drop(v);
+    let _s = String::from("heap");
+}
@@ -0,0 +1,120 @@
+//@ignore-target-windows: No socket support on Windows
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+fn main() {
+    test_tcp();
+    test_tcp_ipv6();
+    test_tcp_errors();
+    test_tcp_read_timeout();
+    test_tcp_nonblocking();
+    test_udp();
+}
+
+fn test_tcp() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert!(addr.ip().is_loopback());
+    assert_ne!(addr.port(), 0);
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        stream.write_all(b"ping").unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pong");
+        stream.local_addr().unwrap()
+    });
+
+    let (mut stream, peer) = listener.accept().unwrap();
+    let mut buf = [0; 4];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+    stream.write_all(b"pong").unwrap();
+    assert_eq!(client.join().unwrap(), peer);
+
+    // The client closed its end, so reading returns end-of-file.
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+}
+
+fn test_tcp_ipv6() {
+    let listener = TcpListener::bind((Ipv6Addr::LOCALHOST, 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert!(addr.is_ipv6());
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    client.write_all(b"hello").unwrap();
+    client.shutdown(Shutdown::Write).unwrap();
+    let mut data = Vec::new();
+    server.read_to_end(&mut data).unwrap();
+    assert_eq!(data, b"hello");
+}
+
+fn test_tcp_errors() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let err = TcpListener::bind(addr).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AddrInUse);
+
+    drop(listener);
+    let err = TcpStream::connect(addr).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+
+    // There is no network besides the loopback interface.
+    let err = TcpListener::bind((Ipv4Addr::new(192, 0, 2, 1), 0)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AddrNotAvailable);
+}
+
+fn test_tcp_read_timeout() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+    let err = stream.read(&mut [0; 4]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+}
+
+fn test_tcp_nonblocking() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let err = listener.accept().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.set_nonblocking(true).unwrap();
+    let err = stream.read(&mut [0; 4]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+    client.write_all(b"data").unwrap();
+    let mut buf = [0; 8];
+    assert_eq!(stream.peek(&mut buf).unwrap(), 4);
+    assert_eq!(stream.read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf[..4], b"data");
+}
+
+fn test_udp() {
+    let a = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let b = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let a_addr = a.local_addr().unwrap();
+    let b_addr = b.local_addr().unwrap();
+
+    let receiver = thread::spawn(move || {
+        let mut buf = [0; 16];
+        let (len, from) = b.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"first");
+        assert_eq!(from, a_addr);
+        b.send_to(b"second", from).unwrap();
+    });
+
+    a.send_to(b"first", b_addr).unwrap();
+    receiver.join().unwrap();
+    a.connect(b_addr).unwrap();
+    let mut buf = [0; 16];
+    let len = a.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"second");
+}
@@ -0,0 +1,47 @@
+//@ignore-target-windows: No process support on Windows
+//@ignore-host-windows: The commands only exist on Unix hosts
+//@compile-flags: -Zmiri-disable-isolation
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+fn main() {
+    test_output();
+    test_pipe_between_threads();
+    test_current_dir();
+}
+
+fn test_output() {
+    let output = Command::new("echo").args(["hello", "host"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"hello host\n");
+
+    let status = Command::new("sh").args(["-c", "exit 3"]).status().unwrap();
+    assert_eq!(status.code(), Some(3));
+}
+
+/// Reading the output of a child blocks only the reading thread, so another thread can still
+/// write the input the child is waiting for.
+fn test_pipe_between_threads() {
+    let mut child =
+        Command::new("cat").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+
+    let writer = thread::spawn(move || {
+        thread::yield_now();
+        stdin.write_all(b"through the host").unwrap();
+        // Dropping `stdin` closes it, so `cat` exits.
+    });
+    let mut output = Vec::new();
+    stdout.read_to_end(&mut output).unwrap();
+    assert_eq!(output, b"through the host");
+    writer.join().unwrap();
+    assert!(child.wait().unwrap().success());
+}
+
+fn test_current_dir() {
+    let output = Command::new("pwd").current_dir("/").output().unwrap();
+    assert_eq!(output.stdout, b"/\n");
+}
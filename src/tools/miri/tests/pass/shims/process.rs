@@ -0,0 +1,60 @@
+//@ignore-target-windows: No process support on Windows
+//@compile-flags: -Zmiri-process-stubs=tests/pass/shims/process.stubs
+
+use std::io::{ErrorKind, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+fn main() {
+    test_output();
+    test_status();
+    test_stdin();
+    test_current_dir();
+    test_fork_fails();
+}
+
+fn test_output() {
+    let output = Command::new("echo").args(["hello", "world"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"hello world\n");
+    assert!(output.stderr.is_empty());
+
+    let output = Command::new("sh").args(["-c", "echo oops >&2; exit 3"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert_eq!(output.stderr, b"oops\n");
+}
+
+fn test_status() {
+    let status = Command::new("false").status().unwrap();
+    assert_eq!(status.code(), Some(1));
+
+    let mut child = Command::new("false").spawn().unwrap();
+    let id = child.id();
+    assert!(id > 0);
+    assert_eq!(child.try_wait().unwrap().unwrap().code(), Some(1));
+    // Killing a child that was already waited for does nothing.
+    child.kill().unwrap();
+}
+
+fn test_stdin() {
+    let mut child =
+        Command::new("cat").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    // The stub consumes all input.
+    child.stdin.as_mut().unwrap().write_all(b"some input").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"done\n");
+}
+
+fn test_current_dir() {
+    // Stubbed processes ignore the working directory, but setting it still uses `posix_spawn`.
+    let output = Command::new("echo").args(["hello", "world"]).current_dir("/").output().unwrap();
+    assert_eq!(output.stdout, b"hello world\n");
+}
+
+fn test_fork_fails() {
+    // Setting the user ID needs `fork`, which is not supported.
+    let err = Command::new("true").uid(0).spawn().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+}
@@ -0,0 +1,218 @@
+//@only-target-windows: this tests the Windows implementation of `std::fs`
+//@compile-flags: -Zmiri-disable-isolation
+
+#![feature(io_error_more)]
+
+use std::fs::{create_dir, read_dir, remove_dir, remove_file, rename, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+
+#[path = "../../utils/mod.rs"]
+mod utils;
+
+fn main() {
+    test_file();
+    test_file_create_new();
+    test_append();
+    test_seek();
+    test_read_write_at();
+    test_metadata();
+    test_file_set_len();
+    test_file_sync();
+    test_errors();
+    test_rename();
+    test_directory();
+}
+
+fn test_file() {
+    let bytes = b"Hello, World!\n";
+    let path = utils::prepare("miri_test_windows_fs_file.txt");
+
+    // Test creating, writing and closing a file (closing is tested when `file` is dropped).
+    let mut file = File::create(&path).unwrap();
+    file.write(&mut []).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 0);
+    file.write_all(bytes).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), bytes.len() as u64);
+
+    // Test opening, reading and closing a file.
+    let mut file = File::open(&path).unwrap();
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    assert_eq!(bytes, contents.as_slice());
+
+    // Creating the file again truncates it.
+    File::create(&path).unwrap();
+    assert_eq!(path.metadata().unwrap().len(), 0);
+
+    remove_file(&path).unwrap();
+}
+
+fn test_file_create_new() {
+    let path = utils::prepare("miri_test_windows_fs_file_create_new.txt");
+
+    OpenOptions::new().write(true).create_new(true).open(&path).unwrap();
+    assert_eq!(
+        ErrorKind::AlreadyExists,
+        OpenOptions::new().write(true).create_new(true).open(&path).unwrap_err().kind()
+    );
+    OpenOptions::new().write(true).create(true).open(&path).unwrap();
+
+    remove_file(&path).unwrap();
+}
+
+fn test_append() {
+    let path = utils::prepare_with_content("miri_test_windows_fs_append.txt", b"Hello");
+
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b", World!").unwrap();
+    drop(file);
+    assert_eq!(std::fs::read(&path).unwrap(), b"Hello, World!");
+
+    remove_file(&path).unwrap();
+}
+
+fn test_seek() {
+    let bytes = b"Hello, entire World!\n";
+    let path = utils::prepare_with_content("miri_test_windows_fs_seek.txt", bytes);
+
+    let mut file = File::open(&path).unwrap();
+    file.seek(SeekFrom::End(-1)).unwrap();
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    assert_eq!(&bytes[bytes.len() - 1..], contents.as_slice());
+
+    file.seek(SeekFrom::Start(5)).unwrap();
+    assert_eq!(file.seek(SeekFrom::Current(-3)).unwrap(), 2);
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    assert_eq!(&bytes[2..], contents.as_slice());
+
+    remove_file(&path).unwrap();
+}
+
+fn test_read_write_at() {
+    use std::os::windows::fs::FileExt;
+
+    let path = utils::prepare_with_content("miri_test_windows_fs_read_write_at.txt", b"Hello!");
+
+    let file = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    assert_eq!(file.seek_write(b"J", 0).unwrap(), 1);
+    let mut buf = [0; 4];
+    assert_eq!(file.seek_read(&mut buf, 1).unwrap(), 4);
+    assert_eq!(&buf, b"ello");
+    // Reading at the end of the file reads nothing.
+    assert_eq!(file.seek_read(&mut buf, 6).unwrap(), 0);
+
+    remove_file(&path).unwrap();
+}
+
+fn test_metadata() {
+    let bytes = b"Hello, meta-World!\n";
+    let path = utils::prepare_with_content("miri_test_windows_fs_metadata.txt", bytes);
+
+    let metadata = path.metadata().unwrap();
+    assert!(metadata.is_file());
+    assert!(!metadata.is_dir());
+    assert_eq!(bytes.len() as u64, metadata.len());
+    assert!(metadata.modified().is_ok());
+
+    let metadata = File::open(&path).unwrap().metadata().unwrap();
+    assert!(metadata.is_file());
+    assert_eq!(bytes.len() as u64, metadata.len());
+
+    let metadata = utils::tmp().metadata().unwrap();
+    assert!(metadata.is_dir());
+
+    remove_file(&path).unwrap();
+}
+
+fn test_file_set_len() {
+    let bytes = b"Hello, World!\n";
+    let path = utils::prepare_with_content("miri_test_windows_fs_set_len.txt", bytes);
+
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_len(20).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 20);
+    file.set_len(5).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 5);
+    // Files without write access cannot be resized.
+    let file = File::open(&path).unwrap();
+    assert_eq!(file.set_len(0).unwrap_err().kind(), ErrorKind::PermissionDenied);
+
+    remove_file(&path).unwrap();
+}
+
+fn test_file_sync() {
+    let path = utils::prepare_with_content("miri_test_windows_fs_sync.txt", b"Hello");
+
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.sync_data().unwrap();
+    file.sync_all().unwrap();
+
+    remove_file(&path).unwrap();
+}
+
+fn test_errors() {
+    let path = utils::prepare("miri_test_windows_fs_errors.txt");
+
+    assert_eq!(ErrorKind::NotFound, File::open(&path).unwrap_err().kind());
+    assert_eq!(ErrorKind::NotFound, remove_file(&path).unwrap_err().kind());
+    assert_eq!(ErrorKind::NotFound, path.metadata().unwrap_err().kind());
+}
+
+fn test_rename() {
+    let path1 = utils::prepare_with_content("miri_test_windows_fs_rename_1.txt", b"Hello");
+    let path2 = utils::prepare("miri_test_windows_fs_rename_2.txt");
+
+    rename(&path1, &path2).unwrap();
+    assert_eq!(ErrorKind::NotFound, path1.metadata().unwrap_err().kind());
+    assert!(path2.metadata().unwrap().is_file());
+
+    remove_file(&path2).unwrap();
+}
+
+fn test_directory() {
+    let dir_path = utils::prepare_dir("miri_test_windows_fs_dir");
+
+    create_dir(&dir_path).unwrap();
+    assert!(dir_path.metadata().unwrap().is_dir());
+    assert_eq!(ErrorKind::AlreadyExists, create_dir(&dir_path).unwrap_err().kind());
+
+    // An empty directory has no entries besides `.` and `..`, which `read_dir` skips.
+    assert_eq!(read_dir(&dir_path).unwrap().count(), 0);
+
+    let path_1 = dir_path.join("test_file_1");
+    File::create(&path_1).unwrap().write_all(b"Hello").unwrap();
+    let path_2 = dir_path.join("test_file_2");
+    File::create(&path_2).unwrap();
+    create_dir(dir_path.join("test_dir")).unwrap();
+
+    let mut entries: Vec<_> = read_dir(&dir_path)
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.file_name().into_string().unwrap(), entry.file_type().unwrap().is_dir())
+        })
+        .collect();
+    entries.sort();
+    assert_eq!(entries, [
+        ("test_dir".to_owned(), true),
+        ("test_file_1".to_owned(), false),
+        ("test_file_2".to_owned(), false),
+    ]);
+    let entry = read_dir(&dir_path)
+        .unwrap()
+        .map(Result::unwrap)
+        .find(|entry| entry.file_name() == "test_file_1")
+        .unwrap();
+    assert_eq!(entry.metadata().unwrap().len(), 5);
+
+    // Removing a non-empty directory fails.
+    assert_eq!(ErrorKind::DirectoryNotEmpty, remove_dir(&dir_path).unwrap_err().kind());
+
+    remove_file(&path_1).unwrap();
+    remove_file(&path_2).unwrap();
+    remove_dir(dir_path.join("test_dir")).unwrap();
+    remove_dir(&dir_path).unwrap();
+    assert_eq!(ErrorKind::NotFound, read_dir(&dir_path).unwrap_err().kind());
+}
@@ -0,0 +1,330 @@
+//@only-target-x86_64: Miri only interprets x86_64 inline assembly
+//@compile-flags: -C target-feature=+sse2,+popcnt
+
+#![feature(asm_goto)]
+
+use std::arch::asm;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::thread;
+
+fn main() {
+    test_arithmetic();
+    test_flags();
+    test_memory();
+    test_loops_and_labels();
+    test_mul_div();
+    test_bits();
+    test_stack();
+    test_atomics();
+    test_cpuid();
+    test_rdtsc();
+    test_asm_goto();
+}
+
+fn test_arithmetic() {
+    let x: u64;
+    unsafe { asm!("mov {0}, 5", "add {0}, {1}", "shl {0}, 2", out(reg) x, in(reg) 3u64) };
+    assert_eq!(x, 32);
+
+    let mut y: u32 = 10;
+    unsafe { asm!("sub {0:e}, 12", "neg {0:e}", inout(reg) y, options(pure, nomem, nostack)) };
+    assert_eq!(y, 2);
+
+    // Writing a 32-bit register zeroes the upper half, writing a smaller one does not.
+    let z: u64;
+    unsafe {
+        asm!(
+            "mov {0}, -1",
+            "mov {0:x}, 0",
+            "mov {1:e}, {0:e}",
+            out(reg) z,
+            out(reg) _,
+        )
+    };
+    assert_eq!(z, 0xffff_ffff_ffff_0000);
+
+    let w: u64;
+    unsafe { asm!("lea {0}, [{1} + {1}*2 + 7]", out(reg) w, in(reg) 5u64) };
+    assert_eq!(w, 22);
+
+    let sx: i64;
+    unsafe { asm!("movsx {0}, {1:l}", out(reg) sx, in(reg) 0xf0u64) };
+    assert_eq!(sx, -16);
+
+    const N: u32 = 42;
+    let c: u32;
+    unsafe { asm!("mov {0:e}, {1}", out(reg) c, const N) };
+    assert_eq!(c, 42);
+}
+
+fn test_flags() {
+    fn less_than(a: i32, b: i32) -> bool {
+        let result: u8;
+        unsafe { asm!("cmp {1:e}, {2:e}", "setl {0}", out(reg_byte) result, in(reg) a, in(reg) b) };
+        result != 0
+    }
+    assert!(less_than(-1, 1));
+    assert!(!less_than(1, -1));
+
+    fn max(a: u64, b: u64) -> u64 {
+        let mut result = a;
+        unsafe { asm!("cmp {0}, {1}", "cmovb {0}, {1}", inout(reg) result, in(reg) b) };
+        result
+    }
+    assert_eq!(max(3, 7), 7);
+    assert_eq!(max(7, 3), 7);
+
+    fn add_with_carry(a: u64, b: u64) -> (u64, bool) {
+        let mut sum = a;
+        let carry: u8;
+        unsafe { asm!("add {0}, {2}", "setc {1}", inout(reg) sum, out(reg_byte) carry, in(reg) b) };
+        (sum, carry != 0)
+    }
+    assert_eq!(add_with_carry(u64::MAX, 2), (1, true));
+    assert_eq!(add_with_carry(1, 2), (3, false));
+}
+
+fn test_memory() {
+    let mut array = [1u32, 2, 3, 4];
+    unsafe {
+        asm!(
+            "mov {tmp:e}, dword ptr [{ptr} + 4]",
+            "add dword ptr [{ptr} + {i}*4], {tmp:e}",
+            ptr = in(reg) array.as_mut_ptr(),
+            i = in(reg) 3usize,
+            tmp = out(reg) _,
+        )
+    };
+    assert_eq!(array, [1, 2, 3, 6]);
+
+    // Unaligned accesses are fine.
+    let bytes = [0u8, 1, 2, 3, 4, 5, 6, 7, 8];
+    let value: u64;
+    unsafe { asm!("mov {0}, qword ptr [{1} + 1]", out(reg) value, in(reg) bytes.as_ptr()) };
+    assert_eq!(value, u64::from_le_bytes([1, 2, 3, 4, 5, 6, 7, 8]));
+
+    // Pointers can be stored and loaded, keeping their provenance.
+    let target = 42u8;
+    let mut slot: *const u8 = std::ptr::null();
+    unsafe {
+        asm!(
+            "mov qword ptr [{slot}], {ptr}",
+            slot = in(reg) &mut slot,
+            ptr = in(reg) &target,
+        )
+    };
+    assert_eq!(unsafe { *slot }, 42);
+
+    let mut dst = [0u8; 5];
+    unsafe {
+        asm!(
+            "rep movsb",
+            inout("rcx") dst.len() => _,
+            inout("rsi") b"hello".as_ptr() => _,
+            inout("rdi") dst.as_mut_ptr() => _,
+        )
+    };
+    assert_eq!(&dst, b"hello");
+}
+
+fn test_loops_and_labels() {
+    // Sums up 1..=n.
+    let n = 10u64;
+    let sum: u64;
+    unsafe {
+        asm!(
+            "xor {sum:e}, {sum:e}",
+            "2:",
+            "add {sum}, {n}",
+            "dec {n}",
+            "jnz 2b",
+            n = inout(reg) n => _,
+            sum = out(reg) sum,
+        )
+    };
+    assert_eq!(sum, 55);
+
+    let abs: i64;
+    unsafe {
+        asm!(
+            "mov {0}, {1}",
+            "test {0}, {0}",
+            "jns 2f",
+            "neg {0}",
+            "2:",
+            out(reg) abs,
+            in(reg) -17i64,
+        )
+    };
+    assert_eq!(abs, 17);
+}
+
+fn test_mul_div() {
+    let (low, high): (u64, u64);
+    unsafe {
+        asm!("mul {0}", in(reg) u64::MAX, inout("rax") 3u64 => low, out("rdx") high)
+    };
+    assert_eq!((u128::from(high) << 64) | u128::from(low), u128::from(u64::MAX) * 3);
+
+    let (quotient, remainder): (i64, i64);
+    unsafe {
+        asm!(
+            "cqo",
+            "idiv {0}",
+            in(reg) 5i64,
+            inout("rax") -17i64 => quotient,
+            out("rdx") remainder,
+        )
+    };
+    assert_eq!((quotient, remainder), (-3, -2));
+
+    let product: i32;
+    unsafe { asm!("imul {0:e}, {1:e}, -3", out(reg) product, in(reg) 7) };
+    assert_eq!(product, -21);
+}
+
+fn test_bits() {
+    let (ones, trailing, swapped): (u64, u64, u64);
+    unsafe {
+        asm!(
+            "popcnt {0}, {3}",
+            "bsf {1}, {3}",
+            "mov {2}, {3}",
+            "bswap {2}",
+            out(reg) ones,
+            out(reg) trailing,
+            out(reg) swapped,
+            in(reg) 0x0000_0000_0000_ff00u64,
+        )
+    };
+    assert_eq!((ones, trailing, swapped), (8, 8, 0x00ff_0000_0000_0000));
+    assert_eq!(0xff00u64.count_ones(), 8);
+
+    let rotated: u32;
+    unsafe { asm!("rol {0:e}, 4", inout(reg) 0x8000_0001u32 => rotated) };
+    assert_eq!(rotated, 0x18);
+}
+
+fn test_stack() {
+    // `rbx` cannot be used as an operand, so it has to be saved around instructions using it.
+    let value: u64;
+    unsafe {
+        asm!(
+            "push rbx",
+            "mov rbx, 42",
+            "mov {0}, rbx",
+            "pop rbx",
+            out(reg) value,
+        )
+    };
+    assert_eq!(value, 42);
+
+    // Registers that are not operands can be saved and restored, even though their values are
+    // unknown to the block.
+    let value: u64;
+    unsafe {
+        asm!(
+            "push rcx",
+            "mov rcx, 7",
+            "mov {0}, rcx",
+            "pop rcx",
+            out(reg) value,
+        )
+    };
+    assert_eq!(value, 7);
+}
+
+fn test_atomics() {
+    let counter = AtomicU64::new(0);
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                for _ in 0..5 {
+                    unsafe { asm!("lock inc qword ptr [{0}]", in(reg) counter.as_ptr()) };
+                }
+            });
+        }
+    });
+    assert_eq!(counter.load(Ordering::Relaxed), 10);
+
+    let value = AtomicU32::new(5);
+    let old: u32;
+    unsafe {
+        asm!("lock xadd dword ptr [{1}], {0:e}", inout(reg) 3u32 => old, in(reg) value.as_ptr())
+    };
+    assert_eq!(old, 5);
+    assert_eq!(value.load(Ordering::Relaxed), 8);
+
+    fn compare_exchange(atomic: &AtomicU32, current: u32, new: u32) -> Result<u32, u32> {
+        let previous: u32;
+        let success: u8;
+        unsafe {
+            asm!(
+                "lock cmpxchg dword ptr [{ptr}], {new:e}",
+                "sete {success}",
+                ptr = in(reg) atomic.as_ptr(),
+                new = in(reg) new,
+                success = out(reg_byte) success,
+                inout("eax") current => previous,
+            )
+        };
+        if success != 0 { Ok(previous) } else { Err(previous) }
+    }
+    assert_eq!(compare_exchange(&value, 8, 1), Ok(8));
+    assert_eq!(compare_exchange(&value, 8, 2), Err(1));
+
+    let old: u32;
+    unsafe { asm!("xchg dword ptr [{1}], {0:e}", inout(reg) 7u32 => old, in(reg) value.as_ptr()) };
+    assert_eq!(old, 1);
+    assert_eq!(value.load(Ordering::Relaxed), 7);
+
+    unsafe { asm!("mfence", "pause", options(nostack, preserves_flags)) };
+}
+
+fn test_cpuid() {
+    let (max_leaf, ebx): (u32, u32);
+    unsafe {
+        asm!(
+            "mov {1:r}, rbx",
+            "cpuid",
+            "xchg {1:r}, rbx",
+            inout("eax") 0 => max_leaf,
+            out(reg) ebx,
+            out("ecx") _,
+            out("edx") _,
+        )
+    };
+    assert!(max_leaf >= 1);
+    assert_eq!(&ebx.to_le_bytes(), b"Miri");
+
+    // `std` detects target features with `cpuid` as well.
+    assert!(std::is_x86_feature_detected!("sse2"));
+    assert!(std::is_x86_feature_detected!("popcnt"));
+}
+
+fn test_rdtsc() {
+    let read = || {
+        let (low, high): (u32, u32);
+        unsafe { asm!("rdtsc", out("eax") low, out("edx") high, options(nomem, nostack)) };
+        (u64::from(high) << 32) | u64::from(low)
+    };
+    let first = read();
+    let second = read();
+    assert!(second >= first);
+}
+
+fn test_asm_goto() {
+    fn is_zero(x: u64) -> bool {
+        unsafe {
+            asm!(
+                "test {0}, {0}",
+                "jz {1}",
+                in(reg) x,
+                label { return true; },
+            )
+        };
+        false
+    }
+    assert!(is_zero(0));
+    assert!(!is_zero(1));
+}
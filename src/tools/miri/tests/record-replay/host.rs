@@ -0,0 +1,19 @@
+// Prints values which Miri gets from the host, to check that replaying a recording of this program
+// prints the same values even though the host provides different ones.
+
+use std::collections::HashSet;
+use std::time::{Instant, SystemTime};
+
+fn main() {
+    let var = std::env::var_os("MIRI_RECORD_REPLAY_TEST").unwrap();
+    println!("env: {:?}", var.as_encoded_bytes());
+    println!("system time: {:?}", SystemTime::now().duration_since(SystemTime::UNIX_EPOCH));
+    let start = Instant::now();
+    println!("elapsed: {:?}", start.elapsed());
+    // The hash map keys are random.
+    let set: HashSet<u32> = (0..16).collect();
+    println!("set: {set:?}");
+    let ptr = Box::into_raw(Box::new(0u8));
+    println!("address: {ptr:p}");
+    drop(unsafe { Box::from_raw(ptr) });
+}
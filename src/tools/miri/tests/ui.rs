@@ -6,7 +6,7 @@
 
 use colored::*;
 use regex::bytes::Regex;
-use ui_test::color_eyre::eyre::{Context, Result};
+use ui_test::color_eyre::eyre::{bail, Context, Result};
 use ui_test::{
     status_emitter, CommandBuilder, Config, Format, Match, Mode, OutputConflictHandling,
     RustfixMode,
@@ -255,6 +255,79 @@ fn ui(
         .with_context(|| format!("ui tests in {path} for {target} failed"))
 }
 
+/// Miri for `target`, outside of `ui_test`.
+fn miri_command(target: &str) -> Command {
+    let mut cmd = Command::new(miri_path());
+    cmd.arg(format!(
+        "--sysroot={}",
+        env::var("MIRI_SYSROOT").expect("MIRI_SYSROOT must be set to run the ui test suite")
+    ))
+    .args(["--edition=2021", "--target", target]);
+    cmd
+}
+
+/// Runs a program with `-Zmiri-record`, then replays the recording with different environment
+/// variables, and checks that the program sees the same values from the host both times.
+#[cfg(unix)]
+fn record_replay(target: &str, tmpdir: &Path) -> Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let msg = format!("## Running record/replay test for {target}");
+    eprintln!("{}", msg.green().bold());
+
+    let recording = tmpdir.join("record-replay.recording");
+    let run = |flag: &str, var: &[u8]| -> Result<Vec<u8>> {
+        let mut flag = OsString::from(flag);
+        flag.push(&recording);
+        let output = miri_command(target)
+            .arg("-Zmiri-disable-isolation")
+            .arg(flag)
+            .arg("tests/record-replay/host.rs")
+            .env("MIRI_RECORD_REPLAY_TEST", std::ffi::OsStr::from_bytes(var))
+            .output()?;
+        if !output.status.success() {
+            bail!("Miri failed:\n{}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(output.stdout)
+    };
+    // Not valid UTF-8, to check that the recording preserves the raw bytes.
+    let recorded = run("-Zmiri-record=", b"recorded \xff")?;
+    let replayed = run("-Zmiri-replay=", b"replayed")?;
+    if recorded != replayed {
+        bail!(
+            "the replayed execution printed\n{}\ninstead of\n{}",
+            String::from_utf8_lossy(&replayed),
+            String::from_utf8_lossy(&recorded)
+        );
+    }
+    Ok(())
+}
+
+/// `ui_test` reads JSON diagnostics itself, so this checks the raw output of
+/// `-Zmiri-error-format=json`: everything on stderr has to be JSON, and Miri has to fail.
+fn json_error_format(target: &str) -> Result<()> {
+    let msg = format!("## Running JSON error format test for {target}");
+    eprintln!("{}", msg.green().bold());
+
+    let output = miri_command(target)
+        .arg("-Zmiri-error-format=json")
+        .arg("tests/fail/json-error-format.rs")
+        .output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.code() != Some(1) {
+        bail!("Miri exited with {} instead of failing:\n{stderr}", output.status);
+    }
+    if let Some(line) =
+        stderr.lines().find(|line| !line.starts_with(r#"{"$message_type":"diagnostic","#))
+    {
+        bail!("Miri printed a line that is not a JSON diagnostic:\n{line}");
+    }
+    if !stderr.contains(r#""message":"kind: undefined_behavior""#) {
+        bail!("the report does not include its kind:\n{stderr}");
+    }
+    Ok(())
+}
+
 fn get_target() -> String {
     env::var("MIRI_TEST_TARGET").ok().unwrap_or_else(get_host)
 }
@@ -301,6 +374,9 @@ fn main() -> Result<()> {
             tmpdir.path(),
         )?;
     }
+    #[cfg(unix)]
+    record_replay(&target, tmpdir.path())?;
+    json_error_format(&target)?;
 
     Ok(())
 }
@@ -430,6 +430,35 @@ function loadSearchJS(doc_folder, resource_suffix) {
             //console.log(this.descShards);
             this.descShards.get(crate)[shard].resolve(data.split("\n"));
         },
+        fullTextIndexes: new Map(),
+        loadFullText: async function(fullTextIndex) {
+            if (fullTextIndex.promise === null) {
+                fullTextIndex.promise = new Promise((resolve, reject) => {
+                    fullTextIndex.resolve = resolve;
+                    const crate = fullTextIndex.crate;
+                    const fname = `${crate}-fulltext-${resource_suffix}.js`;
+                    fs.readFile(
+                        `${doc_folder}/search.fulltext/${crate}/${fname}`,
+                        (err, data) => {
+                            if (err) {
+                                reject(err);
+                            } else {
+                                eval(data.toString("utf8"));
+                            }
+                        },
+                    );
+                });
+            }
+            return fullTextIndex.promise;
+        },
+        loadedFullText: function(crate, data) {
+            const {t, p} = JSON.parse(data);
+            const postings = new Map();
+            for (const [i, term] of t.entries()) {
+                postings.set(term, p[i]);
+            }
+            this.fullTextIndexes.get(crate).resolve(postings);
+        },
     };
 
     const staticFiles = path.join(doc_folder, "static.files");
@@ -12,6 +12,7 @@
 use std::env;
 use std::fs::File;
 use std::io::{self, stdout, Read, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -90,6 +91,9 @@ pub enum OperationError {
     /// supported with standard input.
     #[error("Emit mode {0} not supported with standard output.")]
     StdinBadEmit(EmitMode),
+    /// Attempt to format a range of a file instead of standard input.
+    #[error("The `--range` option only works with standard input.")]
+    RangeWithFiles,
 }
 
 impl From<IoError> for OperationError {
@@ -170,6 +174,13 @@ fn make_opts() -> Options {
              more detail (unstable).",
             "JSON",
         );
+        opts.optopt(
+            "",
+            "range",
+            "Format the items and statements touching a range of bytes of the standard \
+             input, and print the edits to make as JSON (unstable).",
+            "START..END",
+        );
         opts.optflag(
             "",
             "error-on-unformatted",
@@ -262,6 +273,13 @@ fn format_string(input: String, options: GetOptsOptions) -> Result<i32> {
     // try to read config from local directory
     let (mut config, _) = load_config(Some(Path::new(".")), Some(options.clone()))?;
 
+    if let Some(range) = options.range {
+        let mut session = Session::<io::Stdout>::new(config, None);
+        let edits = session.format_range(&input, range)?;
+        println!("{}", serde_json::to_string(&edits)?);
+        return Ok(0);
+    }
+
     if options.check {
         config.set().emit_mode(EmitMode::Diff);
     } else {
@@ -488,6 +506,10 @@ fn determine_operation(matches: &Matches) -> Result<Operation, OperationError> {
         })
         .collect();
 
+    if !files.is_empty() && matches.opt_present("range") {
+        return Err(OperationError::RangeWithFiles);
+    }
+
     // if no file argument is supplied, read from stdin
     if files.is_empty() {
         if minimal_config_path.is_some() {
@@ -521,6 +543,7 @@ struct GetOptsOptions {
     edition: Option<Edition>,
     color: Option<Color>,
     file_lines: FileLines, // Default is all lines in all files.
+    range: Option<Range<usize>>,
     unstable_features: bool,
     error_on_unformatted: Option<bool>,
     print_misformatted_file_names: bool,
@@ -550,6 +573,9 @@ pub fn from_matches(matches: &Matches) -> Result<GetOptsOptions> {
                 if let Some(ref file_lines) = matches.opt_str("file-lines") {
                     options.file_lines = file_lines.parse()?;
                 }
+                if let Some(ref range) = matches.opt_str("range") {
+                    options.range = Some(range_from_str(range)?);
+                }
             } else {
                 let mut unstable_options = vec![];
                 if matches.opt_present("skip-children") {
@@ -561,6 +587,9 @@ pub fn from_matches(matches: &Matches) -> Result<GetOptsOptions> {
                 if matches.opt_present("file-lines") {
                     unstable_options.push("`--file-lines`");
                 }
+                if matches.opt_present("range") {
+                    unstable_options.push("`--range`");
+                }
                 if !unstable_options.is_empty() {
                     let s = if unstable_options.len() == 1 { "" } else { "s" };
                     return Err(format_err!(
@@ -717,3 +746,13 @@ fn emit_mode_from_emit_str(emit_str: &str) -> Result<EmitMode> {
         _ => Err(format_err!("Invalid value for `--emit`")),
     }
 }
+
+fn range_from_str(range_str: &str) -> Result<Range<usize>> {
+    match range_str
+        .split_once("..")
+        .map(|(lo, hi)| (lo.parse(), hi.parse()))
+    {
+        Some((Ok(lo), Ok(hi))) if lo <= hi => Ok(lo..hi),
+        _ => Err(format_err!("Invalid value for `--range`")),
+    }
+}
@@ -410,7 +410,8 @@ fn consume_same_line_comments(
 }
 
 /// Enum indicating if the code block contains rust based on attributes
-enum CodeBlockAttribute {
+#[derive(PartialEq, Eq)]
+pub(crate) enum CodeBlockAttribute {
     Rust,
     NotRust,
 }
@@ -419,11 +420,11 @@ impl CodeBlockAttribute {
     /// Parse comma separated attributes list. Return rust only if all
     /// attributes are valid rust attributes
     /// See <https://doc.rust-lang.org/rustdoc/print.html#attributes>
-    fn new(attributes: &str) -> CodeBlockAttribute {
+    pub(crate) fn new(attributes: &str) -> CodeBlockAttribute {
         for attribute in attributes.split(',') {
             match attribute.trim() {
                 "" | "rust" | "should_panic" | "no_run" | "edition2015" | "edition2018"
-                | "edition2021" => (),
+                | "edition2021" | "edition2024" => (),
                 "ignore" | "compile_fail" | "text" => return CodeBlockAttribute::NotRust,
                 _ => return CodeBlockAttribute::NotRust,
             }
@@ -772,22 +773,16 @@ fn handle_line(
                         if self.fmt.config.format_code_in_doc_comments()
                             && !self.code_block_buffer.trim().is_empty() =>
                     {
-                        let mut config = self.fmt.config.clone();
-                        config.set().wrap_comments(false);
-                        let comment_max_width = config
-                            .doc_comment_code_block_width()
-                            .min(config.max_width());
-                        config.set().max_width(comment_max_width);
-                        if let Some(s) =
-                            crate::format_code_block(&self.code_block_buffer, &config, false)
-                        {
-                            trim_custom_comment_prefix(&s.snippet)
-                        } else {
-                            trim_custom_comment_prefix(&self.code_block_buffer)
-                        }
+                        format_doc_code_block(&self.code_block_buffer, self.fmt.config)
                     }
-                    _ => trim_custom_comment_prefix(&self.code_block_buffer),
-                };
+                    _ => None,
+                }
+                .unwrap_or_else(|| {
+                    self.code_block_buffer
+                        .lines()
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                });
                 if !code_block.is_empty() {
                     self.result.push_str(&self.comment_line_separator);
                     self.result
@@ -798,8 +793,7 @@ fn handle_line(
                 self.result.push_str(line);
                 self.code_block_attr = None;
             } else {
-                self.code_block_buffer
-                    .push_str(&hide_sharp_behind_comment(line));
+                self.code_block_buffer.push_str(line);
                 self.code_block_buffer.push('\n');
             }
             return false;
@@ -952,6 +946,22 @@ fn rewrite_comment_inner(
     Some(rewriter.finish())
 }
 
+/// Formats a Rust code block of the documentation, keeping the lines hidden with `# ` as they
+/// are. Returns `None` if the code can't be formatted.
+pub(crate) fn format_doc_code_block(code: &str, config: &Config) -> Option<String> {
+    let code: String = code
+        .lines()
+        .map(|line| hide_sharp_behind_comment(line) + "\n")
+        .collect();
+    let mut config = config.clone();
+    config.set().wrap_comments(false);
+    let comment_max_width = config
+        .doc_comment_code_block_width()
+        .min(config.max_width());
+    config.set().max_width(comment_max_width);
+    crate::format_code_block(&code, &config, false).map(|s| trim_custom_comment_prefix(&s.snippet))
+}
+
 const RUSTFMT_CUSTOM_COMMENT_PREFIX: &str = "//#### ";
 
 fn hide_sharp_behind_comment(s: &str) -> Cow<'_, str> {
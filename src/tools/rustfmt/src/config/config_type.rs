@@ -1,5 +1,5 @@
 use crate::config::file_lines::FileLines;
-use crate::config::macro_names::MacroSelectors;
+use crate::config::macro_names::{MacroArgGrammars, MacroSelectors};
 use crate::config::options::{IgnoreList, WidthHeuristics};
 
 /// Trait for types that can be used in `Config`.
@@ -53,6 +53,12 @@ fn doc_hint() -> String {
     }
 }
 
+impl ConfigType for MacroArgGrammars {
+    fn doc_hint() -> String {
+        String::from("[<string>, ...]")
+    }
+}
+
 impl ConfigType for WidthHeuristics {
     fn doc_hint() -> String {
         String::new()
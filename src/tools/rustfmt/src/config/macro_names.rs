@@ -3,7 +3,7 @@
 use itertools::Itertools;
 use std::{fmt, str};
 
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json as json;
 use thiserror::Error;
 
@@ -98,6 +98,111 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
     }
 }
 
+/// Defines how the arguments of a macro invocation are formatted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MacroArgGrammar {
+    /// The arguments are statements and an optional trailing expression, like in a block.
+    Block,
+    /// The arguments are items, like in a module.
+    Items,
+}
+
+impl fmt::Display for MacroArgGrammar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Block => write!(f, "block"),
+            Self::Items => write!(f, "items"),
+        }
+    }
+}
+
+/// The argument grammars of macros, given as `"name=grammar"` strings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MacroArgGrammars(pub Vec<(MacroName, MacroArgGrammar)>);
+
+impl MacroArgGrammars {
+    /// Returns the argument grammar of the macro with the given name, if there is one.
+    pub fn get(&self, name: &str) -> Option<MacroArgGrammar> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(macro_name, _)| macro_name.0 == name)
+            .map(|&(_, grammar)| grammar)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = String> + '_ {
+        self.0
+            .iter()
+            .map(|(name, grammar)| format!("{name}={grammar}"))
+    }
+
+    fn parse_entry(entry: &str) -> Result<(MacroName, MacroArgGrammar), MacroArgGrammarsError> {
+        let invalid = || MacroArgGrammarsError::InvalidEntry(entry.to_owned());
+        let (name, grammar) = entry.split_once('=').ok_or_else(invalid)?;
+        let name = name.trim().trim_end_matches('!');
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        let grammar = match grammar.trim() {
+            "block" => MacroArgGrammar::Block,
+            "items" => MacroArgGrammar::Items,
+            _ => return Err(invalid()),
+        };
+        Ok((MacroName(name.to_owned()), grammar))
+    }
+}
+
+impl fmt::Display for MacroArgGrammars {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.entries().format(", "))
+    }
+}
+
+impl Serialize for MacroArgGrammars {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.entries())
+    }
+}
+
+impl<'de> Deserialize<'de> for MacroArgGrammars {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = Vec::<String>::deserialize(de)?;
+        raw.iter()
+            .map(String::as_str)
+            .map(Self::parse_entry)
+            .collect::<Result<_, _>>()
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MacroArgGrammarsError {
+    #[error("{0}")]
+    Json(json::Error),
+    #[error("invalid macro argument grammar `{0}`, expected `name=block` or `name=items`")]
+    InvalidEntry(String),
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl str::FromStr for MacroArgGrammars {
+    type Err = MacroArgGrammarsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw: Vec<&str> = json::from_str(s).map_err(MacroArgGrammarsError::Json)?;
+        raw.into_iter()
+            .map(Self::parse_entry)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -125,4 +230,22 @@ fn macro_names_display() {
         let macro_names = MacroSelectors::from_str(r#"["foo", "*", "bar"]"#).unwrap();
         assert_eq!(format!("{macro_names}"), "foo, *, bar");
     }
+
+    #[test]
+    fn macro_arg_grammars_from_str() {
+        let grammars = MacroArgGrammars::from_str(r#"["html=block", "sql!=items"]"#).unwrap();
+        assert_eq!(
+            grammars,
+            MacroArgGrammars(vec![
+                (MacroName("html".to_owned()), MacroArgGrammar::Block),
+                (MacroName("sql".to_owned()), MacroArgGrammar::Items),
+            ])
+        );
+        assert_eq!(grammars.get("html"), Some(MacroArgGrammar::Block));
+        assert_eq!(grammars.get("println"), None);
+        assert_eq!(format!("{grammars}"), "html=block, sql=items");
+
+        assert!(MacroArgGrammars::from_str(r#"["html"]"#).is_err());
+        assert!(MacroArgGrammars::from_str(r#"["html=expr"]"#).is_err());
+    }
 }
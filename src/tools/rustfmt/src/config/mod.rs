@@ -12,7 +12,9 @@
 #[allow(unreachable_pub)]
 pub use crate::config::lists::*;
 #[allow(unreachable_pub)]
-pub use crate::config::macro_names::{MacroSelector, MacroSelectors};
+pub use crate::config::macro_names::{
+    MacroArgGrammar, MacroArgGrammars, MacroSelector, MacroSelectors,
+};
 #[allow(unreachable_pub)]
 pub use crate::config::options::*;
 
@@ -76,6 +78,8 @@
     format_macro_bodies: bool, true, false, "Format the bodies of macros";
     skip_macro_invocations: MacroSelectors, MacroSelectors::default(), false,
         "Skip formatting the bodies of macros invoked with the following names.";
+    macro_arg_grammars: MacroArgGrammars, MacroArgGrammars::default(), false,
+        "Format the arguments of the given macros as blocks or items";
     hex_literal_case: HexLiteralCase, HexLiteralCase::Preserve, false,
         "Format hexadecimal integer literals";
 
@@ -632,6 +636,7 @@ fn test_dump_default_config() {
 format_macro_matchers = false
 format_macro_bodies = true
 skip_macro_invocations = []
+macro_arg_grammars = []
 hex_literal_case = "Preserve"
 empty_item_single_line = true
 struct_lit_single_line = true
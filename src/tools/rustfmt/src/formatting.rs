@@ -1,6 +1,7 @@
 // High level formatting functions.
 
 use std::collections::HashMap;
+use std::fs;
 use std::io::{self, Write};
 use std::time::{Duration, Instant};
 
@@ -19,7 +20,9 @@
 use crate::{modules, source_file, ErrorKind, FormatReport, Input, Session};
 
 mod generated;
+mod markdown;
 mod newline_style;
+pub(crate) mod range;
 
 // A map of the files of a crate, with their new content
 pub(crate) type SourceFile = Vec<FileRecord>;
@@ -45,7 +48,11 @@ pub(crate) fn format_input_inner(
             }
 
             let config = &self.config.clone();
-            let format_result = format_project(input, config, self, is_macro_def);
+            let format_result = if input.is_markdown() {
+                format_markdown_file(input, config, self)
+            } else {
+                format_project(input, config, self, is_macro_def)
+            };
 
             format_result.map(|report| {
                 self.errors.add(&report.internal.borrow().1);
@@ -170,6 +177,30 @@ fn format_project<T: FormatHandler>(
     Ok(context.report)
 }
 
+// Format the Rust code blocks of a Markdown file.
+fn format_markdown_file<T: FormatHandler>(
+    input: Input,
+    config: &Config,
+    handler: &mut T,
+) -> Result<FormatReport, ErrorKind> {
+    let path = input.file_name();
+    let psess = ParseSess::new(config)?;
+    let mut report = FormatReport::new();
+    if psess.ignore_file(&path) {
+        return Ok(report);
+    }
+
+    let original = match input {
+        Input::File(ref file) => fs::read_to_string(file)?,
+        Input::Text(text) => text,
+    };
+    should_emit_verbose(false, config, || println!("Formatting {}", path));
+    let mut formatted = markdown::format_markdown(&original, config);
+    apply_newline_style(config.newline_style(), &mut formatted, &original);
+    handler.handle_formatted_file(&psess, path, formatted, &mut report)?;
+    Ok(report)
+}
+
 // Used for formatting files.
 struct FormatContext<'a, T: FormatHandler> {
     krate: &'a ast::Crate,
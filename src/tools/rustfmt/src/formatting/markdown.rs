@@ -0,0 +1,177 @@
+//! Formatting the Rust code blocks of Markdown files, like a `README.md` or the chapters of a
+//! book.
+
+use crate::comment::{format_doc_code_block, CodeBlockAttribute};
+use crate::config::Config;
+
+/// The opening line of a fenced code block, e.g. ```` ```rust ```` or `~~~rust,no_run`.
+/// See <https://spec.commonmark.org/0.30/#fenced-code-blocks>.
+struct Fence<'a> {
+    indent: usize,
+    marker: char,
+    len: usize,
+    info: &'a str,
+}
+
+impl<'a> Fence<'a> {
+    fn new(line: &'a str) -> Option<Fence<'a>> {
+        let (indent, marker, len, rest) = fence_parts(line)?;
+        let info = rest.trim();
+        // The info string of a backtick fence can't contain backticks.
+        if marker == '`' && info.contains('`') {
+            return None;
+        }
+        Some(Fence {
+            indent,
+            marker,
+            len,
+            info,
+        })
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        match fence_parts(line) {
+            Some((_, marker, len, rest)) => {
+                marker == self.marker && len >= self.len && rest.trim().is_empty()
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` if the code block is Rust code to format. Unlike in doc comments, the
+    /// language has to be given, and the doctest attributes must not say that the code doesn't
+    /// compile.
+    fn is_rust(&self) -> bool {
+        self.info
+            .split(',')
+            .any(|attribute| attribute.trim() == "rust")
+            && CodeBlockAttribute::new(self.info) == CodeBlockAttribute::Rust
+    }
+
+    /// Removes the indentation of the fence from a line of the code block.
+    fn unindent<'b>(&self, line: &'b str) -> &'b str {
+        let spaces = line.len() - line.trim_start_matches(' ').len();
+        &line[spaces.min(self.indent)..]
+    }
+}
+
+/// Splits a fence line into its indentation, its marker character, the number of marker
+/// characters, and the rest of the line.
+fn fence_parts(line: &str) -> Option<(usize, char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    let indent = line.len() - trimmed.len();
+    // Lines indented by four spaces or more are part of an indented code block.
+    if indent > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let rest = trimmed.trim_start_matches(marker);
+    let len = trimmed.len() - rest.len();
+    if len < 3 {
+        return None;
+    }
+    Some((indent, marker, len, rest))
+}
+
+/// Formats the Rust code blocks of a Markdown text, and leaves the rest of it as it is.
+pub(crate) fn format_markdown(text: &str, config: &Config) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        result.push_str(line);
+        result.push('\n');
+        let fence = match Fence::new(line) {
+            Some(fence) => fence,
+            None => continue,
+        };
+
+        let mut code_lines = vec![];
+        let mut closing_line = None;
+        for line in lines.by_ref() {
+            if fence.is_closed_by(line) {
+                closing_line = Some(line);
+                break;
+            }
+            code_lines.push(line);
+        }
+
+        let code: String = code_lines
+            .iter()
+            .map(|line| format!("{}\n", fence.unindent(line)))
+            .collect();
+        // A code block which isn't closed runs until the end of the text, which may not be
+        // intended, so it is left as it is.
+        let formatted = if fence.is_rust() && closing_line.is_some() && !code.trim().is_empty() {
+            format_doc_code_block(&code, config)
+        } else {
+            None
+        };
+        match formatted {
+            Some(formatted) => {
+                let indent = " ".repeat(fence.indent);
+                for line in formatted.lines() {
+                    if !line.is_empty() {
+                        result.push_str(&indent);
+                    }
+                    result.push_str(line);
+                    result.push('\n');
+                }
+            }
+            None => {
+                for line in code_lines {
+                    result.push_str(line);
+                    result.push('\n');
+                }
+            }
+        }
+        if let Some(line) = closing_line {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    if !text.ends_with('\n') {
+        result.pop();
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.set().max_width(40);
+        config
+    }
+
+    #[test]
+    fn test_format_markdown() {
+        let text = "# Title\n\n```rust\n# fn main() {\nlet x=vec![1,2];\n# }\n```\n\ntext\n";
+        let expected = "# Title\n\n```rust\n# fn main() {\nlet x = vec![1, 2];\n# }\n```\n\ntext\n";
+        assert_eq!(format_markdown(text, &config()), expected);
+    }
+
+    #[test]
+    fn test_format_markdown_fences() {
+        // The closing fence may be longer than the opening one.
+        let text = "  ~~~rust,no_run\n  let x=1;\n  ~~~~\n";
+        let expected = "  ~~~rust,no_run\n  let x = 1;\n  ~~~~\n";
+        assert_eq!(format_markdown(text, &config()), expected);
+    }
+
+    #[test]
+    fn test_format_markdown_skips_blocks() {
+        for text in [
+            "```\nlet x=1;\n```\n",
+            "```rust,ignore\nlet x=1;\n```\n",
+            "```rust,compile_fail\nlet x=1;\n```\n",
+            "```text\nlet x=1;\n```\n",
+            "```rust\nlet x=1;\n",
+            "```rust\nlet x=;\n```",
+        ] {
+            assert_eq!(format_markdown(text, &config()), text);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Formatting a range of a source file, for the "format selection" command of editors.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops;
+
+use serde::Serialize;
+
+use crate::config::{EmitMode, FileLines, FileName, Range, Verbosity};
+use crate::{ErrorKind, Input, Session};
+
+/// A replacement of some text of the original source.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TextEdit {
+    /// The byte offsets of the replaced text in the original source.
+    pub range: ops::Range<usize>,
+    /// The text replacing it.
+    pub new_text: String,
+}
+
+impl<'b, T: Write + 'b> Session<'b, T> {
+    /// Formats the items and statements of `source` which touch `range`, given in byte offsets,
+    /// and returns the edits to apply to `source`. The rest of `source` is left as it is.
+    ///
+    /// Like with the `file_lines` option, the smallest items and statements containing the
+    /// lines of the range are formatted, so an empty range formats the statement or item of the
+    /// line it is on.
+    pub fn format_range(
+        &mut self,
+        source: &str,
+        range: ops::Range<usize>,
+    ) -> Result<Vec<TextEdit>, ErrorKind> {
+        if range.start > range.end
+            || !source.is_char_boundary(range.start)
+            || !source.is_char_boundary(range.end)
+        {
+            return Err(ErrorKind::InvalidRange(range));
+        }
+        if self.config.disable_all_formatting() {
+            return Ok(Vec::new());
+        }
+
+        let line = |offset: usize| source[..offset].matches('\n').count() + 1;
+        let lo = line(range.start);
+        // A range ending at the start of a line doesn't touch that line.
+        let hi = if range.end > range.start && source[..range.end].ends_with('\n') {
+            line(range.end) - 1
+        } else {
+            line(range.end)
+        };
+
+        let mut config = self.config.clone();
+        config
+            .set()
+            .file_lines(FileLines::from_ranges(HashMap::from([(
+                FileName::Stdin,
+                vec![Range::new(lo, hi)],
+            )])));
+        config.set().emit_mode(EmitMode::Stdout);
+        config.set().verbose(Verbosity::Quiet);
+
+        let mut out: Vec<u8> = Vec::with_capacity(source.len());
+        {
+            let mut session = Session::new(config, Some(&mut out));
+            let result = session.format(Input::Text(source.to_owned()));
+            self.errors.add(&session.errors);
+            result?;
+            if session.has_parsing_errors() {
+                return Err(ErrorKind::ParseError);
+            }
+        }
+
+        // Nothing is emitted when the whole source is skipped.
+        if out.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(text_edits(source, &String::from_utf8_lossy(&out)))
+    }
+}
+
+/// Returns the edits turning `original` into `formatted`, one for each run of changed lines.
+fn text_edits(original: &str, formatted: &str) -> Vec<TextEdit> {
+    let original_lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let formatted_lines: Vec<&str> = formatted.split_inclusive('\n').collect();
+
+    let mut edits = Vec::new();
+    let mut edit: Option<TextEdit> = None;
+    let mut offset = 0;
+    for result in diff::slice(&original_lines, &formatted_lines) {
+        match result {
+            diff::Result::Left(line) => {
+                let edit = edit.get_or_insert_with(|| TextEdit::empty(offset));
+                edit.range.end += line.len();
+                offset += line.len();
+            }
+            diff::Result::Right(line) => {
+                let edit = edit.get_or_insert_with(|| TextEdit::empty(offset));
+                edit.new_text.push_str(line);
+            }
+            diff::Result::Both(line, _) => {
+                edits.extend(edit.take().map(|edit| edit.shrink(original)));
+                offset += line.len();
+            }
+        }
+    }
+    edits.extend(edit.map(|edit| edit.shrink(original)));
+    edits
+}
+
+impl TextEdit {
+    fn empty(offset: usize) -> TextEdit {
+        TextEdit {
+            range: offset..offset,
+            new_text: String::new(),
+        }
+    }
+
+    /// Leaves out the text at the start and the end of the edit which doesn't change, so that
+    /// editors keep the cursor and the marks which are there.
+    fn shrink(mut self, original: &str) -> TextEdit {
+        let old_text = &original[self.range.clone()];
+        let prefix: usize = old_text
+            .chars()
+            .zip(self.new_text.chars())
+            .take_while(|(old, new)| old == new)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        let suffix: usize = old_text[prefix..]
+            .chars()
+            .rev()
+            .zip(self.new_text[prefix..].chars().rev())
+            .take_while(|(old, new)| old == new)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+
+        self.range = self.range.start + prefix..self.range.end - suffix;
+        self.new_text = self.new_text[prefix..self.new_text.len() - suffix].to_owned();
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_text_edits() {
+        let original = "fn a() {}\nfn b( ) {}\nfn c() {}\n";
+        let formatted = "fn a() {}\nfn b() {}\nfn c() {}\n";
+        assert_eq!(
+            text_edits(original, formatted),
+            vec![TextEdit {
+                range: 15..16,
+                new_text: String::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_text_edits_lines() {
+        let original = "fn a()\n{\n}\nfn b() {}\n";
+        let formatted = "fn a() {}\nfn b() {}\n";
+        assert_eq!(
+            text_edits(original, formatted),
+            vec![TextEdit {
+                range: 6..9,
+                new_text: String::from(" {"),
+            }]
+        );
+    }
+}
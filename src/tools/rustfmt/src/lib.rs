@@ -34,6 +34,7 @@
 use std::fmt;
 use std::io::{self, Write};
 use std::mem;
+use std::ops;
 use std::panic;
 use std::path::PathBuf;
 use std::rc::Rc;
@@ -57,6 +58,8 @@
 
 pub use crate::format_report_formatter::{FormatReportFormatter, FormatReportFormatterBuilder};
 
+pub use crate::formatting::range::TextEdit;
+
 pub use crate::rustfmt_diff::{ModifiedChunk, ModifiedLines};
 
 #[macro_use]
@@ -139,6 +142,9 @@ pub enum ErrorKind {
     /// Invalid glob pattern in `ignore` configuration option.
     #[error("Invalid glob pattern found in ignore list: {0}")]
     InvalidGlobPattern(ignore::Error),
+    /// The range to format is not a range of the input.
+    #[error("invalid range to format: {}..{}", .0.start, .0.end)]
+    InvalidRange(ops::Range<usize>),
 }
 
 impl ErrorKind {
@@ -173,6 +179,22 @@ fn unwrap_code_block(&mut self) {
             });
     }
 
+    /// Removes the empty lines at the start of the snippet, shifting up the ranges of
+    /// non-formatted code accordingly.
+    fn trim_leading_newlines(&mut self) {
+        let trimmed = self.snippet.trim_start_matches(['\n', '\r']);
+        let removed_lines = self.snippet[..self.snippet.len() - trimmed.len()]
+            .matches('\n')
+            .count();
+        self.snippet = trimmed.to_owned();
+        self.non_formatted_ranges
+            .iter_mut()
+            .for_each(|(low, high)| {
+                *low = low.saturating_sub(removed_lines);
+                *high = high.saturating_sub(removed_lines);
+            });
+    }
+
     /// Returns `true` if the line n did not get formatted.
     fn is_line_non_formatted(&self, n: usize) -> bool {
         self.non_formatted_ranges
@@ -554,6 +576,16 @@ fn file_name(&self) -> FileName {
         }
     }
 
+    /// Returns `true` if the input is a Markdown file, whose Rust code blocks are formatted.
+    fn is_markdown(&self) -> bool {
+        match self {
+            Input::File(ref file) => file
+                .extension()
+                .map_or(false, |ext| ext.eq_ignore_ascii_case("md")),
+            Input::Text(..) => false,
+        }
+    }
+
     fn to_directory_ownership(&self) -> Option<DirectoryOwnership> {
         match self {
             Input::File(ref file) => {
@@ -25,6 +25,7 @@
     contains_comment, CharClasses, FindUncommented, FullCodeCharKind, LineClasses,
 };
 use crate::config::lists::*;
+use crate::config::{Config, MacroArgGrammar, Version};
 use crate::expr::{rewrite_array, rewrite_assign_rhs, RhsAssignKind};
 use crate::lists::{itemize_list, write_list, ListFormatting};
 use crate::overflow;
@@ -39,6 +40,7 @@
     remove_trailing_white_spaces, rewrite_ident, trim_left_preserve_layout, NodeIdExt,
 };
 use crate::visitor::FmtVisitor;
+use crate::FormattedSnippet;
 
 const FORCED_BRACKET_MACROS: &[&str] = &["vec!"];
 
@@ -232,6 +234,25 @@ fn rewrite_macro_inner(
         }
     }
 
+    if let Some(grammar) = context
+        .config
+        .macro_arg_grammars()
+        .get(context.snippet(mac.path.span))
+    {
+        return rewrite_macro_with_grammar(
+            context,
+            grammar,
+            &macro_name,
+            shape,
+            style,
+            position,
+            mac.args.dspan.entire(),
+        )
+        .or_else(|| {
+            return_macro_parse_failure_fallback(context, shape.indent, position, mac.span())
+        });
+    }
+
     let ParsedMacroArgs {
         args: arg_vec,
         vec_with_semi,
@@ -465,9 +486,20 @@ pub(crate) fn rewrite_macro_def(
     )
     .collect::<Vec<_>>();
 
+    // With version Two the arms always get formatted, so the `;` after the last arm is only
+    // written if it was in the original code.
+    let trailing_separator = match parsed_def.branches.last() {
+        Some(branch)
+            if context.config.version() == Version::Two
+                && !context.snippet(branch.span).ends_with(';') =>
+        {
+            SeparatorTactic::Never
+        }
+        _ => SeparatorTactic::Always,
+    };
     let fmt = ListFormatting::new(arm_shape, context.config)
         .separator(if def.macro_rules { ";" } else { "" })
-        .trailing_separator(SeparatorTactic::Always)
+        .trailing_separator(trailing_separator)
         .preserve_newline(true);
 
     if multi_branch_style {
@@ -1239,6 +1271,10 @@ fn rewrite(
         shape: Shape,
         multi_branch_style: bool,
     ) -> Option<String> {
+        if context.config.version() == Version::Two {
+            return self.rewrite_separately(context, shape, multi_branch_style);
+        }
+
         // Only attempt to format function-like macros.
         if self.args_paren_kind != Delimiter::Parenthesis {
             // FIXME(#1539): implement for non-sugared macros.
@@ -1252,10 +1288,49 @@ fn rewrite(
             result += " =>";
         }
 
+        result += " ";
+        result += &self.rewrite_body(context, shape)?;
+
+        Some(result)
+    }
+
+    /// Formats the matcher and the transcriber separately, leaving each of them as it is when it
+    /// can't be formatted.
+    fn rewrite_separately(
+        &self,
+        context: &RewriteContext<'_>,
+        shape: Shape,
+        multi_branch_style: bool,
+    ) -> Option<String> {
+        let args_span = span_for_token_stream(&self.args)?;
+        // 5 = " => {"
+        let mut result = shape
+            .sub_width(5)
+            .and_then(|args_shape| format_macro_args(context, self.args.clone(), args_shape))
+            .unwrap_or_else(|| context.snippet(args_span).to_owned());
+
+        if multi_branch_style {
+            result += " =>";
+        }
+
+        result += " ";
+        match self.rewrite_body(context, shape) {
+            Some(body) => result += &body,
+            None => {
+                let body = context.snippet(self.whole_body);
+                match trim_left_preserve_layout(body, shape.indent, context.config) {
+                    Some(body) => result += &body,
+                    None => result += body,
+                }
+            }
+        }
+
+        Some(result)
+    }
+
+    fn rewrite_body(&self, context: &RewriteContext<'_>, shape: Shape) -> Option<String> {
         if !context.config.format_macro_bodies() {
-            result += " ";
-            result += context.snippet(self.whole_body);
-            return Some(result);
+            return Some(context.snippet(self.whole_body).to_owned());
         }
 
         // The macro body is the most interesting part. It might end up as various
@@ -1271,7 +1346,7 @@ fn rewrite(
         let mut config = context.config.clone();
         config.set().hide_parse_errors(true);
 
-        result += " {";
+        let mut result = String::from("{");
 
         let body_indent = if has_block_body {
             shape.indent
@@ -1299,22 +1374,7 @@ fn rewrite(
         }
 
         // Indent the body since it is in a block.
-        let indent_str = body_indent.to_string(&config);
-        let mut new_body = LineClasses::new(new_body_snippet.snippet.trim_end())
-            .enumerate()
-            .fold(
-                (String::new(), true),
-                |(mut s, need_indent), (i, (kind, ref l))| {
-                    if !is_empty_line(l)
-                        && need_indent
-                        && !new_body_snippet.is_line_non_formatted(i + 1)
-                    {
-                        s += &indent_str;
-                    }
-                    (s + l + "\n", indent_next_line(kind, l, &config))
-                },
-            )
-            .0;
+        let mut new_body = indent_formatted_snippet(&new_body_snippet, body_indent, &config);
 
         // Undo our replacement of macro variables.
         // FIXME: this could be *much* more efficient.
@@ -1340,6 +1400,82 @@ fn rewrite(
     }
 }
 
+/// Indents the lines of a formatted snippet, except for the lines which were left as they were.
+fn indent_formatted_snippet(snippet: &FormattedSnippet, indent: Indent, config: &Config) -> String {
+    let indent_str = indent.to_string(config);
+    LineClasses::new(snippet.snippet.trim_end())
+        .enumerate()
+        .fold(
+            (String::new(), true),
+            |(mut s, need_indent), (i, (kind, ref l))| {
+                if !is_empty_line(l) && need_indent && !snippet.is_line_non_formatted(i + 1) {
+                    s += &indent_str;
+                }
+                (s + l + "\n", indent_next_line(kind, l, config))
+            },
+        )
+        .0
+}
+
+/// Format the arguments of a macro with a grammar given in the `macro_arg_grammars` option, as
+/// the body of a block or of a module. `args_span` includes the delimiters.
+fn rewrite_macro_with_grammar(
+    context: &RewriteContext<'_>,
+    grammar: MacroArgGrammar,
+    macro_name: &str,
+    shape: Shape,
+    style: Delimiter,
+    position: MacroPosition,
+    args_span: Span,
+) -> Option<String> {
+    let (opener, closer) = match style {
+        Delimiter::Parenthesis => ("(", ")"),
+        Delimiter::Bracket => ("[", "]"),
+        Delimiter::Brace => (" {", "}"),
+        Delimiter::Invisible => return None,
+    };
+    let trailing_semicolon = match style {
+        Delimiter::Parenthesis | Delimiter::Bracket if position == MacroPosition::Item => ";",
+        _ => "",
+    };
+
+    let args = context.snippet(args_span);
+    let inner = args.get(1..args.len().checked_sub(1)?)?;
+
+    let nested_indent = shape.indent.block_indent(context.config);
+    let mut config = context.config.clone();
+    let new_width = config.max_width().checked_sub(nested_indent.width())?;
+    config.set().max_width(new_width);
+    let mut formatted = match grammar {
+        MacroArgGrammar::Items => crate::format_snippet(inner, &config, false)?,
+        MacroArgGrammar::Block => {
+            // `format_code_block` wraps the code in a function.
+            let width = new_width + config.tab_spaces();
+            config.set().max_width(width);
+            crate::format_code_block(inner, &config, false)?
+        }
+    };
+    // The opening delimiter is followed by a newline already.
+    formatted.trim_leading_newlines();
+    if formatted.snippet.trim().is_empty() {
+        return Some(format!("{macro_name}{opener}{closer}{trailing_semicolon}"));
+    }
+
+    let mut result = String::with_capacity(256);
+    result.push_str(macro_name);
+    result.push_str(opener);
+    result.push('\n');
+    result.push_str(&indent_formatted_snippet(
+        &formatted,
+        nested_indent,
+        context.config,
+    ));
+    result.push_str(&shape.indent.to_string(context.config));
+    result.push_str(closer);
+    result.push_str(trailing_semicolon);
+    Some(result)
+}
+
 /// Format `lazy_static!` from <https://crates.io/crates/lazy_static>.
 ///
 /// # Expected syntax
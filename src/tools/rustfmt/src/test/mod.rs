@@ -13,7 +13,10 @@
 use crate::formatting::{ReportedErrors, SourceFile};
 use crate::rustfmt_diff::{make_diff, print_diff, DiffLine, Mismatch, ModifiedChunk, OutputWriter};
 use crate::source_file;
-use crate::{is_nightly_channel, FormatReport, FormatReportFormatterBuilder, Input, Session};
+use crate::{
+    is_nightly_channel, ErrorKind, FormatReport, FormatReportFormatterBuilder, Input, Session,
+    TextEdit,
+};
 
 use rustfmt_config_proc_macro::nightly_only_test;
 
@@ -598,6 +601,81 @@ fn stdin_handles_mod_inner_ignore_attr() {
     assert_eq!(input, String::from_utf8(output.stdout).unwrap());
 }
 
+#[test]
+fn format_range_only_formats_the_range() {
+    init_log();
+    let input = "fn a( ) {}\nfn b() {\n    let x=1;\n    let y=2;\n}\n";
+    let start = input.find("let y").unwrap();
+    let mut session = Session::<io::Stdout>::new(Config::default(), None);
+    let edits = session.format_range(input, start..start + 1).unwrap();
+    assert_eq!(
+        edits,
+        vec![TextEdit {
+            range: start + 5..start + 6,
+            new_text: " = ".to_owned(),
+        }]
+    );
+    assert!(session.has_no_errors());
+}
+
+#[test]
+fn format_range_ending_at_line_start() {
+    init_log();
+    let input = "fn a( ) {}\nfn b( ) {}\n";
+    let mut session = Session::<io::Stdout>::new(Config::default(), None);
+    // The range ends at the start of `fn b`, which is left as it is.
+    let edits = session.format_range(input, 0..11).unwrap();
+    assert_eq!(
+        edits,
+        vec![TextEdit {
+            range: 5..6,
+            new_text: String::new(),
+        }]
+    );
+}
+
+#[test]
+fn format_range_errors() {
+    init_log();
+    let mut session = Session::<io::Stdout>::new(Config::default(), None);
+    assert!(matches!(
+        session.format_range("fn main() {}", 5..20),
+        Err(ErrorKind::InvalidRange(_))
+    ));
+    assert!(matches!(
+        session.format_range("fn main( {}", 0..1),
+        Err(ErrorKind::ParseError)
+    ));
+    assert!(session.has_parsing_errors());
+}
+
+#[nightly_only_test]
+#[test]
+fn stdin_format_range() {
+    init_log();
+    let input = "fn a( ) {}\nfn b( ) {}\n";
+    let mut child = Command::new(rustfmt().to_str().unwrap())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .args(["--unstable-features", "--range", "11..12"])
+        .spawn()
+        .expect("failed to execute child");
+
+    {
+        let stdin = child.stdin.as_mut().expect("failed to get stdin");
+        stdin
+            .write_all(input.as_bytes())
+            .expect("failed to write stdin");
+    }
+
+    let output = child.wait_with_output().expect("failed to wait on child");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[{\"range\":{\"start\":16,\"end\":17},\"new_text\":\"\"}]\n"
+    );
+}
+
 #[test]
 fn format_lines_errors_are_reported() {
     init_log();
@@ -184,3 +184,14 @@ fn dont_emit_ICE() {
         assert!(!stderr.contains("thread 'main' panicked"));
     }
 }
+
+#[test]
+fn check_markdown_code_blocks() {
+    let args = ["--check", "tests/markdown/unformatted.md"];
+    let (stdout, _stderr) = rustfmt(&args);
+    assert!(stdout.contains("+let mut map = HashMap::new();"));
+    assert!(stdout.contains("+map.insert(\"a\", 1);"));
+    // The hidden lines and the ignored blocks are left as they are.
+    assert!(!stdout.contains("-# use std::collections::HashMap;"));
+    assert!(!stdout.contains("+let x = 1;"));
+}
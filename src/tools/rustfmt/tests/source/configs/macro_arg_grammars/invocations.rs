@@ -0,0 +1,8 @@
+// rustfmt-macro_arg_grammars: ["html=block","routes=items"]
+
+routes!(fn index()->u8{1});
+
+fn main() {
+    html! { let x=1 ; x }
+    html! { <div> }
+}
@@ -0,0 +1,23 @@
+// rustfmt-version: Two
+// rustfmt-format_macro_matchers: true
+
+// The body of the first arm has repetitions, which can't be formatted, but its matcher and the
+// second arm are still formatted.
+macro_rules! foo {
+    ($($a:ident),*) => {
+        $(let $a=1;)*
+    };
+    [$b:expr] => {
+        let x = $b;
+    };
+}
+
+// There is no `;` after the last arm, so none is added.
+macro_rules! bar {
+    ($a:ident) => {
+        let $a = 1;
+    };
+    ($a:ident, $b:expr) => {
+        let $a = $b;
+    }
+}
@@ -0,0 +1 @@
+assert_eq!(foo::add(2, 2), 4);
@@ -0,0 +1,8 @@
+#![crate_type = "lib"]
+#![feature(doc_example)]
+
+/// Adds two numbers.
+#[doc(example = "examples/add.rs")]
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
@@ -0,0 +1,12 @@
+// Checks that the files included with `#[doc(example)]` are part of the dependencies of the crate,
+// even though attributes are only checked after the dependencies are written.
+
+use std::fs;
+
+use run_make_support::{rustc, tmp_dir};
+
+fn main() {
+    rustc().input("foo.rs").emit("dep-info").run();
+    let deps = fs::read_to_string(tmp_dir().join("foo.d")).unwrap();
+    assert!(deps.contains("examples/add.rs"), "{deps}");
+}
@@ -0,0 +1,22 @@
+#[repr(C)]
+pub struct Pair {
+    pub a: u64,
+    pub b: f64,
+}
+
+#[repr(C)]
+pub struct Big {
+    pub values: [u64; 3],
+}
+
+#[no_mangle]
+pub extern "C" fn sum(pair: Pair, big: Big, extra: u32) -> u64 {
+    pair.a + pair.b as u64 + big.values[0] + extra as u64
+}
+
+#[no_mangle]
+pub static mut COUNTER: u32 = 0;
+
+// Neither of these is part of the C ABI surface.
+pub fn rust_fn() {}
+pub struct NotRepr(pub u8);
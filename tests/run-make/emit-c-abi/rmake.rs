@@ -0,0 +1,38 @@
+// `--emit=c-abi` describes exported `extern "C"` functions, including how each argument is
+// passed, exported statics, and the layouts of `#[repr(C)]` types. Argument passing depends on
+// the target's calling convention, so this checks the x86_64 System V classification.
+
+//@ only-x86_64-unknown-linux-gnu
+
+use run_make_support::{rustc, tmp_dir};
+
+fn main() {
+    let path = tmp_dir().join("lib.c-abi.json");
+    rustc()
+        .input("lib.rs")
+        .crate_type("cdylib")
+        .arg("-Zunstable-options")
+        .emit(&format!("c-abi={}", path.display()))
+        .run();
+
+    let json = std::fs::read_to_string(&path).unwrap();
+    let json: String = json.chars().filter(|c| !c.is_whitespace()).collect();
+    let assert_contains = |needle: &str| {
+        assert!(json.contains(needle), "`{needle}` not found in:\n{json}");
+    };
+
+    assert_contains(r#""path":"lib::sum","symbol":"sum""#);
+    // A 16-byte struct of an integer and a float is split into one register of each class.
+    assert_contains(
+        r#"{"ty":"lib::Pair","size":16,"align":8,"pass":{"mode":"cast","pad_i32":false,"registers":["i64","f64"]}}"#,
+    );
+    // Larger structs are copied onto the stack.
+    assert_contains(r#"{"ty":"lib::Big","size":24,"align":8,"pass":{"mode":"indirect","on_stack":true}}"#);
+    assert_contains(r#"{"ty":"u32","size":4,"align":4,"pass":{"mode":"direct"}}"#);
+
+    assert_contains(r#"{"path":"lib::COUNTER","symbol":"COUNTER","ty":"u32","mutable":true"#);
+
+    assert_contains(r#""path":"lib::Pair","kind":"struct","repr":["C"]"#);
+    assert!(!json.contains("rust_fn"), "Rust ABI function listed in:\n{json}");
+    assert!(!json.contains("NotRepr"), "type without FFI representation listed in:\n{json}");
+}
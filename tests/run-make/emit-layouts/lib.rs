@@ -0,0 +1,25 @@
+#[repr(C)]
+pub struct Header {
+    pub tag: u8,
+    pub len: u32,
+}
+
+#[repr(u8)]
+pub enum Kind {
+    A = 1,
+    B(u16) = 7,
+}
+
+#[repr(C)]
+pub struct Flags {
+    pub enabled: bool,
+    pub pair: (u16, bool),
+}
+
+pub enum MaybeRef<'a> {
+    None,
+    Some(&'a u8),
+}
+
+// Generic types do not have a layout on their own, so they are not listed.
+pub struct Wrapper<T>(pub T);
@@ -0,0 +1,45 @@
+// `--emit=layouts` writes the layouts of all non-generic ADTs of the crate as JSON, including
+// field offsets, niches and how enum discriminants are encoded.
+
+//@ ignore-cross-compile
+
+use run_make_support::{rustc, tmp_dir};
+
+fn main() {
+    let path = tmp_dir().join("lib.layouts.json");
+    rustc()
+        .input("lib.rs")
+        .crate_type("lib")
+        .arg("-Zunstable-options")
+        .emit(&format!("layouts={}", path.display()))
+        .run();
+
+    let json = std::fs::read_to_string(&path).unwrap();
+    let json: String = json.chars().filter(|c| !c.is_whitespace()).collect();
+    let assert_contains = |needle: &str| {
+        assert!(json.contains(needle), "`{needle}` not found in:\n{json}");
+    };
+
+    assert_contains(r#""path":"lib::Header","kind":"struct","repr":["C"]"#);
+    assert_contains(r#"{"name":"tag","ty":"u8","offset":0,"size":1,"align":1}"#);
+    assert_contains(r#"{"name":"len","ty":"u32","offset":4,"size":4,"align":4}"#);
+
+    assert_contains(r#""path":"lib::Kind","kind":"enum","repr":["u8"]"#);
+    assert_contains(r#""discriminant":{"encoding":"direct","tag_offset":0,"tag_size":1}"#);
+    assert_contains(r#"{"name":"B","discriminant":"7""#);
+
+    // Every niche of a field is listed, including the ones of nested fields.
+    assert_contains(
+        r#"{"name":"enabled","ty":"bool","offset":0,"size":1,"align":1,"niches":[{"offset":0,"size":1,"valid_range_start":"0","valid_range_end":"1"}]}"#,
+    );
+    assert_contains(
+        r#"{"name":"pair","ty":"(u16,bool)","offset":2,"size":4,"align":2,"niches":[{"offset":4,"size":1,"valid_range_start":"0","valid_range_end":"1"}]}"#,
+    );
+    assert_contains(r#""largest_niche":{"offset":"#);
+
+    assert_contains(r#""path":"lib::MaybeRef","kind":"enum","repr":[]"#);
+    assert_contains(r#""encoding":"niche","tag_offset":0"#);
+    assert_contains(r#""untagged_variant":"Some","niche_variants":["None","None"],"niche_start":"0""#);
+
+    assert!(!json.contains("lib::Wrapper"), "generic type listed in:\n{json}");
+}
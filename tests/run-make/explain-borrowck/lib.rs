@@ -0,0 +1,10 @@
+pub fn first<'a, 'b>(_x: &'a u32, y: &'b u32) -> &'a u32 {
+    let v = &*y;
+    v
+}
+
+pub fn unrelated(v: &mut Vec<u32>) {
+    let r = &v[0];
+    println!("{r}");
+    v.push(1);
+}
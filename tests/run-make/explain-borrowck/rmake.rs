@@ -0,0 +1,52 @@
+// `-Z explain-borrowck=<fn path>` writes the live ranges of the borrows of that function and the
+// outlives constraints behind its region errors, as text, HTML and graphviz.
+
+use run_make_support::{rustc, tmp_dir};
+
+fn main() {
+    rustc()
+        .input("lib.rs")
+        .crate_type("lib")
+        .arg("-Zexplain-borrowck=lib::first")
+        .run_fail_assert_exit_code(1);
+
+    let text = std::fs::read_to_string(tmp_dir().join("lib.first.borrowck.txt")).unwrap();
+    let assert_contains = |needle: &str| {
+        assert!(text.contains(needle), "`{needle}` not found in:\n{text}");
+    };
+
+    // The reborrow of `y` is returned, so it has to outlive `'a`.
+    assert_contains("bw0: &*y");
+    assert_contains("must outlive 'a:");
+    // It is created on line 2 and still live when it is returned on line 3.
+    assert_contains("    *   2 |     let v = &*y;");
+    assert_contains("    |   3 |     v");
+    // The error, with the constraint it points at marked.
+    assert_contains("'b must outlive 'a");
+    assert_contains("  > ");
+
+    assert!(tmp_dir().join("lib.first.borrowck.html").exists());
+    let dot = std::fs::read_to_string(tmp_dir().join("lib.first.borrowck.dot")).unwrap();
+    assert!(dot.starts_with("digraph ConstraintPaths"), "{dot}");
+
+    // Other functions are not explained.
+    assert!(!tmp_dir().join("lib.unrelated.borrowck.txt").exists());
+
+    // The flag is tracked, so a function is explained even if the crate was compiled
+    // incrementally without it before.
+    let incr = tmp_dir().join("incr");
+    rustc().input("ok.rs").crate_type("lib").incremental(&incr).run();
+    rustc()
+        .input("ok.rs")
+        .crate_type("lib")
+        .incremental(&incr)
+        .arg("-Zexplain-borrowck=ok::longest")
+        .run();
+    assert!(tmp_dir().join("ok.longest.borrowck.txt").exists());
+
+    // A path matching no function is reported.
+    let output =
+        rustc().input("ok.rs").crate_type("lib").arg("-Zexplain-borrowck=ok::missing").run();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no function matches `-Z explain-borrowck=ok::missing`"), "{stderr}");
+}
@@ -0,0 +1,15 @@
+#[inline(never)]
+fn generic<T: Default>() -> T {
+    T::default()
+}
+
+fn not_generic() -> u8 {
+    0
+}
+
+fn main() {
+    let _: u8 = generic();
+    let _: u16 = generic();
+    let _: String = generic();
+    not_generic();
+}
@@ -0,0 +1,19 @@
+// `-Zmono-item-stats` prints one row per generic definition, with the number of distinct
+// instantiations and the crates that requested them. Non-generic items must not be listed.
+
+use run_make_support::rustc;
+
+fn main() {
+    let output = rustc().input("main.rs").arg("-Zmono-item-stats").run();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let row = stdout
+        .lines()
+        .find(|line| line.starts_with("| `generic` |"))
+        .unwrap_or_else(|| panic!("no row for `generic` in:\n{stdout}"));
+    let columns: Vec<_> = row.split('|').map(str::trim).collect();
+    assert_eq!(columns[2], "3", "unexpected instantiation count in {row:?}");
+    assert_eq!(columns[4], "`main`", "unexpected requesting crates in {row:?}");
+
+    assert!(!stdout.contains("`not_generic`"), "non-generic item listed in:\n{stdout}");
+}
@@ -0,0 +1,26 @@
+use std::rc::Rc;
+
+pub fn unchanged(x: u32) -> u32 {
+    x
+}
+
+pub fn added() {}
+
+pub fn retyped(x: u64) -> u64 {
+    x
+}
+
+pub enum Exhaustive {
+    A,
+    B,
+}
+
+pub struct Handle {
+    pub id: u32,
+    _not_send: Rc<()>,
+}
+
+pub trait Visitor {
+    fn visit(&self);
+    fn finish(&self) {}
+}
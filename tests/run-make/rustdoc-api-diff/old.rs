@@ -0,0 +1,21 @@
+pub fn unchanged(x: u32) -> u32 {
+    x
+}
+
+pub fn removed() {}
+
+pub fn retyped(x: u32) -> u32 {
+    x
+}
+
+pub enum Exhaustive {
+    A,
+}
+
+pub struct Handle {
+    pub id: u32,
+}
+
+pub trait Visitor {
+    fn visit(&self);
+}
@@ -0,0 +1,53 @@
+// Checks that `--output-format api-diff` reports the differences between the public API of a crate
+// and a baseline generated with `--output-format json`, classified by their semver impact.
+
+use std::fs;
+
+use run_make_support::{rustdoc, tmp_dir};
+
+fn main() {
+    let baseline_dir = tmp_dir().join("baseline");
+    let diff_dir = tmp_dir().join("diff");
+
+    rustdoc()
+        .input("old.rs")
+        .crate_name("foo")
+        .crate_type("lib")
+        .arg("-Zunstable-options")
+        .output_format("json")
+        .out_dir(&baseline_dir)
+        .run();
+
+    let output = rustdoc()
+        .input("new.rs")
+        .crate_name("foo")
+        .crate_type("lib")
+        .arg("-Zunstable-options")
+        .output_format("api-diff")
+        .arg("--baseline")
+        .arg(baseline_dir.join("foo.json"))
+        .out_dir(&diff_dir)
+        .run();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    for expected in [
+        "major removed function `foo::removed`",
+        "major changed function `foo::retyped`",
+        "        - fn retyped(x: u32) -> u32",
+        "        + fn retyped(x: u64) -> u64",
+        "major added variant `foo::Exhaustive::B`",
+        "major changed struct `foo::Handle`",
+        "major changed auto trait impl `foo::Handle::<impl core::marker::Send>`",
+        "minor added function `foo::added`",
+        "minor added trait method `foo::Visitor::finish`",
+        "required version bump: major",
+    ] {
+        assert!(stdout.lines().any(|line| line == expected), "missing `{expected}` in:\n{stdout}");
+    }
+    assert!(!stdout.contains("`foo::unchanged`"), "unexpected change in:\n{stdout}");
+
+    // The report is also written as JSON.
+    let report = fs::read_to_string(diff_dir.join("foo-api-diff.json")).unwrap();
+    assert!(report.contains(r#""required_bump":"major""#));
+    assert!(report.contains(r#""path":"foo::added","item_kind":"function","change":"added""#));
+}
@@ -0,0 +1,5 @@
+/// ```
+/// std::fs::write(std::env::var("DOCTEST_MARKER").unwrap(), "").unwrap();
+/// assert_eq!(bar::answer(), 42);
+/// ```
+pub fn question() {}
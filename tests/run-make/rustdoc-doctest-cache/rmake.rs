@@ -0,0 +1,50 @@
+// Checks that `--doctest-cache-dir` skips the doctests that passed before, and runs them again when
+// their code or a crate they use changes. The doctest creates a marker file when it runs.
+//@ ignore-cross-compile
+
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+use run_make_support::{rustc, rustdoc, tmp_dir};
+
+/// Runs the doctests of `foo.rs` and returns whether the doctest ran.
+fn doctests(source: &str) -> bool {
+    let input = tmp_dir().join("foo.rs");
+    let marker = tmp_dir().join("marker");
+    fs::write(&input, source).unwrap();
+    let _ = fs::remove_file(&marker);
+    rustdoc()
+        .input(&input)
+        .crate_name("foo")
+        .arg("-Zunstable-options")
+        .arg("--test")
+        .arg("--doctest-cache-dir")
+        .arg(tmp_dir().join("cache"))
+        .extern_("bar", tmp_dir().join("libbar.rlib"))
+        .env("DOCTEST_MARKER", &marker)
+        .run();
+    marker.exists()
+}
+
+fn main() {
+    let foo = fs::read_to_string("foo.rs").unwrap();
+    let bar = tmp_dir().join("bar.rs");
+    fs::copy("bar.rs", &bar).unwrap();
+    rustc().input(&bar).crate_type("rlib").run();
+
+    assert!(doctests(&foo), "the doctest didn't run the first time");
+    assert!(!doctests(&foo), "the doctest wasn't skipped");
+
+    // Changing the code of the doctest runs it again.
+    let foo = foo.replace("42);", "42, \"the answer\");");
+    assert!(doctests(&foo), "the doctest wasn't run after changing it");
+    assert!(!doctests(&foo), "the changed doctest wasn't skipped");
+
+    // So does changing a crate it uses, but not only touching it.
+    let rlib = fs::File::options().write(true).open(tmp_dir().join("libbar.rlib")).unwrap();
+    rlib.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+    assert!(!doctests(&foo), "the doctest wasn't skipped after touching `bar`");
+    fs::write(&bar, fs::read_to_string("bar.rs").unwrap().replace("42", "40 + 2")).unwrap();
+    rustc().input(&bar).crate_type("rlib").run();
+    assert!(doctests(&foo), "the doctest wasn't run after changing `bar`");
+}
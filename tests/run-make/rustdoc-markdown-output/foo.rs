@@ -0,0 +1,25 @@
+//! Tools for parsing.
+//!
+//! # Examples
+//!
+//! Start with [`parse::Parser::new`].
+
+pub mod parse {
+    /// A parser, see [`tokenize`](crate::tokenize).
+    pub struct Parser {
+        /// The input being parsed.
+        pub input: String,
+    }
+
+    impl Parser {
+        /// Creates a [`Parser`].
+        pub fn new(input: &str) -> Parser {
+            Parser { input: input.to_owned() }
+        }
+    }
+}
+
+/// Splits `input` into tokens for a [`parse::Parser`].
+pub fn tokenize(input: &str) -> Vec<&str> {
+    input.split_whitespace().collect()
+}
@@ -0,0 +1,85 @@
+// Checks that `--output-format markdown` writes one page per module and item, and that intra-doc
+// links are rewritten to relative paths between the pages. `--output-format man` writes the same
+// pages as man pages, where links become man page references.
+
+use std::fs;
+
+use run_make_support::{rustdoc, tmp_dir};
+
+fn main() {
+    let out_dir = tmp_dir().join("doc");
+    rustdoc()
+        .input("foo.rs")
+        .crate_name("foo")
+        .crate_type("lib")
+        .arg("-Zunstable-options")
+        .output_format("markdown")
+        .out_dir(&out_dir)
+        .run();
+
+    let read = |path: &str| fs::read_to_string(out_dir.join(path)).unwrap();
+
+    let krate = read("foo/index.md");
+    for expected in [
+        "# Crate foo",
+        "## Examples",
+        "Start with [`parse::Parser::new`](parse/struct.Parser.md#method.new).",
+        "- [parse](parse/index.md)",
+        "- [tokenize](fn.tokenize.md): Splits `input` into tokens for a `parse::Parser`.",
+    ] {
+        assert!(krate.lines().any(|line| line == expected), "missing `{expected}` in:\n{krate}");
+    }
+
+    let tokenize = read("foo/fn.tokenize.md");
+    assert!(tokenize.contains("fn tokenize(input: &str) -> Vec<&str>"), "{tokenize}");
+    assert!(tokenize.contains("[`parse::Parser`](parse/struct.Parser.md)"), "{tokenize}");
+
+    let parser = read("foo/parse/struct.Parser.md");
+    for expected in [
+        "# Struct foo::parse::Parser",
+        "A parser, see [`tokenize`](../fn.tokenize.md).",
+        "<a id=\"structfield.input\"></a>",
+        "### `input: String`",
+        "<a id=\"method.new\"></a>",
+        "#### `fn new(input: &str) -> Parser`",
+        "Creates a [`Parser`](struct.Parser.md).",
+    ] {
+        assert!(parser.lines().any(|line| line == expected), "missing `{expected}` in:\n{parser}");
+    }
+    assert!(out_dir.join("foo/parse/index.md").exists());
+
+    let man_dir = tmp_dir().join("man");
+    rustdoc()
+        .input("foo.rs")
+        .crate_name("foo")
+        .crate_type("lib")
+        .arg("-Zunstable-options")
+        .output_format("man")
+        .out_dir(&man_dir)
+        .run();
+
+    let read = |page: &str| fs::read_to_string(man_dir.join("man3").join(page)).unwrap();
+
+    let krate = read("foo.3");
+    for expected in [
+        ".TH \"foo\" 3",
+        "foo \\- Crate foo",
+        ".SH Examples",
+        "Start with \\fBparse::Parser::new\\fR (\\fBfoo.parse.struct.Parser\\fR(3)).",
+        ".IP \\(bu 2",
+        "parse (\\fBfoo.parse\\fR(3))",
+    ] {
+        assert!(krate.lines().any(|line| line == expected), "missing `{expected}` in:\n{krate}");
+    }
+
+    let parser = read("foo.parse.struct.Parser.3");
+    for expected in [
+        "foo.parse.struct.Parser \\- Struct foo::parse::Parser",
+        ".SS \\fBinput: String\\fR",
+        ".SS \\fBfn new(input: &str) \\-> Parser\\fR",
+    ] {
+        assert!(parser.lines().any(|line| line == expected), "missing `{expected}` in:\n{parser}");
+    }
+    assert!(!parser.contains("<a id="), "{parser}");
+    assert!(man_dir.join("man3/foo.fn.tokenize.3").exists());
+}
@@ -0,0 +1,9 @@
+#![crate_name = "bar"]
+
+extern crate foo;
+
+pub struct Bar;
+
+impl foo::Greet for Bar {
+    fn greet(&self) {}
+}
@@ -0,0 +1,11 @@
+#![crate_name = "foo"]
+
+pub trait Greet {
+    fn greet(&self);
+}
+
+pub struct Foo;
+
+impl Greet for Foo {
+    fn greet(&self) {}
+}
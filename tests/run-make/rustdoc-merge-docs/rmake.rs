@@ -0,0 +1,32 @@
+// Check that merging documentation built into separate directories with `--merge-docs` gives
+// the same shared files as documenting the crates into the same directory.
+
+use run_make_support::{diff, rust_lib, rustc, rustdoc, tmp_dir};
+
+fn main() {
+    rustc().input("foo.rs").crate_type("lib").run();
+
+    let together = tmp_dir().join("together");
+    rustdoc().input("foo.rs").output(&together).run();
+    rustdoc().input("bar.rs").extern_("foo", rust_lib("foo")).output(&together).run();
+
+    let foo_doc = tmp_dir().join("foo_doc");
+    let bar_doc = tmp_dir().join("bar_doc");
+    rustdoc().input("foo.rs").output(&foo_doc).run();
+    rustdoc().input("bar.rs").extern_("foo", rust_lib("foo")).output(&bar_doc).run();
+
+    let merged = tmp_dir().join("merged");
+    rustdoc()
+        .arg("-Zunstable-options")
+        .arg("--merge-docs")
+        .output(&merged)
+        .arg(&foo_doc)
+        .arg(&bar_doc)
+        .run();
+
+    for file in ["search-index.js", "crates.js", "src-files.js", "trait.impl/foo/trait.Greet.js"] {
+        diff().expected_file(together.join(file)).actual_file(merged.join(file)).run();
+    }
+    assert!(merged.join("foo/struct.Foo.html").is_file());
+    assert!(merged.join("bar/struct.Bar.html").is_file());
+}
@@ -0,0 +1,4 @@
+fn main() {
+    foobar::unchanged(0);
+    foobar::changed(0);
+}
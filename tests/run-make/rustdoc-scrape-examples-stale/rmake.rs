@@ -0,0 +1,41 @@
+// With `--check-scraped-examples`, examples calling a function whose signature changed since
+// they were scraped are marked as stale, and the target they were compiled in is shown.
+
+use run_make_support::{htmldocck, rustc, rustdoc, tmp_dir};
+
+fn main() {
+    let out_dir = tmp_dir().join("rustdoc");
+    let crate_name = "foobar";
+    let calls = out_dir.join("ex.calls");
+
+    // The example is scraped against an older version of the crate.
+    rustc().input("src/old.rs").crate_name(crate_name).crate_type("lib").emit("metadata").run();
+    rustdoc()
+        .input("examples/ex.rs")
+        .crate_name("ex")
+        .crate_type("bin")
+        .output(&out_dir)
+        .extern_(crate_name, tmp_dir().join(format!("lib{crate_name}.rmeta")))
+        .arg("-Zunstable-options")
+        .arg("--scrape-examples-output-path")
+        .arg(&calls)
+        .arg("--scrape-examples-target-crate")
+        .arg(crate_name)
+        .arg("--check-scraped-examples")
+        .run();
+
+    let output = rustdoc()
+        .input("src/lib.rs")
+        .output(&out_dir)
+        .crate_name(crate_name)
+        .crate_type("lib")
+        .arg("-Zunstable-options")
+        .arg("--with-examples")
+        .arg(&calls)
+        .arg("--check-scraped-examples")
+        .run();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("the signature of this function changed"), "{stderr}");
+
+    assert!(htmldocck().arg(out_dir).arg("src/lib.rs").status().unwrap().success());
+}
@@ -0,0 +1,8 @@
+// @has foobar/fn.unchanged.html '//*[@class="scraped-example-title"]' 'in example ex'
+// @count - '//*[@class="stale-example"]' 0
+pub fn unchanged(_x: i32) {}
+
+// @has foobar/fn.changed.html '//*[@class="scraped-example-title"]' 'in example ex'
+// @has - '//*[@class="scraped-example expanded stale"]//*[@class="highlight focus"]' 'changed'
+// @has - '//*[@class="stale-example"]' 'stale'
+pub fn changed(_x: i64) {}
@@ -0,0 +1,3 @@
+pub fn unchanged(_x: i32) {}
+
+pub fn changed(_x: i32) {}
@@ -0,0 +1,5 @@
+extern crate common;
+
+pub fn a() -> u32 {
+    common::generic(1u32)
+}
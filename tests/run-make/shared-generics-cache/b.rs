@@ -0,0 +1,5 @@
+extern crate common;
+
+pub fn b() -> u32 {
+    common::generic(2u32)
+}
@@ -0,0 +1,6 @@
+#[inline(never)]
+pub fn generic<T: Copy>(x: T) -> T {
+    #[cfg(changed)]
+    let x = std::hint::black_box(x);
+    x
+}
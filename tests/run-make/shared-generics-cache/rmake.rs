@@ -0,0 +1,72 @@
+// With `-Zshared-generics-cache`, a crate records the instantiations of upstream generic items it
+// defines, and a sibling crate compiled later with the same cache links against them instead of
+// instantiating them again. Instantiations of items from another version of their crate are not
+// reused, and the records a crate reads are listed in its dep-info.
+
+//@ ignore-cross-compile
+
+use std::fs;
+
+use run_make_support::{rustc, tmp_dir};
+
+fn compile(krate: &str, cfg: Option<&str>) {
+    let mut cmd = rustc();
+    cmd.input(format!("{krate}.rs"))
+        .crate_type("rlib")
+        .arg("-Zshare-generics=yes")
+        .arg(format!("-Zshared-generics-cache={}", tmp_dir().join("cache").display()))
+        .emit("link,llvm-ir,dep-info");
+    if let Some(cfg) = cfg {
+        cmd.cfg(cfg);
+    }
+    cmd.run();
+}
+
+fn llvm_ir(krate: &str) -> String {
+    fs::read_to_string(tmp_dir().join(format!("{krate}.ll"))).unwrap()
+}
+
+/// Returns `true` if the LLVM IR defines the given symbol, and `false` if it only declares it.
+fn defines(ir: &str, symbol: &str) -> bool {
+    let needle = format!("@{symbol}(");
+    let line = ir
+        .lines()
+        .find(|line| {
+            line.contains(&needle) && (line.starts_with("define") || line.starts_with("declare"))
+        })
+        .unwrap_or_else(|| panic!("`{symbol}` not found in:\n{ir}"));
+    line.starts_with("define")
+}
+
+fn main() {
+    compile("common", None);
+    compile("a", None);
+
+    // `a` recorded its instantiation of `generic::<u32>`.
+    let record = fs::read_dir(tmp_dir().join("cache"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.file_name().unwrap().to_str().unwrap().starts_with("a-"))
+        .expect("no record for `a`");
+    let record = fs::read_to_string(record).unwrap();
+    let symbol = record.lines().nth(1).unwrap().split('\t').nth(1).unwrap().to_owned();
+    assert!(defines(&llvm_ir("a"), &symbol));
+
+    // `b` links against it, and lists the record of `a` in its dep-info.
+    compile("b", None);
+    assert!(defines(&llvm_ir("a"), &symbol));
+    assert!(!defines(&llvm_ir("b"), &symbol));
+    let dep_info = fs::read_to_string(tmp_dir().join("b.d")).unwrap();
+    assert!(dep_info.contains(".instances"), "no record in the dep-info of `b`:\n{dep_info}");
+
+    // Once `common` changed, the instantiation recorded by `a` is stale, and `b` instantiates
+    // `generic::<u32>` itself.
+    compile("common", Some("changed"));
+    compile("b", None);
+    let ir = llvm_ir("b");
+    assert!(!ir.contains(&format!("@{symbol}(")), "`b` uses the stale instantiation:\n{ir}");
+    assert!(
+        ir.lines().any(|line| line.starts_with("define") && line.contains("generic")),
+        "`b` doesn't define its own instantiation:\n{ir}"
+    );
+}
@@ -0,0 +1,32 @@
+// exact-check
+
+const EXPECTED = [
+    {
+        'query': 'retry policy',
+        'docs': [
+            // Matches both terms.
+            { 'path': 'full_text_search::Client', 'name': 'send' },
+            // Shorter docs rank higher.
+            { 'path': 'full_text_search', 'name': 'RetryPolicy' },
+            { 'path': 'full_text_search::Client', 'name': 'set_retries' },
+        ],
+    },
+    {
+        'query': 'requests',
+        'docs': [
+            { 'path': 'full_text_search::Client', 'name': 'send' },
+            { 'path': 'full_text_search::Client', 'name': 'set_retries' },
+        ],
+    },
+    {
+        'query': 'imaginary service',
+        'docs': [
+            { 'path': '', 'name': 'full_text_search' },
+        ],
+    },
+    {
+        // Code blocks are not indexed.
+        'query': 'attempts',
+        'docs': [],
+    },
+];
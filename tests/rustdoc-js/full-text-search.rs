@@ -0,0 +1,20 @@
+//@ compile-flags: -Zunstable-options --full-text-search
+
+//! A client for an imaginary service.
+
+pub struct Client;
+
+impl Client {
+    /// Sends the request, following the retry policy on failure.
+    pub fn send(&self) {}
+
+    /// Sets how many times a failed request is retried.
+    ///
+    /// ```
+    /// let attempts = 3;
+    /// ```
+    pub fn set_retries(&mut self, _: u32) {}
+}
+
+/// Decides when to give up on retrying.
+pub struct RetryPolicy;
@@ -0,0 +1,6 @@
+// This test purpose is to check that the "api-diff" output format needs a baseline to compare
+// against.
+
+//@ compile-flags: -Zunstable-options --output-format api-diff
+
+pub fn f() {}
@@ -0,0 +1,6 @@
+// This test purpose is to check that the "--baseline" option can only be used with the
+// "api-diff" output format.
+
+//@ compile-flags: -Zunstable-options --baseline baseline.json --output-format json
+
+pub fn f() {}
@@ -0,0 +1,13 @@
+//@ compile-flags:-Z unstable-options --output-format json --show-coverage --coverage-detail
+//@ check-pass
+
+//! Crate docs.
+
+/// Documented, without an example.
+pub fn no_example() {}
+
+pub mod module {
+    //! Module docs.
+
+    pub fn undocumented() {}
+}
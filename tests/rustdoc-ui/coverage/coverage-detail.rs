@@ -0,0 +1,30 @@
+//@ compile-flags:-Z unstable-options --show-coverage --coverage-detail
+//@ check-pass
+
+//! Crate docs.
+//!
+//! ```
+//! let x = 1;
+//! ```
+
+/// Documented, with an example.
+///
+/// ```
+/// let x = 2;
+/// ```
+pub struct Documented;
+
+pub fn undocumented() {}
+
+pub mod module {
+    //! Module docs.
+    //!
+    //! ```
+    //! let x = 3;
+    //! ```
+
+    /// Documented, without an example.
+    pub fn no_example() {}
+
+    pub struct Undocumented;
+}
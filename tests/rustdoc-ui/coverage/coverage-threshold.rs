@@ -0,0 +1,8 @@
+//@ compile-flags:-Z unstable-options --show-coverage --coverage-threshold=80
+
+//! Crate docs.
+
+/// Documented.
+pub struct Documented;
+
+pub struct Undocumented;
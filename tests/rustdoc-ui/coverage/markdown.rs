@@ -0,0 +1,4 @@
+//@ compile-flags:-Z unstable-options --output-format markdown --show-coverage
+
+/// Foo
+pub struct Xo;
@@ -0,0 +1,3 @@
+fn main() {
+    assert_eq!(doc_example::add(2, 2), 4);
+}
@@ -0,0 +1,14 @@
+// Files included with `#[doc(example = "...")]` are run as doctests.
+
+//@ check-pass
+//@ compile-flags:--test --test-args --test-threads=1
+//@ normalize-stdout-test: "tests/rustdoc-ui/doctest" -> "$$DIR"
+//@ normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+
+#![feature(doc_example)]
+
+/// Adds two numbers.
+#[doc(example = "auxiliary/doc-example-file.rs")]
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
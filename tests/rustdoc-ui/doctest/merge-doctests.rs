@@ -0,0 +1,25 @@
+// Merged doctests are still reported one by one, and a failing merged doctest is run again on
+// its own to report its output.
+
+//@ ignore-windows the exit status of the failing doctest is printed differently
+//@ compile-flags:--test --edition 2021 -Zunstable-options --merge-doctests
+//@ compile-flags:--test-args --test-threads=1
+//@ rustc-env:RUST_BACKTRACE=0
+//@ normalize-stdout-test: "tests/rustdoc-ui/doctest" -> "$$DIR"
+//@ normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+//@ failure-status: 101
+
+/// ```
+/// assert_eq!(2 + 2, 4);
+/// ```
+pub struct Merged;
+
+/// ```
+/// panic!("oh no");
+/// ```
+pub struct Failing;
+
+/// ```standalone
+/// assert_eq!(2 + 2, 4);
+/// ```
+pub struct Standalone;
@@ -0,0 +1,4 @@
+fn main() {
+    let sum = foo::add(2, 2);
+    assert_eq!(sum, 4);
+}
@@ -0,0 +1,14 @@
+#![crate_name = "foo"]
+#![feature(doc_example)]
+
+// @has 'foo/fn.add.html'
+// @has - '//*[@class="docblock"]/p' 'Adds two numbers.'
+// @has - '//*[@class="docblock"]//pre[@class="rust rust-example-rendered"]' \
+//     'let sum = foo::add(2, 2);'
+// @has - '//*[@class="docblock"]/p' 'The sum is never negative.'
+/// Adds two numbers.
+#[doc(example = "auxiliary/doc-example-file.rs")]
+/// The sum is never negative.
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
@@ -0,0 +1,2 @@
+#[doc(example = "feature-gate-doc_example.rs")] //~ ERROR: `#[doc(example)]` is experimental
+fn main() {}
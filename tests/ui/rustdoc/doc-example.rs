@@ -0,0 +1,14 @@
+//@ normalize-stderr-test: "missing.rs:.*\(" -> "missing.rs: $$FILE_NOT_FOUND_MSG ("
+//@ normalize-stderr-test: "os error \d+" -> "os error $$FILE_NOT_FOUND_CODE"
+
+#![crate_type = "lib"]
+#![feature(doc_example)]
+
+#[doc(example = "doc-example.rs")]
+pub fn found() {}
+
+#[doc(example = "missing.rs")] //~ ERROR couldn't read example
+pub fn missing() {}
+
+#[doc(example)] //~ ERROR doc example attribute expects a string
+pub fn no_path() {}